@@ -0,0 +1,236 @@
+use actix_session::SessionExt;
+use actix_web::{
+    body::{BoxBody, MessageBody},
+    cookie::{Cookie, SameSite},
+    dev::{Payload, ServiceRequest, ServiceResponse},
+    error::PayloadError,
+    http::Method,
+    middleware::Next,
+    web::{Bytes, BytesMut},
+    Error as ActixError, FromRequest, HttpMessage, HttpRequest, HttpResponse,
+};
+use askama::Template;
+use futures::{
+    future::{ready, Ready},
+    stream, Stream, StreamExt,
+};
+use log::{error, warn};
+use std::pin::Pin;
+
+use super::session_store::LOGIN_ID_KEY;
+use crate::{
+    config::get_session_config,
+    errors::{ErrorResponse, SubmissionError},
+    utils::security::{constant_time_eq, generate_token, sign_csrf_token},
+};
+
+// Token kept in a signed cookie and echoed back by every state-changing request.
+// It lives outside the session so anonymous visitors never get a stored session, but the
+// signature covers the session's login ID so the cookie only verifies for that login.
+const COOKIE_NAME: &str = "tsms-csrf";
+const FORM_FIELD: &str = "csrf_token";
+const HEADER_NAME: &str = "X-CSRF-Token";
+
+// Form bodies larger than this are rejected rather than buffered to look for the token
+const MAX_FORM_BYTES: usize = 1024 * 1024;
+
+type BoxedPayloadStream = Pin<Box<dyn Stream<Item = Result<Bytes, PayloadError>>>>;
+
+#[derive(Template)]
+#[template(path = "errors/csrf.html")]
+struct CsrfErrorTemplate;
+
+// The token for the current request: the cookie's, or a new one whose cookie is set on
+// the response once a page has used it
+struct RequestToken {
+    token: String,
+    from_cookie: bool,
+    used: bool,
+}
+
+// The signed-in session's login ID, or an empty string for anonymous visitors
+fn login_id(req: &HttpRequest) -> String {
+    req.get_session()
+        .get::<String>(LOGIN_ID_KEY)
+        .ok()
+        .flatten()
+        .unwrap_or_default()
+}
+
+// The token from the request's cookie, if it was signed for the current session
+fn cookie_token(req: &ServiceRequest) -> Option<String> {
+    let cookie = req.cookie(COOKIE_NAME)?;
+    let (token, signature) = cookie.value().split_once('.')?;
+    let expected = sign_csrf_token(token, &login_id(req.request()));
+    constant_time_eq(signature.as_bytes(), expected.as_bytes()).then(|| token.to_string())
+}
+
+// Signed for the session as it stands once the handler has run, so the cookie issued
+// by login and logout matches the session they leave behind
+fn token_cookie(token: &str, login_id: &str) -> Cookie<'static> {
+    Cookie::build(
+        COOKIE_NAME,
+        format!("{}.{}", token, sign_csrf_token(token, login_id)),
+    )
+    .path("/")
+    .http_only(true)
+    .secure(get_session_config().cookie_secure)
+    .same_site(SameSite::Lax)
+    .finish()
+}
+
+// The request's token, for pages that render it into their forms and head through
+// the `partials/csrf_field.html` and `partials/csrf_meta.html` includes
+pub struct CsrfToken(String);
+
+impl CsrfToken {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn into_inner(self) -> String {
+        self.0
+    }
+}
+
+impl FromRequest for CsrfToken {
+    type Error = SubmissionError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(request_token(req).map(CsrfToken))
+    }
+}
+
+// The token for pages that only render it for some visitors
+pub fn request_token(req: &HttpRequest) -> Result<String, SubmissionError> {
+    let mut extensions = req.extensions_mut();
+    let state = extensions.get_mut::<RequestToken>().ok_or_else(|| {
+        SubmissionError::InternalError("CSRF protection is not set up".to_string())
+    })?;
+    state.used = true;
+    Ok(state.token.clone())
+}
+
+// Replaces the token so one seen before login doesn't stay valid for the signed-in visitor
+pub fn rotate(req: &HttpRequest) {
+    req.extensions_mut().insert(RequestToken {
+        token: generate_token(),
+        from_cookie: false,
+        used: true,
+    });
+}
+
+fn is_safe_method(method: &Method) -> bool {
+    matches!(
+        *method,
+        Method::GET | Method::HEAD | Method::OPTIONS | Method::TRACE
+    )
+}
+
+// Pulls `csrf_token` out of an application/x-www-form-urlencoded body.
+// Tokens are hex, so no percent-decoding is needed.
+fn token_from_form(body: &[u8]) -> Option<String> {
+    std::str::from_utf8(body)
+        .ok()?
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == FORM_FIELD)
+        .map(|(_, value)| value.to_string())
+}
+
+// Reads the whole form body so the token can be checked, then hands an identical
+// payload back to the request for the handler's own extractor
+async fn buffer_form_body(req: &mut ServiceRequest) -> Result<Bytes, ActixError> {
+    let mut payload = req.take_payload();
+    let mut body = BytesMut::new();
+    while let Some(chunk) = payload.next().await {
+        let chunk = chunk?;
+        if body.len() + chunk.len() > MAX_FORM_BYTES {
+            return Err(PayloadError::Overflow.into());
+        }
+        body.extend_from_slice(&chunk);
+    }
+
+    let body = body.freeze();
+    let replay: BoxedPayloadStream = Box::pin(stream::once(ready(Ok(body.clone()))));
+    req.set_payload(Payload::from(replay));
+    Ok(body)
+}
+
+// The token sent with a request: the header for fetch() calls, the form field otherwise.
+// Multipart bodies are not buffered, so those requests must use the header.
+async fn submitted_token(req: &mut ServiceRequest) -> Result<Option<String>, ActixError> {
+    if let Some(token) = req.headers().get(HEADER_NAME).and_then(|v| v.to_str().ok()) {
+        return Ok(Some(token.to_string()));
+    }
+    if req.content_type() == "application/x-www-form-urlencoded" {
+        let body = buffer_form_body(req).await?;
+        return Ok(token_from_form(&body));
+    }
+    Ok(None)
+}
+
+fn rejection(wants_html: bool) -> HttpResponse {
+    if !wants_html {
+        return HttpResponse::Forbidden().json(ErrorResponse {
+            error: "CSRF_ERROR".to_string(),
+            message: "Missing or invalid CSRF token. Reload the page and try again.".to_string(),
+        });
+    }
+    match CsrfErrorTemplate.render() {
+        Ok(html) => HttpResponse::Forbidden()
+            .content_type("text/html; charset=utf-8")
+            .body(html),
+        Err(e) => {
+            error!("CSRF error template render error: {}", e);
+            HttpResponse::Forbidden().body("Missing or invalid CSRF token.")
+        }
+    }
+}
+
+// Verifies the token on every non-GET request and issues the cookie when a page first
+// renders a token
+pub async fn protect(
+    mut req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, ActixError> {
+    let stored = cookie_token(&req);
+    if !is_safe_method(req.method()) {
+        let expected = stored.clone();
+        let submitted = submitted_token(&mut req).await?;
+        let valid = match (expected, submitted) {
+            (Some(expected), Some(submitted)) => {
+                constant_time_eq(expected.as_bytes(), submitted.as_bytes())
+            }
+            _ => false,
+        };
+        if !valid {
+            warn!(
+                "CSRF token check failed for {} {}",
+                req.method(),
+                req.path()
+            );
+            let wants_html = super::wants_html(req.request());
+            return Ok(req.into_response(rejection(wants_html)));
+        }
+    }
+
+    req.extensions_mut().insert(RequestToken {
+        from_cookie: stored.is_some(),
+        token: stored.unwrap_or_else(generate_token),
+        used: false,
+    });
+    let mut res = next.call(req).await?;
+    let token = res
+        .request()
+        .extensions()
+        .get::<RequestToken>()
+        .filter(|state| state.used && !state.from_cookie)
+        .map(|state| state.token.clone());
+    if let Some(token) = token {
+        let cookie = token_cookie(&token, &login_id(res.request()));
+        res.response_mut().add_cookie(&cookie)?;
+    }
+    Ok(res.map_into_boxed_body())
+}
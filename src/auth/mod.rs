@@ -0,0 +1,239 @@
+pub mod csrf;
+pub mod session_store;
+pub mod throttle;
+pub mod totp;
+
+use actix_session::SessionExt;
+use actix_web::{
+    body::MessageBody,
+    dev::{Payload, ServiceRequest, ServiceResponse},
+    error::ResponseError,
+    http::{header, StatusCode},
+    middleware::Next,
+    Error as ActixError, FromRequest, HttpMessage, HttpRequest, HttpResponse,
+};
+use futures::future::{ready, Ready};
+use log::{error, warn};
+use std::fmt;
+use std::ops::Deref;
+
+use crate::{
+    db::{admin_repository::AdminRepository, review_repository::ReviewRepository, schema::init_db},
+    errors::{ErrorResponse, SubmissionError},
+    models::admin::{Admin, Permission},
+};
+
+// The signed-in, active admin for the current request.
+// Extracting it is the authentication check; `require` is the permission check.
+#[derive(Debug, Clone)]
+pub struct AuthenticatedUser {
+    admin: Admin,
+    wants_html: bool,
+}
+
+#[derive(Debug)]
+pub enum AuthError {
+    Unauthenticated { wants_html: bool },
+    Forbidden { wants_html: bool },
+    TwoFactorSetupRequired { wants_html: bool },
+    Internal(SubmissionError),
+}
+
+impl AuthenticatedUser {
+    // Loads the admin referenced by the session, clearing sessions of deactivated accounts
+    fn authenticate(req: &HttpRequest) -> Result<Self, AuthError> {
+        let wants_html = wants_html(req);
+        let session = req.get_session();
+
+        let admin_id = match session.get::<i32>("admin_id") {
+            Ok(Some(admin_id)) => admin_id,
+            _ => return Err(AuthError::Unauthenticated { wants_html }),
+        };
+
+        let conn = init_db()
+            .map_err(|e| AuthError::Internal(SubmissionError::DatabaseError(e.to_string())))?;
+        match AdminRepository::new(conn).find_admin_by_id(admin_id) {
+            Ok(Some(admin)) if admin.is_active => Ok(Self { admin, wants_html }),
+            Ok(_) => {
+                warn!(
+                    "Session for missing or deactivated admin {} cleared.",
+                    admin_id
+                );
+                session.purge();
+                Err(AuthError::Unauthenticated { wants_html })
+            }
+            Err(e) => {
+                error!("Failed to load admin {}: {}", admin_id, e);
+                Err(AuthError::Internal(e))
+            }
+        }
+    }
+
+    pub fn require(&self, permission: Permission) -> Result<(), AuthError> {
+        if self.admin.can(permission) {
+            return Ok(());
+        }
+        warn!(
+            "Admin {} ({}) denied {:?}",
+            self.admin.email,
+            self.admin.role.as_str(),
+            permission
+        );
+        Err(AuthError::Forbidden {
+            wants_html: self.wants_html,
+        })
+    }
+
+    // Those who can view every submission, or a reviewer assigned to this one
+    pub fn require_submission(&self, submission_id: i32) -> Result<(), AuthError> {
+        self.require(Permission::ReviewSubmissions)?;
+        if self.admin.can(Permission::ViewSubmissions) {
+            return Ok(());
+        }
+        let conn = init_db()
+            .map_err(|e| AuthError::Internal(SubmissionError::DatabaseError(e.to_string())))?;
+        if ReviewRepository::new(conn)
+            .is_assigned(submission_id, self.admin.id)
+            .map_err(AuthError::Internal)?
+        {
+            return Ok(());
+        }
+        warn!(
+            "Admin {} denied submission {} they are not reviewing",
+            self.admin.email, submission_id
+        );
+        Err(AuthError::Forbidden {
+            wants_html: self.wants_html,
+        })
+    }
+
+    // The reviewer whose assignments bound the submissions this user may list
+    pub fn assigned_reviewer(&self) -> Option<i32> {
+        (!self.admin.can(Permission::ViewSubmissions)).then_some(self.admin.id)
+    }
+}
+
+impl Deref for AuthenticatedUser {
+    type Target = Admin;
+
+    fn deref(&self) -> &Admin {
+        &self.admin
+    }
+}
+
+// The socket address rather than X-Forwarded-For, which clients can forge
+pub fn client_ip(req: &HttpRequest) -> String {
+    req.peer_addr()
+        .map(|addr| addr.ip().to_string())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+// Browser navigation and form posts accept HTML; fetch() and API clients don't ask for it
+fn wants_html(req: &HttpRequest) -> bool {
+    req.headers()
+        .get(header::ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.contains("text/html"))
+}
+
+impl FromRequest for AuthenticatedUser {
+    type Error = AuthError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        // Reuse the user loaded by `require_admin` when the route is behind it
+        if let Some(user) = req.extensions().get::<AuthenticatedUser>() {
+            return ready(Ok(user.clone()));
+        }
+        ready(Self::authenticate(req))
+    }
+}
+
+// Guard for the /admin scope: nothing inside it is reachable without a signed-in admin
+pub async fn require_admin(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, ActixError> {
+    match AuthenticatedUser::authenticate(req.request()) {
+        // Roles that must use 2FA can only reach the setup page until it is enabled
+        Ok(user)
+            if user.requires_two_factor()
+                && !user.totp_enabled
+                && !req.path().starts_with("/admin/account/2fa")
+                && req.path() != "/admin/logout" =>
+        {
+            Err(AuthError::TwoFactorSetupRequired {
+                wants_html: user.wants_html,
+            }
+            .into())
+        }
+        Ok(user) => {
+            req.extensions_mut().insert(user);
+            next.call(req).await
+        }
+        Err(e) => {
+            warn!("Unauthorized access attempt to {}", req.path());
+            Err(e.into())
+        }
+    }
+}
+
+impl fmt::Display for AuthError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AuthError::Unauthenticated { .. } => write!(f, "Authentication required"),
+            AuthError::Forbidden { .. } => {
+                write!(f, "You do not have permission to access this page")
+            }
+            AuthError::TwoFactorSetupRequired { .. } => {
+                write!(f, "Two-factor authentication must be enabled for your role")
+            }
+            AuthError::Internal(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl ResponseError for AuthError {
+    fn error_response(&self) -> HttpResponse {
+        match self {
+            AuthError::Unauthenticated { wants_html: true } => HttpResponse::Found()
+                .append_header(("Location", "/admin/login"))
+                .finish(),
+            AuthError::Unauthenticated { wants_html: false } => {
+                HttpResponse::Unauthorized().json(ErrorResponse {
+                    error: "UNAUTHORIZED_ERROR".to_string(),
+                    message: self.to_string(),
+                })
+            }
+            AuthError::Forbidden { wants_html: true } => HttpResponse::Forbidden()
+                .content_type("text/html; charset=utf-8")
+                .body(format!("{}.", self)),
+            AuthError::Forbidden { wants_html: false } => {
+                HttpResponse::Forbidden().json(ErrorResponse {
+                    error: "FORBIDDEN_ERROR".to_string(),
+                    message: self.to_string(),
+                })
+            }
+            AuthError::TwoFactorSetupRequired { wants_html: true } => HttpResponse::Found()
+                .append_header(("Location", "/admin/account/2fa"))
+                .finish(),
+            AuthError::TwoFactorSetupRequired { wants_html: false } => HttpResponse::Forbidden()
+                .json(ErrorResponse {
+                    error: "TWO_FACTOR_REQUIRED".to_string(),
+                    message: self.to_string(),
+                }),
+            AuthError::Internal(e) => e.error_response(),
+        }
+    }
+
+    fn status_code(&self) -> StatusCode {
+        match self {
+            AuthError::Unauthenticated { wants_html: true } => StatusCode::FOUND,
+            AuthError::Unauthenticated { wants_html: false } => StatusCode::UNAUTHORIZED,
+            AuthError::Forbidden { .. } => StatusCode::FORBIDDEN,
+            AuthError::TwoFactorSetupRequired { wants_html: true } => StatusCode::FOUND,
+            AuthError::TwoFactorSetupRequired { wants_html: false } => StatusCode::FORBIDDEN,
+            AuthError::Internal(e) => e.status_code(),
+        }
+    }
+}
@@ -0,0 +1,157 @@
+use actix_session::storage::{
+    generate_session_key, LoadError, SaveError, SessionKey, SessionStore, UpdateError,
+};
+use actix_web::cookie::time::Duration;
+use chrono::Utc;
+use std::collections::HashMap;
+
+use crate::{
+    db::{
+        schema::open_db,
+        session_repository::{SessionOwner, SessionRepository},
+    },
+    errors::SubmissionError,
+    utils::security::hash_session_key,
+};
+
+// Session entries set at login and mirrored into their own columns
+pub const LOGIN_ID_KEY: &str = "login_id";
+pub const LOGIN_IP_KEY: &str = "login_ip";
+pub const USER_AGENT_KEY: &str = "user_agent";
+
+type SessionState = HashMap<String, String>;
+
+// Keeps session state in SQLite so it can be listed and revoked, with only a keyed hash
+// of the cookie value stored. Idle expiry comes from the middleware's TTL; sessions
+// older than `absolute_timeout` are dropped however active they are. Abandoned rows are
+// removed by the hourly housekeeping job.
+#[derive(Clone)]
+pub struct SqliteSessionStore {
+    absolute_timeout: chrono::Duration,
+}
+
+impl SqliteSessionStore {
+    pub fn new(absolute_timeout: chrono::Duration) -> Self {
+        Self { absolute_timeout }
+    }
+
+    fn repository() -> Result<SessionRepository, SubmissionError> {
+        let conn = open_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        Ok(SessionRepository::new(conn))
+    }
+
+    fn oldest_allowed(&self) -> i64 {
+        (Utc::now() - self.absolute_timeout).timestamp()
+    }
+}
+
+fn expires_at(ttl: &Duration) -> i64 {
+    Utc::now().timestamp() + ttl.whole_seconds()
+}
+
+// Values in the state map are JSON encoded by actix-session
+fn decoded<T: serde::de::DeserializeOwned>(state: &SessionState, key: &str) -> Option<T> {
+    state.get(key).and_then(|v| serde_json::from_str(v).ok())
+}
+
+fn owner(state: &SessionState) -> SessionOwner {
+    SessionOwner {
+        admin_id: decoded(state, "admin_id"),
+        login_id: decoded(state, LOGIN_ID_KEY),
+        ip_address: decoded(state, LOGIN_IP_KEY),
+        user_agent: decoded(state, USER_AGENT_KEY),
+    }
+}
+
+impl SessionStore for SqliteSessionStore {
+    async fn load(&self, session_key: &SessionKey) -> Result<Option<SessionState>, LoadError> {
+        let key_hash = hash_session_key(session_key.as_ref());
+        let repository = Self::repository().map_err(|e| LoadError::Other(anyhow::anyhow!(e)))?;
+        let Some(stored) = repository
+            .find_session(&key_hash)
+            .map_err(|e| LoadError::Other(anyhow::anyhow!(e)))?
+        else {
+            return Ok(None);
+        };
+
+        if stored.expires_at < Utc::now().timestamp() || stored.created_at < self.oldest_allowed() {
+            repository
+                .delete_session(&key_hash)
+                .map_err(|e| LoadError::Other(anyhow::anyhow!(e)))?;
+            return Ok(None);
+        }
+
+        serde_json::from_str(&stored.state)
+            .map(Some)
+            .map_err(|e| LoadError::Deserialization(anyhow::anyhow!(e)))
+    }
+
+    async fn save(
+        &self,
+        session_state: SessionState,
+        ttl: &Duration,
+    ) -> Result<SessionKey, SaveError> {
+        let state = serde_json::to_string(&session_state)
+            .map_err(|e| SaveError::Serialization(anyhow::anyhow!(e)))?;
+        let session_key = generate_session_key();
+
+        let repository = Self::repository().map_err(|e| SaveError::Other(anyhow::anyhow!(e)))?;
+        repository
+            .insert_session(
+                &hash_session_key(session_key.as_ref()),
+                &state,
+                &owner(&session_state),
+                expires_at(ttl),
+            )
+            .map_err(|e| SaveError::Other(anyhow::anyhow!(e)))?;
+        Ok(session_key)
+    }
+
+    async fn update(
+        &self,
+        session_key: SessionKey,
+        session_state: SessionState,
+        ttl: &Duration,
+    ) -> Result<SessionKey, UpdateError> {
+        let state = serde_json::to_string(&session_state)
+            .map_err(|e| UpdateError::Serialization(anyhow::anyhow!(e)))?;
+
+        let repository = Self::repository().map_err(|e| UpdateError::Other(anyhow::anyhow!(e)))?;
+        let updated = repository
+            .update_session(
+                &hash_session_key(session_key.as_ref()),
+                &state,
+                &owner(&session_state),
+                expires_at(ttl),
+            )
+            .map_err(|e| UpdateError::Other(anyhow::anyhow!(e)))?;
+        if updated {
+            return Ok(session_key);
+        }
+
+        // Revoked while this request was running: start over without the sign-in
+        let mut session_state = session_state;
+        session_state.remove("admin_id");
+        session_state.remove(LOGIN_ID_KEY);
+        self.save(session_state, ttl).await.map_err(|e| match e {
+            SaveError::Serialization(e) => UpdateError::Serialization(e),
+            SaveError::Other(e) => UpdateError::Other(e),
+        })
+    }
+
+    async fn update_ttl(&self, session_key: &SessionKey, ttl: &Duration) -> anyhow::Result<()> {
+        Self::repository()
+            .and_then(|repository| {
+                repository.touch_session(&hash_session_key(session_key.as_ref()), expires_at(ttl))
+            })
+            .map_err(|e| anyhow::anyhow!(e))
+    }
+
+    async fn delete(&self, session_key: &SessionKey) -> anyhow::Result<()> {
+        Self::repository()
+            .and_then(|repository| {
+                repository.delete_session(&hash_session_key(session_key.as_ref()))
+            })
+            .map_err(|e| anyhow::anyhow!(e))
+    }
+}
@@ -0,0 +1,64 @@
+use chrono::{DateTime, Duration, Utc};
+
+// Consecutive failures allowed on one account before it is locked
+pub const ACCOUNT_FAILURE_THRESHOLD: i32 = 5;
+// First lockout; each further failure doubles it up to the cap
+const ACCOUNT_BASE_LOCKOUT_MINUTES: i64 = 15;
+const ACCOUNT_MAX_LOCKOUT_HOURS: i64 = 24;
+
+// Failures allowed from one address within the window before it is slowed down
+pub const IP_FAILURE_THRESHOLD: i64 = 10;
+pub const IP_WINDOW_MINUTES: i64 = 15;
+const IP_BASE_DELAY_SECONDS: i64 = 30;
+
+// base * 2^(failures - threshold), or None while under the threshold
+fn exponential_backoff(
+    failures: i64,
+    threshold: i64,
+    base: Duration,
+    cap: Duration,
+) -> Option<Duration> {
+    if failures < threshold {
+        return None;
+    }
+    let exponent = (failures - threshold).min(20) as u32;
+    Some((base * 2_i32.pow(exponent)).min(cap))
+}
+
+// When an account with this many consecutive failures should be locked until
+pub fn account_locked_until(failed_login_count: i32) -> Option<DateTime<Utc>> {
+    exponential_backoff(
+        failed_login_count as i64,
+        ACCOUNT_FAILURE_THRESHOLD as i64,
+        Duration::minutes(ACCOUNT_BASE_LOCKOUT_MINUTES),
+        Duration::hours(ACCOUNT_MAX_LOCKOUT_HOURS),
+    )
+    .map(|lockout| Utc::now() + lockout)
+}
+
+// How much longer an address must wait given its recent failures, if at all
+pub fn ip_retry_after(
+    recent_failures: i64,
+    last_failure: Option<DateTime<Utc>>,
+) -> Option<Duration> {
+    let delay = exponential_backoff(
+        recent_failures,
+        IP_FAILURE_THRESHOLD,
+        Duration::seconds(IP_BASE_DELAY_SECONDS),
+        Duration::minutes(IP_WINDOW_MINUTES),
+    )?;
+    let remaining = last_failure? + delay - Utc::now();
+    (remaining > Duration::zero()).then_some(remaining)
+}
+
+// "3 minutes" / "45 seconds" for the login error message
+pub fn describe_wait(wait: Duration) -> String {
+    let minutes = (wait.num_seconds() + 59) / 60;
+    if wait.num_seconds() < 60 {
+        format!("{} seconds", wait.num_seconds().max(1))
+    } else if minutes == 1 {
+        "1 minute".to_string()
+    } else {
+        format!("{} minutes", minutes)
+    }
+}
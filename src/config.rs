@@ -1,3 +1,5 @@
+use crate::models::admin::Role;
+
 pub struct JournalConfig {
     pub name: String,
     pub field: String,
@@ -19,3 +21,137 @@ impl Default for JournalConfig {
 pub fn get_journal_config() -> JournalConfig {
     JournalConfig::default()
 }
+
+pub struct SmtpConfig {
+    pub host: String,
+    pub port: u16,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub from: String,
+}
+
+// Outgoing mail server; None when SMTP_HOST is unset, in which case mail is only logged
+pub fn get_smtp_config() -> Option<SmtpConfig> {
+    let host = std::env::var("SMTP_HOST").ok().filter(|h| !h.is_empty())?;
+    Some(SmtpConfig {
+        host,
+        port: std::env::var("SMTP_PORT")
+            .ok()
+            .and_then(|v| v.parse::<u16>().ok())
+            .unwrap_or(587),
+        username: std::env::var("SMTP_USERNAME").ok(),
+        password: std::env::var("SMTP_PASSWORD").ok(),
+        from: std::env::var("MAIL_FROM")
+            .unwrap_or_else(|_| "Akungba Journal <no-reply@localhost>".to_string()),
+    })
+}
+
+// Roles that must enrol in two-factor authentication, e.g. "super_admin,editor_in_chief"
+pub fn get_required_2fa_roles() -> Vec<Role> {
+    std::env::var("REQUIRE_2FA_ROLES")
+        .unwrap_or_default()
+        .split(',')
+        .filter_map(|role| Role::parse(role.trim()))
+        .collect()
+}
+
+pub struct SessionConfig {
+    pub cookie_secure: bool,
+    pub idle_timeout: chrono::Duration,
+    pub absolute_timeout: chrono::Duration,
+}
+
+// Session cookie and lifetime settings. Cookies are marked Secure by default when
+// BASE_URL is https; SESSION_COOKIE_SECURE overrides that either way.
+pub fn get_session_config() -> SessionConfig {
+    let cookie_secure = std::env::var("SESSION_COOKIE_SECURE")
+        .ok()
+        .and_then(|v| v.parse::<bool>().ok())
+        .unwrap_or_else(|| get_base_url().starts_with("https://"));
+    SessionConfig {
+        cookie_secure,
+        idle_timeout: chrono::Duration::minutes(
+            std::env::var("SESSION_IDLE_MINUTES")
+                .ok()
+                .and_then(|v| v.parse::<i64>().ok())
+                .filter(|m| *m > 0)
+                .unwrap_or(30),
+        ),
+        absolute_timeout: chrono::Duration::hours(
+            std::env::var("SESSION_ABSOLUTE_HOURS")
+                .ok()
+                .and_then(|v| v.parse::<i64>().ok())
+                .filter(|h| *h > 0)
+                .unwrap_or(12),
+        ),
+    }
+}
+
+// Days a trashed article or submission is kept before its record and files are purged
+pub fn get_trash_retention_days() -> i64 {
+    std::env::var("TRASH_RETENTION_DAYS")
+        .ok()
+        .and_then(|v| v.parse::<i64>().ok())
+        .filter(|d| *d >= 0)
+        .unwrap_or(30)
+}
+
+// Days an unsubmitted draft or an idle resumable upload is kept before it is removed
+pub fn get_draft_retention_days() -> i64 {
+    std::env::var("DRAFT_RETENTION_DAYS")
+        .ok()
+        .and_then(|v| v.parse::<i64>().ok())
+        .filter(|d| *d >= 0)
+        .unwrap_or(30)
+}
+
+// Days login attempts are kept for the users page and throttling before being removed
+pub fn get_login_attempt_retention_days() -> i64 {
+    std::env::var("LOGIN_ATTEMPT_RETENTION_DAYS")
+        .ok()
+        .and_then(|v| v.parse::<i64>().ok())
+        .filter(|d| *d >= 1)
+        .unwrap_or(90)
+}
+
+// Public URL of the site, used to build links in outgoing email and JATS exports
+pub fn get_base_url() -> String {
+    std::env::var("BASE_URL")
+        .map(|url| url.trim_end_matches('/').to_string())
+        .unwrap_or_else(|_| "http://localhost:8080".to_string())
+}
+
+// Largest manuscript accepted through resumable uploads, in bytes
+pub fn get_max_upload_size() -> i64 {
+    std::env::var("MAX_UPLOAD_SIZE")
+        .ok()
+        .and_then(|v| v.parse::<i64>().ok())
+        .unwrap_or(50 * 1024 * 1024)
+}
+
+// Largest archive accepted by the bulk article import, in bytes
+pub fn get_max_import_size() -> u64 {
+    std::env::var("MAX_IMPORT_SIZE")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(1024 * 1024 * 1024)
+}
+
+// Similarity score (0.0 - 1.0) at or above which a submission is flagged as a likely duplicate
+pub fn get_similarity_threshold() -> f64 {
+    std::env::var("SIMILARITY_THRESHOLD")
+        .ok()
+        .and_then(|v| v.parse::<f64>().ok())
+        .filter(|t| (0.0..=1.0).contains(t))
+        .unwrap_or(0.5)
+}
+
+// Extra user-agent fragments, comma separated, whose requests are left out of usage statistics
+pub fn get_extra_robot_agents() -> Vec<String> {
+    std::env::var("ROBOT_USER_AGENTS")
+        .unwrap_or_default()
+        .split(',')
+        .map(|agent| agent.trim().to_lowercase())
+        .filter(|agent| !agent.is_empty())
+        .collect()
+}
@@ -1,4 +1,12 @@
-use crate::{errors::SubmissionError, models::admin::Admin};
+use crate::{
+    errors::SubmissionError,
+    models::{
+        admin::{Admin, AdminInvite, Role},
+        auth_token::TokenPurpose,
+    },
+    utils::security::sign_token,
+};
+use chrono::{DateTime, NaiveDateTime, Utc};
 use rusqlite::{params, Connection, OptionalExtension, Result as RusqliteResult};
 
 pub struct AdminRepository {
@@ -10,32 +18,288 @@ impl AdminRepository {
         Self { conn }
     }
 
+    fn map_row_to_admin(row: &rusqlite::Row) -> RusqliteResult<Admin> {
+        let role_str: String = row.get(3)?;
+        let locked_until_ts: Option<i64> = row.get(7)?;
+        let created_at_str: Option<String> = row.get(11)?;
+        let created_at = created_at_str.and_then(|s| {
+            NaiveDateTime::parse_from_str(&s, "%Y-%m-%d %H:%M:%S")
+                .ok()
+                .map(|dt| DateTime::<Utc>::from_naive_utc_and_offset(dt, Utc))
+        });
+
+        Ok(Admin {
+            id: row.get(0)?,
+            email: row.get(1)?,
+            password_hash: row.get(2)?,
+            // Unknown roles get no permissions rather than failing the whole query
+            role: Role::parse(&role_str).unwrap_or(Role::Author),
+            is_active: row.get(4)?,
+            email_verified: row.get(5)?,
+            failed_login_count: row.get(6)?,
+            locked_until: locked_until_ts.and_then(|ts| DateTime::<Utc>::from_timestamp(ts, 0)),
+            totp_secret: row.get(8)?,
+            totp_enabled: row.get(9)?,
+            totp_last_step: row.get(10)?,
+            created_at,
+        })
+    }
+
+    const SELECT_FIELDS: &'static str = "id, email, password_hash, role, is_active, email_verified,
+         failed_login_count, locked_until, totp_secret, totp_enabled, totp_last_step, created_at";
+
     // Find admin by email
     pub fn find_admin_by_email(&self, email: &str) -> Result<Option<Admin>, SubmissionError> {
         self.conn
             .query_row(
-                "SELECT id, email, password_hash FROM admins WHERE email = ?1",
+                &format!(
+                    "SELECT {} FROM admins WHERE email = ?1",
+                    Self::SELECT_FIELDS
+                ),
                 params![email],
-                |row| {
-                    Ok(Admin {
-                        id: row.get(0)?,
-                        email: row.get(1)?,
-                        password_hash: row.get(2)?,
-                    })
-                },
+                Self::map_row_to_admin,
             )
             .optional() // Makes it return Option<Admin>
             .map_err(|e| SubmissionError::DatabaseError(e.to_string()))
     }
 
-    // Create admin (used for seeding)
+    pub fn find_admin_by_id(&self, id: i32) -> Result<Option<Admin>, SubmissionError> {
+        self.conn
+            .query_row(
+                &format!("SELECT {} FROM admins WHERE id = ?1", Self::SELECT_FIELDS),
+                params![id],
+                Self::map_row_to_admin,
+            )
+            .optional()
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))
+    }
+
+    pub fn get_all_admins(&self) -> Result<Vec<Admin>, SubmissionError> {
+        let mut stmt = self
+            .conn
+            .prepare(&format!(
+                "SELECT {} FROM admins ORDER BY is_active DESC, email",
+                Self::SELECT_FIELDS
+            ))
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+
+        let admin_iter = stmt
+            .query_map([], Self::map_row_to_admin)
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+
+        admin_iter
+            .collect::<Result<Vec<Admin>, _>>()
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))
+    }
+
+    // Create admin (used for seeding); the address comes from the server config so it's trusted
     pub fn create_admin(&self, email: &str, password_hash: &str) -> Result<i64, SubmissionError> {
+        let id = self.create_admin_with_role(email, password_hash, Role::SuperAdmin)?;
+        self.mark_email_verified(id as i32)?;
+        Ok(id)
+    }
+
+    pub fn create_admin_with_role(
+        &self,
+        email: &str,
+        password_hash: &str,
+        role: Role,
+    ) -> Result<i64, SubmissionError> {
         self.conn
             .execute(
-                "INSERT INTO admins (email, password_hash) VALUES (?1, ?2)",
-                params![email, password_hash],
+                "INSERT INTO admins (email, password_hash, role, email_verified)
+                 VALUES (?1, ?2, ?3, 0)",
+                params![email, password_hash, role.as_str()],
             )
             .map(|_| self.conn.last_insert_rowid()) // Return the ID
+            .map_err(|e| {
+                if e.to_string()
+                    .contains("UNIQUE constraint failed: admins.email")
+                {
+                    SubmissionError::Conflict(format!("An account for {} already exists", email))
+                } else {
+                    SubmissionError::DatabaseError(e.to_string())
+                }
+            })
+    }
+
+    pub fn update_role(&self, id: i32, role: Role) -> Result<(), SubmissionError> {
+        match self.conn.execute(
+            "UPDATE admins SET role = ?1 WHERE id = ?2",
+            params![role.as_str(), id],
+        ) {
+            Ok(0) => Err(SubmissionError::NotFound(format!(
+                "User with ID {} not found",
+                id
+            ))),
+            Ok(_) => Ok(()),
+            Err(e) => Err(SubmissionError::DatabaseError(e.to_string())),
+        }
+    }
+
+    pub fn set_active(&self, id: i32, is_active: bool) -> Result<(), SubmissionError> {
+        match self.conn.execute(
+            "UPDATE admins SET is_active = ?1 WHERE id = ?2",
+            params![is_active, id],
+        ) {
+            Ok(0) => Err(SubmissionError::NotFound(format!(
+                "User with ID {} not found",
+                id
+            ))),
+            Ok(_) => Ok(()),
+            Err(e) => Err(SubmissionError::DatabaseError(e.to_string())),
+        }
+    }
+
+    pub fn update_password(&self, id: i32, password_hash: &str) -> Result<(), SubmissionError> {
+        match self.conn.execute(
+            "UPDATE admins SET password_hash = ?1 WHERE id = ?2",
+            params![password_hash, id],
+        ) {
+            Ok(0) => Err(SubmissionError::NotFound(format!(
+                "User with ID {} not found",
+                id
+            ))),
+            Ok(_) => Ok(()),
+            Err(e) => Err(SubmissionError::DatabaseError(e.to_string())),
+        }
+    }
+
+    pub fn mark_email_verified(&self, id: i32) -> Result<(), SubmissionError> {
+        self.conn
+            .execute(
+                "UPDATE admins SET email_verified = 1 WHERE id = ?1",
+                params![id],
+            )
+            .map(|_| ())
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))
+    }
+
+    // Counts a failed login in place, so concurrent failures are all counted; returns the new total
+    pub fn record_failed_login(&self, id: i32) -> Result<i32, SubmissionError> {
+        self.conn
+            .query_row(
+                "UPDATE admins SET failed_login_count = failed_login_count + 1 WHERE id = ?1
+                 RETURNING failed_login_count",
+                params![id],
+                |row| row.get(0),
+            )
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))
+    }
+
+    // Never shortens a longer lockout set by a concurrent failure
+    pub fn lock_account(
+        &self,
+        id: i32,
+        locked_until: DateTime<Utc>,
+    ) -> Result<(), SubmissionError> {
+        self.conn
+            .execute(
+                "UPDATE admins SET locked_until = MAX(COALESCE(locked_until, 0), ?1) WHERE id = ?2",
+                params![locked_until.timestamp(), id],
+            )
+            .map(|_| ())
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))
+    }
+
+    // Clears failures after a successful login or a manual unlock
+    pub fn reset_failed_logins(&self, id: i32) -> Result<(), SubmissionError> {
+        self.conn
+            .execute(
+                "UPDATE admins SET failed_login_count = 0, locked_until = NULL WHERE id = ?1",
+                params![id],
+            )
+            .map(|_| ())
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))
+    }
+
+    // Used to stop the last super-admin from being demoted or deactivated
+    pub fn count_active_super_admins(&self) -> Result<i64, SubmissionError> {
+        self.conn
+            .query_row(
+                "SELECT COUNT(*) FROM admins WHERE role = ?1 AND is_active = 1",
+                params![Role::SuperAdmin.as_str()],
+                |row| row.get(0),
+            )
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))
+    }
+
+    // --- Invitations ---
+
+    fn map_row_to_invite(row: &rusqlite::Row) -> RusqliteResult<AdminInvite> {
+        let role_str: String = row.get(2)?;
+        let expires_at_ts: i64 = row.get(4)?;
+
+        Ok(AdminInvite {
+            token: String::new(),
+            email: row.get(1)?,
+            role: Role::parse(&role_str).unwrap_or(Role::Author),
+            invited_by: row.get(3)?,
+            expires_at: DateTime::<Utc>::from_timestamp(expires_at_ts, 0).unwrap_or_default(),
+        })
+    }
+
+    pub fn create_invite(&self, invite: &AdminInvite) -> Result<(), SubmissionError> {
+        self.conn
+            .execute(
+                "INSERT INTO admin_invites (token, email, role, invited_by, expires_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    sign_token(TokenPurpose::AdminInvite, &invite.token),
+                    invite.email,
+                    invite.role.as_str(),
+                    invite.invited_by,
+                    invite.expires_at.timestamp(),
+                ],
+            )
+            .map(|_| ())
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))
+    }
+
+    pub fn find_invite(&self, token: &str) -> Result<Option<AdminInvite>, SubmissionError> {
+        let invite = self
+            .conn
+            .query_row(
+                "SELECT token, email, role, invited_by, expires_at
+                 FROM admin_invites WHERE token = ?1 AND accepted_at IS NULL",
+                params![sign_token(TokenPurpose::AdminInvite, token)],
+                Self::map_row_to_invite,
+            )
+            .optional()
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        Ok(invite.map(|invite| AdminInvite {
+            token: token.to_string(),
+            ..invite
+        }))
+    }
+
+    pub fn get_pending_invites(&self) -> Result<Vec<AdminInvite>, SubmissionError> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT token, email, role, invited_by, expires_at
+                 FROM admin_invites
+                 WHERE accepted_at IS NULL AND expires_at > ?1
+                 ORDER BY expires_at DESC",
+            )
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+
+        let invite_iter = stmt
+            .query_map(params![Utc::now().timestamp()], Self::map_row_to_invite)
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+
+        invite_iter
+            .collect::<Result<Vec<AdminInvite>, _>>()
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))
+    }
+
+    pub fn mark_invite_accepted(&self, token: &str) -> Result<(), SubmissionError> {
+        self.conn
+            .execute(
+                "UPDATE admin_invites SET accepted_at = CURRENT_TIMESTAMP WHERE token = ?1",
+                params![sign_token(TokenPurpose::AdminInvite, token)],
+            )
+            .map(|_| ())
             .map_err(|e| SubmissionError::DatabaseError(e.to_string()))
     }
 }
@@ -0,0 +1,199 @@
+use crate::{
+    errors::SubmissionError,
+    models::analytics::{
+        median, CountrySubmissions, EditorialMetrics, MonthlySubmissions, ReviewerTurnaround,
+    },
+    models::usage::months_between,
+};
+use chrono::{NaiveDate, NaiveTime};
+use rusqlite::{params, Connection, Params};
+use std::collections::{BTreeMap, HashMap};
+
+const SECONDS_PER_DAY: f64 = 86400.0;
+
+// Submission times are stored as "YYYY-MM-DD HH:MM:SS" text
+const SUBMITTED_AT: &str = "CAST(strftime('%s', s.created_at) AS INTEGER)";
+
+pub struct AnalyticsRepository {
+    conn: Connection,
+}
+
+impl AnalyticsRepository {
+    pub fn new(conn: Connection) -> Self {
+        Self { conn }
+    }
+
+    // Rows of a query returning one number of seconds each, as days
+    fn query_days<P: Params>(&self, sql: &str, params: P) -> Result<Vec<f64>, SubmissionError> {
+        let mut stmt = self
+            .conn
+            .prepare(sql)
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        let days = stmt
+            .query_map(params, |row| {
+                Ok(row.get::<_, i64>(0)?.max(0) as f64 / SECONDS_PER_DAY)
+            })
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        Ok(days)
+    }
+
+    fn query_counts<P: Params>(
+        &self,
+        sql: &str,
+        params: P,
+    ) -> Result<Vec<(String, i64)>, SubmissionError> {
+        let mut stmt = self
+            .conn
+            .prepare(sql)
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        let counts = stmt
+            .query_map(params, |row| Ok((row.get(0)?, row.get(1)?)))
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        Ok(counts)
+    }
+
+    // Metrics for the submissions received from `since` (the first day of a month) to
+    // `until`; reviews are counted by when the reviewer was invited
+    pub fn get_editorial_metrics(
+        &self,
+        since: NaiveDate,
+        until: NaiveDate,
+    ) -> Result<EditorialMetrics, SubmissionError> {
+        let since_text = since.format("%Y-%m-%d 00:00:00").to_string();
+        let since_ts = since.and_time(NaiveTime::MIN).and_utc().timestamp();
+
+        let by_month: HashMap<String, i64> = self
+            .query_counts(
+                "SELECT strftime('%Y-%m', s.created_at), COUNT(*) FROM submissions s
+                 WHERE s.deleted_at IS NULL AND s.created_at >= ?1
+                 GROUP BY 1",
+                params![since_text],
+            )?
+            .into_iter()
+            .collect();
+        let months: Vec<MonthlySubmissions> = months_between(since, until)
+            .into_iter()
+            .map(|month| {
+                let month = month.format("%Y-%m").to_string();
+                MonthlySubmissions {
+                    submissions: by_month.get(&month).copied().unwrap_or(0),
+                    month,
+                }
+            })
+            .collect();
+
+        // The latest final decision counts, so a rejection overturned on appeal is an
+        // acceptance
+        let outcomes: HashMap<String, i64> = self
+            .query_counts(
+                "SELECT d.decision, COUNT(*) FROM submission_decisions d
+                 JOIN submissions s ON s.id = d.submission_id
+                 WHERE s.deleted_at IS NULL AND s.created_at >= ?1
+                   AND d.id = (SELECT MAX(f.id) FROM submission_decisions f
+                               WHERE f.submission_id = d.submission_id
+                                 AND f.decision IN ('accept', 'reject'))
+                 GROUP BY d.decision",
+                params![since_text],
+            )?
+            .into_iter()
+            .collect();
+
+        let mut to_decision = self.query_days(
+            &format!(
+                "SELECT MIN(d.decided_at) - {} FROM submissions s
+                 JOIN submission_decisions d ON d.submission_id = s.id
+                 WHERE s.deleted_at IS NULL AND s.created_at >= ?1
+                 GROUP BY s.id",
+                SUBMITTED_AT
+            ),
+            params![since_text],
+        )?;
+        let mut to_publication = self.query_days(
+            &format!(
+                "SELECT CAST(strftime('%s', j.created_at) AS INTEGER) - {} FROM journals j
+                 JOIN submissions s ON s.id = j.submission_id
+                 WHERE j.deleted_at IS NULL AND s.deleted_at IS NULL AND s.created_at >= ?1",
+                SUBMITTED_AT
+            ),
+            params![since_text],
+        )?;
+
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT COALESCE(a.email, ''), r.completed_at - r.assigned_at
+                 FROM submission_reviews r
+                 JOIN submissions s ON s.id = r.submission_id AND s.deleted_at IS NULL
+                 LEFT JOIN admins a ON a.id = r.reviewer_id
+                 WHERE r.assigned_at >= ?1",
+            )
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        let reviews = stmt
+            .query_map(params![since_ts], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, Option<i64>>(1)?))
+            })
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+
+        let mut all_review_days = Vec::new();
+        let mut by_reviewer: BTreeMap<String, (Vec<f64>, i64)> = BTreeMap::new();
+        for (email, seconds) in reviews {
+            let entry = by_reviewer.entry(email).or_default();
+            match seconds {
+                Some(seconds) => {
+                    let days = seconds.max(0) as f64 / SECONDS_PER_DAY;
+                    entry.0.push(days);
+                    all_review_days.push(days);
+                }
+                None => entry.1 += 1,
+            }
+        }
+        let reviewers = by_reviewer
+            .into_iter()
+            .map(
+                |(reviewer_email, (mut days, outstanding))| ReviewerTurnaround {
+                    reviewer_email,
+                    completed: days.len() as i64,
+                    outstanding,
+                    median_days: median(&mut days),
+                },
+            )
+            .collect();
+
+        let countries = self
+            .query_counts(
+                "SELECT MIN(s.country), COUNT(*) FROM submissions s
+                 WHERE s.deleted_at IS NULL AND s.created_at >= ?1
+                 GROUP BY s.country COLLATE NOCASE
+                 ORDER BY 2 DESC, 1",
+                params![since_text],
+            )?
+            .into_iter()
+            .map(|(country, submissions)| CountrySubmissions {
+                country: if country.is_empty() {
+                    "Not given".to_string()
+                } else {
+                    country
+                },
+                submissions,
+            })
+            .collect();
+
+        Ok(EditorialMetrics {
+            total_submissions: months.iter().map(|m| m.submissions).sum(),
+            months,
+            accepted: outcomes.get("accept").copied().unwrap_or(0),
+            rejected: outcomes.get("reject").copied().unwrap_or(0),
+            median_days_to_decision: median(&mut to_decision),
+            median_days_to_publication: median(&mut to_publication),
+            median_review_days: median(&mut all_review_days),
+            reviewers,
+            countries,
+        })
+    }
+}
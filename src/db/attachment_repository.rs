@@ -0,0 +1,170 @@
+use crate::{
+    errors::SubmissionError,
+    models::attachment::{Attachment, AttachmentKind},
+    utils::is_bare_file_name,
+};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use log::warn;
+use rusqlite::{params, Connection, Result as RusqliteResult};
+
+pub struct AttachmentRepository {
+    conn: Connection,
+}
+
+impl AttachmentRepository {
+    pub fn new(conn: Connection) -> Self {
+        Self { conn }
+    }
+
+    fn map_row_to_attachment(row: &rusqlite::Row) -> RusqliteResult<Attachment> {
+        let kind_str: String = row.get(3)?;
+        let created_at_str: Option<String> = row.get(8)?;
+        let created_at = created_at_str.and_then(|s| {
+            NaiveDateTime::parse_from_str(&s, "%Y-%m-%d %H:%M:%S")
+                .ok()
+                .map(|dt| DateTime::<Utc>::from_naive_utc_and_offset(dt, Utc))
+        });
+
+        Ok(Attachment {
+            id: Some(row.get(0)?),
+            owner_type: row.get(1)?,
+            owner_id: row.get(2)?,
+            kind: AttachmentKind::parse(&kind_str).unwrap_or(AttachmentKind::Supplementary),
+            original_name: row.get(4)?,
+            file_name: row.get(5)?,
+            mime_type: row.get(6)?,
+            size_bytes: row.get(7)?,
+            created_at,
+        })
+    }
+
+    const SELECT_FIELDS: &'static str =
+        "id, owner_type, owner_id, kind, original_name, file_name, mime_type, size_bytes, created_at";
+
+    pub fn save_attachment(&self, attachment: &Attachment) -> Result<i64, SubmissionError> {
+        self.conn
+            .execute(
+                "INSERT INTO attachments (owner_type, owner_id, kind, original_name, file_name, mime_type, size_bytes)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    attachment.owner_type,
+                    attachment.owner_id,
+                    attachment.kind.as_str(),
+                    attachment.original_name,
+                    attachment.file_name,
+                    attachment.mime_type,
+                    attachment.size_bytes,
+                ],
+            )
+            .map(|_| self.conn.last_insert_rowid())
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))
+    }
+
+    // Stores attachments collected from a multipart form against their owning record
+    pub fn save_attachments(
+        &self,
+        owner_type: &str,
+        owner_id: i32,
+        attachments: Vec<Attachment>,
+    ) -> Result<(), SubmissionError> {
+        for mut attachment in attachments {
+            attachment.owner_type = owner_type.to_string();
+            attachment.owner_id = owner_id;
+            self.save_attachment(&attachment)?;
+        }
+        Ok(())
+    }
+
+    pub fn get_attachments(
+        &self,
+        owner_type: &str,
+        owner_id: i32,
+    ) -> Result<Vec<Attachment>, SubmissionError> {
+        let query = format!(
+            "SELECT {} FROM attachments WHERE owner_type = ?1 AND owner_id = ?2 ORDER BY kind, id",
+            Self::SELECT_FIELDS
+        );
+        let mut stmt = self
+            .conn
+            .prepare(&query)
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+
+        let attachment_iter = stmt
+            .query_map(params![owner_type, owner_id], Self::map_row_to_attachment)
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+
+        attachment_iter
+            .collect::<Result<Vec<Attachment>, _>>()
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))
+    }
+
+    // Looks up an attachment, checking it belongs to the given owner
+    pub fn get_attachment(
+        &self,
+        owner_type: &str,
+        owner_id: i32,
+        id: i32,
+    ) -> Result<Attachment, SubmissionError> {
+        let query = format!(
+            "SELECT {} FROM attachments WHERE id = ?1 AND owner_type = ?2 AND owner_id = ?3",
+            Self::SELECT_FIELDS
+        );
+        self.conn
+            .query_row(
+                &query,
+                params![id, owner_type, owner_id],
+                Self::map_row_to_attachment,
+            )
+            .map_err(|e| match e {
+                rusqlite::Error::QueryReturnedNoRows => {
+                    SubmissionError::NotFound(format!("Attachment with ID {} not found", id))
+                }
+                _ => SubmissionError::DatabaseError(e.to_string()),
+            })
+    }
+}
+
+// Deletes a record's attachment rows and returns the paths of their files, for the caller
+// to remove once its transaction has committed. Takes the caller's connection so the rows
+// go in the same transaction as the record itself, as in a trash purge.
+pub(crate) fn delete_attachment_records(
+    conn: &Connection,
+    owner_type: &str,
+    owner_id: i32,
+) -> Result<Vec<String>, SubmissionError> {
+    let query = format!(
+        "SELECT {} FROM attachments WHERE owner_type = ?1 AND owner_id = ?2",
+        AttachmentRepository::SELECT_FIELDS
+    );
+    let mut stmt = conn
+        .prepare(&query)
+        .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let attachments = stmt
+        .query_map(
+            params![owner_type, owner_id],
+            AttachmentRepository::map_row_to_attachment,
+        )
+        .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?
+        .collect::<Result<Vec<Attachment>, _>>()
+        .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+
+    conn.execute(
+        "DELETE FROM attachments WHERE owner_type = ?1 AND owner_id = ?2",
+        params![owner_type, owner_id],
+    )
+    .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    Ok(attachments
+        .iter()
+        .filter(|attachment| {
+            let bare = is_bare_file_name(&attachment.file_name);
+            if !bare {
+                warn!(
+                    "Not deleting {:?} for {} {}: not a bare file name",
+                    attachment.file_name, owner_type, owner_id
+                );
+            }
+            bare
+        })
+        .map(Attachment::file_path)
+        .collect())
+}
@@ -0,0 +1,169 @@
+use crate::{
+    errors::SubmissionError,
+    models::audit::{AuditEntry, AuditFilter},
+};
+use chrono::{DateTime, Utc};
+use rusqlite::{params, params_from_iter, Connection, Result as RusqliteResult};
+
+pub struct AuditRepository {
+    conn: Connection,
+}
+
+// One action to append to the log
+pub struct NewAuditEntry<'a> {
+    pub actor_id: Option<i32>,
+    pub actor_email: &'a str,
+    pub action: &'a str,
+    pub target_type: &'a str,
+    pub target_id: String,
+    pub changes: Option<String>,
+    pub ip_address: &'a str,
+}
+
+const SELECT_FIELDS: &str =
+    "id, actor_id, actor_email, action, target_type, target_id, changes, ip_address, created_at";
+
+impl AuditRepository {
+    pub fn new(conn: Connection) -> Self {
+        Self { conn }
+    }
+
+    fn map_row_to_entry(row: &rusqlite::Row) -> RusqliteResult<AuditEntry> {
+        let created_at_ts: i64 = row.get(8)?;
+        Ok(AuditEntry {
+            id: row.get(0)?,
+            actor_id: row.get(1)?,
+            actor_email: row.get(2)?,
+            action: row.get(3)?,
+            target_type: row.get(4)?,
+            target_id: row.get(5)?,
+            changes: row.get(6)?,
+            ip_address: row.get(7)?,
+            created_at: DateTime::<Utc>::from_timestamp(created_at_ts, 0).unwrap_or_default(),
+        })
+    }
+
+    pub fn record(&self, entry: &NewAuditEntry) -> Result<(), SubmissionError> {
+        self.conn
+            .execute(
+                "INSERT INTO audit_log (actor_id, actor_email, action, target_type, target_id,
+                     changes, ip_address, created_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                params![
+                    entry.actor_id,
+                    entry.actor_email,
+                    entry.action,
+                    entry.target_type,
+                    entry.target_id,
+                    entry.changes,
+                    entry.ip_address,
+                    Utc::now().timestamp()
+                ],
+            )
+            .map(|_| ())
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))
+    }
+
+    // WHERE clause and parameters for the filters that are set
+    fn filter_clause(filter: &AuditFilter) -> (String, Vec<String>) {
+        let mut conditions = Vec::new();
+        let mut values = Vec::new();
+
+        if let Some(actor) = filter.actor() {
+            values.push(format!("%{}%", actor));
+            conditions.push(format!("actor_email LIKE ?{}", values.len()));
+        }
+        if let Some(action) = filter.action() {
+            values.push(action.to_string());
+            conditions.push(format!("action = ?{}", values.len()));
+        }
+        if let Some(target_type) = filter.target_type() {
+            values.push(target_type.to_string());
+            conditions.push(format!("target_type = ?{}", values.len()));
+        }
+        if let Some(target_id) = filter.target_id() {
+            values.push(target_id.to_string());
+            conditions.push(format!("target_id = ?{}", values.len()));
+        }
+        if let Some(from) = filter.from_date() {
+            values.push(
+                from.and_hms_opt(0, 0, 0)
+                    .unwrap()
+                    .and_utc()
+                    .timestamp()
+                    .to_string(),
+            );
+            conditions.push(format!("created_at >= CAST(?{} AS INTEGER)", values.len()));
+        }
+        if let Some(to) = filter.to_date() {
+            // Inclusive of the whole "to" day
+            values.push(
+                to.succ_opt()
+                    .unwrap_or(to)
+                    .and_hms_opt(0, 0, 0)
+                    .unwrap()
+                    .and_utc()
+                    .timestamp()
+                    .to_string(),
+            );
+            conditions.push(format!("created_at < CAST(?{} AS INTEGER)", values.len()));
+        }
+
+        let clause = if conditions.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", conditions.join(" AND "))
+        };
+        (clause, values)
+    }
+
+    pub fn count_entries(&self, filter: &AuditFilter) -> Result<i64, SubmissionError> {
+        let (clause, values) = Self::filter_clause(filter);
+        self.conn
+            .query_row(
+                &format!("SELECT COUNT(*) FROM audit_log {}", clause),
+                params_from_iter(values.iter()),
+                |row| row.get(0),
+            )
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))
+    }
+
+    // Newest first; `limit` None returns every matching entry (for export)
+    pub fn search_entries(
+        &self,
+        filter: &AuditFilter,
+        limit: Option<i64>,
+        offset: i64,
+    ) -> Result<Vec<AuditEntry>, SubmissionError> {
+        let (clause, values) = Self::filter_clause(filter);
+        let mut stmt = self
+            .conn
+            .prepare(&format!(
+                "SELECT {} FROM audit_log {} ORDER BY id DESC LIMIT {} OFFSET {}",
+                SELECT_FIELDS,
+                clause,
+                limit.unwrap_or(-1),
+                offset
+            ))
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        let entries = stmt
+            .query_map(params_from_iter(values.iter()), Self::map_row_to_entry)
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        Ok(entries)
+    }
+
+    pub fn get_target_types(&self) -> Result<Vec<String>, SubmissionError> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT DISTINCT target_type FROM audit_log ORDER BY target_type")
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        let types = stmt
+            .query_map([], |row| row.get(0))
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        Ok(types)
+    }
+}
@@ -0,0 +1,106 @@
+use crate::{
+    errors::SubmissionError,
+    models::auth_token::TokenPurpose,
+    utils::security::{generate_token, sign_token},
+};
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension};
+
+// Only the signed digest of each token is stored, so a leaked database can't be
+// used to reset passwords
+pub struct AuthTokenRepository {
+    conn: Connection,
+}
+
+impl AuthTokenRepository {
+    pub fn new(conn: Connection) -> Self {
+        Self { conn }
+    }
+
+    // Issues a new token, replacing any unused token with the same purpose for the account
+    pub fn issue_token(
+        &self,
+        admin_id: i32,
+        purpose: TokenPurpose,
+    ) -> Result<String, SubmissionError> {
+        self.revoke_tokens(admin_id, purpose)?;
+
+        let token = generate_token();
+        let expires_at = Utc::now() + purpose.lifetime();
+        self.conn
+            .execute(
+                "INSERT INTO auth_tokens (token_hash, admin_id, purpose, expires_at)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![
+                    sign_token(purpose, &token),
+                    admin_id,
+                    purpose.as_str(),
+                    expires_at.timestamp(),
+                ],
+            )
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        Ok(token)
+    }
+
+    // Returns the account a valid token belongs to without using it up
+    pub fn find_token_owner(
+        &self,
+        token: &str,
+        purpose: TokenPurpose,
+    ) -> Result<Option<i32>, SubmissionError> {
+        self.conn
+            .query_row(
+                "SELECT admin_id FROM auth_tokens
+                 WHERE token_hash = ?1 AND purpose = ?2 AND used_at IS NULL AND expires_at > ?3",
+                params![
+                    sign_token(purpose, token),
+                    purpose.as_str(),
+                    Utc::now().timestamp()
+                ],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))
+    }
+
+    // Marks a token used and returns its account; fails if it was already used or has expired.
+    // A single conditional UPDATE, so two requests can't both use the same token.
+    pub fn consume_token(
+        &self,
+        token: &str,
+        purpose: TokenPurpose,
+    ) -> Result<i32, SubmissionError> {
+        self.conn
+            .query_row(
+                "UPDATE auth_tokens SET used_at = CURRENT_TIMESTAMP
+                 WHERE token_hash = ?1 AND purpose = ?2 AND used_at IS NULL AND expires_at > ?3
+                 RETURNING admin_id",
+                params![
+                    sign_token(purpose, token),
+                    purpose.as_str(),
+                    Utc::now().timestamp()
+                ],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?
+            .ok_or_else(|| {
+                SubmissionError::ValidationError("This link is invalid or has expired".to_string())
+            })
+    }
+
+    pub fn revoke_tokens(
+        &self,
+        admin_id: i32,
+        purpose: TokenPurpose,
+    ) -> Result<(), SubmissionError> {
+        self.conn
+            .execute(
+                "UPDATE auth_tokens SET used_at = CURRENT_TIMESTAMP
+                 WHERE admin_id = ?1 AND purpose = ?2 AND used_at IS NULL",
+                params![admin_id, purpose.as_str()],
+            )
+            .map(|_| ())
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))
+    }
+}
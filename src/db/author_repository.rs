@@ -0,0 +1,242 @@
+use crate::{
+    errors::SubmissionError,
+    models::author::{name_key, parse_authors, Author},
+};
+use rusqlite::{params, Connection, OptionalExtension, Result as RusqliteResult};
+
+pub struct AuthorRepository {
+    conn: Connection,
+}
+
+// Article counts only include articles that are live on the site
+const AUTHOR_FIELDS: &str = "a.id, a.name, a.affiliation, a.orcid,
+    (SELECT COUNT(*) FROM journal_authors ja JOIN journals j ON j.id = ja.journal_id
+     WHERE ja.author_id = a.id AND j.deleted_at IS NULL)";
+
+impl AuthorRepository {
+    pub fn new(conn: Connection) -> Self {
+        Self { conn }
+    }
+
+    fn map_row_to_author(row: &rusqlite::Row) -> RusqliteResult<Author> {
+        Ok(Author {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            affiliation: row.get(2)?,
+            orcid: row.get(3)?,
+            article_count: row.get(4)?,
+        })
+    }
+
+    fn query_authors(
+        &self,
+        query: &str,
+        params: impl rusqlite::Params,
+    ) -> Result<Vec<Author>, SubmissionError> {
+        let mut stmt = self
+            .conn
+            .prepare(query)
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        let author_iter = stmt
+            .query_map(params, Self::map_row_to_author)
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        author_iter
+            .collect::<Result<Vec<Author>, _>>()
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))
+    }
+
+    // Finds the author for a name, following aliases left by merges, or creates it
+    fn resolve_author(&self, name: &str) -> Result<i32, SubmissionError> {
+        let key = name_key(name);
+        let existing: Option<i32> = self
+            .conn
+            .query_row(
+                "SELECT id FROM authors WHERE name_key = ?1
+                 UNION ALL
+                 SELECT author_id FROM author_aliases WHERE name_key = ?1
+                 LIMIT 1",
+                params![key],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        if let Some(id) = existing {
+            return Ok(id);
+        }
+
+        self.conn
+            .execute(
+                "INSERT INTO authors (name, name_key) VALUES (?1, ?2)",
+                params![name, key],
+            )
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        Ok(self.conn.last_insert_rowid() as i32)
+    }
+
+    // Replaces an article's author links with those parsed from its authors field
+    pub fn set_journal_authors(
+        &self,
+        journal_id: i32,
+        authors: &str,
+    ) -> Result<(), SubmissionError> {
+        let tx = self
+            .conn
+            .unchecked_transaction()
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        tx.execute(
+            "DELETE FROM journal_authors WHERE journal_id = ?1",
+            params![journal_id],
+        )
+        .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        for (position, name) in parse_authors(authors).iter().enumerate() {
+            let author_id = self.resolve_author(name)?;
+            tx.execute(
+                "INSERT OR IGNORE INTO journal_authors (journal_id, author_id, position)
+                 VALUES (?1, ?2, ?3)",
+                params![journal_id, author_id, position as i64],
+            )
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        }
+        tx.commit()
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))
+    }
+
+    // Links articles published before author profiles existed; returns how many were indexed
+    pub fn index_unlinked_journals(&self) -> Result<usize, SubmissionError> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT id, authors FROM journals j
+                 WHERE authors != ''
+                   AND NOT EXISTS (SELECT 1 FROM journal_authors ja WHERE ja.journal_id = j.id)",
+            )
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        let journals = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, i32>(0)?, row.get::<_, String>(1)?))
+            })
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+
+        for (journal_id, authors) in &journals {
+            self.set_journal_authors(*journal_id, authors)?;
+        }
+        Ok(journals.len())
+    }
+
+    pub fn get_journal_authors(&self, journal_id: i32) -> Result<Vec<Author>, SubmissionError> {
+        self.query_authors(
+            &format!(
+                "SELECT {} FROM authors a JOIN journal_authors l ON l.author_id = a.id
+                 WHERE l.journal_id = ?1 ORDER BY l.position",
+                AUTHOR_FIELDS
+            ),
+            params![journal_id],
+        )
+    }
+
+    pub fn get_all_authors(&self) -> Result<Vec<Author>, SubmissionError> {
+        self.query_authors(
+            &format!(
+                "SELECT {} FROM authors a ORDER BY a.name COLLATE NOCASE",
+                AUTHOR_FIELDS
+            ),
+            [],
+        )
+    }
+
+    pub fn get_author(&self, id: i32) -> Result<Author, SubmissionError> {
+        self.query_authors(
+            &format!("SELECT {} FROM authors a WHERE a.id = ?1", AUTHOR_FIELDS),
+            params![id],
+        )?
+        .pop()
+        .ok_or_else(|| SubmissionError::NotFound(format!("Author with ID {} not found", id)))
+    }
+
+    // Author a merged duplicate's ID now points to
+    pub fn find_merged_target(&self, id: i32) -> Result<Option<i32>, SubmissionError> {
+        self.conn
+            .query_row(
+                "SELECT author_id FROM author_aliases WHERE former_id = ?1 LIMIT 1",
+                params![id],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))
+    }
+
+    pub fn update_author(
+        &self,
+        id: i32,
+        affiliation: &str,
+        orcid: &str,
+    ) -> Result<(), SubmissionError> {
+        let updated = self
+            .conn
+            .execute(
+                "UPDATE authors SET affiliation = ?1, orcid = ?2 WHERE id = ?3",
+                params![affiliation, orcid, id],
+            )
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        if updated == 0 {
+            return Err(SubmissionError::NotFound(format!(
+                "Author with ID {} not found",
+                id
+            )));
+        }
+        Ok(())
+    }
+
+    // Folds a duplicate into another author: its articles move over, the target picks up
+    // any affiliation or ORCID iD it lacks, and the duplicate's name becomes an alias
+    pub fn merge_authors(
+        &self,
+        source_id: i32,
+        target_id: i32,
+    ) -> Result<(Author, Author), SubmissionError> {
+        if source_id == target_id {
+            return Err(SubmissionError::ValidationError(
+                "Choose two different authors to merge".to_string(),
+            ));
+        }
+        let source = self.get_author(source_id)?;
+        self.get_author(target_id)?;
+
+        let tx = self
+            .conn
+            .unchecked_transaction()
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        for statement in [
+            "INSERT OR IGNORE INTO journal_authors (journal_id, author_id, position)
+             SELECT journal_id, ?2, position FROM journal_authors WHERE author_id = ?1",
+            "UPDATE author_aliases SET author_id = ?2 WHERE author_id = ?1",
+            "UPDATE authors SET
+                affiliation = CASE WHEN affiliation = ''
+                    THEN (SELECT affiliation FROM authors WHERE id = ?1) ELSE affiliation END,
+                orcid = CASE WHEN orcid = ''
+                    THEN (SELECT orcid FROM authors WHERE id = ?1) ELSE orcid END
+             WHERE id = ?2",
+        ] {
+            tx.execute(statement, params![source_id, target_id])
+                .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        }
+        tx.execute(
+            "INSERT OR REPLACE INTO author_aliases (name_key, author_id, former_id)
+             VALUES (?1, ?2, ?3)",
+            params![name_key(&source.name), target_id, source_id],
+        )
+        .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        for statement in [
+            "DELETE FROM journal_authors WHERE author_id = ?1",
+            "DELETE FROM authors WHERE id = ?1",
+        ] {
+            tx.execute(statement, params![source_id])
+                .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        }
+        tx.commit()
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        Ok((source, self.get_author(target_id)?))
+    }
+}
@@ -0,0 +1,156 @@
+use crate::{
+    errors::SubmissionError,
+    models::{
+        draft::{DraftForm, SubmissionDraft},
+        submission::SubmissionDeclarations,
+    },
+};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use rusqlite::{params, Connection, Result as RusqliteResult};
+
+pub struct DraftRepository {
+    conn: Connection,
+}
+
+const SELECT_FIELDS: &str = "id, full_name, email, phone, title, abstract_text, upload_id,
+    updated_at, country, confirms_originality, conflict_of_interest, ethics_approval,
+    funding_statement, agrees_to_license, cover_letter, suggested_reviewers, opposed_reviewers";
+
+impl DraftRepository {
+    pub fn new(conn: Connection) -> Self {
+        Self { conn }
+    }
+
+    fn map_row_to_draft(row: &rusqlite::Row) -> RusqliteResult<SubmissionDraft> {
+        let updated_at_str: Option<String> = row.get(7)?;
+        let updated_at = updated_at_str.and_then(|s| {
+            NaiveDateTime::parse_from_str(&s, "%Y-%m-%d %H:%M:%S")
+                .ok()
+                .map(|dt| DateTime::<Utc>::from_naive_utc_and_offset(dt, Utc))
+        });
+
+        Ok(SubmissionDraft {
+            id: row.get(0)?,
+            full_name: row.get(1)?,
+            email: row.get(2)?,
+            phone: row.get(3)?,
+            title: row.get(4)?,
+            abstract_text: row.get(5)?,
+            country: row.get(8)?,
+            declarations: SubmissionDeclarations {
+                confirms_originality: row.get(9)?,
+                conflict_of_interest: row.get(10)?,
+                ethics_approval: row.get(11)?,
+                funding_statement: row.get(12)?,
+                agrees_to_license: row.get(13)?,
+                cover_letter: row.get(14)?,
+                suggested_reviewers: row.get(15)?,
+                opposed_reviewers: row.get(16)?,
+            },
+            upload_id: row.get(6)?,
+            updated_at,
+        })
+    }
+
+    pub fn create_draft(&self, id: &str, form: &DraftForm) -> Result<(), SubmissionError> {
+        self.conn
+            .execute(
+                "INSERT INTO submission_drafts (id, full_name, email, phone, title, abstract_text, upload_id,
+                    country, confirms_originality, conflict_of_interest, ethics_approval,
+                    funding_statement, agrees_to_license, cover_letter, suggested_reviewers,
+                    opposed_reviewers)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)",
+                params![
+                    id,
+                    form.full_name,
+                    form.email,
+                    form.phone,
+                    form.title,
+                    form.abstract_text,
+                    form.upload_id,
+                    form.country,
+                    form.declarations.confirms_originality,
+                    form.declarations.conflict_of_interest,
+                    form.declarations.ethics_approval,
+                    form.declarations.funding_statement,
+                    form.declarations.agrees_to_license,
+                    form.declarations.cover_letter,
+                    form.declarations.suggested_reviewers,
+                    form.declarations.opposed_reviewers,
+                ],
+            )
+            .map(|_| ())
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))
+    }
+
+    pub fn update_draft(&self, id: &str, form: &DraftForm) -> Result<(), SubmissionError> {
+        let result = self.conn.execute(
+            "UPDATE submission_drafts SET
+                full_name = ?1, email = ?2, phone = ?3, title = ?4, abstract_text = ?5,
+                upload_id = COALESCE(?6, upload_id), country = ?8, confirms_originality = ?9,
+                conflict_of_interest = ?10, ethics_approval = ?11, funding_statement = ?12,
+                agrees_to_license = ?13, cover_letter = ?14, suggested_reviewers = ?15,
+                opposed_reviewers = ?16, updated_at = CURRENT_TIMESTAMP
+             WHERE id = ?7",
+            params![
+                form.full_name,
+                form.email,
+                form.phone,
+                form.title,
+                form.abstract_text,
+                form.upload_id,
+                id,
+                form.country,
+                form.declarations.confirms_originality,
+                form.declarations.conflict_of_interest,
+                form.declarations.ethics_approval,
+                form.declarations.funding_statement,
+                form.declarations.agrees_to_license,
+                form.declarations.cover_letter,
+                form.declarations.suggested_reviewers,
+                form.declarations.opposed_reviewers,
+            ],
+        );
+
+        match result {
+            Ok(0) => Err(SubmissionError::NotFound(format!("Draft {} not found", id))),
+            Ok(_) => Ok(()),
+            Err(e) => Err(SubmissionError::DatabaseError(e.to_string())),
+        }
+    }
+
+    pub fn get_draft(&self, id: &str) -> Result<SubmissionDraft, SubmissionError> {
+        self.conn
+            .query_row(
+                &format!(
+                    "SELECT {} FROM submission_drafts WHERE id = ?1",
+                    SELECT_FIELDS
+                ),
+                params![id],
+                Self::map_row_to_draft,
+            )
+            .map_err(|e| match e {
+                rusqlite::Error::QueryReturnedNoRows => {
+                    SubmissionError::NotFound(format!("Draft {} not found", id))
+                }
+                _ => SubmissionError::DatabaseError(e.to_string()),
+            })
+    }
+
+    pub fn delete_draft(&self, id: &str) -> Result<(), SubmissionError> {
+        self.conn
+            .execute("DELETE FROM submission_drafts WHERE id = ?1", params![id])
+            .map(|_| ())
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))
+    }
+
+    // Removes drafts not saved since `saved_before`, returning how many were removed
+    pub fn delete_expired(&self, saved_before: DateTime<Utc>) -> Result<usize, SubmissionError> {
+        self.conn
+            .execute(
+                "DELETE FROM submission_drafts WHERE updated_at < ?1",
+                params![saved_before.format("%Y-%m-%d %H:%M:%S").to_string()],
+            )
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))
+    }
+}
@@ -0,0 +1,150 @@
+use crate::{
+    db::submission_repository::STATUS_EXPR,
+    errors::SubmissionError,
+    models::{
+        export::{ExportColumn, ExportDataset, ExportRequest},
+        submission::SubmissionStatus,
+    },
+};
+use chrono::{NaiveDate, NaiveTime};
+use rusqlite::{params_from_iter, types::Value, Connection};
+
+pub struct ExportRepository {
+    conn: Connection,
+}
+
+// SQL for one export column
+fn column_sql(dataset: ExportDataset, column: &ExportColumn) -> &'static str {
+    match (dataset, column.key) {
+        (ExportDataset::Submissions, "id") => "s.id",
+        (ExportDataset::Submissions, "submitted") => "s.created_at",
+        (ExportDataset::Submissions, "submitter") => "s.full_name",
+        (ExportDataset::Submissions, "email") => "s.email",
+        (ExportDataset::Submissions, "phone") => "s.phone",
+        (ExportDataset::Submissions, "country") => "s.country",
+        (ExportDataset::Submissions, "title") => "s.title",
+        (ExportDataset::Submissions, "abstract") => "s.abstract_text",
+        (ExportDataset::Submissions, "status") => STATUS_EXPR,
+        (ExportDataset::Submissions, "editor") => {
+            "(SELECT a.email FROM admins a WHERE a.id = s.editor_id)"
+        }
+        (ExportDataset::Submissions, "article_id") => {
+            "(SELECT MIN(j.id) FROM journals j
+              WHERE j.submission_id = s.id AND j.deleted_at IS NULL)"
+        }
+        (ExportDataset::Articles, "id") => "j.id",
+        (ExportDataset::Articles, "title") => "j.title",
+        (ExportDataset::Articles, "authors") => "j.authors",
+        (ExportDataset::Articles, "abstract") => "j.abstract_text",
+        (ExportDataset::Articles, "keywords") => "j.keywords",
+        (ExportDataset::Articles, "volume") => "j.volume_number",
+        (ExportDataset::Articles, "issue") => "j.issue_number",
+        (ExportDataset::Articles, "pages") => "j.pages",
+        (ExportDataset::Articles, "published") => "date(j.publication_date, 'unixepoch')",
+        (ExportDataset::Articles, "uploaded") => "j.created_at",
+        (ExportDataset::Articles, "downloads") => "j.download_count",
+        (ExportDataset::Articles, "submission_id") => "j.submission_id",
+        // Columns come from ExportDataset::columns, so this is never reached
+        _ => "NULL",
+    }
+}
+
+fn value_text(value: Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::Integer(i) => i.to_string(),
+        Value::Real(f) => f.to_string(),
+        Value::Text(text) => text,
+        Value::Blob(_) => String::new(),
+    }
+}
+
+impl ExportRepository {
+    pub fn new(conn: Connection) -> Self {
+        Self { conn }
+    }
+
+    // Calls `write_row` with each matching row in turn, reading them one at a time so
+    // exports of any size use little memory. Trashed records are left out.
+    pub fn for_each_row<F>(
+        &self,
+        request: &ExportRequest,
+        mut write_row: F,
+    ) -> Result<(), SubmissionError>
+    where
+        F: FnMut(&[String]) -> Result<(), SubmissionError>,
+    {
+        let select = request
+            .columns
+            .iter()
+            .map(|column| column_sql(request.dataset, column))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let mut conditions = Vec::new();
+        let mut values: Vec<Value> = Vec::new();
+        // The range includes the whole of the end day
+        let end = request.to.and_then(|to| to.succ_opt());
+        let (table, alias, date_column) = match request.dataset {
+            ExportDataset::Submissions => ("submissions", "s", "s.created_at"),
+            ExportDataset::Articles => ("journals", "j", "j.publication_date"),
+        };
+        let bound = |date: NaiveDate| -> Value {
+            match request.dataset {
+                ExportDataset::Submissions => {
+                    Value::Text(date.format("%Y-%m-%d 00:00:00").to_string())
+                }
+                ExportDataset::Articles => {
+                    Value::Integer(date.and_time(NaiveTime::MIN).and_utc().timestamp())
+                }
+            }
+        };
+        conditions.push(format!("{}.deleted_at IS NULL", alias));
+        if let Some(from) = request.from {
+            values.push(bound(from));
+            conditions.push(format!("{} >= ?{}", date_column, values.len()));
+        }
+        if let Some(end) = end {
+            values.push(bound(end));
+            conditions.push(format!("{} < ?{}", date_column, values.len()));
+        }
+
+        let sql = format!(
+            "SELECT {} FROM {} {} WHERE {} ORDER BY {}, {}.id",
+            select,
+            table,
+            alias,
+            conditions.join(" AND "),
+            date_column,
+            alias
+        );
+        let mut stmt = self
+            .conn
+            .prepare(&sql)
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        let mut rows = stmt
+            .query(params_from_iter(values))
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+
+        let mut fields = Vec::with_capacity(request.columns.len());
+        while let Some(row) = rows
+            .next()
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?
+        {
+            fields.clear();
+            for (index, column) in request.columns.iter().enumerate() {
+                let value = value_text(
+                    row.get::<_, Value>(index)
+                        .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?,
+                );
+                fields.push(match column.key {
+                    "status" => SubmissionStatus::parse(&value)
+                        .map(|status| status.label().to_string())
+                        .unwrap_or(value),
+                    _ => value,
+                });
+            }
+            write_row(&fields)?;
+        }
+        Ok(())
+    }
+}
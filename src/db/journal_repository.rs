@@ -1,15 +1,43 @@
+use crate::db::attachment_repository::delete_attachment_records;
+use crate::db::version_repository::insert_version;
 use crate::errors::SubmissionError;
+use crate::models::archive::{
+    ArchiveCategory, ArchiveCursor, ArchiveFacets, ArchiveFilter, ArchivePage, ArchiveSort,
+    CursorKey, FacetValue,
+};
 use crate::models::journals::Journal;
+use crate::utils::is_bare_file_name;
+use crate::utils::similarity::{MinHash, TEXT_SHINGLE_SIZE};
 use chrono::{DateTime, NaiveDateTime, Utc};
-use log::{error, info};
-use rusqlite::{params, Connection, OptionalExtension, Result as RusqliteResult};
-use std::fs; // Import fs for file deletion
-use std::path::Path; // Import Path
+use log::{info, warn};
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension, Result as RusqliteResult};
 
 pub struct JournalRepository {
     conn: Connection,
 }
 
+// Volume and issue of the most recent live article
+const LATEST_ISSUE: &str = "SELECT volume_number, issue_number FROM journals
+    WHERE deleted_at IS NULL ORDER BY volume_number DESC, issue_number DESC LIMIT 1";
+
+const YEAR_EXPR: &str = "CAST(strftime('%Y', publication_date, 'unixepoch') AS INTEGER)";
+
+// Keyword and author facets list only the most common values
+const FACET_LIMIT: i64 = 20;
+
+// Words of an archive search beyond this many are ignored
+const MAX_SEARCH_WORDS: usize = 8;
+
+// The archive facets, for leaving one out of the filter while counting it
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Facet {
+    Volume,
+    Issue,
+    Year,
+    Keyword,
+    Author,
+}
+
 impl JournalRepository {
     pub fn new(conn: Connection) -> Self {
         Self { conn }
@@ -20,13 +48,13 @@ impl JournalRepository {
         let created_at_str: Option<String> = row.get(10)?; // created_at is at index 10
         let pdf_filename: String = row.get(9)?; // pdf_url is at index 9
 
-        let naive_dt = NaiveDateTime::from_timestamp_opt(publication_date_timestamp, 0).unwrap();
-        let publication_date = DateTime::<Utc>::from_utc(naive_dt, Utc);
+        let publication_date =
+            DateTime::<Utc>::from_timestamp(publication_date_timestamp, 0).unwrap();
 
         let created_at = match created_at_str {
             Some(s) => NaiveDateTime::parse_from_str(&s, "%Y-%m-%d %H:%M:%S")
                 .ok()
-                .map(|dt| DateTime::<Utc>::from_utc(dt, Utc)),
+                .map(|dt| DateTime::<Utc>::from_naive_utc_and_offset(dt, Utc)),
             None => None,
         };
 
@@ -94,7 +122,8 @@ impl JournalRepository {
             "UPDATE journals SET
                       title = ?1, authors = ?2, abstract_text = ?3, keywords = ?4,
                       volume_number = ?5, issue_number = ?6, pages = ?7,
-                      publication_date = ?8, pdf_url = ?9
+                      publication_date = ?8, pdf_url = ?9,
+                      abstract_minhash = CASE WHEN abstract_text = ?3 THEN abstract_minhash END
                   WHERE id = ?10",
             params![
                 journal.title,
@@ -133,7 +162,10 @@ impl JournalRepository {
 
     // Updated SELECT queries
     pub fn get_journal_by_id(&self, id: i32) -> Result<Journal, SubmissionError> {
-        let query = format!("SELECT {} FROM journals WHERE id = ?1", Self::SELECT_FIELDS);
+        let query = format!(
+            "SELECT {} FROM journals WHERE id = ?1 AND deleted_at IS NULL",
+            Self::SELECT_FIELDS
+        );
         self.conn
             .query_row(&query, params![id], Self::map_row_to_journal)
             .map_err(|e| match e {
@@ -151,7 +183,7 @@ impl JournalRepository {
         offset: i32,
     ) -> Result<Vec<Journal>, SubmissionError> {
         let query = format!(
-               "SELECT {} FROM journals ORDER BY volume_number DESC, issue_number DESC, publication_date DESC LIMIT ?1 OFFSET ?2",
+               "SELECT {} FROM journals WHERE deleted_at IS NULL ORDER BY volume_number DESC, issue_number DESC, publication_date DESC LIMIT ?1 OFFSET ?2",
                Self::SELECT_FIELDS
            );
         let mut stmt = self
@@ -171,7 +203,7 @@ impl JournalRepository {
     // No longer needs pagination, gets all for grouping
     pub fn get_all_journals_for_archive(&self) -> Result<Vec<Journal>, SubmissionError> {
         let query = format!(
-             "SELECT {} FROM journals ORDER BY volume_number DESC, issue_number DESC, publication_date DESC",
+             "SELECT {} FROM journals WHERE deleted_at IS NULL ORDER BY volume_number DESC, issue_number DESC, publication_date DESC",
              Self::SELECT_FIELDS
          );
         let mut stmt = self
@@ -191,7 +223,7 @@ impl JournalRepository {
     // Gets N most recent publications regardless of volume/issue
     pub fn get_latest_journals(&self, limit: i32) -> Result<Vec<Journal>, SubmissionError> {
         let query = format!(
-            "SELECT {} FROM journals ORDER BY publication_date DESC LIMIT ?1",
+            "SELECT {} FROM journals WHERE deleted_at IS NULL ORDER BY publication_date DESC LIMIT ?1",
             Self::SELECT_FIELDS
         );
         let mut stmt = self
@@ -213,6 +245,7 @@ impl JournalRepository {
         self.conn
             .query_row(
                 "SELECT volume_number, issue_number FROM journals
+                    WHERE deleted_at IS NULL
                     ORDER BY volume_number DESC, issue_number DESC, publication_date DESC
                     LIMIT 1",
                 [],
@@ -231,7 +264,7 @@ impl JournalRepository {
             Some((latest_vol, latest_iss)) => {
                 let query = format!(
                     "SELECT {} FROM journals
-                            WHERE volume_number = ?1 AND issue_number = ?2
+                            WHERE deleted_at IS NULL AND volume_number = ?1 AND issue_number = ?2
                             ORDER BY publication_date DESC LIMIT ?3",
                     Self::SELECT_FIELDS
                 );
@@ -268,7 +301,7 @@ impl JournalRepository {
             Some((latest_vol, latest_iss)) => {
                 let query = format!(
                     "SELECT {} FROM journals
-                     WHERE NOT (volume_number = ?1 AND issue_number = ?2)
+                     WHERE deleted_at IS NULL AND NOT (volume_number = ?1 AND issue_number = ?2)
                      ORDER BY volume_number DESC, issue_number DESC, publication_date DESC
                      LIMIT ?3 OFFSET ?4",
                     Self::SELECT_FIELDS
@@ -296,42 +329,520 @@ impl JournalRepository {
         }
     }
 
-    pub fn delete_journal_by_id(&self, id: i32) -> Result<(), SubmissionError> {
-        let journal = self.get_journal_by_id(id)?; // Fetch details first (incl. filename)
+    // Saves a batch of articles with their first versions, all or none; returns their IDs
+    pub fn import_journals(
+        &self,
+        journals: &[Journal],
+        admin_id: i32,
+    ) -> Result<Vec<i32>, SubmissionError> {
+        let tx = self
+            .conn
+            .unchecked_transaction()
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        let mut ids = Vec::with_capacity(journals.len());
+        for journal in journals {
+            let id = self.save_journal(journal)? as i32;
+            let saved = Journal {
+                id: Some(id),
+                ..journal.clone()
+            };
+            insert_version(&self.conn, &saved, Some(admin_id), "Bulk import")?;
+            ids.push(id);
+        }
+        tx.commit()
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        Ok(ids)
+    }
+
+    // Whether a live article with this title is already in the issue, ignoring case
+    pub fn exists_in_issue(
+        &self,
+        title: &str,
+        volume_number: i32,
+        issue_number: i32,
+    ) -> Result<bool, SubmissionError> {
+        self.conn
+            .query_row(
+                "SELECT EXISTS (SELECT 1 FROM journals
+                 WHERE deleted_at IS NULL AND volume_number = ?1 AND issue_number = ?2
+                   AND title = ?3 COLLATE NOCASE)",
+                params![volume_number, issue_number, title.trim()],
+                |row| row.get(0),
+            )
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))
+    }
+
+    // Records which submission a newly published article came from
+    pub fn link_submission(&self, id: i32, submission_id: i32) -> Result<(), SubmissionError> {
+        self.conn
+            .execute(
+                "UPDATE journals SET submission_id = ?1 WHERE id = ?2",
+                params![submission_id, id],
+            )
+            .map(|_| ())
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))
+    }
+
+    // Stores the text extracted from the article PDF along with its MinHash signature
+    pub fn update_full_text(&self, id: i32, full_text: &str) -> Result<(), SubmissionError> {
+        let minhash = MinHash::from_text(full_text, TEXT_SHINGLE_SIZE).map(|m| m.to_hex());
+        self.conn
+            .execute(
+                "UPDATE journals SET full_text = ?1, minhash = ?2 WHERE id = ?3",
+                params![full_text, minhash, id],
+            )
+            .map(|_| ())
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))
+    }
+
+    pub fn get_full_text(&self, id: i32) -> Result<Option<String>, SubmissionError> {
+        self.conn
+            .query_row(
+                "SELECT full_text FROM journals WHERE id = ?1",
+                params![id],
+                |row| row.get(0),
+            )
+            .optional()
+            .map(Option::flatten)
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))
+    }
 
+    // Moves an article to the trash; it disappears from the site but keeps its files
+    pub fn trash_journal(&self, id: i32, admin_id: i32) -> Result<(), SubmissionError> {
         let rows_affected = self
             .conn
-            .execute("DELETE FROM journals WHERE id = ?1", params![id])
+            .execute(
+                "UPDATE journals SET deleted_at = ?1, deleted_by = ?2
+                 WHERE id = ?3 AND deleted_at IS NULL",
+                params![Utc::now().timestamp(), admin_id, id],
+            )
             .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
 
         if rows_affected == 0 {
             return Err(SubmissionError::NotFound(format!(
-                "Journal with ID {} could not be deleted (already removed?)",
+                "Journal with ID {} not found",
                 id
             )));
         }
-        info!("Successfully deleted journal record with ID: {}", id);
+        info!("Moved journal {} to the trash", id);
+        Ok(())
+    }
 
-        // Construct the full path relative to where the application runs
-        let pdf_path = Path::new("./data/uploads").join(&journal.pdf_url);
+    pub fn restore_journal(&self, id: i32) -> Result<(), SubmissionError> {
+        let rows_affected = self
+            .conn
+            .execute(
+                "UPDATE journals SET deleted_at = NULL, deleted_by = NULL
+                 WHERE id = ?1 AND deleted_at IS NOT NULL",
+                params![id],
+            )
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
 
-        match fs::remove_file(&pdf_path) {
-            Ok(_) => {
-                info!("Successfully deleted PDF file: {:?}", pdf_path);
-                Ok(())
-            }
-            Err(e) => {
-                error!(
-                    "Failed to delete PDF file {:?}: {}. DB record was deleted.",
-                    pdf_path, e
+        if rows_affected == 0 {
+            return Err(SubmissionError::NotFound(format!(
+                "Journal with ID {} is not in the trash",
+                id
+            )));
+        }
+        info!("Restored journal {} from the trash", id);
+        Ok(())
+    }
+
+    // Looks up an article in the trash, for the audit snapshot taken before a purge
+    pub fn get_trashed_journal(&self, id: i32) -> Result<Journal, SubmissionError> {
+        let query = format!(
+            "SELECT {} FROM journals WHERE id = ?1 AND deleted_at IS NOT NULL",
+            Self::SELECT_FIELDS
+        );
+        self.conn
+            .query_row(&query, params![id], Self::map_row_to_journal)
+            .map_err(|e| match e {
+                rusqlite::Error::QueryReturnedNoRows => {
+                    SubmissionError::NotFound(format!("Journal with ID {} is not in the trash", id))
+                }
+                _ => SubmissionError::DatabaseError(e.to_string()),
+            })
+    }
+
+    // Permanently removes a trashed article with its version history, notices, taxonomy
+    // links and attachments, all or none. Returns the PDFs and attachment files it used,
+    // which the caller deletes only after this has succeeded.
+    pub fn purge_journal(&self, id: i32) -> Result<Vec<String>, SubmissionError> {
+        let tx = self
+            .conn
+            .unchecked_transaction()
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        let journal = self.get_trashed_journal(id)?; // Fetch details first (incl. filename)
+
+        // PDFs that only earlier versions still pointed to
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT DISTINCT pdf_url FROM journal_versions
+                 WHERE journal_id = ?1 AND pdf_url != ?2",
+            )
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        let old_files = stmt
+            .query_map(params![id, journal.pdf_url], |row| row.get::<_, String>(0))
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?
+            .collect::<Result<Vec<String>, _>>()
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        // Names that aren't bare file names would point outside the uploads directory
+        let mut files = Vec::new();
+        for file in std::iter::once(&journal.pdf_url).chain(&old_files) {
+            if is_bare_file_name(file) {
+                files.push(format!("data/uploads/{}", file));
+            } else {
+                warn!(
+                    "Not deleting {:?} for journal {}: not a bare file name",
+                    file, id
                 );
-                // Consider this an error, as the file system is inconsistent
-                Err(SubmissionError::StorageError(format!(
-                    "Journal record deleted, but failed to remove file {:?}: {}",
-                    pdf_path, e
-                )))
             }
         }
+
+        self.conn
+            .execute(
+                "DELETE FROM journals WHERE id = ?1 AND deleted_at IS NOT NULL",
+                params![id],
+            )
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        self.conn
+            .execute(
+                "DELETE FROM similarity_matches WHERE matched_type = 'journal' AND matched_id = ?1",
+                params![id],
+            )
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        for table in [
+            "journal_versions",
+            "journal_notices",
+            "journal_keywords",
+            "journal_subjects",
+            "journal_authors",
+            "usage_daily",
+        ] {
+            self.conn
+                .execute(
+                    &format!("DELETE FROM {} WHERE journal_id = ?1", table),
+                    params![id],
+                )
+                .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        }
+        files.extend(delete_attachment_records(&self.conn, "journal", id)?);
+
+        tx.commit()
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        info!("Purged journal record with ID: {}", id);
+        Ok(files)
+    }
+
+    // Live articles linked to a keyword, subject or author, newest first. `link_table` is
+    // one of the link tables and `column` its id column.
+    fn get_journals_linked_by(
+        &self,
+        link_table: &str,
+        column: &str,
+        id: i32,
+    ) -> Result<Vec<Journal>, SubmissionError> {
+        let query = format!(
+            "SELECT {} FROM journals
+             WHERE deleted_at IS NULL
+               AND id IN (SELECT journal_id FROM {} WHERE {} = ?1)
+             ORDER BY publication_date DESC",
+            Self::SELECT_FIELDS,
+            link_table,
+            column
+        );
+        let mut stmt = self
+            .conn
+            .prepare(&query)
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+
+        let journal_iter = stmt
+            .query_map(params![id], Self::map_row_to_journal)
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+
+        journal_iter
+            .collect::<Result<Vec<Journal>, _>>()
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))
+    }
+
+    pub fn get_journals_by_keyword(
+        &self,
+        keyword_id: i32,
+    ) -> Result<Vec<Journal>, SubmissionError> {
+        self.get_journals_linked_by("journal_keywords", "keyword_id", keyword_id)
+    }
+
+    pub fn get_journals_by_subject(
+        &self,
+        subject_id: i32,
+    ) -> Result<Vec<Journal>, SubmissionError> {
+        self.get_journals_linked_by("journal_subjects", "subject_id", subject_id)
+    }
+
+    pub fn get_journals_by_author(&self, author_id: i32) -> Result<Vec<Journal>, SubmissionError> {
+        self.get_journals_linked_by("journal_authors", "author_id", author_id)
+    }
+
+    // Conditions and parameters for the archive filters that are set. `skip` leaves one
+    // facet's own filter out so its alternatives can be counted.
+    fn archive_conditions(
+        filter: &ArchiveFilter,
+        skip: Option<Facet>,
+    ) -> (Vec<String>, Vec<String>) {
+        let mut conditions = vec!["deleted_at IS NULL".to_string()];
+        let mut values = Vec::new();
+
+        match filter.category {
+            ArchiveCategory::All => {}
+            ArchiveCategory::Current => conditions.push(format!(
+                "(volume_number, issue_number) = ({})",
+                LATEST_ISSUE
+            )),
+            ArchiveCategory::Past => conditions.push(format!(
+                "(volume_number, issue_number) != ({})",
+                LATEST_ISSUE
+            )),
+        }
+        if let Some(volume) = filter.volume.filter(|_| skip != Some(Facet::Volume)) {
+            values.push(volume.to_string());
+            conditions.push(format!(
+                "volume_number = CAST(?{} AS INTEGER)",
+                values.len()
+            ));
+        }
+        if let Some(issue) = filter.issue.filter(|_| skip != Some(Facet::Issue)) {
+            values.push(issue.to_string());
+            conditions.push(format!("issue_number = CAST(?{} AS INTEGER)", values.len()));
+        }
+        if let Some(year) = filter.year.filter(|_| skip != Some(Facet::Year)) {
+            values.push(year.to_string());
+            conditions.push(format!(
+                "{} = CAST(?{} AS INTEGER)",
+                YEAR_EXPR,
+                values.len()
+            ));
+        }
+        if let Some(keyword) = filter
+            .keyword
+            .as_ref()
+            .filter(|_| skip != Some(Facet::Keyword))
+        {
+            values.push(keyword.clone());
+            conditions.push(format!(
+                "id IN (SELECT jk.journal_id FROM journal_keywords jk
+                        JOIN keywords k ON k.id = jk.keyword_id WHERE k.slug = ?{})",
+                values.len()
+            ));
+        }
+        if let Some(author) = filter.author.filter(|_| skip != Some(Facet::Author)) {
+            values.push(author.to_string());
+            conditions.push(format!(
+                "id IN (SELECT journal_id FROM journal_authors
+                        WHERE author_id = CAST(?{} AS INTEGER))",
+                values.len()
+            ));
+        }
+        if let Some(text) = &filter.text {
+            for word in text.split_whitespace().take(MAX_SEARCH_WORDS) {
+                let escaped = word
+                    .replace('\\', "\\\\")
+                    .replace('%', "\\%")
+                    .replace('_', "\\_");
+                values.push(format!("%{}%", escaped));
+                let n = values.len();
+                conditions.push(format!(
+                    "(title LIKE ?{n} ESCAPE '\\' OR authors LIKE ?{n} ESCAPE '\\'
+                      OR abstract_text LIKE ?{n} ESCAPE '\\' OR keywords LIKE ?{n} ESCAPE '\\'
+                      OR full_text LIKE ?{n} ESCAPE '\\')"
+                ));
+            }
+        }
+        (conditions, values)
+    }
+
+    pub fn count_archive(&self, filter: &ArchiveFilter) -> Result<i64, SubmissionError> {
+        let (conditions, values) = Self::archive_conditions(filter, None);
+        self.conn
+            .query_row(
+                &format!(
+                    "SELECT COUNT(*) FROM journals WHERE {}",
+                    conditions.join(" AND ")
+                ),
+                params_from_iter(values.iter()),
+                |row| row.get(0),
+            )
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))
+    }
+
+    // One page of the archive in a stable order. Pages follow on from `cursor` (keyset
+    // pagination), so articles published meanwhile don't shift or repeat rows; `offset`
+    // is only for callers still paging by number.
+    pub fn search_archive(
+        &self,
+        filter: &ArchiveFilter,
+        sort: ArchiveSort,
+        cursor: Option<&ArchiveCursor>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<ArchivePage, SubmissionError> {
+        let (mut conditions, mut values) = Self::archive_conditions(filter, None);
+        let (key_expr, direction, comparison) = match sort {
+            ArchiveSort::Newest => ("publication_date", "DESC", "<"),
+            ArchiveSort::Oldest => ("publication_date", "ASC", ">"),
+            ArchiveSort::Title => ("title COLLATE NOCASE", "ASC", ">"),
+            ArchiveSort::MostDownloaded => ("download_count", "DESC", "<"),
+        };
+
+        if let Some(cursor) = cursor {
+            if cursor.sort != sort {
+                return Err(SubmissionError::ValidationError(
+                    "The cursor belongs to a different sort order".to_string(),
+                ));
+            }
+            let key = match &cursor.key {
+                CursorKey::Int(value) => {
+                    values.push(value.to_string());
+                    format!("CAST(?{} AS INTEGER)", values.len())
+                }
+                CursorKey::Text(value) => {
+                    values.push(value.clone());
+                    format!("?{}", values.len())
+                }
+            };
+            values.push(cursor.id.to_string());
+            conditions.push(format!(
+                "({key_expr} {comparison} {key} OR ({key_expr} = {key}
+                   AND id {comparison} CAST(?{id} AS INTEGER)))",
+                id = values.len()
+            ));
+        }
+
+        // One extra row tells whether another page follows
+        let query = format!(
+            "SELECT {}, download_count FROM journals WHERE {}
+             ORDER BY {} {}, id {} LIMIT {} OFFSET {}",
+            Self::SELECT_FIELDS,
+            conditions.join(" AND "),
+            key_expr,
+            direction,
+            direction,
+            limit + 1,
+            if cursor.is_some() { 0 } else { offset }
+        );
+        let mut stmt = self
+            .conn
+            .prepare(&query)
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        let mut rows = stmt
+            .query_map(params_from_iter(values.iter()), |row| {
+                Ok((Self::map_row_to_journal(row)?, row.get::<_, i64>(11)?))
+            })
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?
+            .collect::<Result<Vec<(Journal, i64)>, _>>()
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+
+        let next_cursor = if rows.len() as i64 > limit {
+            rows.truncate(limit as usize);
+            rows.last().and_then(|(journal, downloads)| {
+                Some(
+                    ArchiveCursor {
+                        sort,
+                        key: sort.key_of(journal, *downloads),
+                        id: journal.id?,
+                    }
+                    .encode(),
+                )
+            })
+        } else {
+            None
+        };
+
+        Ok(ArchivePage {
+            journals: rows.into_iter().map(|(journal, _)| journal).collect(),
+            total: self.count_archive(filter)?,
+            next_cursor,
+        })
+    }
+
+    fn query_facet(
+        &self,
+        query: &str,
+        values: &[String],
+        label: impl Fn(&str) -> String,
+    ) -> Result<Vec<FacetValue>, SubmissionError> {
+        let mut stmt = self
+            .conn
+            .prepare(query)
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        let facet_iter = stmt
+            .query_map(params_from_iter(values.iter()), |row| {
+                let value: String = row.get(0)?;
+                let name: Option<String> = row.get(1)?;
+                Ok(FacetValue {
+                    label: name.unwrap_or_else(|| label(&value)),
+                    value,
+                    count: row.get(2)?,
+                })
+            })
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        facet_iter
+            .collect::<Result<Vec<FacetValue>, _>>()
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))
+    }
+
+    pub fn get_archive_facets(
+        &self,
+        filter: &ArchiveFilter,
+    ) -> Result<ArchiveFacets, SubmissionError> {
+        let matching = |facet: Facet| {
+            let (conditions, values) = Self::archive_conditions(filter, Some(facet));
+            (
+                format!("SELECT id FROM journals WHERE {}", conditions.join(" AND ")),
+                values,
+            )
+        };
+        let column_facet = |facet: Facet, expr: &str, label: fn(&str) -> String| {
+            let (ids, values) = matching(facet);
+            self.query_facet(
+                &format!(
+                    "SELECT CAST({expr} AS TEXT), NULL, COUNT(*) FROM journals
+                     WHERE id IN ({ids}) GROUP BY {expr} ORDER BY {expr} DESC"
+                ),
+                &values,
+                label,
+            )
+        };
+
+        let (keyword_ids, keyword_values) = matching(Facet::Keyword);
+        let (author_ids, author_values) = matching(Facet::Author);
+        Ok(ArchiveFacets {
+            volume: column_facet(Facet::Volume, "volume_number", |v| format!("Volume {}", v))?,
+            issue: column_facet(Facet::Issue, "issue_number", |v| format!("Issue {}", v))?,
+            year: column_facet(Facet::Year, YEAR_EXPR, str::to_string)?,
+            keyword: self.query_facet(
+                &format!(
+                    "SELECT k.slug, k.name, COUNT(*) FROM journal_keywords jk
+                     JOIN keywords k ON k.id = jk.keyword_id
+                     WHERE jk.journal_id IN ({}) GROUP BY k.id
+                     ORDER BY 3 DESC, k.name COLLATE NOCASE LIMIT {}",
+                    keyword_ids, FACET_LIMIT
+                ),
+                &keyword_values,
+                str::to_string,
+            )?,
+            author: self.query_facet(
+                &format!(
+                    "SELECT CAST(a.id AS TEXT), a.name, COUNT(*) FROM journal_authors ja
+                     JOIN authors a ON a.id = ja.author_id
+                     WHERE ja.journal_id IN ({}) GROUP BY a.id
+                     ORDER BY 3 DESC, a.name COLLATE NOCASE LIMIT {}",
+                    author_ids, FACET_LIMIT
+                ),
+                &author_values,
+                str::to_string,
+            )?,
+        })
     }
 
     pub fn get_journals_by_volume_issue(
@@ -345,7 +856,7 @@ impl JournalRepository {
             (
                 format!(
                     "SELECT {} FROM journals
-                    WHERE volume_number = ?1 AND issue_number = ?2
+                    WHERE deleted_at IS NULL AND volume_number = ?1 AND issue_number = ?2
                     ORDER BY publication_date DESC
                     LIMIT ?3 OFFSET ?4",
                     Self::SELECT_FIELDS
@@ -356,7 +867,7 @@ impl JournalRepository {
             (
                 format!(
                     "SELECT {} FROM journals
-                    WHERE volume_number = ?1
+                    WHERE deleted_at IS NULL AND volume_number = ?1
                     ORDER BY issue_number DESC, publication_date DESC
                     LIMIT ?2 OFFSET ?3",
                     Self::SELECT_FIELDS
@@ -0,0 +1,95 @@
+use crate::{errors::SubmissionError, models::login_attempt::LoginAttempt};
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, Result as RusqliteResult};
+
+pub struct LoginAttemptRepository {
+    conn: Connection,
+}
+
+impl LoginAttemptRepository {
+    pub fn new(conn: Connection) -> Self {
+        Self { conn }
+    }
+
+    fn map_row_to_attempt(row: &rusqlite::Row) -> RusqliteResult<LoginAttempt> {
+        let created_at_ts: i64 = row.get(5)?;
+        Ok(LoginAttempt {
+            id: row.get(0)?,
+            email: row.get(1)?,
+            ip_address: row.get(2)?,
+            succeeded: row.get(3)?,
+            reason: row.get(4)?,
+            created_at: DateTime::<Utc>::from_timestamp(created_at_ts, 0).unwrap_or_default(),
+        })
+    }
+
+    pub fn record_attempt(
+        &self,
+        email: &str,
+        ip_address: &str,
+        succeeded: bool,
+        reason: Option<&str>,
+    ) -> Result<(), SubmissionError> {
+        self.conn
+            .execute(
+                "INSERT INTO login_attempts (email, ip_address, succeeded, reason, created_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![email, ip_address, succeeded, reason, Utc::now().timestamp()],
+            )
+            .map(|_| ())
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))
+    }
+
+    // Failed attempts from an address since `since`, with the time of the latest one
+    pub fn failures_from_ip(
+        &self,
+        ip_address: &str,
+        since: DateTime<Utc>,
+    ) -> Result<(i64, Option<DateTime<Utc>>), SubmissionError> {
+        self.conn
+            .query_row(
+                "SELECT COUNT(*), MAX(created_at) FROM login_attempts
+                 WHERE ip_address = ?1 AND succeeded = 0 AND created_at >= ?2",
+                params![ip_address, since.timestamp()],
+                |row| {
+                    let last: Option<i64> = row.get(1)?;
+                    Ok((
+                        row.get(0)?,
+                        last.and_then(|ts| DateTime::<Utc>::from_timestamp(ts, 0)),
+                    ))
+                },
+            )
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))
+    }
+
+    // Removes attempts made before `before`, returning how many were removed
+    pub fn delete_older_than(&self, before: DateTime<Utc>) -> Result<usize, SubmissionError> {
+        self.conn
+            .execute(
+                "DELETE FROM login_attempts WHERE created_at < ?1",
+                params![before.timestamp()],
+            )
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))
+    }
+
+    pub fn get_recent_failures(&self, limit: i64) -> Result<Vec<LoginAttempt>, SubmissionError> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT id, email, ip_address, succeeded, reason, created_at
+                 FROM login_attempts
+                 WHERE succeeded = 0
+                 ORDER BY created_at DESC, id DESC
+                 LIMIT ?1",
+            )
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+
+        let attempt_iter = stmt
+            .query_map(params![limit], Self::map_row_to_attempt)
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+
+        attempt_iter
+            .collect::<Result<Vec<LoginAttempt>, _>>()
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))
+    }
+}
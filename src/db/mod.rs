@@ -1,4 +1,22 @@
 pub mod admin_repository;
+pub mod analytics_repository;
+pub mod attachment_repository;
+pub mod audit_repository;
+pub mod auth_token_repository;
+pub mod author_repository;
+pub mod draft_repository;
+pub mod export_repository;
 pub mod journal_repository;
+pub mod login_attempt_repository;
+pub mod notice_repository;
+pub mod review_repository;
 pub mod schema;
+pub mod session_repository;
+pub mod similarity_repository;
 pub mod submission_repository;
+pub mod taxonomy_repository;
+pub mod trash_repository;
+pub mod two_factor_repository;
+pub mod upload_repository;
+pub mod usage_repository;
+pub mod version_repository;
@@ -0,0 +1,158 @@
+use crate::{
+    errors::SubmissionError,
+    models::notice::{JournalNotice, NoticeKind},
+};
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, Result as RusqliteResult};
+use std::collections::{HashMap, HashSet};
+
+// New notice as entered by an editor
+pub struct NewNotice<'a> {
+    pub journal_id: i32,
+    pub kind: NoticeKind,
+    pub title: &'a str,
+    pub body: &'a str,
+    pub created_by: i32,
+}
+
+pub struct NoticeRepository {
+    conn: Connection,
+}
+
+// Notices of trashed articles are hidden along with the article
+const SELECT_FIELDS: &str = "n.id, n.journal_id, n.kind, n.title, n.body, n.published_at, a.email
+    FROM journal_notices n
+    JOIN journals j ON j.id = n.journal_id AND j.deleted_at IS NULL
+    LEFT JOIN admins a ON a.id = n.created_by";
+
+impl NoticeRepository {
+    pub fn new(conn: Connection) -> Self {
+        Self { conn }
+    }
+
+    fn map_row_to_notice(row: &rusqlite::Row) -> RusqliteResult<JournalNotice> {
+        let kind: String = row.get(2)?;
+        Ok(JournalNotice {
+            id: row.get(0)?,
+            journal_id: row.get(1)?,
+            kind: NoticeKind::parse(&kind).unwrap_or(NoticeKind::Correction),
+            title: row.get(3)?,
+            body: row.get(4)?,
+            published_at: DateTime::<Utc>::from_timestamp(row.get(5)?, 0).unwrap_or_default(),
+            created_by: row.get(6)?,
+        })
+    }
+
+    pub fn create_notice(&self, notice: &NewNotice) -> Result<i64, SubmissionError> {
+        self.conn
+            .execute(
+                "INSERT INTO journal_notices (journal_id, kind, title, body, published_at, created_by)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    notice.journal_id,
+                    notice.kind.as_str(),
+                    notice.title,
+                    notice.body,
+                    Utc::now().timestamp(),
+                    notice.created_by,
+                ],
+            )
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    pub fn get_notice(&self, id: i32) -> Result<JournalNotice, SubmissionError> {
+        self.conn
+            .query_row(
+                &format!("SELECT {} WHERE n.id = ?1", SELECT_FIELDS),
+                params![id],
+                Self::map_row_to_notice,
+            )
+            .map_err(|e| match e {
+                rusqlite::Error::QueryReturnedNoRows => {
+                    SubmissionError::NotFound(format!("Notice with ID {} not found", id))
+                }
+                _ => SubmissionError::DatabaseError(e.to_string()),
+            })
+    }
+
+    // Notices of one article, newest first
+    pub fn get_notices_for_journal(
+        &self,
+        journal_id: i32,
+    ) -> Result<Vec<JournalNotice>, SubmissionError> {
+        let mut stmt = self
+            .conn
+            .prepare(&format!(
+                "SELECT {} WHERE n.journal_id = ?1 ORDER BY n.published_at DESC, n.id DESC",
+                SELECT_FIELDS
+            ))
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+
+        let notice_iter = stmt
+            .query_map(params![journal_id], Self::map_row_to_notice)
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+
+        notice_iter
+            .collect::<Result<Vec<JournalNotice>, _>>()
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))
+    }
+
+    // Every notice grouped by article, for listings and the API
+    pub fn get_notices_by_journal(
+        &self,
+    ) -> Result<HashMap<i32, Vec<JournalNotice>>, SubmissionError> {
+        let mut stmt = self
+            .conn
+            .prepare(&format!(
+                "SELECT {} ORDER BY n.published_at DESC, n.id DESC",
+                SELECT_FIELDS
+            ))
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+
+        let notice_iter = stmt
+            .query_map([], Self::map_row_to_notice)
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+
+        let mut notices: HashMap<i32, Vec<JournalNotice>> = HashMap::new();
+        for notice in notice_iter {
+            let notice = notice.map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+            notices.entry(notice.journal_id).or_default().push(notice);
+        }
+        Ok(notices)
+    }
+
+    // Articles that have been retracted, to mark them in listings
+    pub fn get_retracted_ids(&self) -> Result<HashSet<i32>, SubmissionError> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT DISTINCT journal_id FROM journal_notices WHERE kind = ?1")
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+
+        let ids = stmt
+            .query_map(params![NoticeKind::Retraction.as_str()], |row| row.get(0))
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+
+        ids.collect::<Result<HashSet<i32>, _>>()
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))
+    }
+
+    // Withdraws a notice issued in error
+    pub fn delete_notice(&self, journal_id: i32, id: i32) -> Result<(), SubmissionError> {
+        let rows_affected = self
+            .conn
+            .execute(
+                "DELETE FROM journal_notices WHERE id = ?1 AND journal_id = ?2",
+                params![id, journal_id],
+            )
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+
+        if rows_affected == 0 {
+            return Err(SubmissionError::NotFound(format!(
+                "Notice with ID {} not found",
+                id
+            )));
+        }
+        Ok(())
+    }
+}
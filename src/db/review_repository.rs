@@ -0,0 +1,205 @@
+use crate::{
+    errors::SubmissionError,
+    models::review::{Decision, ReviewAssignment, SubmissionDecision},
+};
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension, Result as RusqliteResult};
+
+const DECISION_FIELDS: &str = "d.id, d.submission_id, d.decision, d.note, a.email, d.decided_at
+    FROM submission_decisions d
+    LEFT JOIN admins a ON a.id = d.decided_by";
+
+const REVIEW_FIELDS: &str = "r.id, r.submission_id, r.reviewer_id, a.email, r.assigned_at,
+        r.completed_at, r.recommendation
+    FROM submission_reviews r
+    LEFT JOIN admins a ON a.id = r.reviewer_id";
+
+pub struct ReviewRepository {
+    conn: Connection,
+}
+
+fn from_timestamp(value: i64) -> DateTime<Utc> {
+    DateTime::<Utc>::from_timestamp(value, 0).unwrap_or_default()
+}
+
+impl ReviewRepository {
+    pub fn new(conn: Connection) -> Self {
+        Self { conn }
+    }
+
+    fn map_row_to_decision(row: &rusqlite::Row) -> RusqliteResult<SubmissionDecision> {
+        let decision: String = row.get(2)?;
+        Ok(SubmissionDecision {
+            id: row.get(0)?,
+            submission_id: row.get(1)?,
+            decision: Decision::parse(&decision).unwrap_or(Decision::MajorRevision),
+            note: row.get(3)?,
+            decided_by: row.get::<_, Option<String>>(4)?.unwrap_or_default(),
+            decided_at: from_timestamp(row.get(5)?),
+        })
+    }
+
+    fn map_row_to_review(row: &rusqlite::Row) -> RusqliteResult<ReviewAssignment> {
+        let recommendation: String = row.get(6)?;
+        Ok(ReviewAssignment {
+            id: row.get(0)?,
+            submission_id: row.get(1)?,
+            reviewer_id: row.get(2)?,
+            reviewer_email: row.get::<_, Option<String>>(3)?.unwrap_or_default(),
+            assigned_at: from_timestamp(row.get(4)?),
+            completed_at: row.get::<_, Option<i64>>(5)?.map(from_timestamp),
+            recommendation: Decision::parse(&recommendation),
+        })
+    }
+
+    pub fn record_decision(
+        &self,
+        submission_id: i32,
+        decision: Decision,
+        note: &str,
+        decided_by: i32,
+    ) -> Result<i64, SubmissionError> {
+        self.conn
+            .execute(
+                "INSERT INTO submission_decisions (submission_id, decision, note, decided_by, decided_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    submission_id,
+                    decision.as_str(),
+                    note,
+                    decided_by,
+                    Utc::now().timestamp()
+                ],
+            )
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    // The same decision on several submissions, all or none
+    pub fn record_decisions(
+        &self,
+        submission_ids: &[i32],
+        decision: Decision,
+        note: &str,
+        decided_by: i32,
+    ) -> Result<(), SubmissionError> {
+        let tx = self
+            .conn
+            .unchecked_transaction()
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        for submission_id in submission_ids {
+            self.record_decision(*submission_id, decision, note, decided_by)?;
+        }
+        tx.commit()
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))
+    }
+
+    // Decisions on a submission in the order they were made
+    pub fn get_decisions(
+        &self,
+        submission_id: i32,
+    ) -> Result<Vec<SubmissionDecision>, SubmissionError> {
+        let mut stmt = self
+            .conn
+            .prepare(&format!(
+                "SELECT {} WHERE d.submission_id = ?1 ORDER BY d.decided_at, d.id",
+                DECISION_FIELDS
+            ))
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        let decisions = stmt
+            .query_map(params![submission_id], Self::map_row_to_decision)
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        Ok(decisions)
+    }
+
+    pub fn assign_reviewer(
+        &self,
+        submission_id: i32,
+        reviewer_id: i32,
+    ) -> Result<i64, SubmissionError> {
+        let assigned = self
+            .conn
+            .execute(
+                "INSERT OR IGNORE INTO submission_reviews (submission_id, reviewer_id, assigned_at)
+                 VALUES (?1, ?2, ?3)",
+                params![submission_id, reviewer_id, Utc::now().timestamp()],
+            )
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        if assigned == 0 {
+            return Err(SubmissionError::Conflict(
+                "That reviewer is already assigned to this submission".to_string(),
+            ));
+        }
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    pub fn get_review(&self, id: i32) -> Result<ReviewAssignment, SubmissionError> {
+        self.conn
+            .query_row(
+                &format!("SELECT {} WHERE r.id = ?1", REVIEW_FIELDS),
+                params![id],
+                Self::map_row_to_review,
+            )
+            .optional()
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?
+            .ok_or_else(|| SubmissionError::NotFound(format!("Review with ID {} not found", id)))
+    }
+
+    // Reviewers of a submission in the order they were invited
+    pub fn get_reviews(
+        &self,
+        submission_id: i32,
+    ) -> Result<Vec<ReviewAssignment>, SubmissionError> {
+        let mut stmt = self
+            .conn
+            .prepare(&format!(
+                "SELECT {} WHERE r.submission_id = ?1 ORDER BY r.assigned_at, r.id",
+                REVIEW_FIELDS
+            ))
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        let reviews = stmt
+            .query_map(params![submission_id], Self::map_row_to_review)
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        Ok(reviews)
+    }
+
+    pub fn is_assigned(
+        &self,
+        submission_id: i32,
+        reviewer_id: i32,
+    ) -> Result<bool, SubmissionError> {
+        self.conn
+            .query_row(
+                "SELECT EXISTS (SELECT 1 FROM submission_reviews
+                 WHERE submission_id = ?1 AND reviewer_id = ?2)",
+                params![submission_id, reviewer_id],
+                |row| row.get(0),
+            )
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))
+    }
+
+    pub fn complete_review(
+        &self,
+        id: i32,
+        recommendation: Decision,
+    ) -> Result<(), SubmissionError> {
+        let updated = self
+            .conn
+            .execute(
+                "UPDATE submission_reviews SET completed_at = ?1, recommendation = ?2
+                 WHERE id = ?3 AND completed_at IS NULL",
+                params![Utc::now().timestamp(), recommendation.as_str(), id],
+            )
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        if updated == 0 {
+            return Err(SubmissionError::Conflict(
+                "This review has already been completed".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
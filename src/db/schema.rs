@@ -1,7 +1,36 @@
-use rusqlite::Connection;
+use rusqlite::{params, Connection};
+
+use crate::{models::auth_token::TokenPurpose, utils::security::sign_token};
+
+// Adds a column to an existing table when an older database lacks it
+fn ensure_column(
+    conn: &Connection,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> Result<(), rusqlite::Error> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let columns = stmt
+        .query_map([], |row| row.get::<_, String>(1))?
+        .collect::<Result<Vec<String>, _>>()?;
+
+    if !columns.iter().any(|c| c == column) {
+        conn.execute(
+            &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition),
+            [],
+        )?;
+    }
+    Ok(())
+}
+
+// Opens the database without running migrations, for code that runs on every request
+// once `init_db` has brought the schema up to date at startup
+pub fn open_db() -> Result<Connection, rusqlite::Error> {
+    Connection::open("./data/aau_tsms.db")
+}
 
 pub fn init_db() -> Result<Connection, rusqlite::Error> {
-    let conn = Connection::open("./data/aau_tsms.db")?;
+    let conn = open_db()?;
 
     conn.execute(
         "CREATE TABLE IF NOT EXISTS submissions (
@@ -33,6 +62,75 @@ pub fn init_db() -> Result<Connection, rusqlite::Error> {
         )",
         [],
     )?;
+    // Text extracted from the article PDF, used for search
+    ensure_column(&conn, "journals", "full_text", "TEXT")?;
+    // MinHash signatures of the extracted text, used for duplicate detection
+    ensure_column(&conn, "journals", "minhash", "TEXT")?;
+    // and of the abstract, cleared whenever the abstract changes
+    ensure_column(&conn, "journals", "abstract_minhash", "TEXT")?;
+    ensure_column(
+        &conn,
+        "journals",
+        "download_count",
+        "INTEGER NOT NULL DEFAULT 0",
+    )?;
+    ensure_column(&conn, "submissions", "full_text", "TEXT")?;
+    ensure_column(&conn, "submissions", "minhash", "TEXT")?;
+    ensure_column(&conn, "submissions", "abstract_minhash", "TEXT")?;
+    // Author declarations; rows from before these existed read as unconfirmed
+    ensure_column(
+        &conn,
+        "submissions",
+        "confirms_originality",
+        "INTEGER NOT NULL DEFAULT 0",
+    )?;
+    ensure_column(
+        &conn,
+        "submissions",
+        "conflict_of_interest",
+        "TEXT NOT NULL DEFAULT ''",
+    )?;
+    ensure_column(
+        &conn,
+        "submissions",
+        "ethics_approval",
+        "TEXT NOT NULL DEFAULT ''",
+    )?;
+    ensure_column(
+        &conn,
+        "submissions",
+        "funding_statement",
+        "TEXT NOT NULL DEFAULT ''",
+    )?;
+    ensure_column(
+        &conn,
+        "submissions",
+        "agrees_to_license",
+        "INTEGER NOT NULL DEFAULT 0",
+    )?;
+    ensure_column(
+        &conn,
+        "submissions",
+        "cover_letter",
+        "TEXT NOT NULL DEFAULT ''",
+    )?;
+    ensure_column(
+        &conn,
+        "submissions",
+        "suggested_reviewers",
+        "TEXT NOT NULL DEFAULT ''",
+    )?;
+    ensure_column(
+        &conn,
+        "submissions",
+        "opposed_reviewers",
+        "TEXT NOT NULL DEFAULT ''",
+    )?;
+    // Soft delete: unix time the record was moved to the trash and by which admin
+    ensure_column(&conn, "journals", "deleted_at", "INTEGER")?;
+    ensure_column(&conn, "journals", "deleted_by", "INTEGER")?;
+    ensure_column(&conn, "submissions", "deleted_at", "INTEGER")?;
+    ensure_column(&conn, "submissions", "deleted_by", "INTEGER")?;
 
     conn.execute(
         "CREATE TABLE IF NOT EXISTS admins (
@@ -43,6 +141,433 @@ pub fn init_db() -> Result<Connection, rusqlite::Error> {
            )",
         [],
     )?;
+    // Accounts that predate roles were all full administrators
+    ensure_column(
+        &conn,
+        "admins",
+        "role",
+        "TEXT NOT NULL DEFAULT 'super_admin'",
+    )?;
+    ensure_column(&conn, "admins", "is_active", "INTEGER NOT NULL DEFAULT 1")?;
+    // Existing accounts are treated as verified; new ones start unverified
+    ensure_column(
+        &conn,
+        "admins",
+        "email_verified",
+        "INTEGER NOT NULL DEFAULT 1",
+    )?;
+    ensure_column(
+        &conn,
+        "admins",
+        "failed_login_count",
+        "INTEGER NOT NULL DEFAULT 0",
+    )?;
+    ensure_column(&conn, "admins", "locked_until", "INTEGER")?;
+    ensure_column(&conn, "admins", "totp_secret", "TEXT")?;
+    ensure_column(
+        &conn,
+        "admins",
+        "totp_enabled",
+        "INTEGER NOT NULL DEFAULT 0",
+    )?;
+    ensure_column(&conn, "admins", "totp_last_step", "INTEGER")?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS recovery_codes (
+            id         INTEGER PRIMARY KEY,
+            admin_id   INTEGER NOT NULL,
+            code_hash  TEXT NOT NULL,
+            used_at    DATETIME
+        )",
+        [],
+    )?;
+
+    // Server-side session state; the cookie key is only stored as a keyed hash
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS sessions (
+            key_hash     TEXT PRIMARY KEY,
+            state        TEXT NOT NULL,
+            admin_id     INTEGER,
+            login_id     TEXT,
+            ip_address   TEXT,
+            user_agent   TEXT,
+            created_at   INTEGER NOT NULL,
+            last_seen_at INTEGER NOT NULL,
+            expires_at   INTEGER NOT NULL
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_sessions_admin ON sessions (admin_id)",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS login_attempts (
+            id         INTEGER PRIMARY KEY,
+            email      TEXT NOT NULL,
+            ip_address TEXT NOT NULL,
+            succeeded  INTEGER NOT NULL,
+            reason     TEXT,
+            created_at INTEGER NOT NULL
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_login_attempts_ip ON login_attempts (ip_address, created_at)",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS auth_tokens (
+            token_hash TEXT PRIMARY KEY,
+            admin_id   INTEGER NOT NULL,
+            purpose    TEXT NOT NULL,
+            expires_at INTEGER NOT NULL,
+            used_at    DATETIME,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS admin_invites (
+            token       TEXT PRIMARY KEY,
+            email       TEXT NOT NULL,
+            role        TEXT NOT NULL,
+            invited_by  INTEGER NOT NULL,
+            expires_at  INTEGER NOT NULL,
+            accepted_at DATETIME
+        )",
+        [],
+    )?;
+    // Invite tokens were once stored as issued (32 hex characters); keep only their digest
+    let legacy_invites = conn
+        .prepare("SELECT token FROM admin_invites WHERE length(token) = 32")?
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<Result<Vec<String>, _>>()?;
+    for token in legacy_invites {
+        conn.execute(
+            "UPDATE admin_invites SET token = ?1 WHERE token = ?2",
+            params![sign_token(TokenPurpose::AdminInvite, &token), token],
+        )?;
+    }
+
+    // Append-only: the triggers refuse any change to an entry once written
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS audit_log (
+            id          INTEGER PRIMARY KEY,
+            actor_id    INTEGER,
+            actor_email TEXT NOT NULL,
+            action      TEXT NOT NULL,
+            target_type TEXT NOT NULL,
+            target_id   TEXT NOT NULL,
+            changes     TEXT,
+            ip_address  TEXT NOT NULL,
+            created_at  INTEGER NOT NULL
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_audit_log_target ON audit_log (target_type, target_id)",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TRIGGER IF NOT EXISTS audit_log_no_update BEFORE UPDATE ON audit_log
+         BEGIN SELECT RAISE(ABORT, 'audit_log is append-only'); END",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TRIGGER IF NOT EXISTS audit_log_no_delete BEFORE DELETE ON audit_log
+         BEGIN SELECT RAISE(ABORT, 'audit_log is append-only'); END",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS similarity_matches (
+            id             INTEGER PRIMARY KEY,
+            submission_id  INTEGER NOT NULL,
+            matched_type   TEXT NOT NULL,
+            matched_id     INTEGER NOT NULL,
+            matched_title  TEXT NOT NULL,
+            text_score     REAL NOT NULL,
+            abstract_score REAL NOT NULL,
+            flagged        INTEGER NOT NULL DEFAULT 0,
+            created_at     DATETIME DEFAULT CURRENT_TIMESTAMP
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS attachments (
+            id            INTEGER PRIMARY KEY,
+            owner_type    TEXT NOT NULL,
+            owner_id      INTEGER NOT NULL,
+            kind          TEXT NOT NULL,
+            original_name TEXT NOT NULL,
+            file_name     TEXT NOT NULL UNIQUE,
+            mime_type     TEXT NOT NULL,
+            size_bytes    INTEGER NOT NULL,
+            created_at    DATETIME DEFAULT CURRENT_TIMESTAMP
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS submission_drafts (
+            id            TEXT PRIMARY KEY,
+            full_name     TEXT NOT NULL DEFAULT '',
+            email         TEXT NOT NULL DEFAULT '',
+            phone         TEXT NOT NULL DEFAULT '',
+            title         TEXT NOT NULL DEFAULT '',
+            abstract_text TEXT NOT NULL DEFAULT '',
+            upload_id     TEXT,
+            created_at    DATETIME DEFAULT CURRENT_TIMESTAMP,
+            updated_at    DATETIME DEFAULT CURRENT_TIMESTAMP
+        )",
+        [],
+    )?;
+    // Fields added to the submission form after drafts existed
+    for column in [
+        "country",
+        "conflict_of_interest",
+        "ethics_approval",
+        "funding_statement",
+        "cover_letter",
+        "suggested_reviewers",
+        "opposed_reviewers",
+    ] {
+        ensure_column(
+            &conn,
+            "submission_drafts",
+            column,
+            "TEXT NOT NULL DEFAULT ''",
+        )?;
+    }
+    for column in ["confirms_originality", "agrees_to_license"] {
+        ensure_column(
+            &conn,
+            "submission_drafts",
+            column,
+            "INTEGER NOT NULL DEFAULT 0",
+        )?;
+    }
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS resumable_uploads (
+            id            TEXT PRIMARY KEY,
+            upload_length INTEGER NOT NULL,
+            upload_offset INTEGER NOT NULL DEFAULT 0,
+            created_at    DATETIME DEFAULT CURRENT_TIMESTAMP
+        )",
+        [],
+    )?;
+    // When the last chunk arrived, so idle uploads can be expired
+    ensure_column(&conn, "resumable_uploads", "updated_at", "DATETIME")?;
+
+    // Every published state of an article; the latest version mirrors the journals row
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS journal_versions (
+            id               INTEGER PRIMARY KEY,
+            journal_id       INTEGER NOT NULL,
+            version_number   INTEGER NOT NULL,
+            title            TEXT NOT NULL,
+            authors          TEXT NOT NULL,
+            abstract_text    TEXT NOT NULL,
+            keywords         TEXT NOT NULL,
+            volume_number    INTEGER NOT NULL,
+            issue_number     INTEGER NOT NULL,
+            pages            TEXT NOT NULL,
+            publication_date INTEGER NOT NULL,
+            pdf_url          TEXT NOT NULL,
+            change_note      TEXT NOT NULL DEFAULT '',
+            created_by       INTEGER,
+            created_at       INTEGER NOT NULL,
+            UNIQUE (journal_id, version_number)
+        )",
+        [],
+    )?;
+
+    // Errata, corrections and retractions published against an article
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS journal_notices (
+            id           INTEGER PRIMARY KEY,
+            journal_id   INTEGER NOT NULL,
+            kind         TEXT NOT NULL,
+            title        TEXT NOT NULL,
+            body         TEXT NOT NULL,
+            published_at INTEGER NOT NULL,
+            created_by   INTEGER
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_journal_notices_journal ON journal_notices (journal_id)",
+        [],
+    )?;
+
+    // Keyword and subject taxonomy; aliases keep merged synonyms pointing at the keyword
+    // they were merged into
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS keywords (
+            id   INTEGER PRIMARY KEY,
+            name TEXT NOT NULL,
+            slug TEXT NOT NULL UNIQUE
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS keyword_aliases (
+            slug       TEXT PRIMARY KEY,
+            keyword_id INTEGER NOT NULL
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS journal_keywords (
+            journal_id INTEGER NOT NULL,
+            keyword_id INTEGER NOT NULL,
+            position   INTEGER NOT NULL DEFAULT 0,
+            PRIMARY KEY (journal_id, keyword_id)
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_journal_keywords_keyword ON journal_keywords (keyword_id)",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS subjects (
+            id          INTEGER PRIMARY KEY,
+            name        TEXT NOT NULL,
+            slug        TEXT NOT NULL UNIQUE,
+            description TEXT NOT NULL DEFAULT ''
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS journal_subjects (
+            journal_id INTEGER NOT NULL,
+            subject_id INTEGER NOT NULL,
+            PRIMARY KEY (journal_id, subject_id)
+        )",
+        [],
+    )?;
+
+    // Authors parsed from each article's authors field. A merged duplicate leaves an
+    // alias, so its name keeps resolving (and its old profile URL keeps redirecting) to
+    // the author it was merged into
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS authors (
+            id          INTEGER PRIMARY KEY,
+            name        TEXT NOT NULL,
+            name_key    TEXT NOT NULL UNIQUE,
+            affiliation TEXT NOT NULL DEFAULT '',
+            orcid       TEXT NOT NULL DEFAULT ''
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS author_aliases (
+            name_key  TEXT PRIMARY KEY,
+            author_id INTEGER NOT NULL,
+            former_id INTEGER NOT NULL
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS journal_authors (
+            journal_id INTEGER NOT NULL,
+            author_id  INTEGER NOT NULL,
+            position   INTEGER NOT NULL DEFAULT 0,
+            PRIMARY KEY (journal_id, author_id)
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_journal_authors_author ON journal_authors (author_id)",
+        [],
+    )?;
+
+    // Article usage counted by COUNTER rules, one row per article, day and metric.
+    // `usage_clicks` holds the last click of each reader session for double-click
+    // filtering and `usage_sessions` who has already been counted as unique today; both
+    // only ever hold recent rows.
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS usage_daily (
+            journal_id   INTEGER NOT NULL,
+            day          TEXT NOT NULL,
+            metric       TEXT NOT NULL,
+            total        INTEGER NOT NULL DEFAULT 0,
+            unique_total INTEGER NOT NULL DEFAULT 0,
+            PRIMARY KEY (journal_id, day, metric)
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_usage_daily_day ON usage_daily (day)",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS usage_clicks (
+            session_key TEXT NOT NULL,
+            journal_id  INTEGER NOT NULL,
+            metric      TEXT NOT NULL,
+            last_at     INTEGER NOT NULL,
+            PRIMARY KEY (session_key, journal_id, metric)
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_usage_clicks_last_at ON usage_clicks (last_at)",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS usage_sessions (
+            day         TEXT NOT NULL,
+            session_key TEXT NOT NULL,
+            journal_id  INTEGER NOT NULL,
+            metric      TEXT NOT NULL,
+            PRIMARY KEY (day, session_key, journal_id, metric)
+        )",
+        [],
+    )?;
+
+    // Editorial workflow: decisions recorded on submissions and the reviewers invited to
+    // review them, with unix times so turnaround can be measured. A published article
+    // remembers the submission it came from.
+    ensure_column(&conn, "submissions", "country", "TEXT NOT NULL DEFAULT ''")?;
+    ensure_column(&conn, "journals", "submission_id", "INTEGER")?;
+    // Editor handling the submission
+    ensure_column(&conn, "submissions", "editor_id", "INTEGER")?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS submission_decisions (
+            id            INTEGER PRIMARY KEY,
+            submission_id INTEGER NOT NULL,
+            decision      TEXT NOT NULL,
+            note          TEXT NOT NULL DEFAULT '',
+            decided_by    INTEGER,
+            decided_at    INTEGER NOT NULL
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_submission_decisions_submission
+         ON submission_decisions (submission_id)",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS submission_reviews (
+            id             INTEGER PRIMARY KEY,
+            submission_id  INTEGER NOT NULL,
+            reviewer_id    INTEGER NOT NULL,
+            assigned_at    INTEGER NOT NULL,
+            completed_at   INTEGER,
+            recommendation TEXT NOT NULL DEFAULT '',
+            UNIQUE (submission_id, reviewer_id)
+        )",
+        [],
+    )?;
 
     Ok(conn)
 }
@@ -0,0 +1,223 @@
+use crate::{errors::SubmissionError, models::session::ActiveSession};
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension, Result as RusqliteResult};
+use std::collections::HashMap;
+
+// Stored state of one session, looked up by the hash of its cookie key
+pub struct StoredSession {
+    pub state: String,
+    pub created_at: i64,
+    pub expires_at: i64,
+}
+
+// Values mirrored out of the session state so sessions can be listed and revoked per admin
+pub struct SessionOwner {
+    pub admin_id: Option<i32>,
+    pub login_id: Option<String>,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+pub struct SessionRepository {
+    conn: Connection,
+}
+
+const SELECT_FIELDS: &str =
+    "login_id, admin_id, ip_address, user_agent, created_at, last_seen_at, expires_at";
+
+impl SessionRepository {
+    pub fn new(conn: Connection) -> Self {
+        Self { conn }
+    }
+
+    fn map_row_to_session(row: &rusqlite::Row) -> RusqliteResult<ActiveSession> {
+        let timestamp = |ts: i64| DateTime::<Utc>::from_timestamp(ts, 0).unwrap_or_default();
+        Ok(ActiveSession {
+            login_id: row.get(0)?,
+            admin_id: row.get(1)?,
+            ip_address: row.get(2)?,
+            user_agent: row.get(3)?,
+            created_at: timestamp(row.get(4)?),
+            last_seen_at: timestamp(row.get(5)?),
+            expires_at: timestamp(row.get(6)?),
+        })
+    }
+
+    pub fn find_session(&self, key_hash: &str) -> Result<Option<StoredSession>, SubmissionError> {
+        self.conn
+            .query_row(
+                "SELECT state, created_at, expires_at FROM sessions WHERE key_hash = ?1",
+                params![key_hash],
+                |row| {
+                    Ok(StoredSession {
+                        state: row.get(0)?,
+                        created_at: row.get(1)?,
+                        expires_at: row.get(2)?,
+                    })
+                },
+            )
+            .optional()
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))
+    }
+
+    pub fn insert_session(
+        &self,
+        key_hash: &str,
+        state: &str,
+        owner: &SessionOwner,
+        expires_at: i64,
+    ) -> Result<(), SubmissionError> {
+        let now = Utc::now().timestamp();
+        self.conn
+            .execute(
+                "INSERT INTO sessions (key_hash, state, admin_id, login_id, ip_address,
+                     user_agent, created_at, last_seen_at, expires_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?7, ?8)",
+                params![
+                    key_hash,
+                    state,
+                    owner.admin_id,
+                    owner.login_id,
+                    owner.ip_address,
+                    owner.user_agent,
+                    now,
+                    expires_at
+                ],
+            )
+            .map(|_| ())
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))
+    }
+
+    // False when the session no longer exists, e.g. because it was revoked
+    pub fn update_session(
+        &self,
+        key_hash: &str,
+        state: &str,
+        owner: &SessionOwner,
+        expires_at: i64,
+    ) -> Result<bool, SubmissionError> {
+        self.conn
+            .execute(
+                "UPDATE sessions SET state = ?1, admin_id = ?2, login_id = ?3, ip_address = ?4,
+                     user_agent = ?5, last_seen_at = ?6, expires_at = ?7
+                 WHERE key_hash = ?8",
+                params![
+                    state,
+                    owner.admin_id,
+                    owner.login_id,
+                    owner.ip_address,
+                    owner.user_agent,
+                    Utc::now().timestamp(),
+                    expires_at,
+                    key_hash
+                ],
+            )
+            .map(|updated| updated > 0)
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))
+    }
+
+    // Records activity, pushing back the idle expiry
+    pub fn touch_session(&self, key_hash: &str, expires_at: i64) -> Result<(), SubmissionError> {
+        self.conn
+            .execute(
+                "UPDATE sessions SET last_seen_at = ?1, expires_at = ?2 WHERE key_hash = ?3",
+                params![Utc::now().timestamp(), expires_at, key_hash],
+            )
+            .map(|_| ())
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))
+    }
+
+    pub fn delete_session(&self, key_hash: &str) -> Result<(), SubmissionError> {
+        self.conn
+            .execute(
+                "DELETE FROM sessions WHERE key_hash = ?1",
+                params![key_hash],
+            )
+            .map(|_| ())
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))
+    }
+
+    // Removes sessions past their idle expiry or created before `created_before`
+    pub fn delete_expired(&self, created_before: i64) -> Result<usize, SubmissionError> {
+        self.conn
+            .execute(
+                "DELETE FROM sessions WHERE expires_at < ?1 OR created_at < ?2",
+                params![Utc::now().timestamp(), created_before],
+            )
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))
+    }
+
+    pub fn get_admin_sessions(
+        &self,
+        admin_id: i32,
+        created_after: i64,
+    ) -> Result<Vec<ActiveSession>, SubmissionError> {
+        let mut stmt = self
+            .conn
+            .prepare(&format!(
+                "SELECT {} FROM sessions
+                 WHERE admin_id = ?1 AND login_id IS NOT NULL
+                   AND expires_at >= ?2 AND created_at >= ?3
+                 ORDER BY last_seen_at DESC",
+                SELECT_FIELDS
+            ))
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        let sessions = stmt
+            .query_map(
+                params![admin_id, Utc::now().timestamp(), created_after],
+                Self::map_row_to_session,
+            )
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        Ok(sessions)
+    }
+
+    // Number of live sessions per admin, for the users page
+    pub fn count_sessions_by_admin(
+        &self,
+        created_after: i64,
+    ) -> Result<HashMap<i32, i64>, SubmissionError> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT admin_id, COUNT(*) FROM sessions
+                 WHERE admin_id IS NOT NULL AND expires_at >= ?1 AND created_at >= ?2
+                 GROUP BY admin_id",
+            )
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        let counts = stmt
+            .query_map(params![Utc::now().timestamp(), created_after], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?
+            .collect::<Result<HashMap<_, _>, _>>()
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        Ok(counts)
+    }
+
+    pub fn revoke_session(&self, admin_id: i32, login_id: &str) -> Result<bool, SubmissionError> {
+        self.conn
+            .execute(
+                "DELETE FROM sessions WHERE admin_id = ?1 AND login_id = ?2",
+                params![admin_id, login_id],
+            )
+            .map(|deleted| deleted > 0)
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))
+    }
+
+    // Signs the admin out everywhere, optionally keeping the session making the request
+    pub fn revoke_admin_sessions(
+        &self,
+        admin_id: i32,
+        keep_login_id: Option<&str>,
+    ) -> Result<usize, SubmissionError> {
+        self.conn
+            .execute(
+                "DELETE FROM sessions
+                 WHERE admin_id = ?1 AND (?2 IS NULL OR login_id IS NOT ?2)",
+                params![admin_id, keep_login_id],
+            )
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))
+    }
+}
@@ -0,0 +1,256 @@
+use crate::errors::SubmissionError;
+use crate::models::similarity::SimilarityMatch;
+use crate::utils::similarity::{MinHash, ABSTRACT_SHINGLE_SIZE, TEXT_SHINGLE_SIZE};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use log::info;
+use rusqlite::{params, Connection, Result as RusqliteResult};
+
+// Matches below this score are noise and are not stored in the report
+const MIN_REPORTED_SCORE: f64 = 0.2;
+// Keep only the closest matches per submission
+const MAX_REPORTED_MATCHES: usize = 10;
+
+// A submission or published article that a new submission is compared against
+struct Candidate {
+    kind: &'static str,
+    id: i32,
+    title: String,
+    signature: Option<MinHash>,
+    abstract_signature: Option<MinHash>,
+}
+
+pub struct SimilarityRepository {
+    conn: Connection,
+}
+
+impl SimilarityRepository {
+    pub fn new(conn: Connection) -> Self {
+        Self { conn }
+    }
+
+    fn map_row_to_match(row: &rusqlite::Row) -> RusqliteResult<SimilarityMatch> {
+        let created_at_str: Option<String> = row.get(8)?;
+        let created_at = created_at_str.and_then(|s| {
+            NaiveDateTime::parse_from_str(&s, "%Y-%m-%d %H:%M:%S")
+                .ok()
+                .map(|dt| DateTime::<Utc>::from_naive_utc_and_offset(dt, Utc))
+        });
+
+        Ok(SimilarityMatch {
+            id: Some(row.get(0)?),
+            submission_id: row.get(1)?,
+            matched_type: row.get(2)?,
+            matched_id: row.get(3)?,
+            matched_title: row.get(4)?,
+            text_score: row.get(5)?,
+            abstract_score: row.get(6)?,
+            flagged: row.get(7)?,
+            created_at,
+        })
+    }
+
+    // A stored signature, or one computed from `text` and stored in `column` so later
+    // reports don't have to shingle the same text again
+    fn cached_signature(
+        &self,
+        table: &str,
+        column: &str,
+        id: i32,
+        stored: Option<String>,
+        text: Option<String>,
+        shingle_size: usize,
+    ) -> Result<Option<MinHash>, SubmissionError> {
+        if let Some(hex) = stored {
+            return Ok(MinHash::from_hex(&hex));
+        }
+        let signature = text
+            .as_deref()
+            .and_then(|text| MinHash::from_text(text, shingle_size));
+        if let Some(sig) = &signature {
+            self.conn
+                .execute(
+                    &format!("UPDATE {} SET {} = ?1 WHERE id = ?2", table, column),
+                    params![sig.to_hex(), id],
+                )
+                .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        }
+        Ok(signature)
+    }
+
+    // Loads candidates from one table, computing and caching missing signatures
+    fn load_candidates(
+        &self,
+        table: &'static str,
+        kind: &'static str,
+        exclude_id: Option<i32>,
+    ) -> Result<Vec<Candidate>, SubmissionError> {
+        let query = format!(
+            "SELECT id, title, minhash, CASE WHEN minhash IS NULL THEN full_text END,
+                    abstract_minhash, CASE WHEN abstract_minhash IS NULL THEN abstract_text END
+             FROM {} WHERE id != ?1 AND deleted_at IS NULL",
+            table
+        );
+        let mut stmt = self
+            .conn
+            .prepare(&query)
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+
+        let rows = stmt
+            .query_map(params![exclude_id.unwrap_or(-1)], |row| {
+                Ok((
+                    row.get::<_, i32>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                    row.get::<_, Option<String>>(3)?,
+                    row.get::<_, Option<String>>(4)?,
+                    row.get::<_, Option<String>>(5)?,
+                ))
+            })
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+
+        let mut candidates = Vec::with_capacity(rows.len());
+        for (id, title, minhash, full_text, abstract_minhash, abstract_text) in rows {
+            candidates.push(Candidate {
+                kind,
+                id,
+                title,
+                signature: self.cached_signature(
+                    table,
+                    "minhash",
+                    id,
+                    minhash,
+                    full_text,
+                    TEXT_SHINGLE_SIZE,
+                )?,
+                abstract_signature: self.cached_signature(
+                    table,
+                    "abstract_minhash",
+                    id,
+                    abstract_minhash,
+                    abstract_text,
+                    ABSTRACT_SHINGLE_SIZE,
+                )?,
+            });
+        }
+        Ok(candidates)
+    }
+
+    // Compares a submission against every other submission and published article,
+    // replacing any previous report for it
+    pub fn generate_report(
+        &self,
+        submission_id: i32,
+        threshold: f64,
+    ) -> Result<Vec<SimilarityMatch>, SubmissionError> {
+        let (abstract_text, minhash): (String, Option<String>) = self
+            .conn
+            .query_row(
+                "SELECT abstract_text, minhash FROM submissions WHERE id = ?1",
+                params![submission_id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .map_err(|e| match e {
+                rusqlite::Error::QueryReturnedNoRows => SubmissionError::NotFound(format!(
+                    "Submission with ID {} not found",
+                    submission_id
+                )),
+                _ => SubmissionError::DatabaseError(e.to_string()),
+            })?;
+
+        let text_signature = minhash.as_deref().and_then(MinHash::from_hex);
+        let abstract_signature = MinHash::from_text(&abstract_text, ABSTRACT_SHINGLE_SIZE);
+
+        let mut candidates =
+            self.load_candidates("submissions", "submission", Some(submission_id))?;
+        candidates.extend(self.load_candidates("journals", "journal", None)?);
+
+        let mut matches: Vec<SimilarityMatch> = candidates
+            .into_iter()
+            .map(|candidate| {
+                let text_score = match (&text_signature, &candidate.signature) {
+                    (Some(a), Some(b)) => a.similarity(b),
+                    _ => 0.0,
+                };
+                let abstract_score = match (&abstract_signature, &candidate.abstract_signature) {
+                    (Some(a), Some(b)) => a.similarity(b),
+                    _ => 0.0,
+                };
+                SimilarityMatch {
+                    id: None,
+                    submission_id,
+                    matched_type: candidate.kind.to_string(),
+                    matched_id: candidate.id,
+                    matched_title: candidate.title,
+                    text_score,
+                    abstract_score,
+                    flagged: text_score >= threshold || abstract_score >= threshold,
+                    created_at: None,
+                }
+            })
+            .filter(|m| m.score() >= MIN_REPORTED_SCORE)
+            .collect();
+
+        matches.sort_by(|a, b| b.score().total_cmp(&a.score()));
+        matches.truncate(MAX_REPORTED_MATCHES);
+
+        self.conn
+            .execute(
+                "DELETE FROM similarity_matches WHERE submission_id = ?1",
+                params![submission_id],
+            )
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+
+        for m in &matches {
+            self.conn
+                .execute(
+                    "INSERT INTO similarity_matches
+                        (submission_id, matched_type, matched_id, matched_title, text_score, abstract_score, flagged)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                    params![
+                        m.submission_id,
+                        m.matched_type,
+                        m.matched_id,
+                        m.matched_title,
+                        m.text_score,
+                        m.abstract_score,
+                        m.flagged,
+                    ],
+                )
+                .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        }
+
+        info!(
+            "Similarity report for submission {}: {} matches, {} flagged",
+            submission_id,
+            matches.len(),
+            matches.iter().filter(|m| m.flagged).count()
+        );
+        Ok(matches)
+    }
+
+    pub fn get_matches_for_submission(
+        &self,
+        submission_id: i32,
+    ) -> Result<Vec<SimilarityMatch>, SubmissionError> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT id, submission_id, matched_type, matched_id, matched_title,
+                        text_score, abstract_score, flagged, created_at
+                 FROM similarity_matches
+                 WHERE submission_id = ?1
+                 ORDER BY MAX(text_score, abstract_score) DESC",
+            )
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+
+        let match_iter = stmt
+            .query_map(params![submission_id], Self::map_row_to_match)
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+
+        match_iter
+            .collect::<Result<Vec<SimilarityMatch>, _>>()
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))
+    }
+}
@@ -1,6 +1,29 @@
-use crate::{errors::SubmissionError, models::submission::Submission};
+use crate::{
+    db::attachment_repository::delete_attachment_records,
+    errors::SubmissionError,
+    models::submission::{
+        Submission, SubmissionDeclarations, SubmissionFilter, SubmissionListItem, SubmissionSort,
+        SubmissionStatus,
+    },
+    utils::similarity::{MinHash, TEXT_SHINGLE_SIZE},
+};
 use chrono::{DateTime, NaiveDateTime, Utc}; // Add chrono
-use rusqlite::{params, Connection, Result as RusqliteResult}; // Specify RusqliteResult
+use log::info;
+use rusqlite::{params, params_from_iter, Connection, Params, Result as RusqliteResult}; // Specify RusqliteResult
+
+// Status of the submission aliased `s`: published once an article links to it, otherwise
+// its latest decision, otherwise under review once a reviewer is assigned
+pub(crate) const STATUS_EXPR: &str = "(CASE
+    WHEN EXISTS (SELECT 1 FROM journals j WHERE j.submission_id = s.id AND j.deleted_at IS NULL)
+        THEN 'published'
+    ELSE COALESCE(
+        (SELECT CASE d.decision WHEN 'accept' THEN 'accepted' WHEN 'reject' THEN 'rejected'
+                ELSE d.decision END
+         FROM submission_decisions d WHERE d.submission_id = s.id
+         ORDER BY d.decided_at DESC, d.id DESC LIMIT 1),
+        CASE WHEN EXISTS (SELECT 1 FROM submission_reviews r WHERE r.submission_id = s.id)
+            THEN 'under_review' ELSE 'new' END)
+    END)";
 
 pub struct SubmissionRepository {
     conn: Connection,
@@ -18,7 +41,7 @@ impl SubmissionRepository {
         let created_at = created_at_str.and_then(|s| {
             NaiveDateTime::parse_from_str(&s, "%Y-%m-%d %H:%M:%S")
                 .ok()
-                .map(|dt| DateTime::<Utc>::from_utc(dt, Utc))
+                .map(|dt| DateTime::<Utc>::from_naive_utc_and_offset(dt, Utc))
         });
 
         Ok(Submission {
@@ -30,14 +53,32 @@ impl SubmissionRepository {
             abstract_text: row.get(5)?,
             pdf_url: row.get(6)?,
             created_at,
+            declarations: SubmissionDeclarations {
+                confirms_originality: row.get(8)?,
+                conflict_of_interest: row.get(9)?,
+                ethics_approval: row.get(10)?,
+                funding_statement: row.get(11)?,
+                agrees_to_license: row.get(12)?,
+                cover_letter: row.get(13)?,
+                suggested_reviewers: row.get(14)?,
+                opposed_reviewers: row.get(15)?,
+            },
+            country: row.get(16)?,
         })
     }
 
+    const SELECT_FIELDS: &'static str =
+        "id, full_name, email, phone, title, abstract_text, pdf_url, created_at,
+        confirms_originality, conflict_of_interest, ethics_approval, funding_statement,
+        agrees_to_license, cover_letter, suggested_reviewers, opposed_reviewers, country";
+
     // --- save_submission remains the same ---
     pub fn save_submission(&self, submission: &Submission) -> Result<i64, SubmissionError> {
         let result = self.conn.execute(
-            "INSERT INTO submissions (full_name, email, phone, title, abstract_text, pdf_url)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            "INSERT INTO submissions (full_name, email, phone, title, abstract_text, pdf_url,
+                confirms_originality, conflict_of_interest, ethics_approval, funding_statement,
+                agrees_to_license, cover_letter, suggested_reviewers, opposed_reviewers, country)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)",
             params![
                 submission.full_name,
                 submission.email,
@@ -45,6 +86,15 @@ impl SubmissionRepository {
                 submission.title,
                 submission.abstract_text,
                 submission.pdf_url, // Assumes pdf_url in Submission struct is the desired path
+                submission.declarations.confirms_originality,
+                submission.declarations.conflict_of_interest,
+                submission.declarations.ethics_approval,
+                submission.declarations.funding_statement,
+                submission.declarations.agrees_to_license,
+                submission.declarations.cover_letter,
+                submission.declarations.suggested_reviewers,
+                submission.declarations.opposed_reviewers,
+                submission.country,
             ],
         );
 
@@ -54,36 +104,188 @@ impl SubmissionRepository {
         }
     }
 
-    // --- Add get_all_submissions ---
-    pub fn get_all_submissions(&self) -> Result<Vec<Submission>, SubmissionError> {
+    // Stores the text extracted from the manuscript along with its MinHash signature
+    pub fn update_full_text(&self, id: i32, full_text: &str) -> Result<(), SubmissionError> {
+        let minhash = MinHash::from_text(full_text, TEXT_SHINGLE_SIZE).map(|m| m.to_hex());
+        self.conn
+            .execute(
+                "UPDATE submissions SET full_text = ?1, minhash = ?2 WHERE id = ?3",
+                params![full_text, minhash, id],
+            )
+            .map(|_| ())
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))
+    }
+
+    // WHERE clause and parameters for the filters that are set
+    fn filter_clause(filter: &SubmissionFilter) -> (String, Vec<String>) {
+        let mut conditions = vec!["s.deleted_at IS NULL".to_string()];
+        let mut values = Vec::new();
+
+        if let Some(search) = filter.search() {
+            values.push(format!("%{}%", search));
+            let n = values.len();
+            conditions.push(format!(
+                "(s.title LIKE ?{n} OR s.full_name LIKE ?{n} OR s.email LIKE ?{n})"
+            ));
+        }
+        if let Some(status) = filter.status() {
+            values.push(status.as_str().to_string());
+            conditions.push(format!("{} = ?{}", STATUS_EXPR, values.len()));
+        }
+        match filter.editor() {
+            Some("none") => conditions.push("s.editor_id IS NULL".to_string()),
+            Some(editor) => {
+                values.push(editor.to_string());
+                conditions.push(format!("s.editor_id = CAST(?{} AS INTEGER)", values.len()));
+            }
+            None => {}
+        }
+        if let Some(from) = filter.from_date() {
+            values.push(from.format("%Y-%m-%d 00:00:00").to_string());
+            conditions.push(format!("s.created_at >= ?{}", values.len()));
+        }
+        if let Some(to) = filter.to_date() {
+            // Inclusive of the whole "to" day
+            let next = to.succ_opt().unwrap_or(to);
+            values.push(next.format("%Y-%m-%d 00:00:00").to_string());
+            conditions.push(format!("s.created_at < ?{}", values.len()));
+        }
+        if let Some(reviewer_id) = filter.reviewer_id {
+            values.push(reviewer_id.to_string());
+            conditions.push(format!(
+                "s.id IN (SELECT submission_id FROM submission_reviews
+                 WHERE reviewer_id = CAST(?{} AS INTEGER))",
+                values.len()
+            ));
+        }
+
+        (format!("WHERE {}", conditions.join(" AND ")), values)
+    }
+
+    fn order_clause(sort: SubmissionSort) -> String {
+        match sort {
+            SubmissionSort::Newest => "s.created_at DESC, s.id DESC".to_string(),
+            SubmissionSort::Oldest => "s.created_at, s.id".to_string(),
+            SubmissionSort::Title => "s.title COLLATE NOCASE, s.id".to_string(),
+            SubmissionSort::Submitter => "s.full_name COLLATE NOCASE, s.id".to_string(),
+            // In workflow order, newest first within a status
+            SubmissionSort::Status => {
+                let ranks = SubmissionStatus::ALL
+                    .iter()
+                    .enumerate()
+                    .map(|(rank, status)| format!("WHEN '{}' THEN {}", status.as_str(), rank))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                format!(
+                    "CASE {} {} END, s.created_at DESC, s.id DESC",
+                    STATUS_EXPR, ranks
+                )
+            }
+        }
+    }
+
+    pub fn count_submissions(&self, filter: &SubmissionFilter) -> Result<i64, SubmissionError> {
+        let (clause, values) = Self::filter_clause(filter);
+        self.conn
+            .query_row(
+                &format!("SELECT COUNT(*) FROM submissions s {}", clause),
+                params_from_iter(values.iter()),
+                |row| row.get(0),
+            )
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))
+    }
+
+    // One page of the submissions matching the filter; `limit` None returns them all
+    pub fn search_submissions(
+        &self,
+        filter: &SubmissionFilter,
+        limit: Option<i64>,
+        offset: i64,
+    ) -> Result<Vec<SubmissionListItem>, SubmissionError> {
+        let (clause, values) = Self::filter_clause(filter);
+        self.query_list_items(
+            &format!(
+                "{} ORDER BY {} LIMIT {} OFFSET {}",
+                clause,
+                Self::order_clause(filter.sort()),
+                limit.unwrap_or(-1),
+                offset
+            ),
+            params_from_iter(values.iter()),
+        )
+    }
+
+    // The listed submissions among `ids`, newest first
+    pub fn get_list_items(&self, ids: &[i32]) -> Result<Vec<SubmissionListItem>, SubmissionError> {
+        let placeholders = (1..=ids.len())
+            .map(|i| format!("?{}", i))
+            .collect::<Vec<_>>()
+            .join(", ");
+        self.query_list_items(
+            &format!(
+                "WHERE s.deleted_at IS NULL AND s.id IN ({}) ORDER BY {}",
+                placeholders,
+                Self::order_clause(SubmissionSort::Newest)
+            ),
+            params_from_iter(ids.iter()),
+        )
+    }
+
+    fn query_list_items<P: Params>(
+        &self,
+        tail: &str,
+        params: P,
+    ) -> Result<Vec<SubmissionListItem>, SubmissionError> {
         let mut stmt = self
             .conn
-            .prepare(
-                // Added created_at to SELECT if your table has it and you map it
-                "SELECT id, full_name, email, phone, title, abstract_text, pdf_url, created_at
-             FROM submissions ORDER BY created_at DESC",
-            )
+            .prepare(&format!(
+                "SELECT {}, {}, (SELECT e.email FROM admins e WHERE e.id = s.editor_id)
+                 FROM submissions s {}",
+                Self::SELECT_FIELDS,
+                STATUS_EXPR,
+                tail
+            ))
             .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
-
-        let submission_iter = stmt
-            .query_map([], Self::map_row_to_submission) // No params needed here
+        let items = stmt
+            .query_map(params, |row| {
+                let status: String = row.get(17)?;
+                Ok(SubmissionListItem {
+                    submission: Self::map_row_to_submission(row)?,
+                    status: SubmissionStatus::parse(&status).unwrap_or(SubmissionStatus::New),
+                    editor: row.get(18)?,
+                })
+            })
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?
+            .collect::<Result<Vec<_>, _>>()
             .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        Ok(items)
+    }
 
-        let submissions: Result<Vec<Submission>, _> = submission_iter
-            .map(|res| res.map_err(|e| SubmissionError::DatabaseError(e.to_string())))
-            .collect();
-
-        submissions
+    // Makes `editor_id` (or nobody) the handling editor of each submission
+    pub fn set_editor(&self, ids: &[i32], editor_id: Option<i32>) -> Result<(), SubmissionError> {
+        let tx = self
+            .conn
+            .unchecked_transaction()
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        for id in ids {
+            tx.execute(
+                "UPDATE submissions SET editor_id = ?1 WHERE id = ?2 AND deleted_at IS NULL",
+                params![editor_id, id],
+            )
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        }
+        tx.commit()
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))
     }
 
     // --- Add get_submission_by_id (needed for download/details) ---
     pub fn get_submission_by_id(&self, id: i32) -> Result<Submission, SubmissionError> {
         let mut stmt = self
             .conn
-            .prepare(
-                "SELECT id, full_name, email, phone, title, abstract_text, pdf_url, created_at
-              FROM submissions WHERE id = ?1",
-            )
+            .prepare(&format!(
+                "SELECT {} FROM submissions WHERE id = ?1 AND deleted_at IS NULL",
+                Self::SELECT_FIELDS
+            ))
             .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
 
         stmt.query_row(params![id], Self::map_row_to_submission)
@@ -98,12 +300,10 @@ impl SubmissionRepository {
     pub fn get_recent_submissions(&self, limit: i32) -> Result<Vec<Submission>, SubmissionError> {
         let mut stmt = self
             .conn
-            .prepare(
-                "SELECT id, full_name, email, phone, title, abstract_text, pdf_url, created_at
-                     FROM submissions
-                     ORDER BY created_at DESC
-                     LIMIT ?1",
-            )
+            .prepare(&format!(
+                "SELECT {} FROM submissions WHERE deleted_at IS NULL ORDER BY created_at DESC LIMIT ?1",
+                Self::SELECT_FIELDS
+            ))
             .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
 
         let submission_iter = stmt
@@ -116,4 +316,131 @@ impl SubmissionRepository {
 
         submissions
     }
+
+    // Submissions whose latest decision is an acceptance and that have not been published
+    // yet, oldest first
+    pub fn get_accepted_unpublished(&self) -> Result<Vec<Submission>, SubmissionError> {
+        let mut stmt = self
+            .conn
+            .prepare(&format!(
+                "SELECT {} FROM submissions s
+                 WHERE s.deleted_at IS NULL
+                   AND (SELECT d.decision FROM submission_decisions d
+                        WHERE d.submission_id = s.id
+                        ORDER BY d.decided_at DESC, d.id DESC LIMIT 1) = 'accept'
+                   AND NOT EXISTS (SELECT 1 FROM journals j
+                                   WHERE j.submission_id = s.id AND j.deleted_at IS NULL)
+                 ORDER BY s.created_at",
+                Self::SELECT_FIELDS
+            ))
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+
+        let submissions = stmt
+            .query_map([], Self::map_row_to_submission)
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        Ok(submissions)
+    }
+
+    // Moves a submission to the trash; the manuscript stays on disk until it is purged
+    pub fn trash_submission(&self, id: i32, admin_id: i32) -> Result<(), SubmissionError> {
+        let rows_affected = self
+            .conn
+            .execute(
+                "UPDATE submissions SET deleted_at = ?1, deleted_by = ?2
+                 WHERE id = ?3 AND deleted_at IS NULL",
+                params![Utc::now().timestamp(), admin_id, id],
+            )
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+
+        if rows_affected == 0 {
+            return Err(SubmissionError::NotFound(format!(
+                "Submission with ID {} not found",
+                id
+            )));
+        }
+        info!("Moved submission {} to the trash", id);
+        Ok(())
+    }
+
+    pub fn restore_submission(&self, id: i32) -> Result<(), SubmissionError> {
+        let rows_affected = self
+            .conn
+            .execute(
+                "UPDATE submissions SET deleted_at = NULL, deleted_by = NULL
+                 WHERE id = ?1 AND deleted_at IS NOT NULL",
+                params![id],
+            )
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+
+        if rows_affected == 0 {
+            return Err(SubmissionError::NotFound(format!(
+                "Submission with ID {} is not in the trash",
+                id
+            )));
+        }
+        info!("Restored submission {} from the trash", id);
+        Ok(())
+    }
+
+    pub fn get_trashed_submission(&self, id: i32) -> Result<Submission, SubmissionError> {
+        let mut stmt = self
+            .conn
+            .prepare(&format!(
+                "SELECT {} FROM submissions WHERE id = ?1 AND deleted_at IS NOT NULL",
+                Self::SELECT_FIELDS
+            ))
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+
+        stmt.query_row(params![id], Self::map_row_to_submission)
+            .map_err(|e| match e {
+                rusqlite::Error::QueryReturnedNoRows => SubmissionError::NotFound(format!(
+                    "Submission with ID {} is not in the trash",
+                    id
+                )),
+                _ => SubmissionError::DatabaseError(e.to_string()),
+            })
+    }
+
+    // Permanently removes a trashed submission with its similarity report, editorial
+    // history and attachments, all or none. Returns the manuscript and attachment files,
+    // which the caller deletes only after this has succeeded.
+    pub fn purge_submission(&self, id: i32) -> Result<Vec<String>, SubmissionError> {
+        let tx = self
+            .conn
+            .unchecked_transaction()
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        let submission = self.get_trashed_submission(id)?;
+
+        self.conn
+            .execute(
+                "DELETE FROM submissions WHERE id = ?1 AND deleted_at IS NOT NULL",
+                params![id],
+            )
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        self.conn
+            .execute(
+                "DELETE FROM similarity_matches
+                 WHERE submission_id = ?1 OR (matched_type = 'submission' AND matched_id = ?1)",
+                params![id],
+            )
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        for table in ["submission_decisions", "submission_reviews"] {
+            self.conn
+                .execute(
+                    &format!("DELETE FROM {} WHERE submission_id = ?1", table),
+                    params![id],
+                )
+                .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        }
+        // pdf_url already holds the path, e.g. "./data/uploads/uuid.pdf"
+        let mut files = vec![submission.pdf_url];
+        files.extend(delete_attachment_records(&self.conn, "submission", id)?);
+
+        tx.commit()
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        info!("Purged submission record with ID: {}", id);
+        Ok(files)
+    }
 }
@@ -0,0 +1,373 @@
+use crate::{
+    errors::SubmissionError,
+    models::taxonomy::{parse_keywords, slugify, Keyword, Subject},
+};
+use rusqlite::{params, Connection, OptionalExtension, Result as RusqliteResult};
+
+pub struct TaxonomyRepository {
+    conn: Connection,
+}
+
+// Article counts only include articles that are live on the site
+const KEYWORD_FIELDS: &str = "k.id, k.name, k.slug,
+    (SELECT COUNT(*) FROM journal_keywords jk JOIN journals j ON j.id = jk.journal_id
+     WHERE jk.keyword_id = k.id AND j.deleted_at IS NULL)";
+
+const SUBJECT_FIELDS: &str = "s.id, s.name, s.slug, s.description,
+    (SELECT COUNT(*) FROM journal_subjects js JOIN journals j ON j.id = js.journal_id
+     WHERE js.subject_id = s.id AND j.deleted_at IS NULL)";
+
+impl TaxonomyRepository {
+    pub fn new(conn: Connection) -> Self {
+        Self { conn }
+    }
+
+    fn map_row_to_keyword(row: &rusqlite::Row) -> RusqliteResult<Keyword> {
+        Ok(Keyword {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            slug: row.get(2)?,
+            article_count: row.get(3)?,
+        })
+    }
+
+    fn map_row_to_subject(row: &rusqlite::Row) -> RusqliteResult<Subject> {
+        Ok(Subject {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            slug: row.get(2)?,
+            description: row.get(3)?,
+            article_count: row.get(4)?,
+        })
+    }
+
+    fn query_keywords(
+        &self,
+        query: &str,
+        params: impl rusqlite::Params,
+    ) -> Result<Vec<Keyword>, SubmissionError> {
+        let mut stmt = self
+            .conn
+            .prepare(query)
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        let keyword_iter = stmt
+            .query_map(params, Self::map_row_to_keyword)
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        keyword_iter
+            .collect::<Result<Vec<Keyword>, _>>()
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))
+    }
+
+    fn query_subjects(
+        &self,
+        query: &str,
+        params: impl rusqlite::Params,
+    ) -> Result<Vec<Subject>, SubmissionError> {
+        let mut stmt = self
+            .conn
+            .prepare(query)
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        let subject_iter = stmt
+            .query_map(params, Self::map_row_to_subject)
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        subject_iter
+            .collect::<Result<Vec<Subject>, _>>()
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))
+    }
+
+    // Finds the keyword for a slug, following aliases left by merges, or creates it
+    fn resolve_keyword(&self, name: &str) -> Result<i32, SubmissionError> {
+        let slug = slugify(name);
+        let existing: Option<i32> = self
+            .conn
+            .query_row(
+                "SELECT id FROM keywords WHERE slug = ?1
+                 UNION ALL
+                 SELECT keyword_id FROM keyword_aliases WHERE slug = ?1
+                 LIMIT 1",
+                params![slug],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        if let Some(id) = existing {
+            return Ok(id);
+        }
+
+        self.conn
+            .execute(
+                "INSERT INTO keywords (name, slug) VALUES (?1, ?2)",
+                params![name, slug],
+            )
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        Ok(self.conn.last_insert_rowid() as i32)
+    }
+
+    // Replaces an article's keyword links with those parsed from its keywords field
+    pub fn set_journal_keywords(
+        &self,
+        journal_id: i32,
+        keywords: &str,
+    ) -> Result<(), SubmissionError> {
+        let tx = self
+            .conn
+            .unchecked_transaction()
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        tx.execute(
+            "DELETE FROM journal_keywords WHERE journal_id = ?1",
+            params![journal_id],
+        )
+        .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        for (position, name) in parse_keywords(keywords).iter().enumerate() {
+            let keyword_id = self.resolve_keyword(name)?;
+            tx.execute(
+                "INSERT OR IGNORE INTO journal_keywords (journal_id, keyword_id, position)
+                 VALUES (?1, ?2, ?3)",
+                params![journal_id, keyword_id, position as i64],
+            )
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        }
+        tx.commit()
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))
+    }
+
+    // Links articles published before the taxonomy existed; returns how many were indexed
+    pub fn index_unlinked_journals(&self) -> Result<usize, SubmissionError> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT id, keywords FROM journals j
+                 WHERE keywords != ''
+                   AND NOT EXISTS (SELECT 1 FROM journal_keywords jk WHERE jk.journal_id = j.id)",
+            )
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        let journals = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, i32>(0)?, row.get::<_, String>(1)?))
+            })
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+
+        for (journal_id, keywords) in &journals {
+            self.set_journal_keywords(*journal_id, keywords)?;
+        }
+        Ok(journals.len())
+    }
+
+    pub fn get_journal_keywords(&self, journal_id: i32) -> Result<Vec<Keyword>, SubmissionError> {
+        self.query_keywords(
+            &format!(
+                "SELECT {} FROM keywords k JOIN journal_keywords l ON l.keyword_id = k.id
+                 WHERE l.journal_id = ?1 ORDER BY l.position",
+                KEYWORD_FIELDS
+            ),
+            params![journal_id],
+        )
+    }
+
+    pub fn get_all_keywords(&self) -> Result<Vec<Keyword>, SubmissionError> {
+        self.query_keywords(
+            &format!(
+                "SELECT {} FROM keywords k ORDER BY k.name COLLATE NOCASE",
+                KEYWORD_FIELDS
+            ),
+            [],
+        )
+    }
+
+    // The most used keywords, alphabetically, for the keyword cloud
+    pub fn get_keyword_cloud(&self, limit: i64) -> Result<Vec<Keyword>, SubmissionError> {
+        let mut keywords = self.query_keywords(
+            &format!(
+                "SELECT {} FROM keywords k ORDER BY 4 DESC, k.name LIMIT ?1",
+                KEYWORD_FIELDS
+            ),
+            params![limit],
+        )?;
+        keywords.retain(|k| k.article_count > 0);
+        keywords.sort_by_key(|k| k.name.to_lowercase());
+        Ok(keywords)
+    }
+
+    pub fn find_keyword(&self, slug: &str) -> Result<Option<Keyword>, SubmissionError> {
+        Ok(self
+            .query_keywords(
+                &format!(
+                    "SELECT {} FROM keywords k WHERE k.slug = ?1",
+                    KEYWORD_FIELDS
+                ),
+                params![slug],
+            )?
+            .pop())
+    }
+
+    // Slug of the keyword a merged synonym now points to
+    pub fn find_alias_target(&self, slug: &str) -> Result<Option<String>, SubmissionError> {
+        self.conn
+            .query_row(
+                "SELECT k.slug FROM keyword_aliases a JOIN keywords k ON k.id = a.keyword_id
+                 WHERE a.slug = ?1",
+                params![slug],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))
+    }
+
+    fn get_keyword(&self, id: i32) -> Result<Keyword, SubmissionError> {
+        self.query_keywords(
+            &format!("SELECT {} FROM keywords k WHERE k.id = ?1", KEYWORD_FIELDS),
+            params![id],
+        )?
+        .pop()
+        .ok_or_else(|| SubmissionError::NotFound(format!("Keyword with ID {} not found", id)))
+    }
+
+    // Folds a synonym into another keyword: its articles move over and its slug becomes
+    // an alias, so later uses of the synonym resolve to the target
+    pub fn merge_keywords(
+        &self,
+        source_id: i32,
+        target_id: i32,
+    ) -> Result<(Keyword, Keyword), SubmissionError> {
+        if source_id == target_id {
+            return Err(SubmissionError::ValidationError(
+                "Choose two different keywords to merge".to_string(),
+            ));
+        }
+        let source = self.get_keyword(source_id)?;
+        let target = self.get_keyword(target_id)?;
+
+        let tx = self
+            .conn
+            .unchecked_transaction()
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        for statement in [
+            "INSERT OR IGNORE INTO journal_keywords (journal_id, keyword_id, position)
+             SELECT journal_id, ?2, position FROM journal_keywords WHERE keyword_id = ?1",
+            "UPDATE keyword_aliases SET keyword_id = ?2 WHERE keyword_id = ?1",
+        ] {
+            tx.execute(statement, params![source_id, target_id])
+                .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        }
+        tx.execute(
+            "DELETE FROM journal_keywords WHERE keyword_id = ?1",
+            params![source_id],
+        )
+        .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        tx.execute(
+            "INSERT OR REPLACE INTO keyword_aliases (slug, keyword_id) VALUES (?1, ?2)",
+            params![source.slug, target_id],
+        )
+        .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        tx.execute("DELETE FROM keywords WHERE id = ?1", params![source_id])
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        tx.commit()
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        Ok((source, target))
+    }
+
+    pub fn create_subject(&self, name: &str, description: &str) -> Result<i64, SubmissionError> {
+        let slug = slugify(name);
+        if slug.is_empty() {
+            return Err(SubmissionError::ValidationError(
+                "Subject name is required".to_string(),
+            ));
+        }
+        self.conn
+            .execute(
+                "INSERT INTO subjects (name, slug, description) VALUES (?1, ?2, ?3)",
+                params![name, slug, description],
+            )
+            .map_err(|e| {
+                if e.to_string().contains("UNIQUE constraint failed") {
+                    SubmissionError::Conflict(format!("A subject named '{}' already exists", name))
+                } else {
+                    SubmissionError::DatabaseError(e.to_string())
+                }
+            })?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    pub fn delete_subject(&self, id: i32) -> Result<Subject, SubmissionError> {
+        let subject = self
+            .query_subjects(
+                &format!("SELECT {} FROM subjects s WHERE s.id = ?1", SUBJECT_FIELDS),
+                params![id],
+            )?
+            .pop()
+            .ok_or_else(|| {
+                SubmissionError::NotFound(format!("Subject with ID {} not found", id))
+            })?;
+        self.conn
+            .execute(
+                "DELETE FROM journal_subjects WHERE subject_id = ?1",
+                params![id],
+            )
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        self.conn
+            .execute("DELETE FROM subjects WHERE id = ?1", params![id])
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        Ok(subject)
+    }
+
+    pub fn get_all_subjects(&self) -> Result<Vec<Subject>, SubmissionError> {
+        self.query_subjects(
+            &format!(
+                "SELECT {} FROM subjects s ORDER BY s.name COLLATE NOCASE",
+                SUBJECT_FIELDS
+            ),
+            [],
+        )
+    }
+
+    pub fn find_subject(&self, slug: &str) -> Result<Option<Subject>, SubmissionError> {
+        Ok(self
+            .query_subjects(
+                &format!(
+                    "SELECT {} FROM subjects s WHERE s.slug = ?1",
+                    SUBJECT_FIELDS
+                ),
+                params![slug],
+            )?
+            .pop())
+    }
+
+    pub fn get_journal_subjects(&self, journal_id: i32) -> Result<Vec<Subject>, SubmissionError> {
+        self.query_subjects(
+            &format!(
+                "SELECT {} FROM subjects s JOIN journal_subjects l ON l.subject_id = s.id
+                 WHERE l.journal_id = ?1 ORDER BY s.name COLLATE NOCASE",
+                SUBJECT_FIELDS
+            ),
+            params![journal_id],
+        )
+    }
+
+    pub fn set_journal_subjects(
+        &self,
+        journal_id: i32,
+        subject_ids: &[i32],
+    ) -> Result<(), SubmissionError> {
+        let tx = self
+            .conn
+            .unchecked_transaction()
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        tx.execute(
+            "DELETE FROM journal_subjects WHERE journal_id = ?1",
+            params![journal_id],
+        )
+        .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        for subject_id in subject_ids {
+            tx.execute(
+                "INSERT OR IGNORE INTO journal_subjects (journal_id, subject_id)
+                 SELECT ?1, id FROM subjects WHERE id = ?2",
+                params![journal_id, subject_id],
+            )
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        }
+        tx.commit()
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))
+    }
+}
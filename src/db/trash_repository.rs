@@ -0,0 +1,89 @@
+use crate::{
+    errors::SubmissionError,
+    models::trash::{TrashKind, TrashedItem},
+};
+use chrono::{DateTime, Duration, Utc};
+use rusqlite::{params, Connection};
+
+// Trashed articles and submissions, listed together for the trash view and the purge job
+pub struct TrashRepository {
+    conn: Connection,
+}
+
+const TRASHED_ITEMS_QUERY: &str = "
+    SELECT 'journal', t.id, t.title, t.deleted_at, a.email
+    FROM journals t LEFT JOIN admins a ON a.id = t.deleted_by
+    WHERE t.deleted_at IS NOT NULL
+    UNION ALL
+    SELECT 'submission', t.id, t.title, t.deleted_at, a.email
+    FROM submissions t LEFT JOIN admins a ON a.id = t.deleted_by
+    WHERE t.deleted_at IS NOT NULL
+    ORDER BY 4 DESC";
+
+impl TrashRepository {
+    pub fn new(conn: Connection) -> Self {
+        Self { conn }
+    }
+
+    // Everything in the trash, most recently deleted first, with the date each
+    // entry becomes eligible for purging
+    pub fn get_trashed_items(
+        &self,
+        retention: Duration,
+    ) -> Result<Vec<TrashedItem>, SubmissionError> {
+        let mut stmt = self
+            .conn
+            .prepare(TRASHED_ITEMS_QUERY)
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+
+        let rows = stmt
+            .query_map([], |row| {
+                let kind: String = row.get(0)?;
+                let deleted_at =
+                    DateTime::<Utc>::from_timestamp(row.get(3)?, 0).unwrap_or_default();
+                Ok(TrashedItem {
+                    kind: TrashKind::parse(&kind).unwrap_or(TrashKind::Journal),
+                    id: row.get(1)?,
+                    title: row.get(2)?,
+                    deleted_at,
+                    deleted_by: row.get(4)?,
+                    purge_after: deleted_at + retention,
+                })
+            })
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+
+        rows.collect::<Result<Vec<TrashedItem>, _>>()
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))
+    }
+
+    // Entries trashed before the cutoff, which the purge job removes for good
+    pub fn get_expired(
+        &self,
+        deleted_before: DateTime<Utc>,
+    ) -> Result<Vec<(TrashKind, i32)>, SubmissionError> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT 'journal', id FROM journals WHERE deleted_at < ?1
+                 UNION ALL
+                 SELECT 'submission', id FROM submissions WHERE deleted_at < ?1",
+            )
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+
+        let rows = stmt
+            .query_map(params![deleted_before.timestamp()], |row| {
+                let kind: String = row.get(0)?;
+                Ok((kind, row.get::<_, i32>(1)?))
+            })
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+
+        let mut expired = Vec::new();
+        for row in rows {
+            let (kind, id) = row.map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+            if let Some(kind) = TrashKind::parse(&kind) {
+                expired.push((kind, id));
+            }
+        }
+        Ok(expired)
+    }
+}
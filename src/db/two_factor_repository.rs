@@ -0,0 +1,126 @@
+use crate::{errors::SubmissionError, utils::security::hash_recovery_code};
+use rusqlite::{params, Connection};
+
+pub struct TwoFactorRepository {
+    conn: Connection,
+}
+
+impl TwoFactorRepository {
+    pub fn new(conn: Connection) -> Self {
+        Self { conn }
+    }
+
+    // Turns on TOTP for an account and replaces its recovery codes in one step
+    pub fn enable(
+        &self,
+        admin_id: i32,
+        secret: &str,
+        step: i64,
+        recovery_codes: &[String],
+    ) -> Result<(), SubmissionError> {
+        let tx = self
+            .conn
+            .unchecked_transaction()
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        tx.execute(
+            "UPDATE admins SET totp_secret = ?1, totp_enabled = 1, totp_last_step = ?2
+             WHERE id = ?3",
+            params![secret, step, admin_id],
+        )
+        .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        self.insert_recovery_codes(admin_id, recovery_codes)?;
+        tx.commit()
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))
+    }
+
+    pub fn disable(&self, admin_id: i32) -> Result<(), SubmissionError> {
+        let tx = self
+            .conn
+            .unchecked_transaction()
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        tx.execute(
+            "UPDATE admins SET totp_secret = NULL, totp_enabled = 0, totp_last_step = NULL
+             WHERE id = ?1",
+            params![admin_id],
+        )
+        .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        tx.execute(
+            "DELETE FROM recovery_codes WHERE admin_id = ?1",
+            params![admin_id],
+        )
+        .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        tx.commit()
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))
+    }
+
+    // Claims the time step of a verified code; false when another login already used it
+    pub fn record_step(&self, admin_id: i32, step: i64) -> Result<bool, SubmissionError> {
+        self.conn
+            .execute(
+                "UPDATE admins SET totp_last_step = ?2
+                 WHERE id = ?1 AND (totp_last_step IS NULL OR totp_last_step < ?2)",
+                params![admin_id, step],
+            )
+            .map(|updated| updated > 0)
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))
+    }
+
+    // Deletes existing codes and stores hashes of the new ones
+    fn insert_recovery_codes(
+        &self,
+        admin_id: i32,
+        codes: &[String],
+    ) -> Result<(), SubmissionError> {
+        self.conn
+            .execute(
+                "DELETE FROM recovery_codes WHERE admin_id = ?1",
+                params![admin_id],
+            )
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        for code in codes {
+            self.conn
+                .execute(
+                    "INSERT INTO recovery_codes (admin_id, code_hash) VALUES (?1, ?2)",
+                    params![admin_id, hash_recovery_code(code)],
+                )
+                .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        }
+        Ok(())
+    }
+
+    pub fn replace_recovery_codes(
+        &self,
+        admin_id: i32,
+        codes: &[String],
+    ) -> Result<(), SubmissionError> {
+        let tx = self
+            .conn
+            .unchecked_transaction()
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        self.insert_recovery_codes(admin_id, codes)?;
+        tx.commit()
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))
+    }
+
+    // Marks a matching unused code as used; false if there was none
+    pub fn use_recovery_code(&self, admin_id: i32, code: &str) -> Result<bool, SubmissionError> {
+        self.conn
+            .execute(
+                "UPDATE recovery_codes SET used_at = CURRENT_TIMESTAMP
+                 WHERE admin_id = ?1 AND code_hash = ?2 AND used_at IS NULL",
+                params![admin_id, hash_recovery_code(code)],
+            )
+            .map(|updated| updated > 0)
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))
+    }
+
+    pub fn remaining_recovery_codes(&self, admin_id: i32) -> Result<i64, SubmissionError> {
+        self.conn
+            .query_row(
+                "SELECT COUNT(*) FROM recovery_codes WHERE admin_id = ?1 AND used_at IS NULL",
+                params![admin_id],
+                |row| row.get(0),
+            )
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))
+    }
+}
@@ -0,0 +1,158 @@
+use crate::{
+    errors::SubmissionError,
+    models::draft::{ResumableUpload, PARTIAL_DIR},
+};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use log::{info, warn};
+use rusqlite::{params, Connection, Result as RusqliteResult};
+use std::fs;
+use std::io::Read;
+use std::time::SystemTime;
+
+pub struct UploadRepository {
+    conn: Connection,
+}
+
+impl UploadRepository {
+    pub fn new(conn: Connection) -> Self {
+        Self { conn }
+    }
+
+    fn map_row_to_upload(row: &rusqlite::Row) -> RusqliteResult<ResumableUpload> {
+        let created_at_str: Option<String> = row.get(3)?;
+        let created_at = created_at_str.and_then(|s| {
+            NaiveDateTime::parse_from_str(&s, "%Y-%m-%d %H:%M:%S")
+                .ok()
+                .map(|dt| DateTime::<Utc>::from_naive_utc_and_offset(dt, Utc))
+        });
+
+        Ok(ResumableUpload {
+            id: row.get(0)?,
+            upload_length: row.get(1)?,
+            upload_offset: row.get(2)?,
+            created_at,
+        })
+    }
+
+    pub fn create_upload(&self, id: &str, upload_length: i64) -> Result<(), SubmissionError> {
+        self.conn
+            .execute(
+                "INSERT INTO resumable_uploads (id, upload_length, upload_offset) VALUES (?1, ?2, 0)",
+                params![id, upload_length],
+            )
+            .map(|_| ())
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))
+    }
+
+    pub fn get_upload(&self, id: &str) -> Result<ResumableUpload, SubmissionError> {
+        self.conn
+            .query_row(
+                "SELECT id, upload_length, upload_offset, created_at
+                 FROM resumable_uploads WHERE id = ?1",
+                params![id],
+                Self::map_row_to_upload,
+            )
+            .map_err(|e| match e {
+                rusqlite::Error::QueryReturnedNoRows => {
+                    SubmissionError::NotFound(format!("Upload {} not found", id))
+                }
+                _ => SubmissionError::DatabaseError(e.to_string()),
+            })
+    }
+
+    // Moves the offset on from `from` to `to`; false when another request moved it first
+    pub fn update_offset(&self, id: &str, from: i64, to: i64) -> Result<bool, SubmissionError> {
+        self.conn
+            .execute(
+                "UPDATE resumable_uploads SET upload_offset = ?1, updated_at = CURRENT_TIMESTAMP
+                 WHERE id = ?2 AND upload_offset = ?3",
+                params![to, id, from],
+            )
+            .map(|changed| changed == 1)
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))
+    }
+
+    // Checks that an upload is complete and is a PDF, without touching it
+    pub fn check_upload(&self, id: &str) -> Result<ResumableUpload, SubmissionError> {
+        let upload = self.get_upload(id)?;
+        if !upload.is_complete() {
+            return Err(SubmissionError::ValidationError(format!(
+                "Upload {} is incomplete ({} of {} bytes)",
+                id, upload.upload_offset, upload.upload_length
+            )));
+        }
+
+        let partial_path = upload.partial_path();
+        let mut magic = [0u8; 5];
+        fs::File::open(&partial_path)
+            .and_then(|mut f| f.read_exact(&mut magic))
+            .map_err(|e| {
+                SubmissionError::StorageError(format!("Failed to read upload {}: {}", id, e))
+            })?;
+        if &magic != b"%PDF-" {
+            return Err(SubmissionError::ValidationError(
+                "Only PDF files are accepted".to_string(),
+            ));
+        }
+        Ok(upload)
+    }
+
+    // Moves a completed upload into the uploads directory as the manuscript PDF
+    // `file_name`. The upload is gone afterwards, so only call this once the submission
+    // has passed validation.
+    pub fn finalize_upload(&self, id: &str, file_name: &str) -> Result<(), SubmissionError> {
+        let upload = self.check_upload(id)?;
+        fs::rename(
+            upload.partial_path(),
+            format!("./data/uploads/{}", file_name),
+        )
+        .map_err(|e| {
+            SubmissionError::StorageError(format!("Failed to finalize upload {}: {}", id, e))
+        })?;
+        self.delete_upload(id)?;
+
+        info!("Finalized resumable upload {} as {}", id, file_name);
+        Ok(())
+    }
+
+    pub fn delete_upload(&self, id: &str) -> Result<(), SubmissionError> {
+        self.conn
+            .execute("DELETE FROM resumable_uploads WHERE id = ?1", params![id])
+            .map(|_| ())
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))
+    }
+
+    // Removes uploads that have not received a chunk since `idle_since`, and any partial
+    // file that old, including ones left without a row. Returns how many files were removed.
+    pub fn delete_abandoned(&self, idle_since: DateTime<Utc>) -> Result<usize, SubmissionError> {
+        let cutoff = idle_since.format("%Y-%m-%d %H:%M:%S").to_string();
+        self.conn
+            .execute(
+                "DELETE FROM resumable_uploads WHERE COALESCE(updated_at, created_at) < ?1",
+                params![cutoff],
+            )
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+
+        let entries = match fs::read_dir(PARTIAL_DIR) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(SubmissionError::StorageError(e.to_string())),
+        };
+        let idle_since = SystemTime::from(idle_since);
+        let mut files = 0;
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let stale = entry
+                .metadata()
+                .and_then(|m| m.modified())
+                .is_ok_and(|modified| modified < idle_since);
+            if stale && path.extension().is_some_and(|ext| ext == "part") {
+                match fs::remove_file(&path) {
+                    Ok(()) => files += 1,
+                    Err(e) => warn!("Failed to remove {}: {}", path.display(), e),
+                }
+            }
+        }
+        Ok(files)
+    }
+}
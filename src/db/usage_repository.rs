@@ -0,0 +1,264 @@
+use crate::{
+    errors::SubmissionError,
+    models::usage::{ArticleUsage, DailyUsage, MonthlyArticleUsage, UsageMetric, UsageTotals},
+};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use rusqlite::{params, Connection, OptionalExtension, Result as RusqliteResult};
+use std::collections::HashMap;
+
+// Clicks on the same article by the same session this close together count once
+const DOUBLE_CLICK_WINDOW_SECS: i64 = 30;
+
+// Views and downloads summed over a set of usage_daily rows aliased `u`
+const TOTALS_FIELDS: &str = "
+    COALESCE(SUM(CASE WHEN u.metric = 'view' THEN u.total END), 0),
+    COALESCE(SUM(CASE WHEN u.metric = 'view' THEN u.unique_total END), 0),
+    COALESCE(SUM(CASE WHEN u.metric = 'download' THEN u.total END), 0),
+    COALESCE(SUM(CASE WHEN u.metric = 'download' THEN u.unique_total END), 0)";
+
+pub struct UsageRepository {
+    conn: Connection,
+}
+
+fn day_string(day: NaiveDate) -> String {
+    day.format("%Y-%m-%d").to_string()
+}
+
+impl UsageRepository {
+    pub fn new(conn: Connection) -> Self {
+        Self { conn }
+    }
+
+    // Reads the four TOTALS_FIELDS starting at column `start`
+    fn map_totals(row: &rusqlite::Row, start: usize) -> RusqliteResult<UsageTotals> {
+        Ok(UsageTotals {
+            views: row.get(start)?,
+            unique_views: row.get(start + 1)?,
+            downloads: row.get(start + 2)?,
+            unique_downloads: row.get(start + 3)?,
+        })
+    }
+
+    // Counts one view or download unless it repeats the same session's click within the
+    // double-click window. Returns whether it was counted.
+    pub fn record_event(
+        &self,
+        journal_id: i32,
+        metric: UsageMetric,
+        session_key: &str,
+        now: DateTime<Utc>,
+    ) -> Result<bool, SubmissionError> {
+        let day = day_string(now.date_naive());
+        let tx = self
+            .conn
+            .unchecked_transaction()
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+
+        // Forget clicks outside the window and sessions from earlier days
+        tx.execute(
+            "DELETE FROM usage_clicks WHERE last_at < ?1",
+            params![now.timestamp() - DOUBLE_CLICK_WINDOW_SECS],
+        )
+        .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        tx.execute("DELETE FROM usage_sessions WHERE day < ?1", params![day])
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+
+        let recent_click: Option<i64> = tx
+            .query_row(
+                "SELECT last_at FROM usage_clicks
+                 WHERE session_key = ?1 AND journal_id = ?2 AND metric = ?3",
+                params![session_key, journal_id, metric.as_str()],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        // Every click restarts the window, so a burst of clicks is one request
+        tx.execute(
+            "INSERT OR REPLACE INTO usage_clicks (session_key, journal_id, metric, last_at)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![session_key, journal_id, metric.as_str(), now.timestamp()],
+        )
+        .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+
+        let counted = recent_click.is_none();
+        if counted {
+            let first_today = tx
+                .execute(
+                    "INSERT OR IGNORE INTO usage_sessions (day, session_key, journal_id, metric)
+                     VALUES (?1, ?2, ?3, ?4)",
+                    params![day, session_key, journal_id, metric.as_str()],
+                )
+                .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+            tx.execute(
+                "INSERT INTO usage_daily (journal_id, day, metric, total, unique_total)
+                 VALUES (?1, ?2, ?3, 1, ?4)
+                 ON CONFLICT (journal_id, day, metric) DO UPDATE SET
+                    total = total + 1, unique_total = unique_total + excluded.unique_total",
+                params![journal_id, day, metric.as_str(), first_today as i64],
+            )
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+            // Kept on the article as well, for sorting the archive by downloads
+            if metric == UsageMetric::Download {
+                tx.execute(
+                    "UPDATE journals SET download_count = download_count + 1 WHERE id = ?1",
+                    params![journal_id],
+                )
+                .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+            }
+        }
+
+        tx.commit()
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        Ok(counted)
+    }
+
+    pub fn get_journal_totals(&self, journal_id: i32) -> Result<UsageTotals, SubmissionError> {
+        self.conn
+            .query_row(
+                &format!(
+                    "SELECT {} FROM usage_daily u WHERE u.journal_id = ?1",
+                    TOTALS_FIELDS
+                ),
+                params![journal_id],
+                |row| Self::map_totals(row, 0),
+            )
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))
+    }
+
+    // Site-wide usage from `from` to `to` inclusive
+    pub fn get_totals(
+        &self,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<UsageTotals, SubmissionError> {
+        self.conn
+            .query_row(
+                &format!(
+                    "SELECT {} FROM usage_daily u WHERE u.day BETWEEN ?1 AND ?2",
+                    TOTALS_FIELDS
+                ),
+                params![day_string(from), day_string(to)],
+                |row| Self::map_totals(row, 0),
+            )
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))
+    }
+
+    // One entry per day from `from` to `to`, including days without any usage
+    pub fn get_daily_usage(
+        &self,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<Vec<DailyUsage>, SubmissionError> {
+        let mut stmt = self
+            .conn
+            .prepare(&format!(
+                "SELECT u.day, {} FROM usage_daily u WHERE u.day BETWEEN ?1 AND ?2 GROUP BY u.day",
+                TOTALS_FIELDS
+            ))
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        let by_day = stmt
+            .query_map(params![day_string(from), day_string(to)], |row| {
+                Ok((row.get::<_, String>(0)?, Self::map_totals(row, 1)?))
+            })
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?
+            .collect::<Result<HashMap<String, UsageTotals>, _>>()
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+
+        let mut days = Vec::new();
+        let mut day = from;
+        while day <= to {
+            let key = day_string(day);
+            let totals = by_day.get(&key).cloned().unwrap_or_default();
+            days.push(DailyUsage {
+                day: key,
+                views: totals.views,
+                downloads: totals.downloads,
+            });
+            day += Duration::days(1);
+        }
+        Ok(days)
+    }
+
+    // Live articles with the most investigations between `from` and `to`
+    pub fn get_top_articles(
+        &self,
+        from: NaiveDate,
+        to: NaiveDate,
+        limit: i64,
+    ) -> Result<Vec<ArticleUsage>, SubmissionError> {
+        let mut stmt = self
+            .conn
+            .prepare(&format!(
+                "SELECT j.id, j.title, {} FROM usage_daily u
+                 JOIN journals j ON j.id = u.journal_id
+                 WHERE j.deleted_at IS NULL AND u.day BETWEEN ?1 AND ?2
+                 GROUP BY j.id ORDER BY SUM(u.total) DESC, j.id LIMIT ?3",
+                TOTALS_FIELDS
+            ))
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        let articles = stmt
+            .query_map(params![day_string(from), day_string(to), limit], |row| {
+                Ok(ArticleUsage {
+                    journal_id: row.get(0)?,
+                    title: row.get(1)?,
+                    totals: Self::map_totals(row, 2)?,
+                })
+            })
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        Ok(articles)
+    }
+
+    // Usage of every live article used between `from` and `to`, broken down by month
+    pub fn get_monthly_usage(
+        &self,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<Vec<MonthlyArticleUsage>, SubmissionError> {
+        let mut stmt = self
+            .conn
+            .prepare(&format!(
+                "SELECT j.id, j.title, j.authors,
+                        strftime('%Y-%m-%d', j.publication_date, 'unixepoch'),
+                        substr(u.day, 1, 7), {}
+                 FROM usage_daily u JOIN journals j ON j.id = u.journal_id
+                 WHERE j.deleted_at IS NULL AND u.day BETWEEN ?1 AND ?2
+                 GROUP BY j.id, substr(u.day, 1, 7)
+                 ORDER BY j.title COLLATE NOCASE, j.id, 5",
+                TOTALS_FIELDS
+            ))
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        let rows = stmt
+            .query_map(params![day_string(from), day_string(to)], |row| {
+                Ok((
+                    row.get::<_, i32>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, String>(4)?,
+                    Self::map_totals(row, 5)?,
+                ))
+            })
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+
+        let mut articles: Vec<MonthlyArticleUsage> = Vec::new();
+        for (journal_id, title, authors, publication_date, month, totals) in rows {
+            match articles.last_mut() {
+                Some(article) if article.journal_id == journal_id => {
+                    article.months.push((month, totals))
+                }
+                _ => articles.push(MonthlyArticleUsage {
+                    journal_id,
+                    title,
+                    authors,
+                    publication_date,
+                    months: vec![(month, totals)],
+                }),
+            }
+        }
+        Ok(articles)
+    }
+}
@@ -0,0 +1,176 @@
+use crate::{
+    errors::SubmissionError,
+    models::{journals::Journal, version::JournalVersion},
+};
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, Result as RusqliteResult};
+
+pub struct VersionRepository {
+    conn: Connection,
+}
+
+const SELECT_FIELDS: &str = "v.journal_id, v.version_number, v.title, v.authors, v.abstract_text,
+    v.keywords, v.volume_number, v.issue_number, v.pages, v.publication_date, v.pdf_url,
+    v.change_note, a.email, v.created_at";
+
+// Also used inside other repositories' transactions, such as bulk imports
+pub(crate) fn insert_version(
+    conn: &Connection,
+    journal: &Journal,
+    admin_id: Option<i32>,
+    change_note: &str,
+) -> Result<i32, SubmissionError> {
+    let journal_id = journal.id.ok_or_else(|| {
+        SubmissionError::ValidationError("Cannot version a journal without ID".to_string())
+    })?;
+    let version_number: i32 = conn
+        .query_row(
+            "SELECT COALESCE(MAX(version_number), 0) + 1 FROM journal_versions
+             WHERE journal_id = ?1",
+            params![journal_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+
+    conn.execute(
+        "INSERT INTO journal_versions (journal_id, version_number, title, authors,
+            abstract_text, keywords, volume_number, issue_number, pages,
+            publication_date, pdf_url, change_note, created_by, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+        params![
+            journal_id,
+            version_number,
+            journal.title,
+            journal.authors,
+            journal.abstract_text,
+            journal.keywords,
+            journal.volume_number,
+            journal.issue_number,
+            journal.pages,
+            journal.publication_date.timestamp(),
+            journal.pdf_url,
+            change_note,
+            admin_id,
+            Utc::now().timestamp(),
+        ],
+    )
+    .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    Ok(version_number)
+}
+
+impl VersionRepository {
+    pub fn new(conn: Connection) -> Self {
+        Self { conn }
+    }
+
+    fn map_row_to_version(row: &rusqlite::Row) -> RusqliteResult<JournalVersion> {
+        let timestamp = |ts: i64| DateTime::<Utc>::from_timestamp(ts, 0).unwrap_or_default();
+        let journal_id: i32 = row.get(0)?;
+        Ok(JournalVersion {
+            journal_id,
+            version_number: row.get(1)?,
+            journal: Journal {
+                id: Some(journal_id),
+                title: row.get(2)?,
+                authors: row.get(3)?,
+                abstract_text: row.get(4)?,
+                keywords: row.get(5)?,
+                volume_number: row.get(6)?,
+                issue_number: row.get(7)?,
+                pages: row.get(8)?,
+                publication_date: timestamp(row.get(9)?),
+                pdf_url: row.get(10)?,
+                created_at: None,
+            },
+            change_note: row.get(11)?,
+            created_by: row.get(12)?,
+            created_at: timestamp(row.get(13)?),
+        })
+    }
+
+    // Appends the journal's current state as its next version and returns the new number
+    pub fn record_version(
+        &self,
+        journal: &Journal,
+        admin_id: Option<i32>,
+        change_note: &str,
+    ) -> Result<i32, SubmissionError> {
+        insert_version(&self.conn, journal, admin_id, change_note)
+    }
+
+    // Articles published before versioning have no history yet; their state before the
+    // first edit becomes version 1, dated when the article was added
+    pub fn ensure_initial_version(&self, journal: &Journal) -> Result<(), SubmissionError> {
+        let journal_id = journal.id.ok_or_else(|| {
+            SubmissionError::ValidationError("Cannot version a journal without ID".to_string())
+        })?;
+        let created_at = journal.created_at.unwrap_or(journal.publication_date);
+        self.conn
+            .execute(
+                "INSERT INTO journal_versions (journal_id, version_number, title, authors,
+                    abstract_text, keywords, volume_number, issue_number, pages,
+                    publication_date, pdf_url, change_note, created_by, created_at)
+                 SELECT ?1, 1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, '', NULL, ?11
+                 WHERE NOT EXISTS (SELECT 1 FROM journal_versions WHERE journal_id = ?1)",
+                params![
+                    journal_id,
+                    journal.title,
+                    journal.authors,
+                    journal.abstract_text,
+                    journal.keywords,
+                    journal.volume_number,
+                    journal.issue_number,
+                    journal.pages,
+                    journal.publication_date.timestamp(),
+                    journal.pdf_url,
+                    created_at.timestamp(),
+                ],
+            )
+            .map(|_| ())
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))
+    }
+
+    // All versions of an article, newest first
+    pub fn get_versions(&self, journal_id: i32) -> Result<Vec<JournalVersion>, SubmissionError> {
+        let mut stmt = self
+            .conn
+            .prepare(&format!(
+                "SELECT {} FROM journal_versions v LEFT JOIN admins a ON a.id = v.created_by
+                 WHERE v.journal_id = ?1 ORDER BY v.version_number DESC",
+                SELECT_FIELDS
+            ))
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+
+        let version_iter = stmt
+            .query_map(params![journal_id], Self::map_row_to_version)
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+
+        version_iter
+            .collect::<Result<Vec<JournalVersion>, _>>()
+            .map_err(|e| SubmissionError::DatabaseError(e.to_string()))
+    }
+
+    pub fn get_version(
+        &self,
+        journal_id: i32,
+        version_number: i32,
+    ) -> Result<JournalVersion, SubmissionError> {
+        self.conn
+            .query_row(
+                &format!(
+                    "SELECT {} FROM journal_versions v LEFT JOIN admins a ON a.id = v.created_by
+                     WHERE v.journal_id = ?1 AND v.version_number = ?2",
+                    SELECT_FIELDS
+                ),
+                params![journal_id, version_number],
+                Self::map_row_to_version,
+            )
+            .map_err(|e| match e {
+                rusqlite::Error::QueryReturnedNoRows => SubmissionError::NotFound(format!(
+                    "Version {} of journal {} not found",
+                    version_number, journal_id
+                )),
+                _ => SubmissionError::DatabaseError(e.to_string()),
+            })
+    }
+}
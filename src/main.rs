@@ -1,21 +1,28 @@
-use crate::{
-    db::{admin_repository::AdminRepository, schema::init_db}, // Import AdminRepository
-    utils::{ensure_upload_dir, security::hash_password},      // Import hash_password
-};
 use actix_files as fs;
-use actix_session::{storage::CookieSessionStore, SessionMiddleware}; // Import session components
-use actix_web::{cookie::Key, web, App, HttpServer}; // Import Key and web
+use actix_session::{
+    config::{BrowserSession, TtlExtensionPolicy},
+    SessionMiddleware,
+}; // Import session components
+use actix_web::{
+    cookie::{time::Duration, Key},
+    middleware::from_fn,
+    web, App, HttpServer,
+}; // Import Key and web
 use dotenv::dotenv;
 use env_logger::Env;
+use journal_site::{
+    auth::{self, session_store::SqliteSessionStore},
+    config::get_session_config,
+    db::{
+        admin_repository::AdminRepository, author_repository::AuthorRepository, schema::init_db,
+        taxonomy_repository::TaxonomyRepository,
+    }, // Import AdminRepository
+    errors::SubmissionError,
+    routes,
+    utils::{ensure_upload_dir, housekeeping, security::hash_password}, // Import hash_password
+};
 use log::{error, info, warn};
 
-mod config;
-mod db;
-mod errors;
-mod models;
-mod routes;
-mod utils;
-
 async fn seed_admin_user() -> Result<(), Box<dyn std::error::Error>> {
     dotenv().ok(); // Ensure .env is loaded
 
@@ -64,6 +71,28 @@ async fn main() -> std::io::Result<()> {
         warn!("Failed to create uploads directory: {}", e);
     }
 
+    // Articles published before the keyword taxonomy and author profiles existed get
+    // linked to their keywords and authors
+    match init_db()
+        .map_err(|e| SubmissionError::DatabaseError(e.to_string()))
+        .and_then(|conn| TaxonomyRepository::new(conn).index_unlinked_journals())
+    {
+        Ok(0) => {}
+        Ok(count) => info!("Indexed keywords of {} article(s)", count),
+        Err(e) => warn!("Failed to index article keywords: {}", e),
+    }
+    match init_db()
+        .map_err(|e| SubmissionError::DatabaseError(e.to_string()))
+        .and_then(|conn| AuthorRepository::new(conn).index_unlinked_journals())
+    {
+        Ok(0) => {}
+        Ok(count) => info!("Indexed authors of {} article(s)", count),
+        Err(e) => warn!("Failed to index article authors: {}", e),
+    }
+
+    // Purges expired trash, drafts, unfinished uploads and sessions once an hour
+    actix_web::rt::spawn(housekeeping::run());
+
     // --- Session Key from Environment ---
     let session_secret =
         std::env::var("SESSION_SECRET_KEY").expect("SESSION_SECRET_KEY must be set in .env");
@@ -71,6 +100,13 @@ async fn main() -> std::io::Result<()> {
     // Ensure the string in .env is long enough (e.g., 64+ chars recommended).
     let secret_key = Key::from(session_secret.as_bytes());
     // --- End Session Key ---
+    let session_config = get_session_config();
+    info!(
+        "Sessions expire after {} idle minutes or {} hours (secure cookie: {})",
+        session_config.idle_timeout.num_minutes(),
+        session_config.absolute_timeout.num_hours(),
+        session_config.cookie_secure
+    );
 
     info!("Starting server on http://{}:{}...", host, port);
 
@@ -80,14 +116,25 @@ async fn main() -> std::io::Result<()> {
 
         App::new()
             // --- Session Middleware ---
+            .wrap(from_fn(auth::csrf::protect))
             .wrap(
-                SessionMiddleware::builder(CookieSessionStore::default(), secret_key) // Use the cloned key
-                    .cookie_secure(false) // Set to true if using HTTPS
-                    .cookie_path("/".to_string())
-                    .cookie_name("tsms-session".to_string())
-                    .cookie_http_only(true)
-                    .cookie_same_site(actix_web::cookie::SameSite::Lax)
-                    .build(),
+                SessionMiddleware::builder(
+                    SqliteSessionStore::new(session_config.absolute_timeout),
+                    secret_key,
+                ) // Use the cloned key
+                .cookie_secure(session_config.cookie_secure)
+                // Browser-session cookie; the server-side state expires after the idle timeout,
+                // which every request pushes back
+                .session_lifecycle(
+                    BrowserSession::default()
+                        .state_ttl(Duration::seconds(session_config.idle_timeout.num_seconds()))
+                        .state_ttl_extension_policy(TtlExtensionPolicy::OnEveryRequest),
+                )
+                .cookie_path("/".to_string())
+                .cookie_name("tsms-session".to_string())
+                .cookie_http_only(true)
+                .cookie_same_site(actix_web::cookie::SameSite::Lax)
+                .build(),
             )
             // --- End Session Middleware ---
             // --- Logging Middleware ---
@@ -95,13 +142,29 @@ async fn main() -> std::io::Result<()> {
             // --- End Logging ---
             // Serve static files
             .service(fs::Files::new("/static", "./src/static"))
-            .service(fs::Files::new("/download", "./data/uploads"))
             // --- Public Routes ---
             .service(routes::landing::landing_handler)
             .service(routes::journals::journal_detail_handler)
+            .service(routes::journals::journal_attachment_handler)
+            .service(routes::journals::journal_pdf_handler)
+            .service(routes::journals::journal_version_pdf_handler)
+            .service(routes::journals::journal_jats_handler)
+            .service(routes::notices::notice_handler)
+            .service(routes::taxonomy::keyword_handler)
+            .service(routes::taxonomy::subjects_handler)
+            .service(routes::taxonomy::subject_handler)
+            .service(routes::authors::author_index_handler)
+            .service(routes::authors::author_handler)
             .service(routes::about::about_handler)
             .service(routes::submissions::submit_paper_handler)
             .service(routes::submissions::process_submission)
+            .service(routes::submissions::create_draft_handler)
+            .service(routes::submissions::get_draft_handler)
+            .service(routes::submissions::update_draft_handler)
+            .service(routes::uploads::upload_options_handler)
+            .service(routes::uploads::create_upload_handler)
+            .service(routes::uploads::upload_status_handler)
+            .service(routes::uploads::upload_chunk_handler)
             .service(routes::editorial::editorial_board_handler)
             .service(routes::journals::journal_handler)
             .service(routes::journals::journal_initial_data)
@@ -109,18 +172,80 @@ async fn main() -> std::io::Result<()> {
             .service(routes::manuscript::manuscript_guide)
             .service(routes::auth::show_login_form)
             .service(routes::auth::login)
+            .service(routes::auth::show_two_factor_form)
+            .service(routes::auth::verify_two_factor)
+            .service(routes::account::forgot_password_form_handler)
+            .service(routes::account::forgot_password_handler)
+            .service(routes::account::reset_password_form_handler)
+            .service(routes::account::reset_password_handler)
+            .service(routes::account::verify_email_handler)
+            .service(routes::users::accept_invite_form_handler)
+            .service(routes::users::accept_invite_handler)
             // --- Admin Routes (Scoped under /admin) ---
             .service(
                 web::scope("/admin")
+                    .wrap(from_fn(auth::require_admin))
                     .service(routes::auth::logout)
+                    .service(routes::account::change_password_form_handler)
+                    .service(routes::account::change_password_handler)
+                    .service(routes::account::two_factor_handler)
+                    .service(routes::account::enable_two_factor_handler)
+                    .service(routes::account::disable_two_factor_handler)
+                    .service(routes::account::regenerate_recovery_codes_handler)
+                    .service(routes::account::sessions_handler)
+                    .service(routes::account::revoke_session_handler)
+                    .service(routes::account::revoke_other_sessions_handler)
                     .service(routes::admin::admin_dashboard_handler)
+                    .service(routes::admin::export_dashboard_handler)
                     .service(routes::admin::upload_journal_handler)
                     .service(routes::admin::process_upload)
+                    .service(routes::imports::import_form_handler)
+                    .service(routes::imports::import_articles_handler)
+                    .service(routes::admin::extract_metadata_handler)
                     .service(routes::admin::delete_journal_handler)
                     .service(routes::admin::admin_submissions_handler)
+                    .service(routes::admin::bulk_submissions_handler)
+                    .service(routes::admin::admin_submission_detail_handler)
+                    .service(routes::admin::delete_submission_handler)
+                    .service(routes::review::record_decision_handler)
+                    .service(routes::review::assign_reviewer_handler)
+                    .service(routes::review::complete_review_handler)
                     .service(routes::admin::download_submission_handler)
+                    .service(routes::admin::download_submission_attachment_handler)
                     .service(routes::admin::edit_journal_form_handler)
-                    .service(routes::admin::update_journal_handler),
+                    .service(routes::admin::update_journal_handler)
+                    .service(routes::admin::replace_journal_pdf_handler)
+                    .service(routes::versions::journal_versions_handler)
+                    .service(routes::versions::rollback_journal_handler)
+                    .service(routes::notices::journal_notices_handler)
+                    .service(routes::notices::issue_notice_handler)
+                    .service(routes::notices::withdraw_notice_handler)
+                    .service(routes::taxonomy::taxonomy_handler)
+                    .service(routes::taxonomy::merge_keywords_handler)
+                    .service(routes::taxonomy::create_subject_handler)
+                    .service(routes::taxonomy::delete_subject_handler)
+                    .service(routes::taxonomy::assign_subjects_handler)
+                    .service(routes::authors::admin_authors_handler)
+                    .service(routes::authors::merge_authors_handler)
+                    .service(routes::authors::edit_author_form_handler)
+                    .service(routes::authors::update_author_handler)
+                    .service(routes::users::users_handler)
+                    .service(routes::users::invite_user_handler)
+                    .service(routes::users::update_user_role_handler)
+                    .service(routes::users::deactivate_user_handler)
+                    .service(routes::users::activate_user_handler)
+                    .service(routes::users::unlock_user_handler)
+                    .service(routes::users::reset_two_factor_handler)
+                    .service(routes::users::revoke_user_sessions_handler)
+                    .service(routes::audit::audit_log_handler)
+                    .service(routes::audit::export_audit_log_handler)
+                    .service(routes::usage::usage_handler)
+                    .service(routes::usage::usage_report_handler)
+                    .service(routes::exports::exports_handler)
+                    .service(routes::exports::export_download_handler)
+                    .service(routes::trash::trash_handler)
+                    .service(routes::trash::restore_handler)
+                    .service(routes::trash::purge_handler),
             )
     })
     .bind((host.as_str(), port))?
@@ -1,8 +1,183 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Deserialize)]
+use crate::config::get_required_2fa_roles;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    SuperAdmin,
+    EditorInChief,
+    SectionEditor,
+    CopyEditor,
+    Reviewer,
+    Author,
+}
+
+// Actions guarded per route; each role is granted a fixed set of them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Permission {
+    ViewDashboard,
+    // Every submission; reviewers without it only see those assigned to them
+    ViewSubmissions,
+    ReviewSubmissions,
+    PublishArticles,
+    EditArticles,
+    DeleteArticles,
+    ManageUsers,
+    ViewAuditLog,
+}
+
+impl Role {
+    pub const ALL: [Role; 6] = [
+        Role::SuperAdmin,
+        Role::EditorInChief,
+        Role::SectionEditor,
+        Role::CopyEditor,
+        Role::Reviewer,
+        Role::Author,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::SuperAdmin => "super_admin",
+            Role::EditorInChief => "editor_in_chief",
+            Role::SectionEditor => "section_editor",
+            Role::CopyEditor => "copy_editor",
+            Role::Reviewer => "reviewer",
+            Role::Author => "author",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Role::ALL.into_iter().find(|r| r.as_str() == value)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Role::SuperAdmin => "Super Admin",
+            Role::EditorInChief => "Editor-in-Chief",
+            Role::SectionEditor => "Section Editor",
+            Role::CopyEditor => "Copy Editor",
+            Role::Reviewer => "Reviewer",
+            Role::Author => "Author",
+        }
+    }
+
+    pub fn permissions(&self) -> &'static [Permission] {
+        use Permission::*;
+        match self {
+            Role::SuperAdmin => &[
+                ViewDashboard,
+                ViewSubmissions,
+                ReviewSubmissions,
+                PublishArticles,
+                EditArticles,
+                DeleteArticles,
+                ManageUsers,
+                ViewAuditLog,
+            ],
+            Role::EditorInChief => &[
+                ViewDashboard,
+                ViewSubmissions,
+                ReviewSubmissions,
+                PublishArticles,
+                EditArticles,
+                DeleteArticles,
+                ViewAuditLog,
+            ],
+            Role::SectionEditor => &[
+                ViewDashboard,
+                ViewSubmissions,
+                ReviewSubmissions,
+                PublishArticles,
+                EditArticles,
+            ],
+            Role::CopyEditor => &[ViewDashboard, EditArticles],
+            Role::Reviewer => &[ViewDashboard, ReviewSubmissions],
+            Role::Author => &[],
+        }
+    }
+
+    pub fn has_permission(&self, permission: Permission) -> bool {
+        self.permissions().contains(&permission)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Admin {
     pub id: i32,
     pub email: String,
     pub password_hash: String,
+    pub role: Role,
+    pub is_active: bool,
+    pub email_verified: bool,
+    pub failed_login_count: i32,
+    pub locked_until: Option<DateTime<Utc>>,
+    // Base32 TOTP secret; only set once enrolment has been confirmed with a valid code
+    #[serde(skip)]
+    pub totp_secret: Option<String>,
+    pub totp_enabled: bool,
+    // Last accepted TOTP time step, so a code can't be used twice
+    pub totp_last_step: Option<i64>,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+impl Admin {
+    pub fn can(&self, permission: Permission) -> bool {
+        self.is_active && self.role.has_permission(permission)
+    }
+
+    // Remaining lockout after repeated failed logins, if any
+    pub fn lockout_remaining(&self) -> Option<chrono::Duration> {
+        self.locked_until
+            .map(|until| until - Utc::now())
+            .filter(|remaining| *remaining > chrono::Duration::zero())
+    }
+
+    pub fn requires_two_factor(&self) -> bool {
+        get_required_2fa_roles().contains(&self.role)
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.lockout_remaining().is_some()
+    }
+
+    pub fn formatted_locked_until(&self) -> String {
+        self.locked_until
+            .map(|dt| dt.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_default()
+    }
+
+    pub fn formatted_date(&self) -> String {
+        self.created_at
+            .map(|dt| dt.format("%Y-%m-%d").to_string())
+            .unwrap_or_else(|| "N/A".to_string())
+    }
+}
+
+// Pending invitation for a new editorial account. `token` is sent to the invitee and only
+// its signed digest is stored, so invites read back from the database leave it empty
+// unless they were looked up by their token.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AdminInvite {
+    pub token: String,
+    pub email: String,
+    pub role: Role,
+    pub invited_by: i32,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl AdminInvite {
+    pub fn is_expired(&self) -> bool {
+        self.expires_at < Utc::now()
+    }
+
+    pub fn invite_url(&self) -> String {
+        format!("/admin/invite/{}", self.token)
+    }
+
+    pub fn formatted_expiry(&self) -> String {
+        self.expires_at.format("%Y-%m-%d %H:%M").to_string()
+    }
 }
@@ -0,0 +1,83 @@
+use serde::Serialize;
+
+// Submissions received in one calendar month ("YYYY-MM")
+#[derive(Debug, Clone, Serialize)]
+pub struct MonthlySubmissions {
+    pub month: String,
+    pub submissions: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CountrySubmissions {
+    pub country: String,
+    pub submissions: i64,
+}
+
+// How quickly one reviewer returns the reviews they are invited to
+#[derive(Debug, Clone, Serialize)]
+pub struct ReviewerTurnaround {
+    pub reviewer_email: String,
+    pub completed: i64,
+    pub outstanding: i64,
+    pub median_days: Option<f64>,
+}
+
+// Editorial performance over the submissions received since the start of a period.
+// Trashed submissions are left out.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct EditorialMetrics {
+    pub months: Vec<MonthlySubmissions>,
+    pub total_submissions: i64,
+    // Submissions whose latest final decision is an acceptance or a rejection
+    pub accepted: i64,
+    pub rejected: i64,
+    pub median_days_to_decision: Option<f64>,
+    pub median_days_to_publication: Option<f64>,
+    pub median_review_days: Option<f64>,
+    pub reviewers: Vec<ReviewerTurnaround>,
+    pub countries: Vec<CountrySubmissions>,
+}
+
+impl EditorialMetrics {
+    // Share of finally decided submissions that were accepted
+    pub fn acceptance_rate(&self) -> Option<f64> {
+        let decided = self.accepted + self.rejected;
+        (decided > 0).then(|| self.accepted as f64 / decided as f64)
+    }
+
+    pub fn acceptance_rate_display(&self) -> String {
+        self.acceptance_rate()
+            .map(|rate| format!("{:.0}%", rate * 100.0))
+            .unwrap_or_else(|| "-".to_string())
+    }
+
+    pub fn busiest_month(&self) -> i64 {
+        self.months.iter().map(|m| m.submissions).max().unwrap_or(0)
+    }
+
+    pub fn busiest_country(&self) -> i64 {
+        self.countries
+            .iter()
+            .map(|c| c.submissions)
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+pub fn days_display(days: &Option<f64>) -> String {
+    days.map(|d| format!("{:.1} days", d))
+        .unwrap_or_else(|| "-".to_string())
+}
+
+pub fn median(values: &mut [f64]) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_by(|a, b| a.total_cmp(b));
+    let middle = values.len() / 2;
+    Some(if values.len().is_multiple_of(2) {
+        (values[middle - 1] + values[middle]) / 2.0
+    } else {
+        values[middle]
+    })
+}
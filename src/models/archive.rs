@@ -0,0 +1,156 @@
+use serde::Serialize;
+
+use crate::models::journals::Journal;
+
+// Orderings offered on the archive page
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArchiveSort {
+    Newest,
+    Oldest,
+    Title,
+    MostDownloaded,
+}
+
+impl ArchiveSort {
+    pub const ALL: [ArchiveSort; 4] = [
+        ArchiveSort::Newest,
+        ArchiveSort::Oldest,
+        ArchiveSort::Title,
+        ArchiveSort::MostDownloaded,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ArchiveSort::Newest => "newest",
+            ArchiveSort::Oldest => "oldest",
+            ArchiveSort::Title => "title",
+            ArchiveSort::MostDownloaded => "most_downloaded",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        ArchiveSort::ALL.into_iter().find(|s| s.as_str() == value)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ArchiveSort::Newest => "Newest first",
+            ArchiveSort::Oldest => "Oldest first",
+            ArchiveSort::Title => "Title (A-Z)",
+            ArchiveSort::MostDownloaded => "Most downloaded",
+        }
+    }
+
+    // Sort key of an article, compared as text for titles and as an integer otherwise;
+    // ties are broken by ID so every row has a distinct position
+    pub fn key_of(&self, journal: &Journal, download_count: i64) -> CursorKey {
+        match self {
+            ArchiveSort::Newest | ArchiveSort::Oldest => {
+                CursorKey::Int(journal.publication_date.timestamp())
+            }
+            ArchiveSort::Title => CursorKey::Text(journal.title.clone()),
+            ArchiveSort::MostDownloaded => CursorKey::Int(download_count),
+        }
+    }
+}
+
+// Which part of the archive the sidebar categories select
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveCategory {
+    All,
+    // Only the latest volume and issue
+    Current,
+    // Everything before the latest volume and issue
+    Past,
+}
+
+#[derive(Debug, Clone)]
+pub struct ArchiveFilter {
+    pub category: ArchiveCategory,
+    pub volume: Option<i32>,
+    pub issue: Option<i32>,
+    pub year: Option<i32>,
+    // Keyword slug
+    pub keyword: Option<String>,
+    pub author: Option<i32>,
+    // Words that must all appear in the title, authors, abstract, keywords or the text
+    // extracted from the PDF
+    pub text: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CursorKey {
+    Int(i64),
+    Text(String),
+}
+
+// Position after the last article of a page. Opaque to clients: the sort, the sort key
+// and the article ID, hex encoded so it travels safely in a query string.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArchiveCursor {
+    pub sort: ArchiveSort,
+    pub key: CursorKey,
+    pub id: i32,
+}
+
+impl ArchiveCursor {
+    pub fn encode(&self) -> String {
+        let key = match &self.key {
+            CursorKey::Int(value) => format!("i{}", value),
+            CursorKey::Text(value) => format!("t{}", value),
+        };
+        format!("{}\n{}\n{}", self.sort.as_str(), self.id, key)
+            .bytes()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+
+    pub fn decode(value: &str) -> Option<Self> {
+        if !value.len().is_multiple_of(2) {
+            return None;
+        }
+        let bytes = (0..value.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(value.get(i..i + 2)?, 16).ok())
+            .collect::<Option<Vec<u8>>>()?;
+        let text = String::from_utf8(bytes).ok()?;
+
+        let mut parts = text.splitn(3, '\n');
+        let sort = ArchiveSort::parse(parts.next()?)?;
+        let id = parts.next()?.parse().ok()?;
+        let key = parts.next()?;
+        let key = match key.split_at_checked(1)? {
+            ("i", value) => CursorKey::Int(value.parse().ok()?),
+            ("t", value) => CursorKey::Text(value.to_string()),
+            _ => return None,
+        };
+        Some(Self { sort, key, id })
+    }
+}
+
+// One value of a facet with the number of matching articles
+#[derive(Debug, Clone, Serialize)]
+pub struct FacetValue {
+    pub value: String,
+    pub label: String,
+    pub count: i64,
+}
+
+// Each facet is counted with every other active filter applied, but not its own, so
+// the alternatives to the current choice stay visible
+#[derive(Debug, Default, Serialize)]
+pub struct ArchiveFacets {
+    pub volume: Vec<FacetValue>,
+    pub issue: Vec<FacetValue>,
+    pub year: Vec<FacetValue>,
+    pub keyword: Vec<FacetValue>,
+    pub author: Vec<FacetValue>,
+}
+
+#[derive(Debug)]
+pub struct ArchivePage {
+    pub journals: Vec<Journal>,
+    pub total: i64,
+    pub next_cursor: Option<String>,
+}
@@ -0,0 +1,161 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+// Types of files that can accompany a manuscript or a published article
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AttachmentKind {
+    CoverLetter,
+    Supplementary,
+    Dataset,
+    Figure,
+}
+
+impl AttachmentKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AttachmentKind::CoverLetter => "cover_letter",
+            AttachmentKind::Supplementary => "supplementary",
+            AttachmentKind::Dataset => "dataset",
+            AttachmentKind::Figure => "figure",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "cover_letter" => Some(AttachmentKind::CoverLetter),
+            "supplementary" => Some(AttachmentKind::Supplementary),
+            "dataset" => Some(AttachmentKind::Dataset),
+            "figure" => Some(AttachmentKind::Figure),
+            _ => None,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            AttachmentKind::CoverLetter => "Cover letter",
+            AttachmentKind::Supplementary => "Supplementary material",
+            AttachmentKind::Dataset => "Dataset",
+            AttachmentKind::Figure => "Figure",
+        }
+    }
+
+    // Largest accepted file, in bytes
+    pub fn max_size(&self) -> u64 {
+        const MB: u64 = 1024 * 1024;
+        match self {
+            AttachmentKind::CoverLetter => 5 * MB,
+            AttachmentKind::Supplementary => 20 * MB,
+            AttachmentKind::Dataset => 50 * MB,
+            AttachmentKind::Figure => 10 * MB,
+        }
+    }
+
+    // Accepted file extensions and the MIME type each is stored and served with
+    pub fn allowed_types(&self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            AttachmentKind::CoverLetter => &[
+                ("pdf", "application/pdf"),
+                ("doc", "application/msword"),
+                (
+                    "docx",
+                    "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+                ),
+                ("txt", "text/plain"),
+            ],
+            AttachmentKind::Supplementary => &[
+                ("pdf", "application/pdf"),
+                ("doc", "application/msword"),
+                (
+                    "docx",
+                    "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+                ),
+                ("zip", "application/zip"),
+            ],
+            AttachmentKind::Dataset => &[
+                ("csv", "text/csv"),
+                ("json", "application/json"),
+                (
+                    "xlsx",
+                    "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+                ),
+                ("zip", "application/zip"),
+                ("txt", "text/plain"),
+            ],
+            AttachmentKind::Figure => &[
+                ("png", "image/png"),
+                ("jpg", "image/jpeg"),
+                ("jpeg", "image/jpeg"),
+                ("gif", "image/gif"),
+                ("tif", "image/tiff"),
+                ("tiff", "image/tiff"),
+            ],
+        }
+    }
+
+    pub fn mime_for_extension(&self, extension: &str) -> Option<&'static str> {
+        let extension = extension.to_lowercase();
+        self.allowed_types()
+            .iter()
+            .find(|(ext, _)| *ext == extension)
+            .map(|(_, mime)| *mime)
+    }
+
+    // Whether a file's first bytes look like the type its extension claims, so a file
+    // can't be uploaded as a figure or PDF under another format's name. Text formats
+    // have no signature and are only checked for binary content.
+    pub fn content_matches(mime_type: &str, head: &[u8]) -> bool {
+        match mime_type {
+            "application/pdf" => head.starts_with(b"%PDF-"),
+            "image/png" => head.starts_with(b"\x89PNG\r\n\x1a\n"),
+            "image/jpeg" => head.starts_with(b"\xff\xd8\xff"),
+            "image/gif" => head.starts_with(b"GIF87a") || head.starts_with(b"GIF89a"),
+            "image/tiff" => head.starts_with(b"II*\0") || head.starts_with(b"MM\0*"),
+            // Office Open XML documents are zip archives
+            "application/zip"
+            | "application/vnd.openxmlformats-officedocument.wordprocessingml.document"
+            | "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet" => {
+                head.starts_with(b"PK\x03\x04") || head.starts_with(b"PK\x05\x06")
+            }
+            "application/msword" => head.starts_with(b"\xd0\xcf\x11\xe0\xa1\xb1\x1a\xe1"),
+            _ => !head.contains(&0),
+        }
+    }
+
+    // Published articles don't carry the author's cover letter
+    pub fn allowed_for_journal(&self) -> bool {
+        *self != AttachmentKind::CoverLetter
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Attachment {
+    pub id: Option<i32>,
+    pub owner_type: String, // "submission" or "journal"
+    pub owner_id: i32,
+    pub kind: AttachmentKind,
+    pub original_name: String,
+    pub file_name: String,
+    pub mime_type: String,
+    pub size_bytes: i64,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+impl Attachment {
+    pub fn id_string(&self) -> String {
+        self.id.map_or_else(String::new, |id| id.to_string())
+    }
+
+    pub fn file_path(&self) -> String {
+        format!("./data/uploads/attachments/{}", self.file_name)
+    }
+
+    pub fn size_display(&self) -> String {
+        let size = self.size_bytes as f64;
+        if size >= 1024.0 * 1024.0 {
+            format!("{:.1} MB", size / (1024.0 * 1024.0))
+        } else {
+            format!("{:.0} KB", (size / 1024.0).max(1.0))
+        }
+    }
+}
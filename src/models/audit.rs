@@ -0,0 +1,275 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+use crate::utils::query::{encode_query_value, non_empty};
+
+// Editorial and administrative actions recorded in the audit log
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditAction {
+    JournalPublished,
+    JournalUpdated,
+    JournalRolledBack,
+    JournalDeleted,
+    JournalRestored,
+    JournalPurged,
+    NoticeIssued,
+    NoticeWithdrawn,
+    KeywordsMerged,
+    SubjectCreated,
+    SubjectDeleted,
+    SubjectsAssigned,
+    AuthorUpdated,
+    AuthorsMerged,
+    SubmissionDownloaded,
+    SubmissionDeleted,
+    DecisionRecorded,
+    ReviewerAssigned,
+    ReviewCompleted,
+    EditorAssigned,
+    SubmissionsExported,
+    DataExported,
+    SubmissionRestored,
+    SubmissionPurged,
+    AttachmentDownloaded,
+    UserInvited,
+    UserRoleChanged,
+    UserDeactivated,
+    UserReactivated,
+    UserUnlocked,
+    TwoFactorReset,
+    SessionsRevoked,
+    AuditLogExported,
+}
+
+impl AuditAction {
+    pub const ALL: [AuditAction; 33] = [
+        AuditAction::JournalPublished,
+        AuditAction::JournalUpdated,
+        AuditAction::JournalRolledBack,
+        AuditAction::JournalDeleted,
+        AuditAction::JournalRestored,
+        AuditAction::JournalPurged,
+        AuditAction::NoticeIssued,
+        AuditAction::NoticeWithdrawn,
+        AuditAction::KeywordsMerged,
+        AuditAction::SubjectCreated,
+        AuditAction::SubjectDeleted,
+        AuditAction::SubjectsAssigned,
+        AuditAction::AuthorUpdated,
+        AuditAction::AuthorsMerged,
+        AuditAction::SubmissionDownloaded,
+        AuditAction::SubmissionDeleted,
+        AuditAction::DecisionRecorded,
+        AuditAction::ReviewerAssigned,
+        AuditAction::ReviewCompleted,
+        AuditAction::EditorAssigned,
+        AuditAction::SubmissionsExported,
+        AuditAction::DataExported,
+        AuditAction::SubmissionRestored,
+        AuditAction::SubmissionPurged,
+        AuditAction::AttachmentDownloaded,
+        AuditAction::UserInvited,
+        AuditAction::UserRoleChanged,
+        AuditAction::UserDeactivated,
+        AuditAction::UserReactivated,
+        AuditAction::UserUnlocked,
+        AuditAction::TwoFactorReset,
+        AuditAction::SessionsRevoked,
+        AuditAction::AuditLogExported,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditAction::JournalPublished => "journal_published",
+            AuditAction::JournalUpdated => "journal_updated",
+            AuditAction::JournalRolledBack => "journal_rolled_back",
+            AuditAction::JournalDeleted => "journal_deleted",
+            AuditAction::JournalRestored => "journal_restored",
+            AuditAction::JournalPurged => "journal_purged",
+            AuditAction::NoticeIssued => "notice_issued",
+            AuditAction::NoticeWithdrawn => "notice_withdrawn",
+            AuditAction::KeywordsMerged => "keywords_merged",
+            AuditAction::SubjectCreated => "subject_created",
+            AuditAction::SubjectDeleted => "subject_deleted",
+            AuditAction::SubjectsAssigned => "subjects_assigned",
+            AuditAction::AuthorUpdated => "author_updated",
+            AuditAction::AuthorsMerged => "authors_merged",
+            AuditAction::SubmissionDownloaded => "submission_downloaded",
+            AuditAction::SubmissionDeleted => "submission_deleted",
+            AuditAction::DecisionRecorded => "decision_recorded",
+            AuditAction::ReviewerAssigned => "reviewer_assigned",
+            AuditAction::ReviewCompleted => "review_completed",
+            AuditAction::EditorAssigned => "editor_assigned",
+            AuditAction::SubmissionsExported => "submissions_exported",
+            AuditAction::DataExported => "data_exported",
+            AuditAction::SubmissionRestored => "submission_restored",
+            AuditAction::SubmissionPurged => "submission_purged",
+            AuditAction::AttachmentDownloaded => "attachment_downloaded",
+            AuditAction::UserInvited => "user_invited",
+            AuditAction::UserRoleChanged => "user_role_changed",
+            AuditAction::UserDeactivated => "user_deactivated",
+            AuditAction::UserReactivated => "user_reactivated",
+            AuditAction::UserUnlocked => "user_unlocked",
+            AuditAction::TwoFactorReset => "two_factor_reset",
+            AuditAction::SessionsRevoked => "sessions_revoked",
+            AuditAction::AuditLogExported => "audit_log_exported",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        AuditAction::ALL.into_iter().find(|a| a.as_str() == value)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            AuditAction::JournalPublished => "Article published",
+            AuditAction::JournalUpdated => "Article edited",
+            AuditAction::JournalRolledBack => "Article rolled back",
+            AuditAction::JournalDeleted => "Article moved to trash",
+            AuditAction::JournalRestored => "Article restored",
+            AuditAction::JournalPurged => "Article purged",
+            AuditAction::NoticeIssued => "Notice issued",
+            AuditAction::NoticeWithdrawn => "Notice withdrawn",
+            AuditAction::KeywordsMerged => "Keywords merged",
+            AuditAction::SubjectCreated => "Subject created",
+            AuditAction::SubjectDeleted => "Subject deleted",
+            AuditAction::SubjectsAssigned => "Subjects assigned",
+            AuditAction::AuthorUpdated => "Author profile edited",
+            AuditAction::AuthorsMerged => "Authors merged",
+            AuditAction::SubmissionDownloaded => "Manuscript downloaded",
+            AuditAction::SubmissionDeleted => "Submission moved to trash",
+            AuditAction::DecisionRecorded => "Editorial decision recorded",
+            AuditAction::ReviewerAssigned => "Reviewer assigned",
+            AuditAction::ReviewCompleted => "Review completed",
+            AuditAction::EditorAssigned => "Handling editor assigned",
+            AuditAction::SubmissionsExported => "Submissions exported",
+            AuditAction::DataExported => "Data exported",
+            AuditAction::SubmissionRestored => "Submission restored",
+            AuditAction::SubmissionPurged => "Submission purged",
+            AuditAction::AttachmentDownloaded => "Submission file downloaded",
+            AuditAction::UserInvited => "User invited",
+            AuditAction::UserRoleChanged => "Role changed",
+            AuditAction::UserDeactivated => "User deactivated",
+            AuditAction::UserReactivated => "User reactivated",
+            AuditAction::UserUnlocked => "User unlocked",
+            AuditAction::TwoFactorReset => "Two-factor reset",
+            AuditAction::SessionsRevoked => "Sessions signed out",
+            AuditAction::AuditLogExported => "Audit log exported",
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct AuditEntry {
+    pub id: i64,
+    pub actor_id: Option<i32>,
+    pub actor_email: String,
+    pub action: String,
+    pub target_type: String,
+    pub target_id: String,
+    // JSON: {"field": {"before": .., "after": ..}} for edits, or a snapshot of the target
+    pub changes: Option<String>,
+    pub ip_address: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl AuditEntry {
+    pub fn formatted_date(&self) -> String {
+        self.created_at.format("%Y-%m-%d %H:%M:%S").to_string()
+    }
+
+    pub fn action_label(&self) -> &str {
+        AuditAction::parse(&self.action)
+            .map(|a| a.label())
+            .unwrap_or(&self.action)
+    }
+
+    pub fn changes_display(&self) -> &str {
+        self.changes.as_deref().unwrap_or("")
+    }
+}
+
+// Query-string filters for the audit page and its export; blank fields are ignored
+#[derive(Debug, Default, Deserialize)]
+pub struct AuditFilter {
+    pub actor: Option<String>,
+    pub action: Option<String>,
+    pub target_type: Option<String>,
+    pub target_id: Option<String>,
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub page: Option<i64>,
+}
+
+impl AuditFilter {
+    pub fn actor(&self) -> Option<&str> {
+        non_empty(&self.actor)
+    }
+
+    pub fn action(&self) -> Option<&str> {
+        non_empty(&self.action)
+    }
+
+    pub fn target_type(&self) -> Option<&str> {
+        non_empty(&self.target_type)
+    }
+
+    pub fn target_id(&self) -> Option<&str> {
+        non_empty(&self.target_id)
+    }
+
+    pub fn from_date(&self) -> Option<NaiveDate> {
+        non_empty(&self.from).and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+    }
+
+    pub fn to_date(&self) -> Option<NaiveDate> {
+        non_empty(&self.to).and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+    }
+
+    pub fn page(&self) -> i64 {
+        self.page.unwrap_or(1).max(1)
+    }
+
+    // The filters as a query string, for pagination and export links
+    pub fn query_string(&self) -> String {
+        [
+            ("actor", self.actor()),
+            ("action", self.action()),
+            ("target_type", self.target_type()),
+            ("target_id", self.target_id()),
+            ("from", non_empty(&self.from)),
+            ("to", non_empty(&self.to)),
+        ]
+        .iter()
+        .filter_map(|(key, value)| value.map(|v| format!("{}={}", key, encode_query_value(v))))
+        .collect::<Vec<_>>()
+        .join("&")
+    }
+}
+
+// Fields that differ between two versions of a record, as {"field": {"before", "after"}}
+pub fn field_changes<T: Serialize>(before: &T, after: &T) -> Option<String> {
+    let (Ok(Value::Object(before)), Ok(Value::Object(after))) =
+        (serde_json::to_value(before), serde_json::to_value(after))
+    else {
+        return None;
+    };
+
+    let changes: Map<String, Value> = after
+        .iter()
+        .filter(|(field, value)| before.get(*field) != Some(value))
+        .map(|(field, value)| {
+            (
+                field.clone(),
+                json!({ "before": before.get(field), "after": value }),
+            )
+        })
+        .collect();
+    (!changes.is_empty()).then(|| Value::Object(changes).to_string())
+}
+
+// The full record, for creations and deletions where there is nothing to diff against
+pub fn snapshot<T: Serialize>(record: &T) -> Option<String> {
+    serde_json::to_string(record).ok()
+}
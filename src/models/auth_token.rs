@@ -0,0 +1,27 @@
+use chrono::Duration;
+
+// What an emailed token is allowed to do; a token for one purpose is useless for another
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenPurpose {
+    PasswordReset,
+    EmailVerification,
+    AdminInvite,
+}
+
+impl TokenPurpose {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TokenPurpose::PasswordReset => "password_reset",
+            TokenPurpose::EmailVerification => "email_verification",
+            TokenPurpose::AdminInvite => "admin_invite",
+        }
+    }
+
+    pub fn lifetime(&self) -> Duration {
+        match self {
+            TokenPurpose::PasswordReset => Duration::hours(1),
+            TokenPurpose::EmailVerification => Duration::days(3),
+            TokenPurpose::AdminInvite => Duration::days(7),
+        }
+    }
+}
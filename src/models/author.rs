@@ -0,0 +1,140 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::models::taxonomy::{normalize_name, slugify};
+
+// An author of published articles; articles are linked to it by a normalized name key,
+// so "Abebe  Kebede" and "abebe kebede" are the same person
+#[derive(Debug, Clone, Serialize)]
+pub struct Author {
+    pub id: i32,
+    pub name: String,
+    pub affiliation: String,
+    pub orcid: String,
+    pub article_count: i64,
+}
+
+impl Author {
+    pub fn url(&self) -> String {
+        format!("/authors/{}", self.id)
+    }
+
+    pub fn orcid_url(&self) -> Option<String> {
+        (!self.orcid.is_empty()).then(|| format!("https://orcid.org/{}", self.orcid))
+    }
+
+    // Family name, taken as the last word of the name
+    pub fn surname(&self) -> &str {
+        self.name.split_whitespace().last().unwrap_or(&self.name)
+    }
+
+    // Letter of the author index the author is listed under
+    pub fn index_letter(&self) -> String {
+        self.surname()
+            .chars()
+            .find(|c| c.is_alphanumeric())
+            .map_or_else(|| "#".to_string(), |c| c.to_uppercase().collect())
+    }
+
+    // Authors with the same surname and first initial are probably the same person
+    // ("T. Mulu" and "Tesfaye Mulu")
+    pub fn duplicate_key(&self) -> String {
+        let initial = self
+            .name
+            .chars()
+            .find(|c| c.is_alphanumeric())
+            .map(|c| c.to_lowercase().collect::<String>())
+            .unwrap_or_default();
+        format!("{} {}", initial, slugify(self.surname()))
+    }
+}
+
+pub fn name_key(name: &str) -> String {
+    slugify(name)
+}
+
+// Splits the authors field of an article on commas, semicolons, "&" and "and",
+// dropping blanks and repeats
+pub fn parse_authors(value: &str) -> Vec<String> {
+    let mut seen = Vec::new();
+    let mut authors = Vec::new();
+    for part in value.split([',', ';', '&']) {
+        for name in part.split(" and ").map(normalize_name) {
+            let key = name_key(&name);
+            if !key.is_empty() && !seen.contains(&key) {
+                seen.push(key);
+                authors.push(name);
+            }
+        }
+    }
+    authors
+}
+
+// Accepts a bare ORCID iD or its orcid.org URL and returns the bare iD, checking the
+// ISO 7064 11,2 check digit
+pub fn normalize_orcid(value: &str) -> Option<String> {
+    let value = value.trim();
+    let id = value
+        .strip_prefix("https://orcid.org/")
+        .or_else(|| value.strip_prefix("http://orcid.org/"))
+        .or_else(|| value.strip_prefix("orcid.org/"))
+        .unwrap_or(value)
+        .to_uppercase();
+
+    let chars: Vec<char> = id.chars().filter(|c| *c != '-').collect();
+    let well_formed = id.len() == 19
+        && id.chars().enumerate().all(|(i, c)| match i {
+            4 | 9 | 14 => c == '-',
+            18 => c.is_ascii_digit() || c == 'X',
+            _ => c.is_ascii_digit(),
+        });
+    if !well_formed {
+        return None;
+    }
+
+    let total = chars[..15]
+        .iter()
+        .filter_map(|c| c.to_digit(10))
+        .fold(0, |total, digit| (total + digit) * 2);
+    let check = match (12 - total % 11) % 11 {
+        10 => 'X',
+        digit => char::from_digit(digit, 10)?,
+    };
+    (chars[15] == check).then_some(id)
+}
+
+// Authors that look like the same person, with the one to keep: the fullest name, then
+// the one with most articles
+#[derive(Debug)]
+pub struct DuplicateGroup {
+    pub target: Author,
+    pub duplicates: Vec<Author>,
+}
+
+pub fn find_duplicates(authors: &[Author]) -> Vec<DuplicateGroup> {
+    let mut groups: BTreeMap<String, Vec<Author>> = BTreeMap::new();
+    for author in authors {
+        groups
+            .entry(author.duplicate_key())
+            .or_default()
+            .push(author.clone());
+    }
+
+    groups
+        .into_values()
+        .filter(|group| group.len() > 1)
+        .map(|mut group| {
+            group.sort_by_key(|author| {
+                (
+                    std::cmp::Reverse(author.name.chars().count()),
+                    std::cmp::Reverse(author.article_count),
+                )
+            });
+            let target = group.remove(0);
+            DuplicateGroup {
+                target,
+                duplicates: group,
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,61 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::models::submission::SubmissionDeclarations;
+
+// Server-side copy of a partially filled submission form; `id` doubles as the resume token
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct SubmissionDraft {
+    pub id: String,
+    pub full_name: String,
+    pub email: String,
+    pub phone: String,
+    pub title: String,
+    pub abstract_text: String,
+    pub country: String,
+    #[serde(flatten)]
+    pub declarations: SubmissionDeclarations,
+    pub upload_id: Option<String>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DraftForm {
+    #[serde(default)]
+    pub full_name: String,
+    #[serde(default)]
+    pub email: String,
+    #[serde(default)]
+    pub phone: String,
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub abstract_text: String,
+    #[serde(default)]
+    pub country: String,
+    #[serde(flatten)]
+    pub declarations: SubmissionDeclarations,
+    pub upload_id: Option<String>,
+}
+
+// Where chunks are assembled until the upload completes
+pub const PARTIAL_DIR: &str = "./data/uploads/partial";
+
+// A chunked manuscript upload following the tus core protocol
+#[derive(Debug, Serialize, Clone)]
+pub struct ResumableUpload {
+    pub id: String,
+    pub upload_length: i64,
+    pub upload_offset: i64,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+impl ResumableUpload {
+    pub fn is_complete(&self) -> bool {
+        self.upload_offset >= self.upload_length
+    }
+
+    pub fn partial_path(&self) -> String {
+        format!("{}/{}.part", PARTIAL_DIR, self.id)
+    }
+}
@@ -0,0 +1,215 @@
+use chrono::NaiveDate;
+
+use crate::utils::query::non_empty;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportDataset {
+    Submissions,
+    Articles,
+}
+
+impl ExportDataset {
+    pub const ALL: [ExportDataset; 2] = [ExportDataset::Submissions, ExportDataset::Articles];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExportDataset::Submissions => "submissions",
+            ExportDataset::Articles => "articles",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<ExportDataset> {
+        Self::ALL.into_iter().find(|d| d.as_str() == value)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ExportDataset::Submissions => "Submissions",
+            ExportDataset::Articles => "Published articles",
+        }
+    }
+
+    pub fn columns(&self) -> &'static [ExportColumn] {
+        match self {
+            ExportDataset::Submissions => &SUBMISSION_COLUMNS,
+            ExportDataset::Articles => &ARTICLE_COLUMNS,
+        }
+    }
+
+    // What the date range is matched against
+    pub fn date_label(&self) -> &'static str {
+        match self {
+            ExportDataset::Submissions => "Submitted",
+            ExportDataset::Articles => "Published",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    Xlsx,
+}
+
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 2] = [ExportFormat::Csv, ExportFormat::Xlsx];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Xlsx => "xlsx",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<ExportFormat> {
+        Self::ALL.into_iter().find(|f| f.as_str() == value)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "CSV",
+            ExportFormat::Xlsx => "Excel (XLSX)",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+            ExportFormat::Xlsx => {
+                "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet"
+            }
+        }
+    }
+}
+
+// A column that can be included in an export. Numeric columns are written as numbers
+// in spreadsheets so they can be summed and sorted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExportColumn {
+    pub key: &'static str,
+    pub label: &'static str,
+    pub numeric: bool,
+}
+
+const fn column(key: &'static str, label: &'static str) -> ExportColumn {
+    ExportColumn {
+        key,
+        label,
+        numeric: false,
+    }
+}
+
+const fn numeric_column(key: &'static str, label: &'static str) -> ExportColumn {
+    ExportColumn {
+        key,
+        label,
+        numeric: true,
+    }
+}
+
+pub const SUBMISSION_COLUMNS: [ExportColumn; 11] = [
+    numeric_column("id", "ID"),
+    column("submitted", "Submitted"),
+    column("submitter", "Submitter"),
+    column("email", "Email"),
+    column("phone", "Phone"),
+    column("country", "Country"),
+    column("title", "Title"),
+    column("abstract", "Abstract"),
+    column("status", "Status"),
+    column("editor", "Handling editor"),
+    numeric_column("article_id", "Published article ID"),
+];
+
+pub const ARTICLE_COLUMNS: [ExportColumn; 12] = [
+    numeric_column("id", "ID"),
+    column("title", "Title"),
+    column("authors", "Authors"),
+    column("abstract", "Abstract"),
+    column("keywords", "Keywords"),
+    numeric_column("volume", "Volume"),
+    numeric_column("issue", "Issue"),
+    column("pages", "Pages"),
+    column("published", "Publication date"),
+    column("uploaded", "Uploaded"),
+    numeric_column("downloads", "Downloads"),
+    numeric_column("submission_id", "Submission ID"),
+];
+
+// A validated export: which rows, which columns and in what format
+#[derive(Debug, Clone)]
+pub struct ExportRequest {
+    pub dataset: ExportDataset,
+    pub format: ExportFormat,
+    pub columns: Vec<ExportColumn>,
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+}
+
+impl ExportRequest {
+    // Builds a request from query pairs; `columns` may repeat and keeps the order given.
+    // No columns means all of them.
+    pub fn from_pairs(pairs: &[(String, String)]) -> Result<ExportRequest, String> {
+        let value = |name: &str| {
+            pairs
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.trim().to_string())
+        };
+
+        let dataset = value("dataset").unwrap_or_default();
+        let dataset =
+            ExportDataset::parse(&dataset).ok_or_else(|| format!("Unknown export: {}", dataset))?;
+        let format = value("format").unwrap_or_else(|| "csv".to_string());
+        let format =
+            ExportFormat::parse(&format).ok_or_else(|| format!("Unknown format: {}", format))?;
+
+        let mut columns = Vec::new();
+        for (_, key) in pairs.iter().filter(|(key, _)| key == "columns") {
+            let column = dataset
+                .columns()
+                .iter()
+                .find(|c| c.key == key.as_str())
+                .ok_or_else(|| format!("Unknown column: {}", key))?;
+            if !columns.contains(column) {
+                columns.push(*column);
+            }
+        }
+        if columns.is_empty() {
+            columns = dataset.columns().to_vec();
+        }
+
+        let date = |name: &str| -> Result<Option<NaiveDate>, String> {
+            match non_empty(&value(name)) {
+                None => Ok(None),
+                Some(text) => NaiveDate::parse_from_str(text, "%Y-%m-%d")
+                    .map(Some)
+                    .map_err(|_| format!("Dates must look like 2024-01-31, not {}", text)),
+            }
+        };
+        let from = date("from")?;
+        let to = date("to")?;
+        if let (Some(from), Some(to)) = (from, to) {
+            if from > to {
+                return Err("The start date is after the end date".to_string());
+            }
+        }
+
+        Ok(ExportRequest {
+            dataset,
+            format,
+            columns,
+            from,
+            to,
+        })
+    }
+
+    pub fn filename(&self, today: NaiveDate) -> String {
+        format!(
+            "{}-{}.{}",
+            self.dataset.as_str(),
+            today.format("%Y%m%d"),
+            self.format.as_str()
+        )
+    }
+}
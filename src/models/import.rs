@@ -0,0 +1,136 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::Serialize;
+
+use crate::models::journals::Journal;
+
+// Manifest columns, each with the other names accepted for it
+const FIELDS: [(&str, &[&str]); 9] = [
+    ("title", &[]),
+    ("authors", &[]),
+    ("abstract", &["abstract_text"]),
+    ("keywords", &[]),
+    ("volume", &["volume_number"]),
+    ("issue", &["issue_number"]),
+    ("pages", &[]),
+    ("date", &["publication_date"]),
+    ("file", &["pdf"]),
+];
+
+// The manifest field a CSV column or JSON key stands for
+pub fn field_name(column: &str) -> Option<&'static str> {
+    let column = column.trim().to_lowercase();
+    FIELDS
+        .iter()
+        .find(|(name, aliases)| *name == column || aliases.contains(&column.as_str()))
+        .map(|(name, _)| *name)
+}
+
+pub fn field_names() -> impl Iterator<Item = &'static str> {
+    FIELDS.iter().map(|(name, _)| *name)
+}
+
+// One back-issue article as described by an import manifest, before validation
+#[derive(Debug, Clone, Default)]
+pub struct ManifestRow {
+    pub title: String,
+    pub authors: String,
+    pub abstract_text: String,
+    pub keywords: String,
+    pub volume: String,
+    pub issue: String,
+    pub pages: String,
+    pub date: String,
+    // Path of the article PDF inside the archive
+    pub file: String,
+}
+
+impl ManifestRow {
+    pub fn set(&mut self, field: &str, value: String) {
+        let value = value.trim().to_string();
+        match field {
+            "title" => self.title = value,
+            "authors" => self.authors = value,
+            "abstract" => self.abstract_text = value,
+            "keywords" => self.keywords = value,
+            "volume" => self.volume = value,
+            "issue" => self.issue = value,
+            "pages" => self.pages = value,
+            "date" => self.date = value,
+            "file" => self.file = value,
+            _ => {}
+        }
+    }
+
+    // The article this row describes, with no PDF yet, or everything wrong with the row
+    pub fn to_journal(&self, today: NaiveDate) -> Result<Journal, Vec<String>> {
+        let mut errors = Vec::new();
+        for (value, label) in [
+            (&self.title, "Title"),
+            (&self.authors, "Authors"),
+            (&self.abstract_text, "Abstract"),
+            (&self.keywords, "Keywords"),
+            (&self.pages, "Pages"),
+            (&self.file, "File"),
+        ] {
+            if value.is_empty() {
+                errors.push(format!("{} is required", label));
+            }
+        }
+
+        let mut number = |value: &str, label: &str| match value.parse::<i32>() {
+            Ok(n) if n > 0 => n,
+            _ => {
+                errors.push(format!(
+                    "{} must be a whole number above 0, not '{}'",
+                    label, value
+                ));
+                0
+            }
+        };
+        let volume_number = number(&self.volume, "Volume");
+        let issue_number = number(&self.issue, "Issue");
+
+        let date = match NaiveDate::parse_from_str(&self.date, "%Y-%m-%d") {
+            Ok(date) if date > today => {
+                errors.push(format!("Date {} is in the future", self.date));
+                None
+            }
+            Ok(date) => Some(date),
+            Err(_) => {
+                errors.push(format!(
+                    "Date must look like 2019-06-30, not '{}'",
+                    self.date
+                ));
+                None
+            }
+        };
+
+        match date {
+            Some(date) if errors.is_empty() => Ok(Journal::new(
+                self.title.clone(),
+                self.authors.clone(),
+                self.abstract_text.clone(),
+                self.keywords.clone(),
+                volume_number,
+                issue_number,
+                self.pages.clone(),
+                DateTime::<Utc>::from_naive_utc_and_offset(
+                    date.and_hms_opt(0, 0, 0).unwrap_or_default(),
+                    Utc,
+                ),
+                String::new(),
+            )),
+            _ => Err(errors),
+        }
+    }
+}
+
+// The outcome for one manifest row; rows are numbered from 1, not counting a CSV header
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportRowResult {
+    pub row: usize,
+    pub title: String,
+    pub file: String,
+    pub errors: Vec<String>,
+    pub journal_id: Option<i32>,
+}
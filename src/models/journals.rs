@@ -17,6 +17,7 @@ pub struct Journal {
 }
 
 impl Journal {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         title: String,
         authors: String,
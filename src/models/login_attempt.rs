@@ -0,0 +1,23 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+// Audit record of one login attempt; `reason` explains why a failed attempt was refused
+#[derive(Debug, Serialize)]
+pub struct LoginAttempt {
+    pub id: i64,
+    pub email: String,
+    pub ip_address: String,
+    pub succeeded: bool,
+    pub reason: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl LoginAttempt {
+    pub fn formatted_date(&self) -> String {
+        self.created_at.format("%Y-%m-%d %H:%M:%S").to_string()
+    }
+
+    pub fn reason_display(&self) -> &str {
+        self.reason.as_deref().unwrap_or("")
+    }
+}
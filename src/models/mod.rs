@@ -1,4 +1,22 @@
 pub mod admin;
+pub mod analytics;
+pub mod archive;
+pub mod attachment;
+pub mod audit;
+pub mod auth_token;
+pub mod author;
+pub mod draft;
+pub mod export;
+pub mod import;
 pub mod journals;
+pub mod login_attempt;
+pub mod notice;
 pub mod response;
+pub mod review;
+pub mod session;
+pub mod similarity;
 pub mod submission;
+pub mod taxonomy;
+pub mod trash;
+pub mod usage;
+pub mod version;
@@ -0,0 +1,90 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+// Kinds of post-publication notice that can be attached to an article
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NoticeKind {
+    Erratum,
+    Correction,
+    Retraction,
+}
+
+impl NoticeKind {
+    pub const ALL: [NoticeKind; 3] = [
+        NoticeKind::Erratum,
+        NoticeKind::Correction,
+        NoticeKind::Retraction,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NoticeKind::Erratum => "erratum",
+            NoticeKind::Correction => "correction",
+            NoticeKind::Retraction => "retraction",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        NoticeKind::ALL.into_iter().find(|k| k.as_str() == value)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            NoticeKind::Erratum => "Erratum",
+            NoticeKind::Correction => "Correction",
+            NoticeKind::Retraction => "Retraction",
+        }
+    }
+}
+
+// An erratum, correction or retraction published against an article
+#[derive(Debug, Clone, Serialize)]
+pub struct JournalNotice {
+    pub id: i32,
+    pub journal_id: i32,
+    pub kind: NoticeKind,
+    pub title: String,
+    pub body: String,
+    pub published_at: DateTime<Utc>,
+    #[serde(skip)]
+    pub created_by: Option<String>,
+}
+
+impl JournalNotice {
+    pub fn url(&self) -> String {
+        format!("/notices/{}", self.id)
+    }
+
+    pub fn formatted_date(&self) -> String {
+        self.published_at.format("%B %-d, %Y").to_string()
+    }
+
+    pub fn is_retraction(&self) -> bool {
+        self.kind == NoticeKind::Retraction
+    }
+
+    pub fn created_by_display(&self) -> &str {
+        self.created_by.as_deref().unwrap_or("Unknown")
+    }
+}
+
+// How a notice is listed alongside its article in the JSON API
+#[derive(Debug, Serialize)]
+pub struct NoticeLink {
+    pub kind: NoticeKind,
+    pub title: String,
+    pub url: String,
+    pub published_at: DateTime<Utc>,
+}
+
+impl From<&JournalNotice> for NoticeLink {
+    fn from(notice: &JournalNotice) -> Self {
+        Self {
+            kind: notice.kind,
+            title: notice.title.clone(),
+            url: notice.url(),
+            published_at: notice.published_at,
+        }
+    }
+}
@@ -1,3 +1,5 @@
+use crate::models::import::ImportRowResult;
+use crate::utils::pdf::PdfMetadata;
 use serde::Serialize;
 
 #[derive(Serialize)]
@@ -7,6 +9,13 @@ pub struct SubmissionResponse {
     pub message: String,
 }
 
+#[derive(Serialize)]
+pub struct DraftResponse {
+    pub success: bool,
+    pub draft_id: String,
+    pub message: String,
+}
+
 #[derive(Serialize)]
 pub struct UploadResponse {
     pub success: bool,
@@ -14,6 +23,24 @@ pub struct UploadResponse {
     pub message: String,
 }
 
+// Nothing is imported unless every row is valid; `rows` says what was wrong with each
+#[derive(Serialize)]
+pub struct ImportResponse {
+    pub success: bool,
+    pub dry_run: bool,
+    pub imported: usize,
+    pub message: String,
+    pub rows: Vec<ImportRowResult>,
+    // PDFs in the archive that no row refers to; they are not imported
+    pub unused_files: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct MetadataSuggestionResponse {
+    pub success: bool,
+    pub metadata: PdfMetadata,
+}
+
 #[derive(Serialize)]
 pub struct ValidationResponse {
     pub field: String,
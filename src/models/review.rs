@@ -0,0 +1,93 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+// An editor's decision on a submission. Acceptance and rejection close it; a request
+// for revisions leaves it open for another round.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Decision {
+    Accept,
+    MinorRevision,
+    MajorRevision,
+    Reject,
+}
+
+impl Decision {
+    pub const ALL: [Decision; 4] = [
+        Decision::Accept,
+        Decision::MinorRevision,
+        Decision::MajorRevision,
+        Decision::Reject,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Decision::Accept => "accept",
+            Decision::MinorRevision => "minor_revision",
+            Decision::MajorRevision => "major_revision",
+            Decision::Reject => "reject",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Decision::ALL.into_iter().find(|d| d.as_str() == value)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Decision::Accept => "Accept",
+            Decision::MinorRevision => "Minor revisions",
+            Decision::MajorRevision => "Major revisions",
+            Decision::Reject => "Reject",
+        }
+    }
+
+    pub fn is_final(&self) -> bool {
+        matches!(self, Decision::Accept | Decision::Reject)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SubmissionDecision {
+    pub id: i32,
+    pub submission_id: i32,
+    pub decision: Decision,
+    pub note: String,
+    // Email of the editor who made it; empty if their account was removed
+    pub decided_by: String,
+    pub decided_at: DateTime<Utc>,
+}
+
+impl SubmissionDecision {
+    pub fn formatted_date(&self) -> String {
+        self.decided_at.format("%Y-%m-%d %H:%M").to_string()
+    }
+}
+
+// A reviewer invited to review a submission and, once done, their recommendation
+#[derive(Debug, Clone, Serialize)]
+pub struct ReviewAssignment {
+    pub id: i32,
+    pub submission_id: i32,
+    pub reviewer_id: i32,
+    pub reviewer_email: String,
+    pub assigned_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub recommendation: Option<Decision>,
+}
+
+impl ReviewAssignment {
+    pub fn is_complete(&self) -> bool {
+        self.completed_at.is_some()
+    }
+
+    pub fn formatted_assigned(&self) -> String {
+        self.assigned_at.format("%Y-%m-%d").to_string()
+    }
+
+    pub fn formatted_completed(&self) -> String {
+        self.completed_at
+            .map(|at| at.format("%Y-%m-%d").to_string())
+            .unwrap_or_default()
+    }
+}
@@ -0,0 +1,33 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+// A signed-in browser, as listed on the account's sessions page.
+// `login_id` identifies it publicly; the cookie value itself is never stored.
+#[derive(Debug, Serialize)]
+pub struct ActiveSession {
+    pub login_id: String,
+    pub admin_id: i32,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_seen_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl ActiveSession {
+    pub fn formatted_created(&self) -> String {
+        self.created_at.format("%Y-%m-%d %H:%M").to_string()
+    }
+
+    pub fn formatted_last_seen(&self) -> String {
+        self.last_seen_at.format("%Y-%m-%d %H:%M").to_string()
+    }
+
+    pub fn ip_display(&self) -> &str {
+        self.ip_address.as_deref().unwrap_or("Unknown")
+    }
+
+    pub fn user_agent_display(&self) -> &str {
+        self.user_agent.as_deref().unwrap_or("Unknown browser")
+    }
+}
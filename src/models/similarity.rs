@@ -0,0 +1,37 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SimilarityMatch {
+    pub id: Option<i32>,
+    pub submission_id: i32,
+    pub matched_type: String, // "submission" or "journal"
+    pub matched_id: i32,
+    pub matched_title: String,
+    pub text_score: f64,
+    pub abstract_score: f64,
+    pub flagged: bool,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+impl SimilarityMatch {
+    pub fn score(&self) -> f64 {
+        self.text_score.max(self.abstract_score)
+    }
+
+    pub fn text_percent(&self) -> String {
+        format!("{:.0}%", self.text_score * 100.0)
+    }
+
+    pub fn abstract_percent(&self) -> String {
+        format!("{:.0}%", self.abstract_score * 100.0)
+    }
+
+    // Link to the matched record for the editor
+    pub fn matched_url(&self) -> String {
+        match self.matched_type.as_str() {
+            "journal" => format!("/journals/{}", self.matched_id),
+            _ => format!("/admin/submissions/{}", self.matched_id),
+        }
+    }
+}
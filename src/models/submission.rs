@@ -1,19 +1,57 @@
-use crate::models::response::ValidationResponse;
-use chrono::{DateTime, Utc};
+use crate::models::{response::ValidationResponse, taxonomy::normalize_name};
+use crate::utils::query::{encode_query_value, non_empty};
+use chrono::{DateTime, NaiveDate, Utc};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::path::Path;
 
+// Author declarations and editorial extras captured with a submission
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct SubmissionDeclarations {
+    pub confirms_originality: bool,
+    pub conflict_of_interest: String,
+    pub ethics_approval: String,
+    pub funding_statement: String,
+    pub agrees_to_license: bool,
+    pub cover_letter: String,
+    pub suggested_reviewers: String,
+    pub opposed_reviewers: String,
+}
+
+impl SubmissionDeclarations {
+    // Reviewers are entered one per line as "Name, email"
+    pub fn reviewer_lines(value: &str) -> Vec<String> {
+        value
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .map(|l| l.to_string())
+            .collect()
+    }
+
+    pub fn suggested_reviewer_list(&self) -> Vec<String> {
+        Self::reviewer_lines(&self.suggested_reviewers)
+    }
+
+    pub fn opposed_reviewer_list(&self) -> Vec<String> {
+        Self::reviewer_lines(&self.opposed_reviewers)
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Submission {
     pub id: Option<i32>,
     pub full_name: String,
     pub email: String,
     pub phone: String,
+    // Country the submitting author is based in
+    pub country: String,
     pub title: String,
     pub abstract_text: String,
     pub pdf_url: String,
     pub created_at: Option<DateTime<Utc>>,
+    pub declarations: SubmissionDeclarations,
 }
 
 impl Submission {
@@ -31,13 +69,25 @@ impl Submission {
             full_name,
             email,
             phone,
+            country: String::new(),
             title,
             abstract_text,
             pdf_url,
             created_at,
+            declarations: SubmissionDeclarations::default(),
         }
     }
 
+    pub fn with_country(mut self, country: &str) -> Self {
+        self.country = normalize_name(country);
+        self
+    }
+
+    pub fn with_declarations(mut self, declarations: SubmissionDeclarations) -> Self {
+        self.declarations = declarations;
+        self
+    }
+
     fn is_valid_email(email: &str) -> bool {
         let email_regex = Regex::new(r"^[a-zA-Z0-9._%+-]+@[a-zA-Z0-9.-]+\.[a-zA-Z]{2,}$").unwrap();
         email_regex.is_match(email)
@@ -67,6 +117,13 @@ impl Submission {
             });
         }
 
+        if self.country.is_empty() {
+            validation_errors.push(ValidationResponse {
+                field: "country".to_string(),
+                message: "Country cannot be empty".to_string(),
+            });
+        }
+
         if self.title.len() < 10 {
             validation_errors.push(ValidationResponse {
                 field: "title".to_string(),
@@ -81,6 +138,8 @@ impl Submission {
             });
         }
 
+        self.validate_declarations(&mut validation_errors);
+
         if validation_errors.is_empty() {
             Ok(())
         } else {
@@ -88,6 +147,76 @@ impl Submission {
         }
     }
 
+    fn validate_declarations(&self, validation_errors: &mut Vec<ValidationResponse>) {
+        let declarations = &self.declarations;
+
+        if !declarations.confirms_originality {
+            validation_errors.push(ValidationResponse {
+                field: "confirms_originality".to_string(),
+                message:
+                    "You must confirm the manuscript is original and not under review elsewhere"
+                        .to_string(),
+            });
+        }
+
+        if !declarations.agrees_to_license {
+            validation_errors.push(ValidationResponse {
+                field: "agrees_to_license".to_string(),
+                message: "You must agree to the publication license".to_string(),
+            });
+        }
+
+        let statements = [
+            (
+                "conflict_of_interest",
+                &declarations.conflict_of_interest,
+                "Conflict of interest statement",
+            ),
+            (
+                "ethics_approval",
+                &declarations.ethics_approval,
+                "Ethics approval statement",
+            ),
+            (
+                "funding_statement",
+                &declarations.funding_statement,
+                "Funding statement",
+            ),
+        ];
+        for (field, value, label) in statements {
+            if value.trim().is_empty() {
+                validation_errors.push(ValidationResponse {
+                    field: field.to_string(),
+                    message: format!("{} is required (enter \"None\" if not applicable)", label),
+                });
+            }
+        }
+
+        let reviewers = [
+            (
+                "suggested_reviewers",
+                declarations.suggested_reviewer_list(),
+            ),
+            ("opposed_reviewers", declarations.opposed_reviewer_list()),
+        ];
+        for (field, lines) in reviewers {
+            for line in lines {
+                let has_email = line
+                    .split([',', ' ', '<', '>'])
+                    .any(|part| Self::is_valid_email(part.trim()));
+                if !has_email {
+                    validation_errors.push(ValidationResponse {
+                        field: field.to_string(),
+                        message: format!(
+                            "Reviewer \"{}\" must include a valid email address",
+                            line
+                        ),
+                    });
+                }
+            }
+        }
+    }
+
     pub fn pdf_filename(&self) -> Option<String> {
         Path::new(&self.pdf_url)
             .file_name()
@@ -101,3 +230,178 @@ impl Submission {
             .unwrap_or_else(|| "N/A".to_string())
     }
 }
+
+// Where a submission stands, derived from its editorial history: reviewers assigned,
+// the latest decision, and whether it has been published
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SubmissionStatus {
+    New,
+    UnderReview,
+    MinorRevision,
+    MajorRevision,
+    Accepted,
+    Rejected,
+    Published,
+}
+
+impl SubmissionStatus {
+    pub const ALL: [SubmissionStatus; 7] = [
+        SubmissionStatus::New,
+        SubmissionStatus::UnderReview,
+        SubmissionStatus::MinorRevision,
+        SubmissionStatus::MajorRevision,
+        SubmissionStatus::Accepted,
+        SubmissionStatus::Rejected,
+        SubmissionStatus::Published,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SubmissionStatus::New => "new",
+            SubmissionStatus::UnderReview => "under_review",
+            SubmissionStatus::MinorRevision => "minor_revision",
+            SubmissionStatus::MajorRevision => "major_revision",
+            SubmissionStatus::Accepted => "accepted",
+            SubmissionStatus::Rejected => "rejected",
+            SubmissionStatus::Published => "published",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        SubmissionStatus::ALL
+            .into_iter()
+            .find(|s| s.as_str() == value)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SubmissionStatus::New => "New",
+            SubmissionStatus::UnderReview => "Under review",
+            SubmissionStatus::MinorRevision => "Minor revisions",
+            SubmissionStatus::MajorRevision => "Major revisions",
+            SubmissionStatus::Accepted => "Accepted",
+            SubmissionStatus::Rejected => "Rejected",
+            SubmissionStatus::Published => "Published",
+        }
+    }
+}
+
+// A row of the admin submission list
+#[derive(Debug)]
+pub struct SubmissionListItem {
+    pub submission: Submission,
+    pub status: SubmissionStatus,
+    // Email of the handling editor, if one is assigned
+    pub editor: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubmissionSort {
+    Newest,
+    Oldest,
+    Title,
+    Submitter,
+    Status,
+}
+
+impl SubmissionSort {
+    pub const ALL: [SubmissionSort; 5] = [
+        SubmissionSort::Newest,
+        SubmissionSort::Oldest,
+        SubmissionSort::Title,
+        SubmissionSort::Submitter,
+        SubmissionSort::Status,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SubmissionSort::Newest => "newest",
+            SubmissionSort::Oldest => "oldest",
+            SubmissionSort::Title => "title",
+            SubmissionSort::Submitter => "submitter",
+            SubmissionSort::Status => "status",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        SubmissionSort::ALL
+            .into_iter()
+            .find(|s| s.as_str() == value)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SubmissionSort::Newest => "Newest first",
+            SubmissionSort::Oldest => "Oldest first",
+            SubmissionSort::Title => "Title (A-Z)",
+            SubmissionSort::Submitter => "Submitter (A-Z)",
+            SubmissionSort::Status => "Status",
+        }
+    }
+}
+
+// Query-string filters for the admin submission list; blank fields are ignored
+#[derive(Debug, Default, Deserialize)]
+pub struct SubmissionFilter {
+    // Matched against the title, the submitter's name and their email
+    pub q: Option<String>,
+    pub status: Option<String>,
+    // A user ID, or "none" for submissions without a handling editor
+    pub editor: Option<String>,
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub sort: Option<String>,
+    pub page: Option<i64>,
+    // Limits the list to this reviewer's assignments; never taken from the query string
+    #[serde(skip)]
+    pub reviewer_id: Option<i32>,
+}
+
+impl SubmissionFilter {
+    pub fn search(&self) -> Option<&str> {
+        non_empty(&self.q)
+    }
+
+    pub fn status(&self) -> Option<SubmissionStatus> {
+        non_empty(&self.status).and_then(SubmissionStatus::parse)
+    }
+
+    pub fn editor(&self) -> Option<&str> {
+        non_empty(&self.editor)
+    }
+
+    pub fn from_date(&self) -> Option<NaiveDate> {
+        non_empty(&self.from).and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+    }
+
+    pub fn to_date(&self) -> Option<NaiveDate> {
+        non_empty(&self.to).and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+    }
+
+    pub fn sort(&self) -> SubmissionSort {
+        non_empty(&self.sort)
+            .and_then(SubmissionSort::parse)
+            .unwrap_or(SubmissionSort::Newest)
+    }
+
+    pub fn page(&self) -> i64 {
+        self.page.unwrap_or(1).max(1)
+    }
+
+    // The filters and sort as a query string, for pagination links and bulk actions
+    pub fn query_string(&self) -> String {
+        [
+            ("q", self.search()),
+            ("status", non_empty(&self.status)),
+            ("editor", self.editor()),
+            ("from", non_empty(&self.from)),
+            ("to", non_empty(&self.to)),
+            ("sort", non_empty(&self.sort)),
+        ]
+        .iter()
+        .filter_map(|(key, value)| value.map(|v| format!("{}={}", key, encode_query_value(v))))
+        .collect::<Vec<_>>()
+        .join("&")
+    }
+}
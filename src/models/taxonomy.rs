@@ -0,0 +1,65 @@
+use serde::Serialize;
+
+// A normalized keyword; articles are linked to it by slug, so "Mobile  learning" and
+// "mobile learning" are the same keyword
+#[derive(Debug, Clone, Serialize)]
+pub struct Keyword {
+    pub id: i32,
+    pub name: String,
+    pub slug: String,
+    pub article_count: i64,
+}
+
+impl Keyword {
+    pub fn url(&self) -> String {
+        format!("/keywords/{}", self.slug)
+    }
+}
+
+// A broad subject area curated by the editors
+#[derive(Debug, Clone, Serialize)]
+pub struct Subject {
+    pub id: i32,
+    pub name: String,
+    pub slug: String,
+    pub description: String,
+    pub article_count: i64,
+}
+
+impl Subject {
+    pub fn url(&self) -> String {
+        format!("/subjects/{}", self.slug)
+    }
+}
+
+// Trims a keyword and collapses internal runs of whitespace
+pub fn normalize_name(value: &str) -> String {
+    value.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+// Lowercase letters and digits separated by single hyphens
+pub fn slugify(value: &str) -> String {
+    let mut slug = String::with_capacity(value.len());
+    for c in value.chars().flat_map(char::to_lowercase) {
+        if c.is_alphanumeric() {
+            slug.push(c);
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.trim_end_matches('-').to_string()
+}
+
+// Splits the keywords field of an article, dropping blanks and repeats
+pub fn parse_keywords(value: &str) -> Vec<String> {
+    let mut seen = Vec::new();
+    let mut keywords = Vec::new();
+    for keyword in value.split([',', ';']).map(normalize_name) {
+        let slug = slugify(&keyword);
+        if !slug.is_empty() && !seen.contains(&slug) {
+            seen.push(slug);
+            keywords.push(keyword);
+        }
+    }
+    keywords
+}
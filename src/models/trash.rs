@@ -0,0 +1,59 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+// What a trash entry refers to; used in the trash routes and audit records
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrashKind {
+    Journal,
+    Submission,
+}
+
+impl TrashKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TrashKind::Journal => "journal",
+            TrashKind::Submission => "submission",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "journal" => Some(TrashKind::Journal),
+            "submission" => Some(TrashKind::Submission),
+            _ => None,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            TrashKind::Journal => "Article",
+            TrashKind::Submission => "Submission",
+        }
+    }
+}
+
+// An article or submission sitting in the trash, waiting to be restored or purged
+#[derive(Debug, Serialize)]
+pub struct TrashedItem {
+    pub kind: TrashKind,
+    pub id: i32,
+    pub title: String,
+    pub deleted_at: DateTime<Utc>,
+    pub deleted_by: Option<String>,
+    pub purge_after: DateTime<Utc>,
+}
+
+impl TrashedItem {
+    pub fn formatted_deleted(&self) -> String {
+        self.deleted_at.format("%Y-%m-%d %H:%M").to_string()
+    }
+
+    pub fn formatted_purge_after(&self) -> String {
+        self.purge_after.format("%Y-%m-%d").to_string()
+    }
+
+    pub fn deleted_by_display(&self) -> &str {
+        self.deleted_by.as_deref().unwrap_or("Unknown")
+    }
+}
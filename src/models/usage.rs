@@ -0,0 +1,87 @@
+use chrono::{Datelike, Months, NaiveDate};
+use serde::Serialize;
+
+// What a reader did with an article: opened its page or fetched its PDF. In COUNTER
+// terms a view is an investigation, and a download is a request (which also counts as
+// an investigation).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UsageMetric {
+    View,
+    Download,
+}
+
+impl UsageMetric {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UsageMetric::View => "view",
+            UsageMetric::Download => "download",
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct UsageTotals {
+    pub views: i64,
+    pub unique_views: i64,
+    pub downloads: i64,
+    pub unique_downloads: i64,
+}
+
+impl UsageTotals {
+    pub fn investigations(&self) -> i64 {
+        self.views + self.downloads
+    }
+
+    pub fn unique_investigations(&self) -> i64 {
+        self.unique_views + self.unique_downloads
+    }
+}
+
+// Usage of the whole site on one day
+#[derive(Debug, Clone, Serialize)]
+pub struct DailyUsage {
+    pub day: String,
+    pub views: i64,
+    pub downloads: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ArticleUsage {
+    pub journal_id: i32,
+    pub title: String,
+    pub totals: UsageTotals,
+}
+
+// An article's usage per calendar month ("YYYY-MM"), for the monthly report
+#[derive(Debug, Clone)]
+pub struct MonthlyArticleUsage {
+    pub journal_id: i32,
+    pub title: String,
+    pub authors: String,
+    pub publication_date: String,
+    pub months: Vec<(String, UsageTotals)>,
+}
+
+impl MonthlyArticleUsage {
+    pub fn month(&self, month: &str) -> UsageTotals {
+        self.months
+            .iter()
+            .find(|(m, _)| m == month)
+            .map(|(_, totals)| totals.clone())
+            .unwrap_or_default()
+    }
+}
+
+// First day of each month from `begin` to `end`, inclusive
+pub fn months_between(begin: NaiveDate, end: NaiveDate) -> Vec<NaiveDate> {
+    let mut months = Vec::new();
+    let mut month = begin.with_day(1).unwrap_or(begin);
+    while month <= end {
+        months.push(month);
+        match month.checked_add_months(Months::new(1)) {
+            Some(next) => month = next,
+            None => break,
+        }
+    }
+    months
+}
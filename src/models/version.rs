@@ -0,0 +1,93 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use super::journals::Journal;
+
+// A stored state of a published article. Version 1 is the article as first published;
+// every edit or rollback adds the next number, so the latest version matches the live record.
+#[derive(Debug, Clone, Serialize)]
+pub struct JournalVersion {
+    pub journal_id: i32,
+    pub version_number: i32,
+    pub journal: Journal,
+    pub change_note: String,
+    pub created_by: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl JournalVersion {
+    pub fn formatted_created(&self) -> String {
+        self.created_at.format("%Y-%m-%d %H:%M").to_string()
+    }
+
+    pub fn formatted_date(&self) -> String {
+        self.created_at.format("%B %-d, %Y").to_string()
+    }
+
+    pub fn created_by_display(&self) -> &str {
+        self.created_by.as_deref().unwrap_or("Unknown")
+    }
+
+    // Names of the fields that differ from an earlier version
+    pub fn changed_fields(&self, earlier: &JournalVersion) -> Vec<&'static str> {
+        let (a, b) = (&earlier.journal, &self.journal);
+        let mut fields = Vec::new();
+        if a.title != b.title {
+            fields.push("title");
+        }
+        if a.authors != b.authors {
+            fields.push("authors");
+        }
+        if a.abstract_text != b.abstract_text {
+            fields.push("abstract");
+        }
+        if a.keywords != b.keywords {
+            fields.push("keywords");
+        }
+        if a.volume_number != b.volume_number || a.issue_number != b.issue_number {
+            fields.push("volume/issue");
+        }
+        if a.pages != b.pages {
+            fields.push("pages");
+        }
+        if a.publication_date != b.publication_date {
+            fields.push("publication date");
+        }
+        if a.pdf_url != b.pdf_url {
+            fields.push("PDF");
+        }
+        fields
+    }
+}
+
+// A version together with what it changed from the one before it
+#[derive(Debug, Serialize)]
+pub struct VersionEntry {
+    pub version: JournalVersion,
+    pub changes: Vec<&'static str>,
+}
+
+impl VersionEntry {
+    // Pairs each version (newest first) with the fields it changed
+    pub fn from_versions(versions: Vec<JournalVersion>) -> Vec<VersionEntry> {
+        let changes: Vec<Vec<&'static str>> = versions
+            .iter()
+            .enumerate()
+            .map(|(i, version)| {
+                versions
+                    .get(i + 1)
+                    .map(|earlier| version.changed_fields(earlier))
+                    .unwrap_or_default()
+            })
+            .collect();
+        versions
+            .into_iter()
+            .zip(changes)
+            .map(|(version, changes)| VersionEntry { version, changes })
+            .collect()
+    }
+
+    pub fn changes_display(&self) -> String {
+        self.changes.join(", ")
+    }
+}
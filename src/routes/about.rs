@@ -8,5 +8,7 @@ struct AboutTemplate {}
 
 #[get("/about")]
 pub async fn about_handler() -> impl Responder {
-    HttpResponse::Ok().body(AboutTemplate {}.render().unwrap())
+    HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(AboutTemplate {}.render().unwrap())
 }
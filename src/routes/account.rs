@@ -0,0 +1,703 @@
+use actix_session::Session;
+use actix_web::{get, post, web, Error as ActixError, HttpResponse};
+use askama::Template;
+use log::{error, info, warn};
+use serde::Deserialize;
+
+use crate::{
+    auth::{csrf::CsrfToken, session_store::LOGIN_ID_KEY, totp, AuthenticatedUser},
+    config::{get_base_url, get_session_config},
+    db::{
+        admin_repository::AdminRepository, auth_token_repository::AuthTokenRepository,
+        schema::init_db, session_repository::SessionRepository,
+        two_factor_repository::TwoFactorRepository,
+    },
+    errors::SubmissionError,
+    models::{admin::Admin, auth_token::TokenPurpose, session::ActiveSession},
+    utils::{
+        mail::send_email,
+        security::{hash_password, validate_new_password, verify_password},
+    },
+};
+
+#[derive(Template)]
+#[template(path = "admin/forgot_password.html")]
+struct ForgotPasswordTemplate {
+    sent: bool,
+    csrf_token: String,
+}
+
+#[derive(Template)]
+#[template(path = "admin/reset_password.html")]
+struct ResetPasswordTemplate {
+    token: String,
+    valid: bool,
+    error: Option<String>,
+    csrf_token: String,
+}
+
+#[derive(Template)]
+#[template(path = "admin/auth_message.html")]
+struct AuthMessageTemplate {
+    heading: &'static str,
+    message: String,
+}
+
+#[derive(Template)]
+#[template(path = "admin/change_password.html")]
+struct ChangePasswordTemplate {
+    current_page: &'static str,
+    error: Option<String>,
+    notice: Option<String>,
+    csrf_token: String,
+}
+
+#[derive(Template)]
+#[template(path = "admin/two_factor.html")]
+struct TwoFactorTemplate {
+    current_page: &'static str,
+    enabled: bool,
+    required: bool,
+    setup_secret: String,
+    qr_svg: String,
+    recovery_codes: Vec<String>,
+    remaining_codes: i64,
+    error: Option<String>,
+    notice: Option<String>,
+    csrf_token: String,
+}
+
+#[derive(Template)]
+#[template(path = "admin/sessions.html")]
+struct SessionsTemplate {
+    current_page: &'static str,
+    sessions: Vec<ActiveSession>,
+    current_login_id: String,
+    notice: Option<String>,
+    csrf_token: String,
+}
+
+#[derive(Deserialize)]
+pub struct ForgotPasswordForm {
+    email: String,
+}
+
+#[derive(Deserialize)]
+pub struct ResetPasswordForm {
+    password: String,
+    confirm_password: String,
+}
+
+#[derive(Deserialize)]
+pub struct ChangePasswordForm {
+    current_password: String,
+    password: String,
+    confirm_password: String,
+}
+
+#[derive(Deserialize)]
+pub struct EnableTwoFactorForm {
+    code: String,
+}
+
+#[derive(Deserialize)]
+pub struct ConfirmPasswordForm {
+    password: String,
+}
+
+fn html(template: impl Template) -> Result<String, SubmissionError> {
+    template
+        .render()
+        .map_err(|e| SubmissionError::InternalError(format!("Template error: {}", e)))
+}
+
+pub(crate) fn auth_message(
+    heading: &'static str,
+    message: String,
+) -> Result<String, SubmissionError> {
+    html(AuthMessageTemplate { heading, message })
+}
+
+async fn hash_in_background(password: String) -> Result<String, SubmissionError> {
+    Ok(web::block(move || hash_password(&password))
+        .await
+        .map_err(|e| SubmissionError::InternalError(format!("Hashing task failed: {}", e)))??)
+}
+
+async fn check_password(
+    user: &AuthenticatedUser,
+    password: String,
+) -> Result<bool, SubmissionError> {
+    let stored_hash = user.password_hash.clone();
+    web::block(move || verify_password(&password, &stored_hash))
+        .await
+        .map_err(|e| {
+            SubmissionError::InternalError(format!("Password verification task failed: {}", e))
+        })?
+        .map_err(SubmissionError::from)
+}
+
+// Emails a fresh verification link, invalidating any earlier one
+pub(crate) async fn send_verification_email(
+    admin_id: i32,
+    email: &str,
+) -> Result<(), SubmissionError> {
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let token =
+        AuthTokenRepository::new(conn).issue_token(admin_id, TokenPurpose::EmailVerification)?;
+
+    let body = format!(
+        "Please confirm your email address for the Akungba Journal editorial system by \
+         opening the link below:\n\n{}/admin/verify-email/{}\n\nThe link expires in {} days.",
+        get_base_url(),
+        token,
+        TokenPurpose::EmailVerification.lifetime().num_days()
+    );
+    send_email(email, "Confirm your email address", body).await
+}
+
+#[get("/admin/forgot-password")]
+pub async fn forgot_password_form_handler(
+    csrf: CsrfToken,
+) -> Result<HttpResponse, SubmissionError> {
+    Ok(HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(html(ForgotPasswordTemplate {
+            sent: false,
+            csrf_token: csrf.into_inner(),
+        })?))
+}
+
+#[post("/admin/forgot-password")]
+pub async fn forgot_password_handler(
+    csrf: CsrfToken,
+    form: web::Form<ForgotPasswordForm>,
+) -> Result<HttpResponse, SubmissionError> {
+    let email = form.email.trim().to_string();
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let admin = AdminRepository::new(conn).find_admin_by_email(&email)?;
+
+    // The response is identical whether or not the account exists, so the form
+    // can't be used to discover which addresses are registered
+    match admin {
+        Some(admin) if admin.is_active => {
+            let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+            let token = AuthTokenRepository::new(conn)
+                .issue_token(admin.id, TokenPurpose::PasswordReset)?;
+            let body = format!(
+                "A password reset was requested for your Akungba Journal account.\n\n\
+                 Open the link below to choose a new password:\n\n{}/admin/reset-password/{}\n\n\
+                 The link expires in {} minutes and can only be used once. If you did not \
+                 request this, you can ignore this email.",
+                get_base_url(),
+                token,
+                TokenPurpose::PasswordReset.lifetime().num_minutes()
+            );
+            if let Err(e) = send_email(&admin.email, "Reset your password", body).await {
+                error!(
+                    "Failed to send password reset email to {}: {}",
+                    admin.email, e
+                );
+            } else {
+                info!("Password reset requested for {}", admin.email);
+            }
+        }
+        Some(_) => warn!("Password reset requested for deactivated account {}", email),
+        None => warn!("Password reset requested for unknown email {}", email),
+    }
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(html(ForgotPasswordTemplate {
+            sent: true,
+            csrf_token: csrf.into_inner(),
+        })?))
+}
+
+#[get("/admin/reset-password/{token}")]
+pub async fn reset_password_form_handler(
+    csrf: CsrfToken,
+    token: web::Path<String>,
+) -> Result<HttpResponse, SubmissionError> {
+    let token = token.into_inner();
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let valid = AuthTokenRepository::new(conn)
+        .find_token_owner(&token, TokenPurpose::PasswordReset)?
+        .is_some();
+
+    let mut response = if valid {
+        HttpResponse::Ok()
+    } else {
+        HttpResponse::NotFound()
+    };
+    Ok(response
+        .content_type("text/html; charset=utf-8")
+        .body(html(ResetPasswordTemplate {
+            token,
+            valid,
+            error: None,
+            csrf_token: csrf.into_inner(),
+        })?))
+}
+
+#[post("/admin/reset-password/{token}")]
+pub async fn reset_password_handler(
+    csrf: CsrfToken,
+    token: web::Path<String>,
+    form: web::Form<ResetPasswordForm>,
+) -> Result<HttpResponse, SubmissionError> {
+    let token = token.into_inner();
+
+    if let Some(error) = validate_new_password(&form.password, &form.confirm_password) {
+        return Ok(HttpResponse::BadRequest()
+            .content_type("text/html; charset=utf-8")
+            .body(html(ResetPasswordTemplate {
+                token,
+                valid: true,
+                error: Some(error),
+                csrf_token: csrf.into_inner(),
+            })?));
+    }
+    let password_hash = hash_in_background(form.password.clone()).await?;
+
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let admin_id =
+        match AuthTokenRepository::new(conn).consume_token(&token, TokenPurpose::PasswordReset) {
+            Ok(admin_id) => admin_id,
+            Err(SubmissionError::ValidationError(_)) => {
+                return Ok(HttpResponse::NotFound()
+                    .content_type("text/html; charset=utf-8")
+                    .body(html(ResetPasswordTemplate {
+                        token,
+                        valid: false,
+                        error: None,
+                        csrf_token: csrf.into_inner(),
+                    })?));
+            }
+            Err(e) => return Err(e),
+        };
+
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let repository = AdminRepository::new(conn);
+    repository.update_password(admin_id, &password_hash)?;
+    // Receiving the reset link proves the address belongs to the account holder
+    repository.mark_email_verified(admin_id)?;
+    // Whoever knew the old password may still be signed in somewhere
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    SessionRepository::new(conn).revoke_admin_sessions(admin_id, None)?;
+    info!("Password reset completed for admin {}", admin_id);
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(auth_message(
+            "Password updated",
+            "Your password has been changed. You can now log in with the new password.".to_string(),
+        )?))
+}
+
+#[get("/admin/verify-email/{token}")]
+pub async fn verify_email_handler(
+    token: web::Path<String>,
+) -> Result<HttpResponse, SubmissionError> {
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let admin_id = match AuthTokenRepository::new(conn)
+        .consume_token(&token, TokenPurpose::EmailVerification)
+    {
+        Ok(admin_id) => admin_id,
+        Err(SubmissionError::ValidationError(_)) => {
+            return Ok(HttpResponse::NotFound()
+                .content_type("text/html; charset=utf-8")
+                .body(auth_message(
+                    "Link expired",
+                    "This verification link is invalid, has expired or has already been \
+                         used. Log in to receive a new one."
+                        .to_string(),
+                )?));
+        }
+        Err(e) => return Err(e),
+    };
+
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    AdminRepository::new(conn).mark_email_verified(admin_id)?;
+    info!("Email verified for admin {}", admin_id);
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(auth_message(
+            "Email verified",
+            "Thank you for confirming your email address. You can now log in.".to_string(),
+        )?))
+}
+
+fn render_change_password(
+    csrf: CsrfToken,
+    error: Option<String>,
+    notice: Option<String>,
+) -> Result<String, SubmissionError> {
+    html(ChangePasswordTemplate {
+        current_page: "account",
+        error,
+        notice,
+        csrf_token: csrf.into_inner(),
+    })
+}
+
+#[get("/account/password")]
+pub async fn change_password_form_handler(
+    _user: AuthenticatedUser,
+    csrf: CsrfToken,
+) -> Result<HttpResponse, ActixError> {
+    Ok(HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(render_change_password(csrf, None, None)?))
+}
+
+#[post("/account/password")]
+pub async fn change_password_handler(
+    user: AuthenticatedUser,
+    session: Session,
+    csrf: CsrfToken,
+    form: web::Form<ChangePasswordForm>,
+) -> Result<HttpResponse, ActixError> {
+    let form = form.into_inner();
+    let matches = check_password(&user, form.current_password).await?;
+
+    let error = if !matches {
+        Some("Current password is incorrect".to_string())
+    } else {
+        validate_new_password(&form.password, &form.confirm_password)
+    };
+    if let Some(error) = error {
+        return Ok(HttpResponse::BadRequest()
+            .content_type("text/html; charset=utf-8")
+            .body(render_change_password(csrf, Some(error), None)?));
+    }
+
+    let password_hash = hash_in_background(form.password).await?;
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    AdminRepository::new(conn).update_password(user.id, &password_hash)?;
+
+    // Outstanding reset links would otherwise still work with the old account state
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    AuthTokenRepository::new(conn).revoke_tokens(user.id, TokenPurpose::PasswordReset)?;
+    // ...as would other signed-in sessions
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    SessionRepository::new(conn)
+        .revoke_admin_sessions(user.id, current_login_id(&session).as_deref())?;
+    info!("Admin {} changed their password", user.email);
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(render_change_password(
+            csrf,
+            None,
+            Some("Your password has been changed and your other sessions signed out.".to_string()),
+        )?))
+}
+
+// Key under which the secret being enrolled is kept until the first code confirms it
+const TOTP_SETUP_SECRET_KEY: &str = "totp_setup_secret";
+
+fn reload_admin(admin_id: i32) -> Result<Admin, SubmissionError> {
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    AdminRepository::new(conn)
+        .find_admin_by_id(admin_id)?
+        .ok_or_else(|| SubmissionError::NotFound(format!("Admin {} not found", admin_id)))
+}
+
+fn render_two_factor(
+    user: &Admin,
+    session: &Session,
+    csrf: CsrfToken,
+    recovery_codes: Vec<String>,
+    error: Option<String>,
+    notice: Option<String>,
+) -> Result<String, SubmissionError> {
+    let (setup_secret, qr_svg) = if user.totp_enabled {
+        (String::new(), String::new())
+    } else {
+        // Keep the same secret across reloads so a scanned code stays valid
+        let secret = match session.get::<String>(TOTP_SETUP_SECRET_KEY).unwrap_or(None) {
+            Some(secret) => secret,
+            None => {
+                let secret = totp::new_secret();
+                session
+                    .insert(TOTP_SETUP_SECRET_KEY, &secret)
+                    .map_err(|e| {
+                        SubmissionError::StorageError(format!("Session insert error: {}", e))
+                    })?;
+                secret
+            }
+        };
+        let qr_svg = totp::provisioning_qr_svg(&secret, &user.email)?;
+        (secret, qr_svg)
+    };
+
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let remaining_codes = TwoFactorRepository::new(conn).remaining_recovery_codes(user.id)?;
+
+    html(TwoFactorTemplate {
+        current_page: "account",
+        enabled: user.totp_enabled,
+        required: user.requires_two_factor(),
+        setup_secret,
+        qr_svg,
+        recovery_codes,
+        remaining_codes,
+        error,
+        notice,
+        csrf_token: csrf.into_inner(),
+    })
+}
+
+#[get("/account/2fa")]
+pub async fn two_factor_handler(
+    user: AuthenticatedUser,
+    session: Session,
+    csrf: CsrfToken,
+) -> Result<HttpResponse, ActixError> {
+    Ok(HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(render_two_factor(
+            &user,
+            &session,
+            csrf,
+            Vec::new(),
+            None,
+            None,
+        )?))
+}
+
+#[post("/account/2fa/enable")]
+pub async fn enable_two_factor_handler(
+    user: AuthenticatedUser,
+    session: Session,
+    csrf: CsrfToken,
+    form: web::Form<EnableTwoFactorForm>,
+) -> Result<HttpResponse, ActixError> {
+    if user.totp_enabled {
+        return Ok(HttpResponse::Found()
+            .append_header(("Location", "/admin/account/2fa"))
+            .finish());
+    }
+
+    let secret = session
+        .get::<String>(TOTP_SETUP_SECRET_KEY)
+        .unwrap_or(None)
+        .unwrap_or_default();
+    let step = if secret.is_empty() {
+        None
+    } else {
+        totp::verify_code(&secret, &user.email, &form.code, None)?
+    };
+    let Some(step) = step else {
+        return Ok(HttpResponse::BadRequest()
+            .content_type("text/html; charset=utf-8")
+            .body(render_two_factor(
+                &user,
+                &session,
+                csrf,
+                Vec::new(),
+                Some(
+                    "That code didn't match. Check your device's clock and try again.".to_string(),
+                ),
+                None,
+            )?));
+    };
+
+    let recovery_codes = totp::generate_recovery_codes();
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    TwoFactorRepository::new(conn).enable(user.id, &secret, step, &recovery_codes)?;
+    session.remove(TOTP_SETUP_SECRET_KEY);
+    info!("Admin {} enabled two-factor authentication", user.email);
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(render_two_factor(
+            &reload_admin(user.id)?,
+            &session,
+            csrf,
+            recovery_codes,
+            None,
+            Some("Two-factor authentication is now enabled.".to_string()),
+        )?))
+}
+
+#[post("/account/2fa/disable")]
+pub async fn disable_two_factor_handler(
+    user: AuthenticatedUser,
+    session: Session,
+    csrf: CsrfToken,
+    form: web::Form<ConfirmPasswordForm>,
+) -> Result<HttpResponse, ActixError> {
+    let error = if !user.totp_enabled {
+        Some("Two-factor authentication is not enabled.".to_string())
+    } else if user.requires_two_factor() {
+        Some("Your role requires two-factor authentication, so it can't be turned off.".to_string())
+    } else if !check_password(&user, form.into_inner().password).await? {
+        Some("Password is incorrect".to_string())
+    } else {
+        None
+    };
+    if let Some(error) = error {
+        return Ok(HttpResponse::BadRequest()
+            .content_type("text/html; charset=utf-8")
+            .body(render_two_factor(
+                &user,
+                &session,
+                csrf,
+                Vec::new(),
+                Some(error),
+                None,
+            )?));
+    }
+
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    TwoFactorRepository::new(conn).disable(user.id)?;
+    warn!("Admin {} disabled two-factor authentication", user.email);
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(render_two_factor(
+            &reload_admin(user.id)?,
+            &session,
+            csrf,
+            Vec::new(),
+            None,
+            Some("Two-factor authentication has been turned off.".to_string()),
+        )?))
+}
+
+#[post("/account/2fa/recovery-codes")]
+pub async fn regenerate_recovery_codes_handler(
+    user: AuthenticatedUser,
+    session: Session,
+    csrf: CsrfToken,
+    form: web::Form<ConfirmPasswordForm>,
+) -> Result<HttpResponse, ActixError> {
+    let error = if !user.totp_enabled {
+        Some("Two-factor authentication is not enabled.".to_string())
+    } else if !check_password(&user, form.into_inner().password).await? {
+        Some("Password is incorrect".to_string())
+    } else {
+        None
+    };
+    if let Some(error) = error {
+        return Ok(HttpResponse::BadRequest()
+            .content_type("text/html; charset=utf-8")
+            .body(render_two_factor(
+                &user,
+                &session,
+                csrf,
+                Vec::new(),
+                Some(error),
+                None,
+            )?));
+    }
+
+    let recovery_codes = totp::generate_recovery_codes();
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    TwoFactorRepository::new(conn).replace_recovery_codes(user.id, &recovery_codes)?;
+    info!("Admin {} regenerated their recovery codes", user.email);
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(render_two_factor(
+            &user,
+            &session,
+            csrf,
+            recovery_codes,
+            None,
+            Some("New recovery codes generated. Your old codes no longer work.".to_string()),
+        )?))
+}
+
+fn current_login_id(session: &Session) -> Option<String> {
+    session.get::<String>(LOGIN_ID_KEY).unwrap_or(None)
+}
+
+fn render_sessions(
+    user: &AuthenticatedUser,
+    session: &Session,
+    csrf: CsrfToken,
+    notice: Option<String>,
+) -> Result<String, SubmissionError> {
+    let created_after = (chrono::Utc::now() - get_session_config().absolute_timeout).timestamp();
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let sessions = SessionRepository::new(conn).get_admin_sessions(user.id, created_after)?;
+    html(SessionsTemplate {
+        current_page: "account",
+        sessions,
+        current_login_id: current_login_id(session).unwrap_or_default(),
+        notice,
+        csrf_token: csrf.into_inner(),
+    })
+}
+
+#[get("/account/sessions")]
+pub async fn sessions_handler(
+    user: AuthenticatedUser,
+    session: Session,
+    csrf: CsrfToken,
+) -> Result<HttpResponse, ActixError> {
+    Ok(HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(render_sessions(&user, &session, csrf, None)?))
+}
+
+#[post("/account/sessions/{login_id}/revoke")]
+pub async fn revoke_session_handler(
+    user: AuthenticatedUser,
+    session: Session,
+    csrf: CsrfToken,
+    login_id: web::Path<String>,
+) -> Result<HttpResponse, ActixError> {
+    let login_id = login_id.into_inner();
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let revoked = SessionRepository::new(conn).revoke_session(user.id, &login_id)?;
+
+    if current_login_id(&session).as_deref() == Some(login_id.as_str()) {
+        session.purge();
+        return Ok(HttpResponse::Found()
+            .append_header(("Location", "/admin/login"))
+            .finish());
+    }
+    let notice = if revoked {
+        info!("Admin {} signed out one of their sessions", user.email);
+        "The session has been signed out."
+    } else {
+        "That session had already ended."
+    };
+    Ok(HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(render_sessions(
+            &user,
+            &session,
+            csrf,
+            Some(notice.to_string()),
+        )?))
+}
+
+#[post("/account/sessions/revoke-others")]
+pub async fn revoke_other_sessions_handler(
+    user: AuthenticatedUser,
+    session: Session,
+    csrf: CsrfToken,
+) -> Result<HttpResponse, ActixError> {
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let revoked = SessionRepository::new(conn)
+        .revoke_admin_sessions(user.id, current_login_id(&session).as_deref())?;
+    info!(
+        "Admin {} signed out {} other session(s)",
+        user.email, revoked
+    );
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(render_sessions(
+            &user,
+            &session,
+            csrf,
+            Some(format!("Signed out {} other session(s).", revoked)),
+        )?))
+}
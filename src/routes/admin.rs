@@ -1,10 +1,9 @@
 use actix_files::NamedFile;
 use actix_multipart::Multipart;
-use actix_session::Session;
 use actix_web::http::header::{ContentDisposition, DispositionParam, DispositionType};
 use actix_web::{delete, get, post, web, Error as ActixError, HttpRequest, HttpResponse}; // Keep ActixError
 use askama::Template;
-use chrono::{DateTime, NaiveDate, Utc};
+use chrono::{DateTime, Datelike, Months, NaiveDate, Utc};
 use futures::StreamExt;
 use log::{debug, error, warn};
 use serde::Deserialize;
@@ -12,73 +11,159 @@ use serde_json::json;
 use std::path::PathBuf; // Use PathBuf
 
 use crate::{
+    auth::{csrf::CsrfToken, AuthenticatedUser},
+    config::{get_max_upload_size, get_similarity_threshold},
     db::{
-        journal_repository::JournalRepository, schema::init_db,
-        submission_repository::SubmissionRepository,
+        admin_repository::AdminRepository, analytics_repository::AnalyticsRepository,
+        attachment_repository::AttachmentRepository, author_repository::AuthorRepository,
+        journal_repository::JournalRepository, review_repository::ReviewRepository,
+        schema::init_db, similarity_repository::SimilarityRepository,
+        submission_repository::SubmissionRepository, taxonomy_repository::TaxonomyRepository,
+        version_repository::VersionRepository,
     },
     errors::SubmissionError,
-    models::{journals::Journal, response::UploadResponse, submission::Submission},
-    utils, // Import the utils module
+    models::{
+        admin::{Admin, Permission},
+        analytics::{days_display, EditorialMetrics},
+        attachment::{Attachment, AttachmentKind},
+        audit::{field_changes, snapshot, AuditAction},
+        journals::Journal,
+        response::{MetadataSuggestionResponse, UploadResponse},
+        review::{Decision, ReviewAssignment, SubmissionDecision},
+        similarity::SimilarityMatch,
+        submission::{
+            Submission, SubmissionFilter, SubmissionListItem, SubmissionSort, SubmissionStatus,
+        },
+        taxonomy::Subject,
+    },
+    utils::{
+        self, audit,
+        export::{csv_response, CsvWriter},
+        pdf,
+    }, // Import the utils module
 };
 
-type AuthResult = Result<i32, HttpResponse>;
-
-fn check_authentication(session: &Session) -> AuthResult {
-    match session.get::<i32>("admin_id") {
-        Ok(Some(admin_id)) => Ok(admin_id),
-        _ => {
-            warn!("Unauthorized access attempt to admin route.");
-            Err(HttpResponse::Found()
-                .append_header(("Location", "/admin/login")) // Redirect path
-                .finish())
-        }
-    }
-}
-
 // --- Templates ---
 #[derive(Template)]
 #[template(path = "admin/index.html")]
 struct AdminDashboardTemplate {
     current_page: &'static str,
+    csrf_token: String,
     recent_submissions: Vec<Submission>,
+    metrics: EditorialMetrics,
+    months: u32,
+    periods: &'static [u32],
+}
+
+impl AdminDashboardTemplate {
+    // Bar length as a percentage of the largest value in its chart
+    fn bar_width(&self, value: &i64, largest: i64) -> i64 {
+        if largest == 0 {
+            0
+        } else {
+            value * 100 / largest
+        }
+    }
+
+    fn days(&self, value: &Option<f64>) -> String {
+        days_display(value)
+    }
 }
 
 #[derive(Template)]
 #[template(path = "admin/upload.html")]
 struct AdminUploadTemplate {
     current_page: &'static str,
+    csrf_token: String,
+    // Submissions the article being published may come from
+    accepted_submissions: Vec<Submission>,
 }
 
 #[derive(Template)]
 #[template(path = "admin/submitted.html")]
 struct AdminSubmissionsTemplate {
-    submissions: Vec<Submission>,
+    submissions: Vec<SubmissionListItem>,
+    filter: SubmissionFilter,
+    query: String,
+    statuses: &'static [SubmissionStatus],
+    sorts: &'static [SubmissionSort],
+    // Users who can be made handling editor
+    editors: Vec<Admin>,
+    decision_options: &'static [Decision],
+    can_manage: bool,
+    total: i64,
+    page: i64,
+    total_pages: i64,
+    error: Option<String>,
+    notice: Option<String>,
     current_page: &'static str,
-    title: &'static str,
+    csrf_token: String,
+}
+
+impl AdminSubmissionsTemplate {
+    fn filter_value<'a>(&self, value: &'a Option<String>) -> &'a str {
+        value.as_deref().unwrap_or("")
+    }
+}
+
+#[derive(Template)]
+#[template(path = "admin/submission_detail.html")]
+struct AdminSubmissionDetailTemplate {
+    submission: Submission,
+    attachments: Vec<Attachment>,
+    matches: Vec<SimilarityMatch>,
+    threshold_percent: String,
+    can_delete: bool,
+    decisions: Vec<SubmissionDecision>,
+    reviews: Vec<ReviewAssignment>,
+    // Accounts that can still be invited to review
+    reviewers: Vec<Admin>,
+    decision_options: &'static [Decision],
+    can_manage_reviews: bool,
+    user_id: i32,
+    error: Option<String>,
+    current_page: &'static str,
+    csrf_token: String,
 }
 
 #[derive(Template)]
 #[template(path = "admin/login.html")]
 struct AdminLoginTemplate {
     error: Option<String>,
+    csrf_token: String,
 }
 
 #[derive(Template)]
 #[template(path = "admin/edit_journal.html")]
 pub struct EditJournalTemplate {
     pub journal: Journal,
+    pub subjects: Vec<Subject>,
+    pub assigned_subjects: Vec<i32>,
     pub error: Option<String>,
     pub current_page: String,
+    pub csrf_token: String,
 }
 
 impl EditJournalTemplate {
-    pub fn new(journal: Journal) -> Self {
+    pub fn new(
+        csrf: CsrfToken,
+        journal: Journal,
+        subjects: Vec<Subject>,
+        assigned_subjects: Vec<i32>,
+    ) -> Self {
         Self {
             journal,
+            subjects,
+            assigned_subjects,
             error: None,
             current_page: "journals".to_string(),
+            csrf_token: csrf.into_inner(),
         }
     }
+
+    fn is_assigned(&self, subject: &Subject) -> bool {
+        self.assigned_subjects.contains(&subject.id)
+    }
 }
 
 #[derive(Deserialize)]
@@ -91,425 +176,1174 @@ pub struct EditJournalForm {
     pub issue_number: i32,
     pub pages: String,
     pub publication_date: String,
-    pub pdf_url: String,
+    // Shown publicly in the article's version history
+    #[serde(default)]
+    pub change_note: String,
 }
 
 // --- Handlers ---
 
 #[get("/login")]
-pub async fn admin_login_form_handler() -> HttpResponse {
+pub async fn admin_login_form_handler(csrf: CsrfToken) -> HttpResponse {
     // No auth check needed to view the login form
     HttpResponse::Ok()
         .content_type("text/html; charset=utf-8")
         .body(
-            AdminLoginTemplate { error: None }
-                .render()
-                .unwrap_or_else(|e| {
-                    error!("Login template render error: {:?}", e); // Changed {} to {:?}
-                    "Error rendering login page.".to_string()
-                }),
+            AdminLoginTemplate {
+                error: None,
+                csrf_token: csrf.into_inner(),
+            }
+            .render()
+            .unwrap_or_else(|e| {
+                error!("Login template render error: {:?}", e); // Changed {} to {:?}
+                "Error rendering login page.".to_string()
+            }),
         )
 }
 
+// Lengths of the analytics period offered on the dashboard, in months
+const ANALYTICS_PERIODS: [u32; 4] = [3, 6, 12, 24];
+const DEFAULT_ANALYTICS_MONTHS: u32 = 12;
+
+#[derive(Deserialize)]
+pub struct AnalyticsQuery {
+    pub months: Option<u32>,
+}
+
+impl AnalyticsQuery {
+    fn months(&self) -> u32 {
+        self.months
+            .filter(|m| ANALYTICS_PERIODS.contains(m))
+            .unwrap_or(DEFAULT_ANALYTICS_MONTHS)
+    }
+}
+
+// Editorial metrics for the submissions received in the last `months` calendar months,
+// including the current one
+fn editorial_metrics(months: u32) -> Result<EditorialMetrics, SubmissionError> {
+    let today = Utc::now().date_naive();
+    let this_month = today.with_day(1).unwrap_or(today);
+    let since = this_month
+        .checked_sub_months(Months::new(months - 1))
+        .unwrap_or(this_month);
+
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    AnalyticsRepository::new(conn).get_editorial_metrics(since, today)
+}
+
 #[get("/dashboard")]
-pub async fn admin_dashboard_handler(session: Session) -> Result<HttpResponse, ActixError> {
-    match check_authentication(&session) {
-        Ok(_admin_id) => {
-            // Get recent submissions
-            let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
-            let sub_repo = SubmissionRepository::new(conn);
-            let recent_submissions = sub_repo.get_recent_submissions(10)?;
+pub async fn admin_dashboard_handler(
+    user: AuthenticatedUser,
+    csrf: CsrfToken,
+    query: web::Query<AnalyticsQuery>,
+) -> Result<HttpResponse, ActixError> {
+    user.require(Permission::ViewDashboard)?;
+    // Get recent submissions
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let sub_repo = SubmissionRepository::new(conn);
+    let recent_submissions = if user.can(Permission::ViewSubmissions) {
+        sub_repo.get_recent_submissions(10)?
+    } else {
+        Vec::new()
+    };
 
-            let template = AdminDashboardTemplate {
-                current_page: "dashboard",
-                recent_submissions,
-            };
+    let months = query.months();
+    let template = AdminDashboardTemplate {
+        current_page: "dashboard",
+        csrf_token: csrf.into_inner(),
+        recent_submissions,
+        metrics: editorial_metrics(months)?,
+        months,
+        periods: &ANALYTICS_PERIODS,
+    };
 
-            Ok(HttpResponse::Ok()
-                .content_type("text/html; charset=utf-8")
-                .body(template.render().map_err(|e| {
-                    error!("Dashboard template render error: {:?}", e); // Changed {} to {:?}
-                    actix_web::error::ErrorInternalServerError("Template error")
-                })?))
-        }
-        Err(redirect) => Ok(redirect),
+    Ok(HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(template.render().map_err(|e| {
+            error!("Dashboard template render error: {:?}", e); // Changed {} to {:?}
+            actix_web::error::ErrorInternalServerError("Template error")
+        })?))
+}
+
+// The dashboard metrics as CSV, one row per figure
+#[get("/dashboard/export")]
+pub async fn export_dashboard_handler(
+    user: AuthenticatedUser,
+    query: web::Query<AnalyticsQuery>,
+) -> Result<HttpResponse, ActixError> {
+    user.require(Permission::ViewDashboard)?;
+    let months = query.months();
+    let metrics = editorial_metrics(months)?;
+
+    let optional = |value: Option<f64>| value.map(|v| format!("{:.2}", v)).unwrap_or_default();
+    let mut rows: Vec<[String; 3]> = Vec::new();
+    for month in &metrics.months {
+        rows.push([
+            "submissions_per_month".to_string(),
+            month.month.clone(),
+            month.submissions.to_string(),
+        ]);
+    }
+    rows.push([
+        "submissions".to_string(),
+        String::new(),
+        metrics.total_submissions.to_string(),
+    ]);
+    rows.push([
+        "accepted".to_string(),
+        String::new(),
+        metrics.accepted.to_string(),
+    ]);
+    rows.push([
+        "rejected".to_string(),
+        String::new(),
+        metrics.rejected.to_string(),
+    ]);
+    rows.push([
+        "acceptance_rate".to_string(),
+        String::new(),
+        optional(metrics.acceptance_rate()),
+    ]);
+    rows.push([
+        "median_days_to_first_decision".to_string(),
+        String::new(),
+        optional(metrics.median_days_to_decision),
+    ]);
+    rows.push([
+        "median_days_to_publication".to_string(),
+        String::new(),
+        optional(metrics.median_days_to_publication),
+    ]);
+    rows.push([
+        "median_review_days".to_string(),
+        String::new(),
+        optional(metrics.median_review_days),
+    ]);
+    for reviewer in &metrics.reviewers {
+        rows.push([
+            "reviewer_median_days".to_string(),
+            reviewer.reviewer_email.clone(),
+            optional(reviewer.median_days),
+        ]);
+        rows.push([
+            "reviewer_completed".to_string(),
+            reviewer.reviewer_email.clone(),
+            reviewer.completed.to_string(),
+        ]);
+        rows.push([
+            "reviewer_outstanding".to_string(),
+            reviewer.reviewer_email.clone(),
+            reviewer.outstanding.to_string(),
+        ]);
     }
+    for country in &metrics.countries {
+        rows.push([
+            "submissions_by_country".to_string(),
+            country.country.clone(),
+            country.submissions.to_string(),
+        ]);
+    }
+
+    let mut csv = CsvWriter::new(Vec::new());
+    csv.write_row(&["metric", "key", "value"])?;
+    for row in &rows {
+        csv.write_row(row)?;
+    }
+
+    csv_response(
+        csv,
+        &format!(
+            "editorial-metrics-{}m-{}.csv",
+            months,
+            Utc::now().format("%Y%m%d")
+        ),
+    )
+    .map_err(ActixError::from)
 }
 
 #[get("/upload")]
-pub async fn upload_journal_handler(session: Session) -> Result<HttpResponse, ActixError> {
-    match check_authentication(&session) {
-        Ok(_) => Ok(HttpResponse::Ok()
-            .content_type("text/html; charset=utf-8")
-            // Pass the current page identifier
-            .body(
-                AdminUploadTemplate {
-                    current_page: "upload",
-                }
-                .render()
-                .map_err(|e| {
-                    error!("Upload template render error: {:?}", e); // Changed {} to {:?}
-                    actix_web::error::ErrorInternalServerError("Template error")
-                })?,
-            )),
-        Err(redirect) => Ok(redirect),
-    }
+pub async fn upload_journal_handler(
+    user: AuthenticatedUser,
+    csrf: CsrfToken,
+) -> Result<HttpResponse, ActixError> {
+    user.require(Permission::PublishArticles)?;
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let accepted_submissions = SubmissionRepository::new(conn).get_accepted_unpublished()?;
+    Ok(HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        // Pass the current page identifier
+        .body(
+            AdminUploadTemplate {
+                current_page: "upload",
+                csrf_token: csrf.into_inner(),
+                accepted_submissions,
+            }
+            .render()
+            .map_err(|e| {
+                error!("Upload template render error: {:?}", e); // Changed {} to {:?}
+                actix_web::error::ErrorInternalServerError("Template error")
+            })?,
+        ))
 }
 
 #[post("/upload")]
 pub async fn process_upload(
-    session: Session,
+    user: AuthenticatedUser,
+    req: HttpRequest,
     mut payload: Multipart,
 ) -> Result<HttpResponse, ActixError> {
-    match check_authentication(&session) {
-        Ok(_) => {
-            let result: Result<HttpResponse, SubmissionError> = async move {
-                let mut title: Option<String> = None;
-                let mut authors: Option<String> = None;
-                let mut abstract_text: Option<String> = None;
-                let mut keywords: Option<String> = None;
-                let mut volume_number: Option<i32> = None;
-                let mut issue_number: Option<i32> = None;
-                let mut pages: Option<String> = None;
-                let mut publication_date: Option<String> = None;
-                let mut pdf_filename: Option<String> = None;
-
-                while let Some(field_result) = payload.next().await {
-                    let mut field = field_result.map_err(|e| {
-                        SubmissionError::FileProcessingError(format!("Multipart error: {:?}", e))
-                        // Changed {} to {:?}
-                    })?;
-                    let content_disposition =
-                        field.content_disposition().cloned().ok_or_else(|| {
-                            SubmissionError::ValidationError(
-                                "Content disposition missing".to_string(),
-                            )
-                        })?;
+    user.require(Permission::PublishArticles)?;
+    let result: Result<HttpResponse, SubmissionError> = async move {
+        let mut title: Option<String> = None;
+        let mut authors: Option<String> = None;
+        let mut abstract_text: Option<String> = None;
+        let mut keywords: Option<String> = None;
+        let mut volume_number: Option<i32> = None;
+        let mut issue_number: Option<i32> = None;
+        let mut pages: Option<String> = None;
+        let mut publication_date: Option<String> = None;
+        let mut pdf_filename: Option<String> = None;
+        let mut submission_id: Option<i32> = None;
+        let mut attachments: Vec<Attachment> = Vec::new();
+        let mut staged = utils::StagedFiles::default();
 
-                    let name = content_disposition.get_name().ok_or_else(|| {
-                        SubmissionError::ValidationError("Field name missing".to_string())
-                    })?;
-
-                    match name {
-                        "title" => title = Some(utils::read_field(field).await?),
-                        "authors" => authors = Some(utils::read_field(field).await?),
-                        "abstract_text" => abstract_text = Some(utils::read_field(field).await?),
-                        "keywords" => keywords = Some(utils::read_field(field).await?),
-                        "volume_number" => {
-                            volume_number =
-                                Some(utils::read_field(field).await?.parse().map_err(|_| {
-                                    SubmissionError::ValidationError(
-                                        "Invalid volume number".to_string(),
-                                    )
-                                })?)
-                        }
-                        "issue_number" => {
-                            issue_number =
-                                Some(utils::read_field(field).await?.parse().map_err(|_| {
-                                    SubmissionError::ValidationError(
-                                        "Invalid issue number".to_string(),
-                                    )
-                                })?)
-                        }
-                        "pages" => pages = Some(utils::read_field(field).await?),
-                        "publication_date" => {
-                            publication_date = Some(utils::read_field(field).await?)
-                        }
-                        "pdf" => pdf_filename = Some(utils::save_uploaded_file(field).await?),
-                        _ => while field.next().await.is_some() {},
+        while let Some(field_result) = payload.next().await {
+            let mut field = field_result.map_err(|e| {
+                SubmissionError::FileProcessingError(format!("Multipart error: {:?}", e))
+                // Changed {} to {:?}
+            })?;
+            let content_disposition = field.content_disposition().cloned().ok_or_else(|| {
+                SubmissionError::ValidationError("Content disposition missing".to_string())
+            })?;
+
+            let name = content_disposition.get_name().ok_or_else(|| {
+                SubmissionError::ValidationError("Field name missing".to_string())
+            })?;
+
+            match name {
+                "title" => title = Some(utils::read_field(field).await?),
+                "authors" => authors = Some(utils::read_field(field).await?),
+                "abstract_text" => abstract_text = Some(utils::read_field(field).await?),
+                "keywords" => keywords = Some(utils::read_field(field).await?),
+                "volume_number" => {
+                    volume_number = Some(utils::read_field(field).await?.parse().map_err(|_| {
+                        SubmissionError::ValidationError("Invalid volume number".to_string())
+                    })?)
+                }
+                "issue_number" => {
+                    issue_number = Some(utils::read_field(field).await?.parse().map_err(|_| {
+                        SubmissionError::ValidationError("Invalid issue number".to_string())
+                    })?)
+                }
+                "pages" => pages = Some(utils::read_field(field).await?),
+                "publication_date" => publication_date = Some(utils::read_field(field).await?),
+                "pdf" => {
+                    let file_name = utils::save_uploaded_file(field).await?;
+                    staged.add(format!("./data/uploads/{}", file_name));
+                    pdf_filename = Some(file_name);
+                }
+                // The accepted submission this article is the published version of
+                "submission_id" => {
+                    let value = utils::read_field(field).await?;
+                    if !value.trim().is_empty() {
+                        submission_id = Some(value.trim().parse().map_err(|_| {
+                            SubmissionError::ValidationError("Invalid submission".to_string())
+                        })?)
+                    }
+                }
+                // Supplementary material, datasets and figures published with the article
+                name if name.starts_with("attachment_") => {
+                    let kind = AttachmentKind::parse(&name["attachment_".len()..])
+                        .filter(AttachmentKind::allowed_for_journal)
+                        .ok_or_else(|| {
+                            SubmissionError::ValidationError(format!(
+                                "Unknown attachment type: {}",
+                                name
+                            ))
+                        })?;
+                    if let Some(attachment) = utils::save_attachment(field, kind).await? {
+                        staged.add(attachment.file_path());
+                        attachments.push(attachment);
                     }
                 }
+                _ => while field.next().await.is_some() {},
+            }
+        }
 
-                let title = title.ok_or(SubmissionError::ValidationError(
-                    "Title is required".to_string(),
-                ))?;
-                let authors = authors.ok_or(SubmissionError::ValidationError(
-                    "Authors are required".to_string(),
-                ))?;
-                let abstract_text = abstract_text.ok_or(SubmissionError::ValidationError(
-                    "Abstract is required".to_string(),
-                ))?;
-                let keywords = keywords.ok_or(SubmissionError::ValidationError(
-                    "Keywords are required".to_string(),
-                ))?;
-                let volume_number = volume_number.ok_or(SubmissionError::ValidationError(
-                    "Volume number is required".to_string(),
-                ))?;
-                let issue_number = issue_number.ok_or(SubmissionError::ValidationError(
-                    "Issue number is required".to_string(),
-                ))?;
-                let pages = pages.ok_or(SubmissionError::ValidationError(
-                    "Pages are required".to_string(),
-                ))?;
-                let publication_date_str = publication_date.ok_or(
-                    SubmissionError::ValidationError("Publication date is required".to_string()),
-                )?;
-                let pdf_url = pdf_filename.ok_or(SubmissionError::ValidationError(
-                    "PDF file is required".to_string(),
-                ))?;
-
-                let naive_date = NaiveDate::parse_from_str(&publication_date_str, "%Y-%m-%d")
-                    .map_err(|_| {
-                        SubmissionError::ValidationError(
-                            "Invalid publication date format".to_string(),
-                        )
-                    })?;
-                let publication_datetime = DateTime::<Utc>::from_naive_utc_and_offset(
-                    naive_date.and_hms_opt(0, 0, 0).unwrap(),
-                    Utc,
-                );
-
-                let journal = Journal::new(
-                    title,
-                    authors,
-                    abstract_text,
-                    keywords,
-                    volume_number,
-                    issue_number,
-                    pages,
-                    publication_datetime,
-                    pdf_url,
-                );
+        let title = title.ok_or(SubmissionError::ValidationError(
+            "Title is required".to_string(),
+        ))?;
+        let authors = authors.ok_or(SubmissionError::ValidationError(
+            "Authors are required".to_string(),
+        ))?;
+        let abstract_text = abstract_text.ok_or(SubmissionError::ValidationError(
+            "Abstract is required".to_string(),
+        ))?;
+        let keywords = keywords.ok_or(SubmissionError::ValidationError(
+            "Keywords are required".to_string(),
+        ))?;
+        let volume_number = volume_number.ok_or(SubmissionError::ValidationError(
+            "Volume number is required".to_string(),
+        ))?;
+        let issue_number = issue_number.ok_or(SubmissionError::ValidationError(
+            "Issue number is required".to_string(),
+        ))?;
+        let pages = pages.ok_or(SubmissionError::ValidationError(
+            "Pages are required".to_string(),
+        ))?;
+        let publication_date_str = publication_date.ok_or(SubmissionError::ValidationError(
+            "Publication date is required".to_string(),
+        ))?;
+        let pdf_url = pdf_filename.ok_or(SubmissionError::ValidationError(
+            "PDF file is required".to_string(),
+        ))?;
 
-                let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
-                let repository = JournalRepository::new(conn);
-                let journal_id = repository.save_journal(&journal)?;
+        let naive_date =
+            NaiveDate::parse_from_str(&publication_date_str, "%Y-%m-%d").map_err(|_| {
+                SubmissionError::ValidationError("Invalid publication date format".to_string())
+            })?;
+        let publication_datetime = DateTime::<Utc>::from_naive_utc_and_offset(
+            naive_date.and_hms_opt(0, 0, 0).unwrap(),
+            Utc,
+        );
 
-                Ok(HttpResponse::Ok().json(UploadResponse {
-                    success: true,
-                    journal_id: journal_id as i32,
-                    message: "Journal uploaded successfully".to_string(),
-                }))
-            }
-            .await;
+        let journal = Journal::new(
+            title,
+            authors,
+            abstract_text,
+            keywords,
+            volume_number,
+            issue_number,
+            pages,
+            publication_datetime,
+            pdf_url,
+        );
 
-            result.map_err(ActixError::from)
+        let pdf_path = PathBuf::from("./data/uploads").join(&journal.pdf_url);
+
+        if let Some(submission_id) = submission_id {
+            let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+            SubmissionRepository::new(conn).get_submission_by_id(submission_id)?;
+        }
+
+        let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        let repository = JournalRepository::new(conn);
+        let journal_id = repository.save_journal(&journal)?;
+        if let Some(submission_id) = submission_id {
+            repository.link_submission(journal_id as i32, submission_id)?;
         }
-        Err(redirect) => Ok(redirect),
+        let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        AttachmentRepository::new(conn).save_attachments(
+            "journal",
+            journal_id as i32,
+            attachments,
+        )?;
+        staged.keep();
+        let published = Journal {
+            id: Some(journal_id as i32),
+            ..journal.clone()
+        };
+        audit::record(
+            &req,
+            &user,
+            AuditAction::JournalPublished,
+            "journal",
+            journal_id,
+            snapshot(&published),
+        )?;
+
+        let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        VersionRepository::new(conn).record_version(&published, Some(user.id), "")?;
+
+        let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        TaxonomyRepository::new(conn).set_journal_keywords(journal_id as i32, &journal.keywords)?;
+
+        let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        AuthorRepository::new(conn).set_journal_authors(journal_id as i32, &journal.authors)?;
+
+        // Keep the extracted text for search; a PDF we can't parse shouldn't block publishing
+        match web::block(move || pdf::extract_pdf_metadata(&pdf_path)).await {
+            Ok(Ok(metadata)) => repository.update_full_text(journal_id as i32, &metadata.text)?,
+            Ok(Err(e)) => warn!("Text extraction failed for journal {}: {}", journal_id, e),
+            Err(e) => warn!(
+                "Text extraction task failed for journal {}: {}",
+                journal_id, e
+            ),
+        }
+
+        Ok(HttpResponse::Ok().json(UploadResponse {
+            success: true,
+            journal_id: journal_id as i32,
+            message: "Journal uploaded successfully".to_string(),
+        }))
     }
+    .await;
+
+    result.map_err(ActixError::from)
 }
 
+#[post("/upload/extract")]
+pub async fn extract_metadata_handler(
+    user: AuthenticatedUser,
+    mut payload: Multipart,
+) -> Result<HttpResponse, ActixError> {
+    user.require(Permission::PublishArticles)?;
+    let result: Result<HttpResponse, SubmissionError> = async move {
+        let mut pdf_bytes: Option<Vec<u8>> = None;
+
+        while let Some(field_result) = payload.next().await {
+            let mut field = field_result.map_err(|e| {
+                SubmissionError::FileProcessingError(format!("Multipart error: {:?}", e))
+            })?;
+            let is_pdf = field
+                .content_disposition()
+                .and_then(|cd| cd.get_name())
+                .is_some_and(|name| name == "pdf");
+
+            if is_pdf {
+                let max_bytes = get_max_upload_size() as usize;
+                pdf_bytes = Some(utils::read_field_bytes_capped(field, max_bytes).await?);
+            } else {
+                while field.next().await.is_some() {}
+            }
+        }
+
+        let pdf_bytes = pdf_bytes.ok_or(SubmissionError::ValidationError(
+            "PDF file is required".to_string(),
+        ))?;
+
+        let metadata = web::block(move || pdf::extract_pdf_metadata_from_mem(&pdf_bytes))
+            .await
+            .map_err(|e| {
+                SubmissionError::InternalError(format!("PDF extraction task failed: {}", e))
+            })??;
+
+        Ok(HttpResponse::Ok().json(MetadataSuggestionResponse {
+            success: true,
+            metadata,
+        }))
+    }
+    .await;
+
+    result.map_err(ActixError::from)
+}
+
+// Moves the article to the trash; it can be restored until the purge job removes it
 #[delete("/journals/{id}")]
 pub async fn delete_journal_handler(
-    session: Session,
+    user: AuthenticatedUser,
+    req: HttpRequest,
     id: web::Path<i32>,
 ) -> Result<HttpResponse, ActixError> {
-    match check_authentication(&session) {
-        Ok(_) => {
-            let result: Result<HttpResponse, SubmissionError> = async move {
-                let journal_id = id.into_inner();
-                debug!("Attempting to delete journal with ID: {}", journal_id);
+    user.require(Permission::DeleteArticles)?;
+    let result: Result<HttpResponse, SubmissionError> = async move {
+        let journal_id = id.into_inner();
+        debug!("Attempting to trash journal with ID: {}", journal_id);
 
-                let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
-                let repository = JournalRepository::new(conn);
-                repository.delete_journal_by_id(journal_id)?; // Returns Result<(), SubmissionError>
+        let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        let repository = JournalRepository::new(conn);
+        let journal = repository.get_journal_by_id(journal_id)?;
+        repository.trash_journal(journal_id, user.id)?;
+        audit::record(
+            &req,
+            &user,
+            AuditAction::JournalDeleted,
+            "journal",
+            journal_id,
+            snapshot(&journal),
+        )?;
 
-                Ok(HttpResponse::Ok().json(json!({
-                    "success": true,
-                    "message": format!("Journal with ID {} deleted successfully", journal_id)
-                })))
-            }
-            .await; // Await the inner async block
-
-            result.map_err(ActixError::from) // Map SubmissionError -> ActixError
-        }
-        Err(redirect) => Ok(redirect), // Return the redirect HttpResponse
+        Ok(HttpResponse::Ok().json(json!({
+            "success": true,
+            "message": format!("Journal with ID {} moved to the trash", journal_id)
+        })))
     }
+    .await; // Await the inner async block
+
+    result.map_err(ActixError::from) // Map SubmissionError -> ActixError
+}
+
+const SUBMISSIONS_PER_PAGE: i64 = 25;
+
+fn render_submissions_page(
+    csrf: CsrfToken,
+    user: &AuthenticatedUser,
+    mut filter: SubmissionFilter,
+    error: Option<String>,
+    notice: Option<String>,
+) -> Result<HttpResponse, SubmissionError> {
+    filter.reviewer_id = user.assigned_reviewer();
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let repository = SubmissionRepository::new(conn);
+    let total = repository.count_submissions(&filter)?;
+    let total_pages = ((total + SUBMISSIONS_PER_PAGE - 1) / SUBMISSIONS_PER_PAGE).max(1);
+    let page = filter.page().min(total_pages);
+    let submissions = repository.search_submissions(
+        &filter,
+        Some(SUBMISSIONS_PER_PAGE),
+        (page - 1) * SUBMISSIONS_PER_PAGE,
+    )?;
+
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let editors = AdminRepository::new(conn)
+        .get_all_admins()?
+        .into_iter()
+        .filter(|admin| admin.can(Permission::PublishArticles))
+        .collect();
+
+    let template = AdminSubmissionsTemplate {
+        submissions,
+        query: filter.query_string(),
+        filter,
+        statuses: &SubmissionStatus::ALL,
+        sorts: &SubmissionSort::ALL,
+        editors,
+        decision_options: &Decision::ALL,
+        can_manage: user.can(Permission::PublishArticles),
+        total,
+        page,
+        total_pages,
+        error,
+        notice,
+        current_page: "submissions",
+        csrf_token: csrf.into_inner(),
+    };
+    Ok(HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(template.render().map_err(|e| {
+            error!("Submissions template render error: {:?}", e);
+            SubmissionError::InternalError(format!("Template error: {:?}", e))
+        })?))
 }
 
 #[get("/submissions")]
-pub async fn admin_submissions_handler(session: Session) -> Result<HttpResponse, ActixError> {
-    match check_authentication(&session) {
-        Ok(_) => {
-            let result: Result<HttpResponse, SubmissionError> = async move {
-                let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
-                let sub_repo = SubmissionRepository::new(conn);
-                let submissions = sub_repo.get_all_submissions()?;
-
-                // Pass the current page identifier
-                let template = AdminSubmissionsTemplate {
-                    submissions,
-                    current_page: "submissions",
-                    title: "Admin Submissions",
+pub async fn admin_submissions_handler(
+    user: AuthenticatedUser,
+    csrf: CsrfToken,
+    filter: web::Query<SubmissionFilter>,
+) -> Result<HttpResponse, ActixError> {
+    user.require(Permission::ReviewSubmissions)?;
+    render_submissions_page(csrf, &user, filter.into_inner(), None, None).map_err(ActixError::from)
+}
+
+// Selected submissions as CSV
+fn export_submissions(items: &[SubmissionListItem]) -> Result<HttpResponse, SubmissionError> {
+    let mut csv = CsvWriter::new(Vec::new());
+    csv.write_row(&[
+        "id",
+        "submitted",
+        "submitter",
+        "email",
+        "phone",
+        "country",
+        "title",
+        "status",
+        "editor",
+    ])?;
+    for item in items {
+        let submission = &item.submission;
+        csv.write_row(&[
+            submission.id.unwrap_or(0).to_string(),
+            submission.formatted_date(),
+            submission.full_name.clone(),
+            submission.email.clone(),
+            submission.phone.clone(),
+            submission.country.clone(),
+            submission.title.clone(),
+            item.status.label().to_string(),
+            item.editor.clone().unwrap_or_default(),
+        ])?;
+    }
+
+    csv_response(
+        csv,
+        &format!("submissions-{}.csv", Utc::now().format("%Y%m%d")),
+    )
+}
+
+enum BulkOutcome {
+    Export(Vec<SubmissionListItem>),
+    // Message shown above the list
+    Done(String),
+}
+
+// Applies one action to the submissions ticked on the list: assigning a handling editor,
+// recording a decision, or exporting them. The list is shown again with the same filters.
+#[post("/submissions/bulk")]
+pub async fn bulk_submissions_handler(
+    user: AuthenticatedUser,
+    csrf: CsrfToken,
+    req: HttpRequest,
+    form: web::Form<Vec<(String, String)>>,
+) -> Result<HttpResponse, ActixError> {
+    user.require(Permission::ReviewSubmissions)?;
+    let field = |name: &str| {
+        form.iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.trim())
+            .unwrap_or("")
+    };
+    let filter = web::Query::<SubmissionFilter>::from_query(field("query"))
+        .map(|query| query.into_inner())
+        .unwrap_or_default();
+    let ids: Vec<i32> = form
+        .iter()
+        .filter(|(key, _)| key == "ids")
+        .filter_map(|(_, value)| value.parse().ok())
+        .collect();
+    let action = field("action");
+    if matches!(action, "assign_editor" | "decision") {
+        user.require(Permission::PublishArticles)?;
+    }
+    for id in &ids {
+        user.require_submission(*id)?;
+    }
+
+    let result: Result<BulkOutcome, SubmissionError> = (|| {
+        if ids.is_empty() {
+            return Err(SubmissionError::ValidationError(
+                "Select at least one submission".to_string(),
+            ));
+        }
+        let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        let items = SubmissionRepository::new(conn).get_list_items(&ids)?;
+        if items.len() != ids.len() {
+            return Err(SubmissionError::ValidationError(
+                "Some of the selected submissions no longer exist".to_string(),
+            ));
+        }
+
+        match action {
+            "export" => {
+                audit::record(
+                    &req,
+                    &user,
+                    AuditAction::SubmissionsExported,
+                    "submission",
+                    "",
+                    Some(json!({ "ids": ids }).to_string()),
+                )?;
+                Ok(BulkOutcome::Export(items))
+            }
+            "assign_editor" => {
+                let editor = match field("editor_id") {
+                    "" => None,
+                    value => {
+                        let id: i32 = value.parse().map_err(|_| {
+                            SubmissionError::ValidationError("Invalid editor".to_string())
+                        })?;
+                        let conn =
+                            init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+                        let editor = AdminRepository::new(conn)
+                            .find_admin_by_id(id)?
+                            .filter(|admin| admin.can(Permission::PublishArticles))
+                            .ok_or_else(|| {
+                                SubmissionError::ValidationError(
+                                    "Handling editors must be active users who can publish"
+                                        .to_string(),
+                                )
+                            })?;
+                        Some(editor)
+                    }
                 };
-                Ok(HttpResponse::Ok()
-                    .content_type("text/html; charset=utf-8")
-                    .body(template.render().map_err(|e| {
-                        error!("Submissions template render error: {:?}", e); // Changed {} to {:?}
-                        SubmissionError::FileProcessingError(format!("Template error: {:?}", e))
-                        // Changed {} to {:?}
-                    })?))
+
+                let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+                SubmissionRepository::new(conn)
+                    .set_editor(&ids, editor.as_ref().map(|editor| editor.id))?;
+                let editor_email = editor.map(|editor| editor.email).unwrap_or_default();
+                for id in &ids {
+                    audit::record(
+                        &req,
+                        &user,
+                        AuditAction::EditorAssigned,
+                        "submission",
+                        id,
+                        Some(json!({ "editor": editor_email }).to_string()),
+                    )?;
+                }
+                Ok(BulkOutcome::Done(if editor_email.is_empty() {
+                    format!(
+                        "Removed the handling editor from {} submission(s).",
+                        ids.len()
+                    )
+                } else {
+                    format!(
+                        "{} is now handling {} submission(s).",
+                        editor_email,
+                        ids.len()
+                    )
+                }))
             }
-            .await;
-            result.map_err(ActixError::from)
+            "decision" => {
+                let decision = Decision::parse(field("decision")).ok_or_else(|| {
+                    SubmissionError::ValidationError("Choose a decision".to_string())
+                })?;
+                let note = field("note");
+
+                let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+                ReviewRepository::new(conn).record_decisions(&ids, decision, note, user.id)?;
+                for id in &ids {
+                    audit::record(
+                        &req,
+                        &user,
+                        AuditAction::DecisionRecorded,
+                        "submission",
+                        id,
+                        Some(json!({ "decision": decision.as_str(), "note": note }).to_string()),
+                    )?;
+                }
+                Ok(BulkOutcome::Done(format!(
+                    "Recorded '{}' on {} submission(s).",
+                    decision.label(),
+                    ids.len()
+                )))
+            }
+            _ => Err(SubmissionError::ValidationError(
+                "Choose an action".to_string(),
+            )),
+        }
+    })();
+
+    match result {
+        Ok(BulkOutcome::Export(items)) => export_submissions(&items),
+        Ok(BulkOutcome::Done(notice)) => {
+            render_submissions_page(csrf, &user, filter, None, Some(notice))
         }
-        Err(redirect) => Ok(redirect),
+        Err(SubmissionError::ValidationError(msg)) => {
+            render_submissions_page(csrf, &user, filter, Some(msg), None)
+        }
+        Err(e) => Err(e),
+    }
+    .map_err(ActixError::from)
+}
+
+// The submission page with its editorial history; `error` reports a workflow action
+// that could not be carried out
+pub(crate) fn render_submission_detail(
+    csrf: CsrfToken,
+    user: &AuthenticatedUser,
+    submission_id: i32,
+    error: Option<String>,
+) -> Result<HttpResponse, SubmissionError> {
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let submission = SubmissionRepository::new(conn).get_submission_by_id(submission_id)?;
+
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let attachments =
+        AttachmentRepository::new(conn).get_attachments("submission", submission_id)?;
+
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let matches = SimilarityRepository::new(conn).get_matches_for_submission(submission_id)?;
+
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let review_repository = ReviewRepository::new(conn);
+    let decisions = review_repository.get_decisions(submission_id)?;
+    let reviews = review_repository.get_reviews(submission_id)?;
+
+    let can_manage_reviews = user.can(Permission::PublishArticles);
+    let reviewers = if can_manage_reviews {
+        let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        AdminRepository::new(conn)
+            .get_all_admins()?
+            .into_iter()
+            .filter(|admin| {
+                admin.can(Permission::ReviewSubmissions)
+                    && !reviews.iter().any(|review| review.reviewer_id == admin.id)
+            })
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    let template = AdminSubmissionDetailTemplate {
+        submission,
+        attachments,
+        matches,
+        threshold_percent: format!("{:.0}%", get_similarity_threshold() * 100.0),
+        can_delete: user.can(Permission::DeleteArticles),
+        decisions,
+        reviews,
+        reviewers,
+        decision_options: &Decision::ALL,
+        can_manage_reviews,
+        user_id: user.id,
+        error,
+        current_page: "submissions",
+        csrf_token: csrf.into_inner(),
+    };
+    Ok(HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(template.render().map_err(|e| {
+            error!("Submission detail template render error: {:?}", e);
+            SubmissionError::InternalError(format!("Template error: {:?}", e))
+        })?))
+}
+
+#[get("/submissions/{id}")]
+pub async fn admin_submission_detail_handler(
+    user: AuthenticatedUser,
+    csrf: CsrfToken,
+    id: web::Path<i32>,
+) -> Result<HttpResponse, ActixError> {
+    let submission_id = id.into_inner();
+    user.require_submission(submission_id)?;
+    render_submission_detail(csrf, &user, submission_id, None).map_err(ActixError::from)
+}
+
+// Moves the submission to the trash and returns to the submissions list
+#[post("/submissions/{id}/delete")]
+pub async fn delete_submission_handler(
+    user: AuthenticatedUser,
+    req: HttpRequest,
+    id: web::Path<i32>,
+) -> Result<HttpResponse, ActixError> {
+    user.require(Permission::DeleteArticles)?;
+    let result: Result<HttpResponse, SubmissionError> = (|| {
+        let submission_id = id.into_inner();
+
+        let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        let repository = SubmissionRepository::new(conn);
+        let submission = repository.get_submission_by_id(submission_id)?;
+        repository.trash_submission(submission_id, user.id)?;
+        audit::record(
+            &req,
+            &user,
+            AuditAction::SubmissionDeleted,
+            "submission",
+            submission_id,
+            snapshot(&submission),
+        )?;
+
+        Ok(HttpResponse::Found()
+            .append_header(("Location", "/admin/submissions"))
+            .finish())
+    })();
+    result.map_err(ActixError::from)
+}
+
+#[get("/submissions/{id}/attachments/{attachment_id}")]
+pub async fn download_submission_attachment_handler(
+    user: AuthenticatedUser,
+    req: HttpRequest,
+    path: web::Path<(i32, i32)>,
+) -> Result<HttpResponse, ActixError> {
+    let (submission_id, attachment_id) = path.into_inner();
+    user.require_submission(submission_id)?;
+    let result: Result<HttpResponse, SubmissionError> = async move {
+        let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        SubmissionRepository::new(conn).get_submission_by_id(submission_id)?;
+
+        let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        let attachment = AttachmentRepository::new(conn).get_attachment(
+            "submission",
+            submission_id,
+            attachment_id,
+        )?;
+        let response = utils::serve_attachment(&attachment, &req).await?;
+        audit::record(
+            &req,
+            &user,
+            AuditAction::AttachmentDownloaded,
+            "submission",
+            submission_id,
+            snapshot(&json!({ "attachment_id": attachment.id, "file": attachment.original_name })),
+        )?;
+        Ok(response)
     }
+    .await;
+    result.map_err(ActixError::from)
 }
 
 #[get("/submissions/{id}/download")]
 pub async fn download_submission_handler(
-    session: Session,
+    user: AuthenticatedUser,
     req: HttpRequest, // Need request to build absolute paths if needed, but NamedFile handles relative
     id: web::Path<i32>,
 ) -> Result<HttpResponse, ActixError> {
-    match check_authentication(&session) {
-        Ok(_) => {
-            let submission_id = id.into_inner();
-            debug!(
-                "Attempting to download submission PDF for ID: {}",
-                submission_id
-            );
+    let submission_id = id.into_inner();
+    user.require_submission(submission_id)?;
+    debug!(
+        "Attempting to download submission PDF for ID: {}",
+        submission_id
+    );
 
-            let result: Result<NamedFile, SubmissionError> = async move {
-                let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
-                let sub_repo = SubmissionRepository::new(conn);
-                let submission = sub_repo.get_submission_by_id(submission_id)?;
-
-                // pdf_url in submission should be like "./data/uploads/uuid.pdf"
-                let file_path = PathBuf::from(&submission.pdf_url);
-
-                // Extract filename for content disposition
-                let filename = file_path
-                    .file_name()
-                    .and_then(|name| name.to_str())
-                    .unwrap_or("submission.pdf") // Fallback filename
-                    .to_string();
-
-                // Attempt to open the file
-                let named_file = NamedFile::open_async(&file_path).await.map_err(|io_err| {
-                    error!(
-                        "Failed to open submission file {:?} for ID {}: {:?}", // Changed {} to {:?}
-                        file_path, submission_id, io_err
-                    );
-                    // Map IO error to NotFound or InternalError appropriately
-                    if io_err.kind() == std::io::ErrorKind::NotFound {
-                        SubmissionError::NotFound(format!(
-                            "Submission file not found for ID {}",
-                            submission_id
-                        ))
-                    } else {
-                        SubmissionError::StorageError(format!(
-                            "Error opening submission file: {:?}", // Changed {} to {:?}
-                            io_err
-                        ))
-                    }
-                })?;
+    let result: Result<NamedFile, SubmissionError> = async move {
+        let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        let sub_repo = SubmissionRepository::new(conn);
+        let submission = sub_repo.get_submission_by_id(submission_id)?;
 
-                // Set headers for download
-                Ok(named_file.set_content_disposition(ContentDisposition {
-                    disposition: DispositionType::Attachment, // Force download
-                    parameters: vec![DispositionParam::Filename(filename)],
-                }))
-            }
-            .await; // await the inner block
+        // pdf_url in submission should be like "./data/uploads/uuid.pdf"
+        let file_path = PathBuf::from(&submission.pdf_url);
 
-            // Map SubmissionError to ActixError OR directly return NamedFile response
-            match result {
-                Ok(named_file) => Ok(named_file.into_response(&req)), // Convert NamedFile to HttpResponse
-                Err(e) => Err(ActixError::from(e)), // Convert SubmissionError to ActixError
+        // Extract filename for content disposition
+        let filename = file_path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or("submission.pdf") // Fallback filename
+            .to_string();
+
+        // Attempt to open the file
+        let named_file = NamedFile::open_async(&file_path).await.map_err(|io_err| {
+            error!(
+                "Failed to open submission file {:?} for ID {}: {:?}", // Changed {} to {:?}
+                file_path, submission_id, io_err
+            );
+            // Map IO error to NotFound or InternalError appropriately
+            if io_err.kind() == std::io::ErrorKind::NotFound {
+                SubmissionError::NotFound(format!(
+                    "Submission file not found for ID {}",
+                    submission_id
+                ))
+            } else {
+                SubmissionError::StorageError(format!(
+                    "Error opening submission file: {:?}", // Changed {} to {:?}
+                    io_err
+                ))
             }
+        })?;
+
+        // Set headers for download
+        Ok(named_file.set_content_disposition(ContentDisposition {
+            disposition: DispositionType::Attachment, // Force download
+            parameters: vec![DispositionParam::Filename(filename)],
+        }))
+    }
+    .await; // await the inner block
+
+    // Map SubmissionError to ActixError OR directly return NamedFile response
+    match result {
+        Ok(named_file) => {
+            audit::record(
+                &req,
+                &user,
+                AuditAction::SubmissionDownloaded,
+                "submission",
+                submission_id,
+                None,
+            )?;
+            Ok(named_file.into_response(&req)) // Convert NamedFile to HttpResponse
         }
-        Err(redirect) => Ok(redirect),
+        Err(e) => Err(ActixError::from(e)), // Convert SubmissionError to ActixError
     }
 }
 
 #[post("/{id}/edit")]
 pub async fn update_journal_handler(
-    session: Session,
+    user: AuthenticatedUser,
+    req: HttpRequest,
     id: web::Path<i32>,
     form: web::Form<EditJournalForm>,
 ) -> Result<HttpResponse, ActixError> {
-    match check_authentication(&session) {
-        Ok(_) => {
-            let journal_id = id.into_inner();
-
-            // Parse the publication date
-            let naive_date = NaiveDate::parse_from_str(&form.publication_date, "%Y-%m-%d")
-                .map_err(|_| {
-                    SubmissionError::ValidationError("Invalid publication date format".to_string())
-                })?;
+    user.require(Permission::EditArticles)?;
+    let journal_id = id.into_inner();
 
-            let publication_datetime = DateTime::<Utc>::from_naive_utc_and_offset(
-                naive_date.and_hms_opt(0, 0, 0).unwrap(),
-                Utc,
-            );
+    // Parse the publication date
+    let naive_date =
+        NaiveDate::parse_from_str(&form.publication_date, "%Y-%m-%d").map_err(|_| {
+            SubmissionError::ValidationError("Invalid publication date format".to_string())
+        })?;
+
+    let publication_datetime =
+        DateTime::<Utc>::from_naive_utc_and_offset(naive_date.and_hms_opt(0, 0, 0).unwrap(), Utc);
+
+    // Validate form data
+    if form.title.is_empty()
+        || form.authors.is_empty()
+        || form.abstract_text.is_empty()
+        || form.keywords.is_empty()
+        || form.pages.is_empty()
+    {
+        return Err(SubmissionError::ValidationError("All fields are required".to_string()).into());
+    }
 
-            // Validate form data
-            if form.title.is_empty()
-                || form.authors.is_empty()
-                || form.abstract_text.is_empty()
-                || form.keywords.is_empty()
-                || form.pages.is_empty()
-                || form.pdf_url.is_empty()
-            {
-                return Err(SubmissionError::ValidationError(
-                    "All fields are required".to_string(),
-                )
-                .into());
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let repository = JournalRepository::new(conn);
+    let previous = repository.get_journal_by_id(journal_id)?;
+
+    // Create updated journal
+    let updated_journal = Journal {
+        id: Some(journal_id),
+        title: form.title.clone(),
+        authors: form.authors.clone(),
+        abstract_text: form.abstract_text.clone(),
+        keywords: form.keywords.clone(),
+        volume_number: form.volume_number,
+        issue_number: form.issue_number,
+        pages: form.pages.clone(),
+        publication_date: publication_datetime,
+        // Only replaced by uploading a new file
+        pdf_url: previous.pdf_url.clone(),
+        created_at: previous.created_at, // We don't update created_at
+    };
+
+    // Saving the form unchanged doesn't make a new version
+    let Some(changes) = field_changes(&previous, &updated_journal) else {
+        return Ok(HttpResponse::Found()
+            .append_header(("Location", format!("/journals/{}", journal_id)))
+            .finish());
+    };
+
+    // Update the journal, keeping the previous state in its version history
+    let versions = VersionRepository::new(
+        init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?,
+    );
+    versions.ensure_initial_version(&previous)?;
+    repository.update_journal(&updated_journal)?;
+    versions.record_version(&updated_journal, Some(user.id), form.change_note.trim())?;
+    if updated_journal.keywords != previous.keywords {
+        let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        TaxonomyRepository::new(conn)
+            .set_journal_keywords(journal_id, &updated_journal.keywords)?;
+    }
+    if updated_journal.authors != previous.authors {
+        let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        AuthorRepository::new(conn).set_journal_authors(journal_id, &updated_journal.authors)?;
+    }
+    audit::record(
+        &req,
+        &user,
+        AuditAction::JournalUpdated,
+        "journal",
+        journal_id,
+        Some(changes),
+    )?;
+
+    // Redirect to the journal detail page
+    Ok(HttpResponse::Found()
+        .append_header(("Location", format!("/journals/{}", journal_id)))
+        .finish())
+}
+
+// Replaces an article's PDF with an uploaded file, as a new version. The old file stays
+// on disk for the earlier versions that point to it.
+#[post("/{id}/pdf")]
+pub async fn replace_journal_pdf_handler(
+    user: AuthenticatedUser,
+    req: HttpRequest,
+    id: web::Path<i32>,
+    mut payload: Multipart,
+) -> Result<HttpResponse, ActixError> {
+    user.require(Permission::EditArticles)?;
+    let journal_id = id.into_inner();
+    let result: Result<HttpResponse, SubmissionError> = async move {
+        let mut pdf_filename: Option<String> = None;
+        let mut change_note = String::new();
+        let mut staged = utils::StagedFiles::default();
+
+        while let Some(field_result) = payload.next().await {
+            let mut field = field_result.map_err(|e| {
+                SubmissionError::FileProcessingError(format!("Multipart error: {:?}", e))
+            })?;
+            let content_disposition = field.content_disposition().cloned().ok_or_else(|| {
+                SubmissionError::ValidationError("Content disposition missing".to_string())
+            })?;
+
+            match content_disposition.get_name() {
+                Some("pdf") => {
+                    let file_name = utils::save_uploaded_file(field).await?;
+                    staged.add(format!("./data/uploads/{}", file_name));
+                    pdf_filename = Some(file_name);
+                }
+                Some("change_note") => change_note = utils::read_field(field).await?,
+                _ => while field.next().await.is_some() {},
             }
+        }
 
-            // Create updated journal
-            let updated_journal = Journal {
-                id: Some(journal_id),
-                title: form.title.clone(),
-                authors: form.authors.clone(),
-                abstract_text: form.abstract_text.clone(),
-                keywords: form.keywords.clone(),
-                volume_number: form.volume_number,
-                issue_number: form.issue_number,
-                pages: form.pages.clone(),
-                publication_date: publication_datetime,
-                pdf_url: form.pdf_url.clone(),
-                created_at: None, // We don't update created_at
-            };
+        let pdf_url = pdf_filename.ok_or(SubmissionError::ValidationError(
+            "PDF file is required".to_string(),
+        ))?;
+        if !pdf_url.to_ascii_lowercase().ends_with(".pdf") {
+            return Err(SubmissionError::ValidationError(
+                "The new file must be a PDF".to_string(),
+            ));
+        }
 
-            let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
-            let repository = JournalRepository::new(conn);
+        let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        let repository = JournalRepository::new(conn);
+        let previous = repository.get_journal_by_id(journal_id)?;
+        let updated = Journal {
+            pdf_url,
+            ..previous.clone()
+        };
 
-            // Update the journal
-            repository.update_journal(&updated_journal)?;
+        let versions = VersionRepository::new(
+            init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?,
+        );
+        versions.ensure_initial_version(&previous)?;
+        repository.update_journal(&updated)?;
+        staged.keep();
+        let note = match change_note.trim() {
+            "" => "Replaced the PDF".to_string(),
+            note => note.to_string(),
+        };
+        versions.record_version(&updated, Some(user.id), &note)?;
+        audit::record(
+            &req,
+            &user,
+            AuditAction::JournalUpdated,
+            "journal",
+            journal_id,
+            field_changes(&previous, &updated),
+        )?;
 
-            // Redirect to the journal detail page
-            Ok(HttpResponse::Found()
-                .append_header(("Location", format!("/journals/{}", journal_id)))
-                .finish())
+        // Search and similarity checks use the text of the current PDF
+        let pdf_path = PathBuf::from("./data/uploads").join(&updated.pdf_url);
+        match web::block(move || pdf::extract_pdf_metadata(&pdf_path)).await {
+            Ok(Ok(metadata)) => repository.update_full_text(journal_id, &metadata.text)?,
+            Ok(Err(e)) => warn!("Text extraction failed for journal {}: {}", journal_id, e),
+            Err(e) => warn!(
+                "Text extraction task failed for journal {}: {}",
+                journal_id, e
+            ),
         }
-        Err(redirect) => Ok(redirect),
+
+        Ok(HttpResponse::Ok().json(UploadResponse {
+            success: true,
+            journal_id,
+            message: "PDF replaced".to_string(),
+        }))
     }
+    .await;
+
+    result.map_err(ActixError::from)
 }
 
 #[get("/{id}/edit")]
 pub async fn edit_journal_form_handler(
-    session: Session,
+    user: AuthenticatedUser,
+    csrf: CsrfToken,
     id: web::Path<i32>,
 ) -> Result<HttpResponse, ActixError> {
-    match check_authentication(&session) {
-        Ok(_) => {
-            let journal_id = id.into_inner();
+    user.require(Permission::EditArticles)?;
+    let journal_id = id.into_inner();
 
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let repository = JournalRepository::new(conn);
+
+    match repository.get_journal_by_id(journal_id) {
+        Ok(journal) => {
             let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
-            let repository = JournalRepository::new(conn);
-
-            match repository.get_journal_by_id(journal_id) {
-                Ok(journal) => {
-                    let template = EditJournalTemplate::new(journal);
-
-                    Ok(HttpResponse::Ok()
-                        .content_type("text/html; charset=utf-8")
-                        .body(template.render().map_err(|e| {
-                            error!("Edit journal template render error: {:?}", e); // Changed {} to {:?}
-                            actix_web::error::ErrorInternalServerError("Template error")
-                        })?))
-                }
-                Err(e) => {
-                    error!("Failed to fetch journal: {:?}", e); // Changed {} to {:?}
-                    Ok(HttpResponse::Found()
-                        .append_header(("Location", "/admin/dashboard"))
-                        .finish())
-                }
-            }
+            let taxonomy = TaxonomyRepository::new(conn);
+            let assigned = taxonomy
+                .get_journal_subjects(journal_id)?
+                .iter()
+                .map(|subject| subject.id)
+                .collect();
+            let template =
+                EditJournalTemplate::new(csrf, journal, taxonomy.get_all_subjects()?, assigned);
+
+            Ok(HttpResponse::Ok()
+                .content_type("text/html; charset=utf-8")
+                .body(template.render().map_err(|e| {
+                    error!("Edit journal template render error: {:?}", e); // Changed {} to {:?}
+                    actix_web::error::ErrorInternalServerError("Template error")
+                })?))
+        }
+        Err(e) => {
+            error!("Failed to fetch journal: {:?}", e); // Changed {} to {:?}
+            Ok(HttpResponse::Found()
+                .append_header(("Location", "/admin/dashboard"))
+                .finish())
         }
-        Err(redirect) => Ok(redirect),
     }
 }
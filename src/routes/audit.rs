@@ -0,0 +1,144 @@
+use actix_web::{get, web, Error as ActixError, HttpRequest, HttpResponse};
+use askama::Template;
+use chrono::Utc;
+use log::{error, info};
+
+use crate::{
+    auth::{csrf::CsrfToken, AuthenticatedUser},
+    db::{audit_repository::AuditRepository, schema::init_db},
+    errors::SubmissionError,
+    models::{
+        admin::Permission,
+        audit::{AuditAction, AuditEntry, AuditFilter},
+    },
+    utils::{
+        audit,
+        export::{csv_response, CsvWriter},
+    },
+};
+
+const ENTRIES_PER_PAGE: i64 = 50;
+
+#[derive(Template)]
+#[template(path = "admin/audit.html")]
+struct AuditLogTemplate {
+    current_page: &'static str,
+    csrf_token: String,
+    entries: Vec<AuditEntry>,
+    actions: &'static [AuditAction],
+    target_types: Vec<String>,
+    filter: AuditFilter,
+    query: String,
+    total: i64,
+    page: i64,
+    total_pages: i64,
+}
+
+impl AuditLogTemplate {
+    fn filter_value<'a>(&self, value: &'a Option<String>) -> &'a str {
+        value.as_deref().unwrap_or("")
+    }
+}
+
+#[get("/audit")]
+pub async fn audit_log_handler(
+    user: AuthenticatedUser,
+    csrf: CsrfToken,
+    filter: web::Query<AuditFilter>,
+) -> Result<HttpResponse, ActixError> {
+    user.require(Permission::ViewAuditLog)?;
+    let filter = filter.into_inner();
+
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let repository = AuditRepository::new(conn);
+    let total = repository.count_entries(&filter)?;
+    let total_pages = ((total + ENTRIES_PER_PAGE - 1) / ENTRIES_PER_PAGE).max(1);
+    let page = filter.page().min(total_pages);
+    let entries = repository.search_entries(
+        &filter,
+        Some(ENTRIES_PER_PAGE),
+        (page - 1) * ENTRIES_PER_PAGE,
+    )?;
+
+    let template = AuditLogTemplate {
+        current_page: "audit",
+        csrf_token: csrf.into_inner(),
+        entries,
+        actions: &AuditAction::ALL,
+        target_types: repository.get_target_types()?,
+        query: filter.query_string(),
+        filter,
+        total,
+        page,
+        total_pages,
+    };
+    let body = template.render().map_err(|e| {
+        error!("Audit log template render error: {:?}", e);
+        SubmissionError::InternalError("Template error".to_string())
+    })?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(body))
+}
+
+// Every entry matching the filters, as CSV
+#[get("/audit/export")]
+pub async fn export_audit_log_handler(
+    user: AuthenticatedUser,
+    req: HttpRequest,
+    filter: web::Query<AuditFilter>,
+) -> Result<HttpResponse, ActixError> {
+    user.require(Permission::ViewAuditLog)?;
+    let filter = filter.into_inner();
+
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let entries = AuditRepository::new(conn).search_entries(&filter, None, 0)?;
+
+    // Exports are themselves recorded, before the entries leave the system
+    audit::record(
+        &req,
+        &user,
+        AuditAction::AuditLogExported,
+        "audit_log",
+        "",
+        (!filter.query_string().is_empty()).then(|| filter.query_string()),
+    )?;
+    info!(
+        "Admin {} exported {} audit log entries",
+        user.email,
+        entries.len()
+    );
+
+    let mut csv = CsvWriter::new(Vec::new());
+    csv.write_row(&[
+        "id",
+        "timestamp",
+        "actor_id",
+        "actor_email",
+        "action",
+        "target_type",
+        "target_id",
+        "changes",
+        "ip_address",
+    ])?;
+    for entry in &entries {
+        csv.write_row(&[
+            entry.id.to_string(),
+            entry.created_at.to_rfc3339(),
+            entry.actor_id.map(|id| id.to_string()).unwrap_or_default(),
+            entry.actor_email.clone(),
+            entry.action.clone(),
+            entry.target_type.clone(),
+            entry.target_id.clone(),
+            entry.changes_display().to_string(),
+            entry.ip_address.clone(),
+        ])?;
+    }
+
+    csv_response(
+        csv,
+        &format!("audit-log-{}.csv", Utc::now().format("%Y%m%d")),
+    )
+    .map_err(ActixError::from)
+}
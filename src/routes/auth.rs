@@ -1,11 +1,28 @@
 use crate::{
-    db::{admin_repository::AdminRepository, schema::init_db},
+    auth::{
+        client_ip,
+        csrf::{self, CsrfToken},
+        session_store::{LOGIN_ID_KEY, LOGIN_IP_KEY, USER_AGENT_KEY},
+        throttle::{account_locked_until, describe_wait, ip_retry_after, IP_WINDOW_MINUTES},
+        totp,
+    },
+    db::{
+        admin_repository::AdminRepository, login_attempt_repository::LoginAttemptRepository,
+        schema::init_db, two_factor_repository::TwoFactorRepository,
+    },
     errors::SubmissionError,
-    utils::security::verify_password,
+    models::admin::Admin,
+    routes::account::send_verification_email,
+    utils::security::{generate_token, verify_dummy_password, verify_password},
 };
 use actix_session::Session;
-use actix_web::{get, post, web, HttpResponse, Responder};
+use actix_web::{
+    get,
+    http::{header, StatusCode},
+    post, web, HttpRequest, HttpResponse, HttpResponseBuilder, Responder,
+};
 use askama::Template;
+use chrono::Utc;
 use log::{error, info, warn};
 use serde::Deserialize;
 
@@ -13,6 +30,24 @@ use serde::Deserialize;
 #[template(path = "admin/login.html")]
 struct LoginTemplate {
     error: Option<String>,
+    csrf_token: String,
+}
+
+#[derive(Template)]
+#[template(path = "admin/login_2fa.html")]
+struct TwoFactorLoginTemplate {
+    error: Option<String>,
+    csrf_token: String,
+}
+
+// Session keys for a login that has passed the password step but not the second factor
+const PENDING_2FA_ADMIN_KEY: &str = "pending_2fa_admin_id";
+const PENDING_2FA_AT_KEY: &str = "pending_2fa_at";
+const PENDING_2FA_TTL_SECONDS: i64 = 5 * 60;
+
+#[derive(Deserialize)]
+pub struct TwoFactorFormData {
+    code: String,
 }
 
 #[derive(Deserialize)]
@@ -23,7 +58,7 @@ pub struct LoginFormData {
 
 // Show Login Form
 #[get("/admin/login")]
-pub async fn show_login_form(session: Session) -> impl Responder {
+pub async fn show_login_form(session: Session, csrf: CsrfToken) -> impl Responder {
     // ... (same as before) ...
     if session.get::<i32>("admin_id").unwrap_or(None).is_some() {
         return HttpResponse::Found()
@@ -32,18 +67,162 @@ pub async fn show_login_form(session: Session) -> impl Responder {
     }
     HttpResponse::Ok()
         .content_type("text/html; charset=utf-8")
-        .body(LoginTemplate { error: None }.render().unwrap_or_else(|e| {
+        .body(
+            LoginTemplate {
+                error: None,
+                csrf_token: csrf.into_inner(),
+            }
+            .render()
+            .unwrap_or_else(|e| {
+                error!("Login template render error: {}", e);
+                "Error rendering login page.".to_string()
+            }),
+        )
+}
+
+fn login_page(csrf: &CsrfToken, mut response: HttpResponseBuilder, error: &str) -> HttpResponse {
+    response.content_type("text/html; charset=utf-8").body(
+        LoginTemplate {
+            error: Some(error.to_string()),
+            csrf_token: csrf.as_str().to_string(),
+        }
+        .render()
+        .unwrap_or_else(|e| {
             error!("Login template render error: {}", e);
             "Error rendering login page.".to_string()
-        }))
+        }),
+    )
+}
+
+fn too_many_attempts(csrf: &CsrfToken, wait: chrono::Duration) -> HttpResponse {
+    let mut response = HttpResponse::TooManyRequests();
+    response.append_header(("Retry-After", wait.num_seconds().max(1).to_string()));
+    login_page(
+        csrf,
+        response,
+        &format!(
+            "Too many failed login attempts. Try again in {}.",
+            describe_wait(wait)
+        ),
+    )
+}
+
+// Every attempt is kept for the audit log and per-IP throttling
+fn record_attempt(
+    email: &str,
+    ip_address: &str,
+    succeeded: bool,
+    reason: Option<&str>,
+) -> Result<(), SubmissionError> {
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    LoginAttemptRepository::new(conn).record_attempt(email, ip_address, succeeded, reason)
+}
+
+// Per-IP backoff, checked before touching the account
+// Throttled requests aren't recorded as attempts, so they don't extend the backoff
+fn check_ip_throttle(
+    csrf: &CsrfToken,
+    ip_address: &str,
+) -> Result<Option<HttpResponse>, SubmissionError> {
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let (ip_failures, last_failure) = LoginAttemptRepository::new(conn).failures_from_ip(
+        ip_address,
+        Utc::now() - chrono::Duration::minutes(IP_WINDOW_MINUTES),
+    )?;
+    match ip_retry_after(ip_failures, last_failure) {
+        Some(wait) => {
+            warn!(
+                "Admin login throttled for IP {} ({} recent failures)",
+                ip_address, ip_failures
+            );
+            Ok(Some(too_many_attempts(csrf, wait)))
+        }
+        None => Ok(None),
+    }
+}
+
+// Counts a failed password or code against the account, locking it once the threshold is hit
+fn register_failure(
+    csrf: &CsrfToken,
+    admin: &Admin,
+    ip_address: &str,
+    reason: &str,
+) -> Result<HttpResponse, SubmissionError> {
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let repository = AdminRepository::new(conn);
+    let failed_login_count = repository.record_failed_login(admin.id)?;
+    let locked_until = account_locked_until(failed_login_count);
+    if let Some(until) = locked_until {
+        repository.lock_account(admin.id, until)?;
+    }
+    record_attempt(&admin.email, ip_address, false, Some(reason))?;
+
+    match locked_until {
+        Some(until) => {
+            warn!(
+                "Admin account {} locked until {} after {} failed logins",
+                admin.email, until, failed_login_count
+            );
+            Ok(too_many_attempts(csrf, until - Utc::now()))
+        }
+        None => {
+            warn!("Admin login failed ({}) for email: {}", reason, admin.email);
+            let message = if reason == "invalid_2fa" {
+                "Invalid authentication or recovery code."
+            } else {
+                "Invalid email or password."
+            };
+            Ok(login_page(csrf, HttpResponse::Unauthorized(), message))
+        }
+    }
+}
+
+// Final step of a login, once every factor has been checked
+fn complete_login(
+    req: &HttpRequest,
+    session: &Session,
+    admin: &Admin,
+    ip_address: &str,
+) -> Result<HttpResponse, SubmissionError> {
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    AdminRepository::new(conn).reset_failed_logins(admin.id)?;
+    record_attempt(&admin.email, ip_address, true, None)?;
+
+    session.remove(PENDING_2FA_ADMIN_KEY);
+    session.remove(PENDING_2FA_AT_KEY);
+    csrf::rotate(req);
+    let user_agent = req
+        .headers()
+        .get(header::USER_AGENT)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("");
+    // The login id lets this session be listed and revoked without exposing its cookie
+    session
+        .insert("admin_id", admin.id)
+        .and_then(|_| session.insert(LOGIN_ID_KEY, generate_token()))
+        .and_then(|_| session.insert(LOGIN_IP_KEY, ip_address))
+        .and_then(|_| session.insert(USER_AGENT_KEY, user_agent))
+        .map_err(|e| SubmissionError::StorageError(format!("Session insert error: {}", e)))?;
+    session.renew();
+    info!("Admin login successful for email: {}", admin.email);
+    Ok(HttpResponse::Found()
+        .append_header(("Location", "/admin/dashboard"))
+        .finish())
 }
 
 // Process Login
 #[post("/admin/login")]
 pub async fn login(
+    req: HttpRequest,
     session: Session,
+    csrf: CsrfToken,
     form: web::Form<LoginFormData>,
 ) -> Result<HttpResponse, SubmissionError> {
+    let ip_address = client_ip(&req);
+    if let Some(throttled) = check_ip_throttle(&csrf, &ip_address)? {
+        return Ok(throttled);
+    }
+
     // --- Clone the necessary data BEFORE the closure ---
     let email_clone = form.email.clone();
     let password_clone = form.password.clone(); // Also clone password for the second block
@@ -58,85 +237,207 @@ pub async fn login(
     .await
     .map_err(|e| SubmissionError::DatabaseError(format!("Blocking error: {}", e)))??; // Handle blocking error and inner DB error
 
-    match result {
-        Some(admin) => {
-            let stored_hash = admin.password_hash.clone();
-            // --- Use the cloned password in the second web::block ---
-            let match_result = web::block(move || verify_password(&password_clone, &stored_hash))
-                .await
+    let Some(admin) = result else {
+        // Take as long as a wrong password would, so response times don't reveal accounts
+        let password = form.password.clone();
+        web::block(move || verify_dummy_password(&password))
+            .await
+            .map_err(|e| {
+                SubmissionError::InternalError(format!("Password verification task failed: {}", e))
+            })?;
+        warn!("Admin login failed (email not found): {}", form.email);
+        record_attempt(&form.email, &ip_address, false, Some("unknown_email"))?;
+        return Ok(login_page(
+            &csrf,
+            HttpResponse::Unauthorized(),
+            "Invalid email or password.",
+        ));
+    };
+
+    // A locked account is refused before the password is checked, so guessing can't continue
+    if let Some(wait) = admin.lockout_remaining() {
+        warn!("Admin login refused (locked) for email: {}", form.email);
+        record_attempt(&form.email, &ip_address, false, Some("account_locked"))?;
+        return Ok(too_many_attempts(&csrf, wait));
+    }
+
+    let stored_hash = admin.password_hash.clone();
+    // --- Use the cloned password in the second web::block ---
+    let match_result = web::block(move || verify_password(&password_clone, &stored_hash))
+        .await
+        .map_err(|e| {
+            SubmissionError::InternalError(format!("Password verification task failed: {}", e))
+        })?; // Handle blocking error for verify
+
+    match match_result {
+        Ok(false) => register_failure(&csrf, &admin, &ip_address, "invalid_password"),
+        Ok(true) if !admin.is_active => {
+            warn!(
+                "Admin login refused (deactivated) for email: {}",
+                form.email
+            );
+            record_attempt(&form.email, &ip_address, false, Some("deactivated"))?;
+            Ok(login_page(
+                &csrf,
+                HttpResponse::Forbidden(),
+                "This account has been deactivated.",
+            ))
+        }
+        Ok(true) if !admin.email_verified => {
+            warn!("Admin login refused (unverified) for email: {}", form.email);
+            let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+            AdminRepository::new(conn).reset_failed_logins(admin.id)?;
+            record_attempt(&form.email, &ip_address, false, Some("unverified"))?;
+            send_verification_email(admin.id, &admin.email).await?;
+            Ok(login_page(
+                &csrf,
+                HttpResponse::Forbidden(),
+                "Please confirm your email address first. \
+                 We've sent you a new verification link.",
+            ))
+        }
+        Ok(true) if admin.totp_enabled => {
+            // Failures are only cleared once the second factor succeeds, so knowing the
+            // password doesn't give unlimited guesses at the code
+            session
+                .insert(PENDING_2FA_ADMIN_KEY, admin.id)
+                .and_then(|_| session.insert(PENDING_2FA_AT_KEY, Utc::now().timestamp()))
                 .map_err(|e| {
-                    SubmissionError::InternalError(format!(
-                        "Password verification task failed: {}",
-                        e
-                    ))
-                })?; // Handle blocking error for verify
-
-            match match_result {
-                Ok(true) => {
-                    session.insert("admin_id", admin.id).map_err(|e| {
-                        SubmissionError::StorageError(format!("Session insert error: {}", e))
-                    })?;
-                    session.renew();
-                    // Use the original email (or the clone) for logging
-                    info!("Admin login successful for email: {}", form.email);
-                    Ok(HttpResponse::Found()
-                        .append_header(("Location", "/admin/dashboard"))
-                        .finish())
-                }
-                Ok(false) => {
-                    warn!(
-                        "Admin login failed (wrong password) for email: {}",
-                        form.email
-                    );
-                    Ok(HttpResponse::Unauthorized()
-                        .content_type("text/html; charset=utf-8")
-                        .body(
-                            LoginTemplate {
-                                error: Some("Invalid email or password.".to_string()),
-                            }
-                            .render()
-                            .unwrap_or_else(|e| {
-                                error!("Login template render error: {}", e);
-                                "Error rendering login page.".to_string()
-                            }),
-                        ))
-                }
-                Err(e) => {
-                    error!(
-                        "Password verification error for email {}: {}",
-                        form.email, e
-                    );
-                    Err(SubmissionError::HashingError(format!(
-                        "Password verification failed: {}",
-                        e
-                    )))
-                }
-            }
+                    SubmissionError::StorageError(format!("Session insert error: {}", e))
+                })?;
+            session.renew();
+            info!(
+                "Password accepted for {}, awaiting second factor",
+                form.email
+            );
+            Ok(HttpResponse::Found()
+                .append_header(("Location", "/admin/login/2fa"))
+                .finish())
         }
-        None => {
-            warn!("Admin login failed (email not found): {}", form.email);
-            Ok(HttpResponse::Unauthorized()
-                .content_type("text/html; charset=utf-8")
-                .body(
-                    LoginTemplate {
-                        error: Some("Invalid email or password.".to_string()),
-                    }
-                    .render()
-                    .unwrap_or_else(|e| {
-                        error!("Login template render error: {}", e);
-                        "Error rendering login page.".to_string()
-                    }),
-                ))
+        Ok(true) => complete_login(&req, &session, &admin, &ip_address),
+        Err(e) => {
+            error!(
+                "Password verification error for email {}: {}",
+                form.email, e
+            );
+            Err(SubmissionError::HashingError(format!(
+                "Password verification failed: {}",
+                e
+            )))
+        }
+    }
+}
+
+// Admin whose password was accepted and who still needs to enter a code
+fn pending_two_factor_admin(session: &Session) -> Result<Option<Admin>, SubmissionError> {
+    let admin_id = session.get::<i32>(PENDING_2FA_ADMIN_KEY).unwrap_or(None);
+    let started_at = session.get::<i64>(PENDING_2FA_AT_KEY).unwrap_or(None);
+    let (Some(admin_id), Some(started_at)) = (admin_id, started_at) else {
+        return Ok(None);
+    };
+    if Utc::now().timestamp() - started_at > PENDING_2FA_TTL_SECONDS {
+        session.remove(PENDING_2FA_ADMIN_KEY);
+        session.remove(PENDING_2FA_AT_KEY);
+        return Ok(None);
+    }
+
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    Ok(AdminRepository::new(conn)
+        .find_admin_by_id(admin_id)?
+        .filter(|admin| admin.is_active && admin.totp_enabled))
+}
+
+fn render_two_factor(csrf: CsrfToken, error: Option<String>) -> String {
+    TwoFactorLoginTemplate {
+        error,
+        csrf_token: csrf.into_inner(),
+    }
+    .render()
+    .unwrap_or_else(|e| {
+        error!("Two-factor template render error: {}", e);
+        "Error rendering login page.".to_string()
+    })
+}
+
+#[get("/admin/login/2fa")]
+pub async fn show_two_factor_form(
+    session: Session,
+    csrf: CsrfToken,
+) -> Result<HttpResponse, SubmissionError> {
+    if pending_two_factor_admin(&session)?.is_none() {
+        return Ok(HttpResponse::Found()
+            .append_header(("Location", "/admin/login"))
+            .finish());
+    }
+    Ok(HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(render_two_factor(csrf, None)))
+}
+
+#[post("/admin/login/2fa")]
+pub async fn verify_two_factor(
+    req: HttpRequest,
+    session: Session,
+    csrf: CsrfToken,
+    form: web::Form<TwoFactorFormData>,
+) -> Result<HttpResponse, SubmissionError> {
+    let Some(admin) = pending_two_factor_admin(&session)? else {
+        return Ok(HttpResponse::Found()
+            .append_header(("Location", "/admin/login"))
+            .finish());
+    };
+
+    let ip_address = client_ip(&req);
+    if let Some(throttled) = check_ip_throttle(&csrf, &ip_address)? {
+        return Ok(throttled);
+    }
+    if let Some(wait) = admin.lockout_remaining() {
+        record_attempt(&admin.email, &ip_address, false, Some("account_locked"))?;
+        return Ok(too_many_attempts(&csrf, wait));
+    }
+
+    let secret = admin.totp_secret.clone().unwrap_or_default();
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let two_factor_repo = TwoFactorRepository::new(conn);
+
+    if let Some(step) = totp::verify_code(&secret, &admin.email, &form.code, admin.totp_last_step)?
+    {
+        if two_factor_repo.record_step(admin.id, step)? {
+            return complete_login(&req, &session, &admin, &ip_address);
         }
+        warn!("Admin {} replayed an authentication code", admin.email);
     }
+    if two_factor_repo.use_recovery_code(admin.id, &form.code)? {
+        warn!(
+            "Admin {} logged in with a recovery code ({} left)",
+            admin.email,
+            two_factor_repo.remaining_recovery_codes(admin.id)?
+        );
+        return complete_login(&req, &session, &admin, &ip_address);
+    }
+
+    let response = register_failure(&csrf, &admin, &ip_address, "invalid_2fa")?;
+    if response.status() == StatusCode::TOO_MANY_REQUESTS {
+        // Locked out: start again from the password step once the lock expires
+        session.remove(PENDING_2FA_ADMIN_KEY);
+        session.remove(PENDING_2FA_AT_KEY);
+        return Ok(response);
+    }
+    Ok(HttpResponse::Unauthorized()
+        .content_type("text/html; charset=utf-8")
+        .body(render_two_factor(
+            csrf,
+            Some("Invalid authentication or recovery code.".to_string()),
+        )))
 }
 
 // Logout Handler
 #[post("/logout")]
 // --- End change ---
-pub async fn logout(session: Session) -> impl Responder {
+pub async fn logout(req: HttpRequest, session: Session) -> impl Responder {
     let admin_id_result = session.get::<i32>("admin_id");
     session.purge();
+    csrf::rotate(&req);
     match admin_id_result {
         Ok(Some(id)) => info!("Admin logout successful for ID: {}", id),
         Ok(None) => info!("Admin logout successful (no ID found in session)."),
@@ -0,0 +1,286 @@
+use actix_web::{get, post, web, Error as ActixError, HttpRequest, HttpResponse};
+use askama::Template;
+use log::{error, info};
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::{BTreeMap, HashSet};
+
+use crate::{
+    auth::{csrf::CsrfToken, AuthenticatedUser},
+    db::{
+        author_repository::AuthorRepository, journal_repository::JournalRepository,
+        notice_repository::NoticeRepository, schema::init_db,
+    },
+    errors::SubmissionError,
+    models::{
+        admin::Permission,
+        audit::{field_changes, AuditAction},
+        author::{find_duplicates, normalize_orcid, Author, DuplicateGroup},
+        journals::Journal,
+    },
+    utils::audit,
+};
+
+#[derive(Template)]
+#[template(path = "journals/authors.html")]
+struct AuthorIndexTemplate {
+    // Authors under the first letter of their surname
+    index: BTreeMap<String, Vec<Author>>,
+}
+
+#[derive(Template)]
+#[template(path = "journals/author.html")]
+struct AuthorTemplate {
+    author: Author,
+    journals: Vec<Journal>,
+    retracted: HashSet<i32>,
+}
+
+impl AuthorTemplate {
+    fn is_retracted(&self, journal: &Journal) -> bool {
+        journal.id.is_some_and(|id| self.retracted.contains(&id))
+    }
+}
+
+#[derive(Template)]
+#[template(path = "admin/authors.html")]
+struct AdminAuthorsTemplate {
+    current_page: &'static str,
+    csrf_token: String,
+    authors: Vec<Author>,
+    duplicates: Vec<DuplicateGroup>,
+    error: Option<String>,
+    notice: Option<String>,
+}
+
+#[derive(Template)]
+#[template(path = "admin/edit_author.html")]
+struct EditAuthorTemplate {
+    current_page: &'static str,
+    csrf_token: String,
+    author: Author,
+    error: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct MergeAuthorsForm {
+    pub source_id: i32,
+    pub target_id: i32,
+}
+
+#[derive(Deserialize)]
+pub struct AuthorForm {
+    #[serde(default)]
+    pub affiliation: String,
+    #[serde(default)]
+    pub orcid: String,
+}
+
+fn render(template: impl Template) -> Result<HttpResponse, SubmissionError> {
+    let body = template.render().map_err(|e| {
+        error!("Author template render error: {:?}", e);
+        SubmissionError::InternalError("Template error".to_string())
+    })?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(body))
+}
+
+// Alphabetical index of everyone with a live article
+#[get("/authors")]
+pub async fn author_index_handler() -> Result<HttpResponse, SubmissionError> {
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let mut authors = AuthorRepository::new(conn).get_all_authors()?;
+    authors.retain(|author| author.article_count > 0);
+    authors.sort_by_key(|author| (author.surname().to_lowercase(), author.name.to_lowercase()));
+
+    let mut index: BTreeMap<String, Vec<Author>> = BTreeMap::new();
+    for author in authors {
+        index.entry(author.index_letter()).or_default().push(author);
+    }
+
+    render(AuthorIndexTemplate { index })
+}
+
+// An author's profile; the ID of a merged duplicate redirects to the author it became
+#[get("/authors/{id}")]
+pub async fn author_handler(id: web::Path<i32>) -> Result<HttpResponse, SubmissionError> {
+    let author_id = id.into_inner();
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let repository = AuthorRepository::new(conn);
+    let author = match repository.get_author(author_id) {
+        Ok(author) => author,
+        Err(SubmissionError::NotFound(msg)) => {
+            return match repository.find_merged_target(author_id)? {
+                Some(target) => Ok(HttpResponse::MovedPermanently()
+                    .append_header(("Location", format!("/authors/{}", target)))
+                    .finish()),
+                None => Err(SubmissionError::NotFound(msg)),
+            };
+        }
+        Err(e) => return Err(e),
+    };
+
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let journals = JournalRepository::new(conn).get_journals_by_author(author_id)?;
+
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    render(AuthorTemplate {
+        author,
+        journals,
+        retracted: NoticeRepository::new(conn).get_retracted_ids()?,
+    })
+}
+
+fn render_authors_page(
+    csrf: CsrfToken,
+    error: Option<String>,
+    notice: Option<String>,
+) -> Result<HttpResponse, SubmissionError> {
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let authors = AuthorRepository::new(conn).get_all_authors()?;
+
+    render(AdminAuthorsTemplate {
+        current_page: "authors",
+        csrf_token: csrf.into_inner(),
+        duplicates: find_duplicates(&authors),
+        authors,
+        error,
+        notice,
+    })
+}
+
+fn authors_page_or_error(
+    csrf: CsrfToken,
+    result: Result<String, SubmissionError>,
+) -> Result<HttpResponse, SubmissionError> {
+    match result {
+        Ok(notice) => render_authors_page(csrf, None, Some(notice)),
+        Err(SubmissionError::ValidationError(msg)) => render_authors_page(csrf, Some(msg), None),
+        Err(e) => Err(e),
+    }
+}
+
+#[get("/authors")]
+pub async fn admin_authors_handler(
+    user: AuthenticatedUser,
+    csrf: CsrfToken,
+) -> Result<HttpResponse, ActixError> {
+    user.require(Permission::EditArticles)?;
+    render_authors_page(csrf, None, None).map_err(ActixError::from)
+}
+
+#[post("/authors/merge")]
+pub async fn merge_authors_handler(
+    user: AuthenticatedUser,
+    csrf: CsrfToken,
+    req: HttpRequest,
+    form: web::Form<MergeAuthorsForm>,
+) -> Result<HttpResponse, ActixError> {
+    user.require(Permission::EditArticles)?;
+    let result = (|| {
+        let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        let (source, target) =
+            AuthorRepository::new(conn).merge_authors(form.source_id, form.target_id)?;
+        audit::record(
+            &req,
+            &user,
+            AuditAction::AuthorsMerged,
+            "author",
+            target.id,
+            Some(
+                json!({ "merged": source.name, "merged_id": source.id, "into": target.name })
+                    .to_string(),
+            ),
+        )?;
+        info!(
+            "Admin {} merged author '{}' into '{}'",
+            user.email, source.name, target.name
+        );
+        Ok(format!(
+            "'{}' has been merged into '{}'.",
+            source.name, target.name
+        ))
+    })();
+
+    authors_page_or_error(csrf, result).map_err(ActixError::from)
+}
+
+#[get("/authors/{id}")]
+pub async fn edit_author_form_handler(
+    user: AuthenticatedUser,
+    csrf: CsrfToken,
+    id: web::Path<i32>,
+) -> Result<HttpResponse, ActixError> {
+    user.require(Permission::EditArticles)?;
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let author = AuthorRepository::new(conn).get_author(id.into_inner())?;
+
+    render(EditAuthorTemplate {
+        current_page: "authors",
+        csrf_token: csrf.into_inner(),
+        author,
+        error: None,
+    })
+    .map_err(ActixError::from)
+}
+
+#[post("/authors/{id}")]
+pub async fn update_author_handler(
+    user: AuthenticatedUser,
+    csrf: CsrfToken,
+    req: HttpRequest,
+    id: web::Path<i32>,
+    form: web::Form<AuthorForm>,
+) -> Result<HttpResponse, ActixError> {
+    user.require(Permission::EditArticles)?;
+    let author_id = id.into_inner();
+
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let repository = AuthorRepository::new(conn);
+    let previous = repository.get_author(author_id)?;
+
+    let orcid = match form.orcid.trim() {
+        "" => String::new(),
+        value => match normalize_orcid(value) {
+            Some(orcid) => orcid,
+            None => {
+                return render(EditAuthorTemplate {
+                    current_page: "authors",
+                    csrf_token: csrf.into_inner(),
+                    author: Author {
+                        affiliation: form.affiliation.trim().to_string(),
+                        orcid: value.to_string(),
+                        ..previous
+                    },
+                    error: Some(
+                        "That is not a valid ORCID iD (expected 0000-0000-0000-0000)".to_string(),
+                    ),
+                })
+                .map_err(ActixError::from);
+            }
+        },
+    };
+
+    let updated = Author {
+        affiliation: form.affiliation.trim().to_string(),
+        orcid,
+        ..previous.clone()
+    };
+    if let Some(changes) = field_changes(&previous, &updated) {
+        repository.update_author(author_id, &updated.affiliation, &updated.orcid)?;
+        audit::record(
+            &req,
+            &user,
+            AuditAction::AuthorUpdated,
+            "author",
+            author_id,
+            Some(changes),
+        )?;
+    }
+
+    Ok(HttpResponse::Found()
+        .append_header(("Location", "/admin/authors"))
+        .finish())
+}
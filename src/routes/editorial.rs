@@ -7,5 +7,7 @@ struct EditorialTemplate {}
 
 #[get("/editorial-board")]
 pub async fn editorial_board_handler() -> impl Responder {
-    HttpResponse::Ok().body(EditorialTemplate {}.render().unwrap())
+    HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(EditorialTemplate {}.render().unwrap())
 }
@@ -0,0 +1,124 @@
+use actix_web::{get, web, Error as ActixError, HttpRequest, HttpResponse};
+use askama::Template;
+use chrono::Utc;
+use log::{error, info};
+use serde_json::json;
+
+use crate::{
+    auth::{csrf::CsrfToken, AuthenticatedUser},
+    db::{export_repository::ExportRepository, schema::init_db},
+    errors::SubmissionError,
+    models::{
+        admin::Permission,
+        audit::AuditAction,
+        export::{ExportDataset, ExportFormat, ExportRequest},
+    },
+    utils::{
+        audit,
+        export::{stream_download, SheetWriter},
+    },
+};
+
+#[derive(Template)]
+#[template(path = "admin/exports.html")]
+struct ExportsTemplate {
+    current_page: &'static str,
+    csrf_token: String,
+    // Datasets this user may export
+    datasets: Vec<ExportDataset>,
+    formats: &'static [ExportFormat],
+    error: Option<String>,
+}
+
+fn permission_for(dataset: ExportDataset) -> Permission {
+    match dataset {
+        ExportDataset::Submissions => Permission::ViewSubmissions,
+        ExportDataset::Articles => Permission::ViewDashboard,
+    }
+}
+
+fn render_exports_page(
+    csrf: CsrfToken,
+    user: &AuthenticatedUser,
+    error: Option<String>,
+) -> Result<HttpResponse, SubmissionError> {
+    let template = ExportsTemplate {
+        current_page: "exports",
+        csrf_token: csrf.into_inner(),
+        datasets: ExportDataset::ALL
+            .into_iter()
+            .filter(|dataset| user.can(permission_for(*dataset)))
+            .collect(),
+        formats: &ExportFormat::ALL,
+        error,
+    };
+    let body = template.render().map_err(|e| {
+        error!("Exports template render error: {:?}", e);
+        SubmissionError::InternalError("Template error".to_string())
+    })?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(body))
+}
+
+#[get("/exports")]
+pub async fn exports_handler(
+    user: AuthenticatedUser,
+    csrf: CsrfToken,
+) -> Result<HttpResponse, ActixError> {
+    user.require(Permission::ViewDashboard)?;
+    render_exports_page(csrf, &user, None).map_err(ActixError::from)
+}
+
+// Submissions or published articles as CSV or XLSX, with the columns and date range
+// chosen on the exports page. Rows are streamed straight from the database.
+#[get("/exports/download")]
+pub async fn export_download_handler(
+    user: AuthenticatedUser,
+    csrf: CsrfToken,
+    req: HttpRequest,
+    query: web::Query<Vec<(String, String)>>,
+) -> Result<HttpResponse, ActixError> {
+    user.require(Permission::ViewDashboard)?;
+    let request = match ExportRequest::from_pairs(&query) {
+        Ok(request) => request,
+        Err(msg) => return render_exports_page(csrf, &user, Some(msg)).map_err(ActixError::from),
+    };
+    user.require(permission_for(request.dataset))?;
+
+    audit::record(
+        &req,
+        &user,
+        AuditAction::DataExported,
+        "export",
+        request.dataset.as_str(),
+        Some(
+            json!({
+                "format": request.format.as_str(),
+                "columns": request.columns.iter().map(|c| c.key).collect::<Vec<_>>(),
+                "from": request.from.map(|d| d.to_string()),
+                "to": request.to.map(|d| d.to_string()),
+            })
+            .to_string(),
+        ),
+    )?;
+    info!(
+        "Admin {} exported {} as {}",
+        user.email,
+        request.dataset.as_str(),
+        request.format.as_str()
+    );
+
+    let filename = request.filename(Utc::now().date_naive());
+    Ok(stream_download(
+        request.format.content_type(),
+        &filename,
+        move |out| {
+            let mut sheet = SheetWriter::new(request.format, out, &request.columns)?;
+            let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+            ExportRepository::new(conn).for_each_row(&request, |fields| sheet.write_row(fields))?;
+            sheet.finish()
+        },
+    ))
+}
@@ -0,0 +1,279 @@
+use actix_multipart::Multipart;
+use actix_web::{
+    get, post, rt::task::spawn_blocking, web, Error as ActixError, HttpRequest, HttpResponse,
+};
+use askama::Template;
+use chrono::Utc;
+use futures::StreamExt;
+use log::{error, info, warn};
+use std::fs;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+use crate::{
+    auth::{csrf::CsrfToken, AuthenticatedUser},
+    config::{get_max_import_size, get_max_upload_size},
+    db::{
+        author_repository::AuthorRepository, journal_repository::JournalRepository,
+        schema::init_db, taxonomy_repository::TaxonomyRepository,
+    },
+    errors::SubmissionError,
+    models::{
+        admin::Permission,
+        audit::{snapshot, AuditAction},
+        journals::Journal,
+        response::ImportResponse,
+    },
+    utils::{
+        audit,
+        import::{parse_manifest, plan_import, read_manifest, save_archive, ImportArchive},
+        pdf,
+    },
+};
+
+const IMPORT_DIR: &str = "./data/uploads/imports";
+
+#[derive(Template)]
+#[template(path = "admin/import.html")]
+struct ImportTemplate {
+    current_page: &'static str,
+    csrf_token: String,
+    max_import_mb: u64,
+    max_pdf_mb: u64,
+}
+
+#[get("/import")]
+pub async fn import_form_handler(
+    user: AuthenticatedUser,
+    csrf: CsrfToken,
+) -> Result<HttpResponse, ActixError> {
+    user.require(Permission::PublishArticles)?;
+    let template = ImportTemplate {
+        current_page: "import",
+        csrf_token: csrf.into_inner(),
+        max_import_mb: get_max_import_size() / (1024 * 1024),
+        max_pdf_mb: get_max_upload_size() as u64 / (1024 * 1024),
+    };
+    let body = template.render().map_err(|e| {
+        error!("Import template render error: {:?}", e);
+        SubmissionError::InternalError("Template error".to_string())
+    })?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(body))
+}
+
+// Validates the manifest against the archive and, unless this is a dry run and every row
+// is valid, publishes all the articles in one transaction. Returns the response and the
+// articles that were saved.
+fn run_import(
+    archive_path: &Path,
+    manifest: Option<(String, Vec<u8>)>,
+    dry_run: bool,
+    admin_id: i32,
+) -> Result<(ImportResponse, Vec<Journal>), SubmissionError> {
+    let mut archive = ImportArchive::open(archive_path)?;
+    let manifests = match manifest {
+        Some(manifest) => vec![manifest],
+        None => archive.manifests()?,
+    };
+    let mut rows = Vec::new();
+    for (name, bytes) in &manifests {
+        rows.extend(parse_manifest(name, bytes)?);
+    }
+
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let repository = JournalRepository::new(conn);
+    let max_pdf_bytes = get_max_upload_size() as u64;
+    let mut plan = plan_import(
+        &mut archive,
+        &rows,
+        &repository,
+        max_pdf_bytes,
+        Utc::now().date_naive(),
+    )?;
+
+    if !plan.is_valid() {
+        let failed = plan
+            .rows
+            .iter()
+            .filter(|row| !row.errors.is_empty())
+            .count();
+        let response = ImportResponse {
+            success: false,
+            dry_run,
+            imported: 0,
+            message: format!(
+                "{} of {} rows have errors; nothing was imported",
+                failed,
+                plan.rows.len()
+            ),
+            rows: plan.rows,
+            unused_files: plan.unused_files,
+        };
+        return Ok((response, Vec::new()));
+    }
+    if dry_run {
+        let response = ImportResponse {
+            success: true,
+            dry_run,
+            imported: 0,
+            message: format!("All {} rows are valid", plan.rows.len()),
+            rows: plan.rows,
+            unused_files: plan.unused_files,
+        };
+        return Ok((response, Vec::new()));
+    }
+
+    let mut saved_files: Vec<PathBuf> = Vec::new();
+    let result = (|| {
+        let mut journals = Vec::with_capacity(plan.articles.len());
+        for (journal, path) in &plan.articles {
+            let file_name = format!("{}.pdf", Uuid::new_v4());
+            let dest = Path::new("./data/uploads").join(&file_name);
+            saved_files.push(dest.clone());
+            archive.extract(path, &dest, max_pdf_bytes)?;
+            journals.push(Journal {
+                pdf_url: file_name,
+                ..journal.clone()
+            });
+        }
+        let ids = repository.import_journals(&journals, admin_id)?;
+        Ok(journals
+            .into_iter()
+            .zip(ids)
+            .map(|(journal, id)| Journal {
+                id: Some(id),
+                ..journal
+            })
+            .collect::<Vec<_>>())
+    })();
+    let journals = match result {
+        Ok(journals) => journals,
+        Err(e) => {
+            for path in &saved_files {
+                let _ = fs::remove_file(path);
+            }
+            return Err(e);
+        }
+    };
+
+    // Every row was valid, so the rows and the saved articles line up
+    for (row, journal) in plan.rows.iter_mut().zip(&journals) {
+        row.journal_id = journal.id;
+    }
+    let response = ImportResponse {
+        success: true,
+        dry_run,
+        imported: journals.len(),
+        message: format!("Imported {} articles", journals.len()),
+        rows: plan.rows,
+        unused_files: plan.unused_files,
+    };
+    Ok((response, journals))
+}
+
+// Bulk import of back issues: a ZIP of PDFs and a CSV, JSON or JATS XML manifest, uploaded
+// separately or inside the ZIP. Nothing is imported unless every row is valid.
+#[post("/import")]
+pub async fn import_articles_handler(
+    user: AuthenticatedUser,
+    req: HttpRequest,
+    mut payload: Multipart,
+) -> Result<HttpResponse, ActixError> {
+    user.require(Permission::PublishArticles)?;
+    fs::create_dir_all(IMPORT_DIR).map_err(|e| {
+        SubmissionError::StorageError(format!("Failed to create import dir: {}", e))
+    })?;
+    let archive_path = PathBuf::from(IMPORT_DIR).join(format!("{}.zip", Uuid::new_v4()));
+
+    let received: Result<_, SubmissionError> = async {
+        let mut has_archive = false;
+        let mut manifest = None;
+        let mut dry_run = false;
+        while let Some(field_result) = payload.next().await {
+            let mut field = field_result.map_err(|e| {
+                SubmissionError::FileProcessingError(format!("Multipart error: {:?}", e))
+            })?;
+            match field.name().unwrap_or("") {
+                "archive" => {
+                    save_archive(field, &archive_path, get_max_import_size()).await?;
+                    has_archive = true;
+                }
+                "manifest" => manifest = read_manifest(field).await?,
+                "dry_run" => {
+                    dry_run = true;
+                    while field.next().await.is_some() {}
+                }
+                _ => while field.next().await.is_some() {},
+            }
+        }
+        if !has_archive {
+            return Err(SubmissionError::ValidationError(
+                "Choose a ZIP archive of PDFs".to_string(),
+            ));
+        }
+        Ok((manifest, dry_run))
+    }
+    .await;
+
+    let result = match received {
+        Ok((manifest, dry_run)) => {
+            let path = archive_path.clone();
+            let admin_id = user.id;
+            web::block(move || run_import(&path, manifest, dry_run, admin_id))
+                .await
+                .map_err(|e| SubmissionError::InternalError(format!("Import task failed: {}", e)))
+                .and_then(|result| result)
+        }
+        Err(e) => Err(e),
+    };
+    let _ = fs::remove_file(&archive_path);
+    let (response, journals) = result?;
+
+    if !response.success {
+        return Ok(HttpResponse::BadRequest().json(response));
+    }
+    if journals.is_empty() {
+        return Ok(HttpResponse::Ok().json(response));
+    }
+
+    for journal in &journals {
+        audit::record(
+            &req,
+            &user,
+            AuditAction::JournalPublished,
+            "journal",
+            journal.id.unwrap_or(0),
+            snapshot(journal),
+        )?;
+    }
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    TaxonomyRepository::new(conn).index_unlinked_journals()?;
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    AuthorRepository::new(conn).index_unlinked_journals()?;
+    info!("Admin {} imported {} articles", user.email, journals.len());
+
+    // Text extraction is slow for a whole back catalogue, so search catches up afterwards
+    spawn_blocking(move || {
+        let repository = match init_db() {
+            Ok(conn) => JournalRepository::new(conn),
+            Err(e) => return error!("Text extraction skipped for imported articles: {}", e),
+        };
+        for journal in journals {
+            let journal_id = journal.id.unwrap_or(0);
+            let pdf_path = PathBuf::from("./data/uploads").join(&journal.pdf_url);
+            match pdf::extract_pdf_metadata(&pdf_path) {
+                Ok(metadata) => {
+                    if let Err(e) = repository.update_full_text(journal_id, &metadata.text) {
+                        warn!("Saving text failed for journal {}: {}", journal_id, e);
+                    }
+                }
+                Err(e) => warn!("Text extraction failed for journal {}: {}", journal_id, e),
+            }
+        }
+    });
+
+    Ok(HttpResponse::Ok().json(response))
+}
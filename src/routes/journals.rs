@@ -1,22 +1,63 @@
-use actix_session::Session;
-use actix_web::{get, web, HttpResponse};
+use actix_files::NamedFile;
+use actix_web::http::header::{ContentDisposition, DispositionParam, DispositionType};
+use actix_web::{get, web, HttpRequest, HttpResponse};
 use askama::Template;
 use chrono::Datelike;
-use serde::Deserialize;
+use log::warn;
+use serde::{Deserialize, Serialize};
 use serde_json::json;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashSet};
 
+use crate::auth::{csrf, AuthenticatedUser};
+use crate::db::attachment_repository::AttachmentRepository;
+use crate::db::author_repository::AuthorRepository;
 use crate::db::journal_repository::JournalRepository;
+use crate::db::notice_repository::NoticeRepository;
 use crate::db::schema::init_db;
+use crate::db::taxonomy_repository::TaxonomyRepository;
+use crate::db::usage_repository::UsageRepository;
+use crate::db::version_repository::VersionRepository;
 use crate::errors::SubmissionError;
+use crate::models::admin::Permission;
+use crate::models::archive::{ArchiveCategory, ArchiveCursor, ArchiveFilter, ArchiveSort};
+use crate::models::attachment::Attachment;
+use crate::models::author::Author;
 use crate::models::journals::Journal;
+use crate::models::notice::{JournalNotice, NoticeLink};
+use crate::models::taxonomy::{Keyword, Subject};
+use crate::models::usage::{UsageMetric, UsageTotals};
+use crate::models::version::VersionEntry;
+use crate::utils::{self, jats, usage};
+
+// Number of keywords shown in the archive page's keyword cloud
+const KEYWORD_CLOUD_SIZE: i64 = 40;
+
+// Largest page the archive API returns
+const MAX_PAGE_SIZE: i32 = 100;
 
 #[derive(Template)]
 #[template(path = "journals/details.html")]
 struct JournalDetailTemplate {
     journal: Journal,
+    attachments: Vec<Attachment>,
+    // Empty until the article is first edited
+    versions: Vec<VersionEntry>,
+    notices: Vec<JournalNotice>,
+    authors: Vec<Author>,
+    keywords: Vec<Keyword>,
+    subjects: Vec<Subject>,
+    usage: UsageTotals,
     id_string: String,
     is_admin: bool,
+    can_issue_notices: bool,
+    // Only signed-in editors get a token, for the delete and notice requests
+    csrf_token: Option<String>,
+}
+
+impl JournalDetailTemplate {
+    fn is_retracted(&self) -> bool {
+        self.notices.iter().any(JournalNotice::is_retraction)
+    }
 }
 
 #[derive(Template, Debug)]
@@ -24,9 +65,26 @@ struct JournalDetailTemplate {
 struct JournalTemplate {
     journals: Vec<Journal>,
     archives: BTreeMap<i32, BTreeMap<i32, Vec<Journal>>>,
+    retracted: HashSet<i32>,
+    keyword_cloud: Vec<Keyword>,
 }
 
 impl JournalTemplate {
+    fn is_retracted(&self, journal: &Journal) -> bool {
+        journal.id.is_some_and(|id| self.retracted.contains(&id))
+    }
+
+    // Scales from 0.8rem for the least used keyword in the cloud to 1.8rem for the most used
+    fn cloud_font_size(&self, keyword: &Keyword) -> String {
+        let max = self
+            .keyword_cloud
+            .iter()
+            .map(|k| k.article_count)
+            .max()
+            .unwrap_or(1);
+        format!("{:.2}rem", 0.8 + keyword.article_count as f64 / max as f64)
+    }
+
     fn get_journal_count(&self, journals: &[Journal]) -> usize {
         journals.len()
     }
@@ -39,6 +97,44 @@ pub struct JournalQueryParams {
     pub category: Option<String>,
     pub volume: Option<i32>,
     pub issue: Option<i32>,
+    pub year: Option<i32>,
+    // Keyword slug
+    pub keyword: Option<String>,
+    // Author ID
+    pub author: Option<i32>,
+    pub sort: Option<String>,
+    pub cursor: Option<String>,
+    // Free-text search
+    pub q: Option<String>,
+}
+
+// An article as returned by the JSON API, with any notices published against it
+#[derive(Serialize)]
+struct JournalListing<'a> {
+    #[serde(flatten)]
+    journal: &'a Journal,
+    retracted: bool,
+    notices: Vec<NoticeLink>,
+}
+
+fn with_notices(journals: &[Journal]) -> Result<Vec<JournalListing<'_>>, SubmissionError> {
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let mut notices = NoticeRepository::new(conn).get_notices_by_journal()?;
+
+    Ok(journals
+        .iter()
+        .map(|journal| {
+            let journal_notices = journal
+                .id
+                .and_then(|id| notices.remove(&id))
+                .unwrap_or_default();
+            JournalListing {
+                journal,
+                retracted: journal_notices.iter().any(JournalNotice::is_retraction),
+                notices: journal_notices.iter().map(NoticeLink::from).collect(),
+            }
+        })
+        .collect())
 }
 
 // New API endpoint for initial data
@@ -48,34 +144,180 @@ pub async fn journal_initial_data() -> Result<HttpResponse, SubmissionError> {
     let repository = JournalRepository::new(conn);
     let all_journals = repository.get_all_journals_for_archive()?;
 
-    Ok(HttpResponse::Ok().json(all_journals))
+    Ok(HttpResponse::Ok().json(with_notices(&all_journals)?))
 }
 
 #[get("/journals/{id}")]
 pub async fn journal_detail_handler(
+    req: HttpRequest,
     id: web::Path<i32>,
-    session: Session,
+    user: Option<AuthenticatedUser>,
 ) -> Result<HttpResponse, SubmissionError> {
     let journal_id = id.into_inner();
 
     let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
     let repository = JournalRepository::new(conn);
     let journal = repository.get_journal_by_id(journal_id)?;
+    usage::record(&req, journal_id, UsageMetric::View);
+
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let usage = UsageRepository::new(conn).get_journal_totals(journal_id)?;
 
-    let is_admin = session
-        .get::<i32>("admin_id")
-        .map_err(|e| SubmissionError::DatabaseError(e.to_string()))?
-        .is_some();
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let attachments = AttachmentRepository::new(conn).get_attachments("journal", journal_id)?;
 
-    Ok(HttpResponse::Ok().body(
-        JournalDetailTemplate {
-            journal,
-            id_string: journal_id.to_string(),
-            is_admin,
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let versions =
+        VersionEntry::from_versions(VersionRepository::new(conn).get_versions(journal_id)?);
+
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let notices = NoticeRepository::new(conn).get_notices_for_journal(journal_id)?;
+
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let authors = AuthorRepository::new(conn).get_journal_authors(journal_id)?;
+
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let taxonomy = TaxonomyRepository::new(conn);
+    let keywords = taxonomy.get_journal_keywords(journal_id)?;
+    let subjects = taxonomy.get_journal_subjects(journal_id)?;
+
+    let is_admin = user
+        .as_ref()
+        .is_some_and(|user| user.can(Permission::EditArticles));
+    let can_issue_notices = user
+        .as_ref()
+        .is_some_and(|user| user.can(Permission::PublishArticles));
+    let csrf_token = user.map(|_| csrf::request_token(&req)).transpose()?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(
+            JournalDetailTemplate {
+                journal,
+                attachments,
+                versions,
+                notices,
+                authors,
+                keywords,
+                subjects,
+                usage,
+                id_string: journal_id.to_string(),
+                is_admin,
+                can_issue_notices,
+                csrf_token,
+            }
+            .render()
+            .map_err(|e| SubmissionError::InternalError(format!("Template error: {}", e)))?,
+        ))
+}
+
+// Sends a PDF of the article inline, counting the download in the usage statistics
+async fn serve_pdf(
+    req: &HttpRequest,
+    journal_id: i32,
+    journal: &Journal,
+) -> Result<HttpResponse, SubmissionError> {
+    // The file name comes from the database; anything but a file in the uploads
+    // directory is treated as missing rather than opened
+    let path = utils::upload_path(&journal.pdf_url).map_err(|e| {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            warn!(
+                "Refusing to serve {:?} as the PDF of journal {}: {}",
+                journal.pdf_url, journal_id, e
+            );
         }
-        .render()
-        .map_err(|e| SubmissionError::InternalError(format!("Template error: {}", e)))?,
-    ))
+        SubmissionError::NotFound(format!("PDF of journal {} not found", journal_id))
+    })?;
+    let named_file = NamedFile::open_async(path).await.map_err(|e| {
+        if e.kind() == std::io::ErrorKind::NotFound {
+            SubmissionError::NotFound(format!("PDF of journal {} not found", journal_id))
+        } else {
+            SubmissionError::StorageError(format!("Error opening PDF: {:?}", e))
+        }
+    })?;
+    usage::record(req, journal_id, UsageMetric::Download);
+
+    Ok(named_file
+        .set_content_disposition(ContentDisposition {
+            disposition: DispositionType::Inline,
+            parameters: vec![DispositionParam::Filename(journal.pdf_url.clone())],
+        })
+        .into_response(req))
+}
+
+// Serves an article's PDF
+#[get("/journals/{id}/pdf")]
+pub async fn journal_pdf_handler(
+    req: HttpRequest,
+    id: web::Path<i32>,
+) -> Result<HttpResponse, SubmissionError> {
+    let journal_id = id.into_inner();
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let repository = JournalRepository::new(conn);
+    let journal = repository.get_journal_by_id(journal_id)?;
+
+    serve_pdf(&req, journal_id, &journal).await
+}
+
+// Serves the PDF an earlier version of an article had
+#[get("/journals/{id}/versions/{version}/pdf")]
+pub async fn journal_version_pdf_handler(
+    req: HttpRequest,
+    path: web::Path<(i32, i32)>,
+) -> Result<HttpResponse, SubmissionError> {
+    let (journal_id, version_number) = path.into_inner();
+
+    // Earlier versions of a trashed article are hidden along with it
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    JournalRepository::new(conn).get_journal_by_id(journal_id)?;
+
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let version = VersionRepository::new(conn).get_version(journal_id, version_number)?;
+
+    serve_pdf(&req, journal_id, &version.journal).await
+}
+
+// The article's metadata and text as JATS XML, the format indexes such as PubMed Central,
+// DOAJ and Crossref exchange articles in
+#[get("/journals/{id}/jats")]
+pub async fn journal_jats_handler(id: web::Path<i32>) -> Result<HttpResponse, SubmissionError> {
+    let journal_id = id.into_inner();
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let repository = JournalRepository::new(conn);
+    let journal = repository.get_journal_by_id(journal_id)?;
+    let full_text = repository.get_full_text(journal_id)?;
+
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let authors = AuthorRepository::new(conn).get_journal_authors(journal_id)?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/xml; charset=utf-8")
+        .insert_header(ContentDisposition {
+            disposition: DispositionType::Inline,
+            parameters: vec![DispositionParam::Filename(format!(
+                "article-{}.xml",
+                journal_id
+            ))],
+        })
+        .body(jats::article_xml(&journal, &authors, full_text.as_deref())))
+}
+
+#[get("/journals/{id}/attachments/{attachment_id}")]
+pub async fn journal_attachment_handler(
+    req: HttpRequest,
+    path: web::Path<(i32, i32)>,
+) -> Result<HttpResponse, SubmissionError> {
+    let (journal_id, attachment_id) = path.into_inner();
+
+    // Attachments of a trashed article are hidden along with it
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    JournalRepository::new(conn).get_journal_by_id(journal_id)?;
+
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let attachment =
+        AttachmentRepository::new(conn).get_attachment("journal", journal_id, attachment_id)?;
+
+    utils::serve_attachment(&attachment, &req).await
 }
 
 #[get("/journal")]
@@ -89,17 +331,23 @@ pub async fn journal_handler() -> Result<HttpResponse, SubmissionError> {
     for journal in all_journals.iter() {
         archives
             .entry(journal.volume_number)
-            .or_insert_with(BTreeMap::new)
+            .or_default()
             .entry(journal.issue_number)
-            .or_insert_with(Vec::new)
+            .or_default()
             .push(journal.clone());
     }
 
-    let initial_journals: Vec<Journal> = all_journals.iter().take(12).map(|j| j.clone()).collect();
+    let initial_journals: Vec<Journal> = all_journals.iter().take(12).cloned().collect();
 
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
     let template = JournalTemplate {
         journals: initial_journals,
         archives,
+        retracted: NoticeRepository::new(conn).get_retracted_ids()?,
+        keyword_cloud: TaxonomyRepository::new(
+            init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?,
+        )
+        .get_keyword_cloud(KEYWORD_CLOUD_SIZE)?,
     };
 
     Ok(HttpResponse::Ok()
@@ -111,35 +359,61 @@ pub async fn journal_handler() -> Result<HttpResponse, SubmissionError> {
         ))
 }
 
+// Archive listing with filters, facets and cursor pagination. `page` is still honoured
+// when no cursor is given, for clients written before cursors existed.
 #[get("/api/journals")]
 pub async fn journal_api_handler(
     query: web::Query<JournalQueryParams>,
 ) -> Result<HttpResponse, SubmissionError> {
-    let page = query.page.unwrap_or(1);
-    let limit = query.limit.unwrap_or(12);
-    let offset = (page - 1) * limit;
+    let limit = query.limit.unwrap_or(12).clamp(1, MAX_PAGE_SIZE);
+    // Widened first so a huge page number can't overflow
+    let offset = (i64::from(query.page.unwrap_or(1).max(1)) - 1).saturating_mul(i64::from(limit));
     let category = query.category.as_deref().unwrap_or("all");
 
-    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
-    let repository = JournalRepository::new(conn);
-
-    let mut journals = match category {
-        "latest" => repository.get_latest_journals(limit)?,
-        "current" => repository.get_current_edition(limit)?,
-        "past" => repository.get_past_issues(limit, offset)?,
-        _ => repository.get_all_journals(limit, offset)?,
+    // "latest" is the whole archive in the default, newest-first order
+    let sort = match query.sort.as_deref() {
+        None | Some("") => ArchiveSort::Newest,
+        Some(value) => ArchiveSort::parse(value).ok_or_else(|| {
+            SubmissionError::ValidationError(format!("Unknown sort order '{}'", value))
+        })?,
+    };
+    let cursor = match query.cursor.as_deref() {
+        None | Some("") => None,
+        Some(value) => Some(ArchiveCursor::decode(value).ok_or_else(|| {
+            SubmissionError::ValidationError("Invalid pagination cursor".to_string())
+        })?),
+    };
+    let filter = ArchiveFilter {
+        category: match category {
+            "current" => ArchiveCategory::Current,
+            "past" => ArchiveCategory::Past,
+            _ => ArchiveCategory::All,
+        },
+        volume: query.volume,
+        // An issue number only means something within a volume
+        issue: query.issue.filter(|_| query.volume.is_some()),
+        year: query.year,
+        keyword: query.keyword.clone().filter(|k| !k.is_empty()),
+        author: query.author,
+        text: query
+            .q
+            .as_deref()
+            .map(str::trim)
+            .filter(|q| !q.is_empty())
+            .map(str::to_string),
     };
 
-    if let Some(volume) = query.volume {
-        journals.retain(|j| j.volume_number == volume);
-
-        if let Some(issue) = query.issue {
-            journals.retain(|j| j.issue_number == issue);
-        }
-    }
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let repository = JournalRepository::new(conn);
+    let page = repository.search_archive(&filter, sort, cursor.as_ref(), offset, limit as i64)?;
+    let facets = repository.get_archive_facets(&filter)?;
 
     Ok(HttpResponse::Ok().json(json!({
-        "journals": journals,
-        "hasMore": journals.len() == limit as usize
+        "journals": with_notices(&page.journals)?,
+        "hasMore": page.next_cursor.is_some(),
+        "nextCursor": page.next_cursor,
+        "total": page.total,
+        "sort": sort,
+        "facets": facets,
     })))
 }
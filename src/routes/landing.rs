@@ -1,7 +1,9 @@
 use actix_web::{get, HttpResponse};
 use askama::Template;
+use std::collections::HashSet;
 
 use crate::db::journal_repository::JournalRepository;
+use crate::db::notice_repository::NoticeRepository;
 use crate::db::schema::init_db;
 use crate::errors::SubmissionError;
 use crate::models::journals::Journal;
@@ -10,6 +12,13 @@ use crate::models::journals::Journal;
 #[template(path = "landing.html")]
 struct LandingTemplate {
     journals: Vec<Journal>,
+    retracted: HashSet<i32>,
+}
+
+impl LandingTemplate {
+    fn is_retracted(&self, journal: &Journal) -> bool {
+        journal.id.is_some_and(|id| self.retracted.contains(&id))
+    }
 }
 
 #[get("/")]
@@ -18,9 +27,17 @@ pub async fn landing_handler() -> Result<HttpResponse, SubmissionError> {
     let repository = JournalRepository::new(conn);
     let journals = repository.get_latest_journals(3)?; // Get latest 3 journals
 
-    Ok(HttpResponse::Ok().body(
-        LandingTemplate { journals }
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let retracted = NoticeRepository::new(conn).get_retracted_ids()?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(
+            LandingTemplate {
+                journals,
+                retracted,
+            }
             .render()
             .map_err(|e| SubmissionError::InternalError(format!("Template error: {}", e)))?,
-    ))
+        ))
 }
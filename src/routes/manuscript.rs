@@ -7,5 +7,7 @@ struct ManuscriptTemplate {}
 
 #[get("/manuscript")]
 pub async fn manuscript_guide() -> impl Responder {
-    HttpResponse::Ok().body(ManuscriptTemplate {}.render().unwrap())
+    HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(ManuscriptTemplate {}.render().unwrap())
 }
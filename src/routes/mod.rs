@@ -1,8 +1,21 @@
 pub mod about;
+pub mod account;
 pub mod admin;
+pub mod audit;
 pub mod auth;
+pub mod authors;
 pub mod editorial;
+pub mod exports;
+pub mod imports;
 pub mod journals;
 pub mod landing;
 pub mod manuscript;
+pub mod notices;
+pub mod review;
 pub mod submissions;
+pub mod taxonomy;
+pub mod trash;
+pub mod uploads;
+pub mod usage;
+pub mod users;
+pub mod versions;
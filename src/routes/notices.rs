@@ -0,0 +1,207 @@
+use actix_web::{get, post, web, Error as ActixError, HttpRequest, HttpResponse};
+use askama::Template;
+use log::{error, info};
+use serde::Deserialize;
+
+use crate::{
+    auth::{csrf::CsrfToken, AuthenticatedUser},
+    db::{
+        journal_repository::JournalRepository,
+        notice_repository::{NewNotice, NoticeRepository},
+        schema::init_db,
+    },
+    errors::SubmissionError,
+    models::{
+        admin::Permission,
+        audit::{snapshot, AuditAction},
+        journals::Journal,
+        notice::{JournalNotice, NoticeKind},
+    },
+    utils::audit,
+};
+
+#[derive(Template)]
+#[template(path = "journals/notice.html")]
+struct NoticeTemplate {
+    notice: JournalNotice,
+    journal: Journal,
+}
+
+#[derive(Template)]
+#[template(path = "admin/journal_notices.html")]
+struct JournalNoticesTemplate {
+    current_page: &'static str,
+    csrf_token: String,
+    journal: Journal,
+    notices: Vec<JournalNotice>,
+    kinds: &'static [NoticeKind],
+    error: Option<String>,
+    notice: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct NoticeForm {
+    pub kind: String,
+    pub title: String,
+    pub body: String,
+}
+
+// Public page of a notice, linking back to the article it concerns
+#[get("/notices/{id}")]
+pub async fn notice_handler(id: web::Path<i32>) -> Result<HttpResponse, SubmissionError> {
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let notice = NoticeRepository::new(conn).get_notice(id.into_inner())?;
+
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let journal = JournalRepository::new(conn).get_journal_by_id(notice.journal_id)?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(
+            NoticeTemplate { notice, journal }
+                .render()
+                .map_err(|e| SubmissionError::InternalError(format!("Template error: {}", e)))?,
+        ))
+}
+
+fn render_notices_page(
+    csrf: CsrfToken,
+    journal_id: i32,
+    error: Option<String>,
+    notice: Option<String>,
+) -> Result<HttpResponse, SubmissionError> {
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let journal = JournalRepository::new(conn).get_journal_by_id(journal_id)?;
+
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let template = JournalNoticesTemplate {
+        current_page: "journals",
+        csrf_token: csrf.into_inner(),
+        notices: NoticeRepository::new(conn).get_notices_for_journal(journal_id)?,
+        journal,
+        kinds: &NoticeKind::ALL,
+        error,
+        notice,
+    };
+    let body = template.render().map_err(|e| {
+        error!("Journal notices template render error: {:?}", e);
+        SubmissionError::InternalError("Template error".to_string())
+    })?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(body))
+}
+
+fn notices_page_or_error(
+    csrf: CsrfToken,
+    journal_id: i32,
+    result: Result<String, SubmissionError>,
+) -> Result<HttpResponse, SubmissionError> {
+    match result {
+        Ok(notice) => render_notices_page(csrf, journal_id, None, Some(notice)),
+        Err(SubmissionError::ValidationError(msg)) => {
+            render_notices_page(csrf, journal_id, Some(msg), None)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+#[get("/journals/{id}/notices")]
+pub async fn journal_notices_handler(
+    user: AuthenticatedUser,
+    csrf: CsrfToken,
+    id: web::Path<i32>,
+) -> Result<HttpResponse, ActixError> {
+    user.require(Permission::PublishArticles)?;
+    render_notices_page(csrf, id.into_inner(), None, None).map_err(ActixError::from)
+}
+
+#[post("/journals/{id}/notices")]
+pub async fn issue_notice_handler(
+    user: AuthenticatedUser,
+    csrf: CsrfToken,
+    req: HttpRequest,
+    id: web::Path<i32>,
+    form: web::Form<NoticeForm>,
+) -> Result<HttpResponse, ActixError> {
+    user.require(Permission::PublishArticles)?;
+    let journal_id = id.into_inner();
+    let result = (|| {
+        let kind = NoticeKind::parse(&form.kind).ok_or_else(|| {
+            SubmissionError::ValidationError("Choose the type of notice".to_string())
+        })?;
+        let (title, body) = (form.title.trim(), form.body.trim());
+        if title.is_empty() || body.is_empty() {
+            return Err(SubmissionError::ValidationError(
+                "A notice needs a title and text".to_string(),
+            ));
+        }
+
+        let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        JournalRepository::new(conn).get_journal_by_id(journal_id)?;
+
+        let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        let repository = NoticeRepository::new(conn);
+        let notice_id = repository.create_notice(&NewNotice {
+            journal_id,
+            kind,
+            title,
+            body,
+            created_by: user.id,
+        })?;
+        let notice = repository.get_notice(notice_id as i32)?;
+        audit::record(
+            &req,
+            &user,
+            AuditAction::NoticeIssued,
+            "journal",
+            journal_id,
+            snapshot(&notice),
+        )?;
+        info!(
+            "Admin {} issued a {} for journal {}",
+            user.email,
+            kind.as_str(),
+            journal_id
+        );
+        Ok(format!(
+            "The {} has been published.",
+            kind.label().to_lowercase()
+        ))
+    })();
+
+    notices_page_or_error(csrf, journal_id, result).map_err(ActixError::from)
+}
+
+// For notices issued in error; a retraction that stands should never be withdrawn
+#[post("/journals/{id}/notices/{notice_id}/delete")]
+pub async fn withdraw_notice_handler(
+    user: AuthenticatedUser,
+    csrf: CsrfToken,
+    req: HttpRequest,
+    path: web::Path<(i32, i32)>,
+) -> Result<HttpResponse, ActixError> {
+    user.require(Permission::PublishArticles)?;
+    let (journal_id, notice_id) = path.into_inner();
+    let result = (|| {
+        let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        let repository = NoticeRepository::new(conn);
+        let notice = repository.get_notice(notice_id)?;
+        repository.delete_notice(journal_id, notice_id)?;
+        audit::record(
+            &req,
+            &user,
+            AuditAction::NoticeWithdrawn,
+            "journal",
+            journal_id,
+            snapshot(&notice),
+        )?;
+        Ok(format!(
+            "The {} has been withdrawn.",
+            notice.kind.label().to_lowercase()
+        ))
+    })();
+
+    notices_page_or_error(csrf, journal_id, result).map_err(ActixError::from)
+}
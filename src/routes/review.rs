@@ -0,0 +1,183 @@
+use actix_web::{post, web, Error as ActixError, HttpRequest, HttpResponse};
+use log::info;
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::{
+    auth::{csrf::CsrfToken, AuthenticatedUser},
+    db::{
+        admin_repository::AdminRepository, review_repository::ReviewRepository, schema::init_db,
+        submission_repository::SubmissionRepository,
+    },
+    errors::SubmissionError,
+    models::{admin::Permission, audit::AuditAction, review::Decision},
+    routes::admin::render_submission_detail,
+    utils::audit,
+};
+
+#[derive(Deserialize)]
+pub struct DecisionForm {
+    pub decision: String,
+    #[serde(default)]
+    pub note: String,
+}
+
+#[derive(Deserialize)]
+pub struct AssignReviewerForm {
+    pub reviewer_id: i32,
+}
+
+#[derive(Deserialize)]
+pub struct CompleteReviewForm {
+    pub recommendation: String,
+}
+
+fn parse_decision(value: &str) -> Result<Decision, SubmissionError> {
+    Decision::parse(value)
+        .ok_or_else(|| SubmissionError::ValidationError(format!("Unknown decision: {}", value)))
+}
+
+// Back to the submission on success, or the submission page with the reason it failed
+fn submission_page_or_error(
+    csrf: CsrfToken,
+    user: &AuthenticatedUser,
+    submission_id: i32,
+    result: Result<(), SubmissionError>,
+) -> Result<HttpResponse, SubmissionError> {
+    match result {
+        Ok(()) => Ok(HttpResponse::Found()
+            .append_header(("Location", format!("/admin/submissions/{}", submission_id)))
+            .finish()),
+        Err(SubmissionError::ValidationError(msg)) | Err(SubmissionError::Conflict(msg)) => {
+            render_submission_detail(csrf, user, submission_id, Some(msg))
+        }
+        Err(e) => Err(e),
+    }
+}
+
+#[post("/submissions/{id}/decision")]
+pub async fn record_decision_handler(
+    user: AuthenticatedUser,
+    csrf: CsrfToken,
+    req: HttpRequest,
+    id: web::Path<i32>,
+    form: web::Form<DecisionForm>,
+) -> Result<HttpResponse, ActixError> {
+    user.require(Permission::PublishArticles)?;
+    let submission_id = id.into_inner();
+    let result = (|| {
+        let decision = parse_decision(&form.decision)?;
+        let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        SubmissionRepository::new(conn).get_submission_by_id(submission_id)?;
+
+        let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        let note = form.note.trim();
+        ReviewRepository::new(conn).record_decision(submission_id, decision, note, user.id)?;
+        audit::record(
+            &req,
+            &user,
+            AuditAction::DecisionRecorded,
+            "submission",
+            submission_id,
+            Some(json!({ "decision": decision.as_str(), "note": note }).to_string()),
+        )?;
+        info!(
+            "Admin {} recorded '{}' on submission {}",
+            user.email,
+            decision.as_str(),
+            submission_id
+        );
+        Ok(())
+    })();
+
+    submission_page_or_error(csrf, &user, submission_id, result).map_err(ActixError::from)
+}
+
+// Invites someone who can read submissions to review this one
+#[post("/submissions/{id}/reviewers")]
+pub async fn assign_reviewer_handler(
+    user: AuthenticatedUser,
+    csrf: CsrfToken,
+    req: HttpRequest,
+    id: web::Path<i32>,
+    form: web::Form<AssignReviewerForm>,
+) -> Result<HttpResponse, ActixError> {
+    user.require(Permission::PublishArticles)?;
+    let submission_id = id.into_inner();
+    let result = (|| {
+        let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        SubmissionRepository::new(conn).get_submission_by_id(submission_id)?;
+
+        let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        let reviewer = AdminRepository::new(conn)
+            .find_admin_by_id(form.reviewer_id)?
+            .filter(|admin| admin.can(Permission::ReviewSubmissions))
+            .ok_or_else(|| {
+                SubmissionError::ValidationError(
+                    "Reviewers must be active users who can read submissions".to_string(),
+                )
+            })?;
+
+        let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        let review_id = ReviewRepository::new(conn).assign_reviewer(submission_id, reviewer.id)?;
+        audit::record(
+            &req,
+            &user,
+            AuditAction::ReviewerAssigned,
+            "submission",
+            submission_id,
+            Some(json!({ "review_id": review_id, "reviewer": reviewer.email }).to_string()),
+        )?;
+        Ok(())
+    })();
+
+    submission_page_or_error(csrf, &user, submission_id, result).map_err(ActixError::from)
+}
+
+// Reviewers complete their own reviews; editors may record one on a reviewer's behalf
+#[post("/submissions/{id}/reviews/{review_id}/complete")]
+pub async fn complete_review_handler(
+    user: AuthenticatedUser,
+    csrf: CsrfToken,
+    req: HttpRequest,
+    path: web::Path<(i32, i32)>,
+    form: web::Form<CompleteReviewForm>,
+) -> Result<HttpResponse, ActixError> {
+    user.require(Permission::ReviewSubmissions)?;
+    let (submission_id, review_id) = path.into_inner();
+
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let repository = ReviewRepository::new(conn);
+    let review = repository.get_review(review_id)?;
+    if review.submission_id != submission_id {
+        return Err(
+            SubmissionError::NotFound(format!("Review with ID {} not found", review_id)).into(),
+        );
+    }
+    if review.reviewer_id != user.id {
+        user.require(Permission::PublishArticles)?;
+    }
+
+    let result = (|| {
+        let recommendation = parse_decision(&form.recommendation)?;
+        repository.complete_review(review_id, recommendation)?;
+        audit::record(
+            &req,
+            &user,
+            AuditAction::ReviewCompleted,
+            "submission",
+            submission_id,
+            Some(
+                json!({
+                    "review_id": review_id,
+                    "reviewer": review.reviewer_email,
+                    "recommendation": recommendation.as_str(),
+                })
+                .to_string(),
+            ),
+        )?;
+        Ok(())
+    })();
+
+    submission_page_or_error(csrf, &user, submission_id, result).map_err(ActixError::from)
+}
@@ -1,23 +1,71 @@
 use actix_multipart::Multipart;
-use actix_web::{get, post, HttpResponse, Responder};
+use actix_web::{get, post, put, web, HttpResponse, Responder};
 use askama::Template;
 use futures::{StreamExt, TryStreamExt};
+use log::warn;
+use serde_json::json;
 use std::io::Write;
+use std::path::PathBuf;
 use uuid::Uuid;
 
+use crate::auth::csrf::CsrfToken;
+use crate::config::get_similarity_threshold;
+use crate::db::attachment_repository::AttachmentRepository;
+use crate::db::draft_repository::DraftRepository;
 use crate::db::schema::init_db;
+use crate::db::similarity_repository::SimilarityRepository;
 use crate::db::submission_repository::SubmissionRepository;
+use crate::db::upload_repository::UploadRepository;
 use crate::errors::SubmissionError;
-use crate::models::response::SubmissionResponse;
-use crate::models::submission::Submission;
+use crate::models::attachment::{Attachment, AttachmentKind};
+use crate::models::draft::DraftForm;
+use crate::models::response::{DraftResponse, SubmissionResponse};
+use crate::models::submission::{Submission, SubmissionDeclarations};
+use crate::utils::{self, pdf};
 
 #[derive(Template)]
 #[template(path = "submissions/submit.html")]
-struct SubmissionsTemplate {}
+struct SubmissionsTemplate {
+    csrf_token: String,
+}
 
 #[get("/submit")]
-pub async fn submit_paper_handler() -> impl Responder {
-    HttpResponse::Ok().body(SubmissionsTemplate {}.render().unwrap())
+pub async fn submit_paper_handler(csrf: CsrfToken) -> impl Responder {
+    HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(
+            SubmissionsTemplate {
+                csrf_token: csrf.into_inner(),
+            }
+            .render()
+            .unwrap(),
+        )
+}
+
+// Extracts the manuscript's text and compares the submission with everything else.
+// Both are slow, so they run on the blocking pool.
+async fn check_similarity(submission_id: i32, pdf_path: &str) -> Result<(), SubmissionError> {
+    let pdf_path = PathBuf::from(pdf_path);
+    web::block(move || {
+        match pdf::extract_pdf_metadata(&pdf_path) {
+            Ok(metadata) => {
+                let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+                SubmissionRepository::new(conn).update_full_text(submission_id, &metadata.text)?
+            }
+            // Without text we can still compare abstracts
+            Err(e) => warn!(
+                "Text extraction failed for submission {}: {}",
+                submission_id, e
+            ),
+        }
+
+        let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        SimilarityRepository::new(conn)
+            .generate_report(submission_id, get_similarity_threshold())
+            .map(|_| ())
+    })
+    .await
+    .map_err(|e| SubmissionError::InternalError(format!("Similarity task failed: {}", e)))?
 }
 
 #[post("/submit")]
@@ -25,10 +73,16 @@ pub async fn process_submission(mut payload: Multipart) -> Result<HttpResponse,
     let mut full_name = None;
     let mut email = None;
     let mut phone = None;
+    let mut country = String::new();
     let mut title = None;
     let mut abstract_text = None;
     let mut pdf_filename = None;
-    let mut created_at = None;
+    let mut upload_id: Option<String> = None;
+    let mut draft_id: Option<String> = None;
+    let mut attachments: Vec<Attachment> = Vec::new();
+    let mut staged = utils::StagedFiles::default();
+    let mut declarations = SubmissionDeclarations::default();
+    let created_at = None;
 
     // Process the multipart form
     while let Ok(Some(mut field)) = payload.try_next().await {
@@ -88,12 +142,13 @@ pub async fn process_submission(mut payload: Multipart) -> Result<HttpResponse,
             }
             "pdf" => {
                 let uuid = Uuid::new_v4();
-                let file_name = format!("{}.pdf", uuid.to_string());
+                let file_name = format!("{}.pdf", uuid);
                 let file_path = format!("./data/uploads/{}", file_name);
 
                 // Create the file
                 let mut f = std::fs::File::create(&file_path)
                     .map_err(|e| SubmissionError::FileProcessingError(e.to_string()))?;
+                staged.add(file_path);
 
                 // Write file content
                 while let Some(chunk) = field.next().await {
@@ -105,9 +160,48 @@ pub async fn process_submission(mut payload: Multipart) -> Result<HttpResponse,
 
                 pdf_filename = Some(file_name);
             }
+            "country" => country = utils::read_field(field).await?,
+            // Manuscript already sent through the resumable upload endpoints
+            "upload_id" => upload_id = Some(utils::read_field(field).await?),
+            "draft_id" => draft_id = Some(utils::read_field(field).await?),
+            // Declarations checklist; checkboxes are only sent when ticked
+            "confirms_originality" => {
+                declarations.confirms_originality = utils::read_field(field).await? == "true"
+            }
+            "agrees_to_license" => {
+                declarations.agrees_to_license = utils::read_field(field).await? == "true"
+            }
+            "conflict_of_interest" => {
+                declarations.conflict_of_interest = utils::read_field(field).await?
+            }
+            "ethics_approval" => declarations.ethics_approval = utils::read_field(field).await?,
+            "funding_statement" => {
+                declarations.funding_statement = utils::read_field(field).await?
+            }
+            "cover_letter" => declarations.cover_letter = utils::read_field(field).await?,
+            "suggested_reviewers" => {
+                declarations.suggested_reviewers = utils::read_field(field).await?
+            }
+            "opposed_reviewers" => {
+                declarations.opposed_reviewers = utils::read_field(field).await?
+            }
+            // Cover letter, supplementary material, datasets and figures
+            name if name.starts_with("attachment_") => {
+                let kind =
+                    AttachmentKind::parse(&name["attachment_".len()..]).ok_or_else(|| {
+                        SubmissionError::ValidationError(format!(
+                            "Unknown attachment type: {}",
+                            name
+                        ))
+                    })?;
+                if let Some(attachment) = utils::save_attachment(field, kind).await? {
+                    staged.add(attachment.file_path());
+                    attachments.push(attachment);
+                }
+            }
             _ => {
                 // Skip other fields
-                while let Some(_) = field.next().await {}
+                while field.next().await.is_some() {}
             }
         }
     }
@@ -128,9 +222,24 @@ pub async fn process_submission(mut payload: Multipart) -> Result<HttpResponse,
     let abstract_text = abstract_text.ok_or(SubmissionError::ValidationError(
         "Abstract is required".to_string(),
     ))?;
-    let pdf_url = pdf_filename.ok_or(SubmissionError::ValidationError(
-        "PDF file is required".to_string(),
-    ))?;
+    // A resumable upload is only checked here and moved into place once the submission
+    // is valid, so a rejected submission can be corrected and sent again
+    let mut pending_upload = None;
+    let pdf_url = match (pdf_filename, upload_id.filter(|id| !id.is_empty())) {
+        (Some(file_name), _) => file_name,
+        (None, Some(upload_id)) => {
+            let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+            UploadRepository::new(conn).check_upload(&upload_id)?;
+            let file_name = format!("{}.pdf", Uuid::new_v4());
+            pending_upload = Some((upload_id, file_name.clone()));
+            file_name
+        }
+        (None, None) => {
+            return Err(SubmissionError::ValidationError(
+                "PDF file is required".to_string(),
+            ))
+        }
+    };
 
     // Create submission object
     let submission = Submission::new(
@@ -141,19 +250,103 @@ pub async fn process_submission(mut payload: Multipart) -> Result<HttpResponse,
         abstract_text,
         format!("./data/uploads/{}", pdf_url),
         created_at,
-    );
+    )
+    .with_country(&country)
+    .with_declarations(declarations);
 
     // Validate submission
     submission.validate_submission()?;
 
+    if let Some((upload_id, file_name)) = pending_upload {
+        let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        UploadRepository::new(conn).finalize_upload(&upload_id, &file_name)?;
+        staged.add(format!("./data/uploads/{}", file_name));
+    }
+
     // Save to database
     let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
     let repository = SubmissionRepository::new(conn);
     let submission_id = repository.save_submission(&submission)?;
 
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    AttachmentRepository::new(conn).save_attachments(
+        "submission",
+        submission_id as i32,
+        attachments,
+    )?;
+    staged.keep();
+
+    if let Some(draft_id) = draft_id.filter(|id| !id.is_empty()) {
+        let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        DraftRepository::new(conn).delete_draft(&draft_id)?;
+    }
+
+    // Compare against earlier submissions and published articles; failures here
+    // shouldn't reject the author's submission
+    if let Err(e) = check_similarity(submission_id as i32, &submission.pdf_url).await {
+        warn!(
+            "Similarity check failed for submission {}: {}",
+            submission_id, e
+        );
+    }
+
     Ok(HttpResponse::Ok().json(SubmissionResponse {
         success: true,
         submission_id: submission_id as i32,
         message: "Submission uploaded successfully".to_string(),
     }))
 }
+
+#[post("/submit/drafts")]
+pub async fn create_draft_handler(
+    form: web::Json<DraftForm>,
+) -> Result<HttpResponse, SubmissionError> {
+    let draft_id = Uuid::new_v4().to_string();
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    DraftRepository::new(conn).create_draft(&draft_id, &form)?;
+
+    Ok(HttpResponse::Ok().json(DraftResponse {
+        success: true,
+        draft_id,
+        message: "Draft saved".to_string(),
+    }))
+}
+
+#[get("/submit/drafts/{id}")]
+pub async fn get_draft_handler(id: web::Path<String>) -> Result<HttpResponse, SubmissionError> {
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let draft = DraftRepository::new(conn).get_draft(&id)?;
+
+    // Include upload progress so the client knows where to resume the file
+    let upload = match &draft.upload_id {
+        Some(upload_id) => {
+            let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+            match UploadRepository::new(conn).get_upload(upload_id) {
+                Ok(upload) => Some(upload),
+                Err(SubmissionError::NotFound(_)) => None,
+                Err(e) => return Err(e),
+            }
+        }
+        None => None,
+    };
+
+    Ok(HttpResponse::Ok().json(json!({
+        "draft": draft,
+        "upload": upload,
+    })))
+}
+
+#[put("/submit/drafts/{id}")]
+pub async fn update_draft_handler(
+    id: web::Path<String>,
+    form: web::Json<DraftForm>,
+) -> Result<HttpResponse, SubmissionError> {
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    DraftRepository::new(conn).update_draft(&id, &form)?;
+
+    Ok(HttpResponse::Ok().json(DraftResponse {
+        success: true,
+        draft_id: id.into_inner(),
+        message: "Draft saved".to_string(),
+    }))
+}
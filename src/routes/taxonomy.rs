@@ -0,0 +1,326 @@
+use actix_web::{get, post, web, Error as ActixError, HttpRequest, HttpResponse};
+use askama::Template;
+use log::{error, info};
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::HashSet;
+
+use crate::{
+    auth::{csrf::CsrfToken, AuthenticatedUser},
+    db::{
+        journal_repository::JournalRepository, notice_repository::NoticeRepository,
+        schema::init_db, taxonomy_repository::TaxonomyRepository,
+    },
+    errors::SubmissionError,
+    models::{
+        admin::Permission,
+        audit::{field_changes, snapshot, AuditAction},
+        journals::Journal,
+        taxonomy::{normalize_name, Keyword, Subject},
+    },
+    utils::audit,
+};
+
+#[derive(Template)]
+#[template(path = "journals/browse.html")]
+struct BrowseTemplate {
+    heading: String,
+    description: String,
+    journals: Vec<Journal>,
+    retracted: HashSet<i32>,
+}
+
+impl BrowseTemplate {
+    fn is_retracted(&self, journal: &Journal) -> bool {
+        journal.id.is_some_and(|id| self.retracted.contains(&id))
+    }
+}
+
+#[derive(Template)]
+#[template(path = "journals/subjects.html")]
+struct SubjectsTemplate {
+    subjects: Vec<Subject>,
+}
+
+#[derive(Template)]
+#[template(path = "admin/taxonomy.html")]
+struct TaxonomyTemplate {
+    current_page: &'static str,
+    csrf_token: String,
+    keywords: Vec<Keyword>,
+    subjects: Vec<Subject>,
+    error: Option<String>,
+    notice: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct MergeKeywordsForm {
+    pub source_id: i32,
+    pub target_id: i32,
+}
+
+#[derive(Deserialize)]
+pub struct SubjectForm {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+}
+
+fn render_browse_page(
+    heading: String,
+    description: String,
+    journals: Vec<Journal>,
+) -> Result<HttpResponse, SubmissionError> {
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let template = BrowseTemplate {
+        heading,
+        description,
+        journals,
+        retracted: NoticeRepository::new(conn).get_retracted_ids()?,
+    };
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(
+            template
+                .render()
+                .map_err(|e| SubmissionError::InternalError(format!("Template error: {}", e)))?,
+        ))
+}
+
+// Articles tagged with a keyword; the slug of a merged synonym redirects to its replacement
+#[get("/keywords/{slug}")]
+pub async fn keyword_handler(slug: web::Path<String>) -> Result<HttpResponse, SubmissionError> {
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let taxonomy = TaxonomyRepository::new(conn);
+    let Some(keyword) = taxonomy.find_keyword(&slug)? else {
+        return match taxonomy.find_alias_target(&slug)? {
+            Some(target) => Ok(HttpResponse::MovedPermanently()
+                .append_header(("Location", format!("/keywords/{}", target)))
+                .finish()),
+            None => Err(SubmissionError::NotFound(format!(
+                "Keyword '{}' not found",
+                slug
+            ))),
+        };
+    };
+
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let journals = JournalRepository::new(conn).get_journals_by_keyword(keyword.id)?;
+    render_browse_page(
+        format!("Keyword: {}", keyword.name),
+        String::new(),
+        journals,
+    )
+}
+
+#[get("/subjects")]
+pub async fn subjects_handler() -> Result<HttpResponse, SubmissionError> {
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let template = SubjectsTemplate {
+        subjects: TaxonomyRepository::new(conn).get_all_subjects()?,
+    };
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(
+            template
+                .render()
+                .map_err(|e| SubmissionError::InternalError(format!("Template error: {}", e)))?,
+        ))
+}
+
+#[get("/subjects/{slug}")]
+pub async fn subject_handler(slug: web::Path<String>) -> Result<HttpResponse, SubmissionError> {
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let subject = TaxonomyRepository::new(conn)
+        .find_subject(&slug)?
+        .ok_or_else(|| SubmissionError::NotFound(format!("Subject '{}' not found", slug)))?;
+
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let journals = JournalRepository::new(conn).get_journals_by_subject(subject.id)?;
+    render_browse_page(subject.name, subject.description, journals)
+}
+
+fn render_taxonomy_page(
+    csrf: CsrfToken,
+    error: Option<String>,
+    notice: Option<String>,
+) -> Result<HttpResponse, SubmissionError> {
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let taxonomy = TaxonomyRepository::new(conn);
+    let template = TaxonomyTemplate {
+        current_page: "taxonomy",
+        csrf_token: csrf.into_inner(),
+        keywords: taxonomy.get_all_keywords()?,
+        subjects: taxonomy.get_all_subjects()?,
+        error,
+        notice,
+    };
+    let body = template.render().map_err(|e| {
+        error!("Taxonomy template render error: {:?}", e);
+        SubmissionError::InternalError("Template error".to_string())
+    })?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(body))
+}
+
+fn taxonomy_page_or_error(
+    csrf: CsrfToken,
+    result: Result<String, SubmissionError>,
+) -> Result<HttpResponse, SubmissionError> {
+    match result {
+        Ok(notice) => render_taxonomy_page(csrf, None, Some(notice)),
+        Err(SubmissionError::ValidationError(msg)) | Err(SubmissionError::Conflict(msg)) => {
+            render_taxonomy_page(csrf, Some(msg), None)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+#[get("/taxonomy")]
+pub async fn taxonomy_handler(
+    user: AuthenticatedUser,
+    csrf: CsrfToken,
+) -> Result<HttpResponse, ActixError> {
+    user.require(Permission::EditArticles)?;
+    render_taxonomy_page(csrf, None, None).map_err(ActixError::from)
+}
+
+#[post("/taxonomy/keywords/merge")]
+pub async fn merge_keywords_handler(
+    user: AuthenticatedUser,
+    csrf: CsrfToken,
+    req: HttpRequest,
+    form: web::Form<MergeKeywordsForm>,
+) -> Result<HttpResponse, ActixError> {
+    user.require(Permission::EditArticles)?;
+    let result = (|| {
+        let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        let (source, target) =
+            TaxonomyRepository::new(conn).merge_keywords(form.source_id, form.target_id)?;
+        audit::record(
+            &req,
+            &user,
+            AuditAction::KeywordsMerged,
+            "keyword",
+            target.id,
+            Some(json!({ "merged": source.name, "into": target.name }).to_string()),
+        )?;
+        info!(
+            "Admin {} merged keyword '{}' into '{}'",
+            user.email, source.name, target.name
+        );
+        Ok(format!(
+            "'{}' has been merged into '{}'.",
+            source.name, target.name
+        ))
+    })();
+
+    taxonomy_page_or_error(csrf, result).map_err(ActixError::from)
+}
+
+#[post("/taxonomy/subjects")]
+pub async fn create_subject_handler(
+    user: AuthenticatedUser,
+    csrf: CsrfToken,
+    req: HttpRequest,
+    form: web::Form<SubjectForm>,
+) -> Result<HttpResponse, ActixError> {
+    user.require(Permission::EditArticles)?;
+    let result = (|| {
+        let name = normalize_name(&form.name);
+        let description = form.description.trim();
+
+        let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        let subject_id = TaxonomyRepository::new(conn).create_subject(&name, description)?;
+        audit::record(
+            &req,
+            &user,
+            AuditAction::SubjectCreated,
+            "subject",
+            subject_id,
+            Some(json!({ "name": name, "description": description }).to_string()),
+        )?;
+        Ok(format!("Subject '{}' has been added.", name))
+    })();
+
+    taxonomy_page_or_error(csrf, result).map_err(ActixError::from)
+}
+
+#[post("/taxonomy/subjects/{id}/delete")]
+pub async fn delete_subject_handler(
+    user: AuthenticatedUser,
+    csrf: CsrfToken,
+    req: HttpRequest,
+    id: web::Path<i32>,
+) -> Result<HttpResponse, ActixError> {
+    user.require(Permission::EditArticles)?;
+    let subject_id = id.into_inner();
+    let result = (|| {
+        let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        let subject = TaxonomyRepository::new(conn).delete_subject(subject_id)?;
+        audit::record(
+            &req,
+            &user,
+            AuditAction::SubjectDeleted,
+            "subject",
+            subject_id,
+            snapshot(&subject),
+        )?;
+        Ok(format!("Subject '{}' has been deleted.", subject.name))
+    })();
+
+    taxonomy_page_or_error(csrf, result).map_err(ActixError::from)
+}
+
+// Checkbox form from the article edit page; every ticked box arrives as a separate
+// "subject" field, which serde_urlencoded can only collect as a list of pairs
+#[post("/journals/{id}/subjects")]
+pub async fn assign_subjects_handler(
+    user: AuthenticatedUser,
+    req: HttpRequest,
+    id: web::Path<i32>,
+    form: web::Form<Vec<(String, String)>>,
+) -> Result<HttpResponse, ActixError> {
+    user.require(Permission::EditArticles)?;
+    let journal_id = id.into_inner();
+
+    let subject_ids: Vec<i32> = form
+        .iter()
+        .filter(|(key, _)| key == "subject")
+        .filter_map(|(_, value)| value.parse().ok())
+        .collect();
+
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    JournalRepository::new(conn).get_journal_by_id(journal_id)?;
+
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let taxonomy = TaxonomyRepository::new(conn);
+    let names = |subjects: Vec<Subject>| -> Vec<String> {
+        subjects.into_iter().map(|subject| subject.name).collect()
+    };
+    let before = names(taxonomy.get_journal_subjects(journal_id)?);
+    taxonomy.set_journal_subjects(journal_id, &subject_ids)?;
+    let after = names(taxonomy.get_journal_subjects(journal_id)?);
+
+    if let Some(changes) = field_changes(
+        &json!({ "subjects": before }),
+        &json!({ "subjects": after }),
+    ) {
+        audit::record(
+            &req,
+            &user,
+            AuditAction::SubjectsAssigned,
+            "journal",
+            journal_id,
+            Some(changes),
+        )?;
+    }
+
+    Ok(HttpResponse::Found()
+        .append_header(("Location", format!("/admin/{}/edit", journal_id)))
+        .finish())
+}
@@ -0,0 +1,139 @@
+use actix_web::{get, post, web, Error as ActixError, HttpRequest, HttpResponse};
+use askama::Template;
+use log::{error, warn};
+
+use crate::{
+    auth::{csrf::CsrfToken, AuthenticatedUser},
+    config::get_trash_retention_days,
+    db::{
+        journal_repository::JournalRepository, schema::init_db,
+        submission_repository::SubmissionRepository, trash_repository::TrashRepository,
+    },
+    errors::SubmissionError,
+    models::{
+        admin::Permission,
+        audit::AuditAction,
+        trash::{TrashKind, TrashedItem},
+    },
+    utils::{audit, trash},
+};
+
+#[derive(Template)]
+#[template(path = "admin/trash.html")]
+struct TrashTemplate {
+    current_page: &'static str,
+    csrf_token: String,
+    items: Vec<TrashedItem>,
+    retention_days: i64,
+    error: Option<String>,
+    notice: Option<String>,
+}
+
+fn render_trash_page(
+    csrf: CsrfToken,
+    error: Option<String>,
+    notice: Option<String>,
+) -> Result<HttpResponse, SubmissionError> {
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let template = TrashTemplate {
+        current_page: "trash",
+        csrf_token: csrf.into_inner(),
+        items: TrashRepository::new(conn).get_trashed_items(trash::retention())?,
+        retention_days: get_trash_retention_days(),
+        error,
+        notice,
+    };
+    let body = template.render().map_err(|e| {
+        error!("Trash template render error: {:?}", e);
+        SubmissionError::InternalError("Template error".to_string())
+    })?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(body))
+}
+
+// Shows the outcome of an action on the trash page; an item that is no longer in
+// the trash (already restored or purged) is reported there rather than as a 404
+fn trash_page_or_error(
+    csrf: CsrfToken,
+    result: Result<String, SubmissionError>,
+) -> Result<HttpResponse, SubmissionError> {
+    match result {
+        Ok(notice) => render_trash_page(csrf, None, Some(notice)),
+        Err(SubmissionError::NotFound(msg)) => render_trash_page(csrf, Some(msg), None),
+        Err(e) => Err(e),
+    }
+}
+
+fn parse_kind(kind: &str) -> Result<TrashKind, SubmissionError> {
+    TrashKind::parse(kind)
+        .ok_or_else(|| SubmissionError::NotFound(format!("Unknown item type '{}'", kind)))
+}
+
+#[get("/trash")]
+pub async fn trash_handler(
+    user: AuthenticatedUser,
+    csrf: CsrfToken,
+) -> Result<HttpResponse, ActixError> {
+    user.require(Permission::DeleteArticles)?;
+    render_trash_page(csrf, None, None).map_err(ActixError::from)
+}
+
+#[post("/trash/{kind}/{id}/restore")]
+pub async fn restore_handler(
+    user: AuthenticatedUser,
+    csrf: CsrfToken,
+    req: HttpRequest,
+    path: web::Path<(String, i32)>,
+) -> Result<HttpResponse, ActixError> {
+    user.require(Permission::DeleteArticles)?;
+    let (kind, id) = path.into_inner();
+    let result = (|| {
+        let kind = parse_kind(&kind)?;
+        let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        let action = match kind {
+            TrashKind::Journal => {
+                JournalRepository::new(conn).restore_journal(id)?;
+                AuditAction::JournalRestored
+            }
+            TrashKind::Submission => {
+                SubmissionRepository::new(conn).restore_submission(id)?;
+                AuditAction::SubmissionRestored
+            }
+        };
+        audit::record(&req, &user, action, kind.as_str(), id, None)?;
+        Ok(format!("{} #{} has been restored.", kind.label(), id))
+    })();
+
+    trash_page_or_error(csrf, result).map_err(ActixError::from)
+}
+
+// Deletes an item for good without waiting for the retention window
+#[post("/trash/{kind}/{id}/purge")]
+pub async fn purge_handler(
+    user: AuthenticatedUser,
+    csrf: CsrfToken,
+    req: HttpRequest,
+    path: web::Path<(String, i32)>,
+) -> Result<HttpResponse, ActixError> {
+    user.require(Permission::DeleteArticles)?;
+    let (kind, id) = path.into_inner();
+    let result = (|| {
+        let kind = parse_kind(&kind)?;
+        let record = trash::purge(kind, id)?;
+        let action = match kind {
+            TrashKind::Journal => AuditAction::JournalPurged,
+            TrashKind::Submission => AuditAction::SubmissionPurged,
+        };
+        audit::record(&req, &user, action, kind.as_str(), id, record)?;
+        warn!("Admin {} purged {} {}", user.email, kind.as_str(), id);
+        Ok(format!(
+            "{} #{} has been permanently deleted.",
+            kind.label(),
+            id
+        ))
+    })();
+
+    trash_page_or_error(csrf, result).map_err(ActixError::from)
+}
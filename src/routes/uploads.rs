@@ -0,0 +1,191 @@
+// Resumable manuscript uploads implementing the core of the tus 1.0 protocol
+// (https://tus.io/protocols/resumable-upload) plus its "creation" extension.
+use actix_web::{head, options, patch, post, web, HttpRequest, HttpResponse};
+use futures::StreamExt;
+use log::{info, warn};
+use std::collections::HashSet;
+use std::fs::{self, OpenOptions};
+use std::io::{Seek, SeekFrom, Write};
+use std::sync::{LazyLock, Mutex};
+use uuid::Uuid;
+
+use crate::config::get_max_upload_size;
+use crate::db::schema::init_db;
+use crate::db::upload_repository::UploadRepository;
+use crate::errors::SubmissionError;
+use crate::models::draft::PARTIAL_DIR;
+
+const TUS_VERSION: &str = "1.0.0";
+
+fn header_i64(req: &HttpRequest, name: &str) -> Result<i64, SubmissionError> {
+    req.headers()
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<i64>().ok())
+        .filter(|v| *v >= 0)
+        .ok_or_else(|| SubmissionError::ValidationError(format!("Missing or invalid {}", name)))
+}
+
+#[options("/uploads")]
+pub async fn upload_options_handler() -> HttpResponse {
+    HttpResponse::NoContent()
+        .append_header(("Tus-Resumable", TUS_VERSION))
+        .append_header(("Tus-Version", TUS_VERSION))
+        .append_header(("Tus-Extension", "creation"))
+        .append_header(("Tus-Max-Size", get_max_upload_size().to_string()))
+        .finish()
+}
+
+#[post("/uploads")]
+pub async fn create_upload_handler(req: HttpRequest) -> Result<HttpResponse, SubmissionError> {
+    let upload_length = header_i64(&req, "Upload-Length")?;
+    if upload_length == 0 || upload_length > get_max_upload_size() {
+        return Err(SubmissionError::ValidationError(format!(
+            "Upload-Length must be between 1 and {} bytes",
+            get_max_upload_size()
+        )));
+    }
+
+    fs::create_dir_all(PARTIAL_DIR).map_err(|e| {
+        SubmissionError::StorageError(format!("Failed to create upload dir: {}", e))
+    })?;
+
+    let upload_id = Uuid::new_v4().to_string();
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let repository = UploadRepository::new(conn);
+    repository.create_upload(&upload_id, upload_length)?;
+    let upload = repository.get_upload(&upload_id)?;
+
+    fs::File::create(upload.partial_path())
+        .map_err(|e| SubmissionError::StorageError(format!("Failed to create upload: {}", e)))?;
+
+    info!(
+        "Created resumable upload {} ({} bytes)",
+        upload_id, upload_length
+    );
+    Ok(HttpResponse::Created()
+        .append_header(("Tus-Resumable", TUS_VERSION))
+        .append_header(("Location", format!("/uploads/{}", upload_id)))
+        .finish())
+}
+
+// Uploads currently receiving a chunk, so two PATCH requests never write the same file
+static IN_FLIGHT: LazyLock<Mutex<HashSet<String>>> = LazyLock::new(Default::default);
+
+// Marks an upload as receiving a chunk until dropped
+struct ChunkGuard(String);
+
+impl ChunkGuard {
+    fn acquire(id: &str) -> Result<Self, SubmissionError> {
+        let mut in_flight = IN_FLIGHT.lock().unwrap_or_else(|e| e.into_inner());
+        if !in_flight.insert(id.to_string()) {
+            return Err(SubmissionError::Conflict(format!(
+                "Upload {} is already receiving a chunk",
+                id
+            )));
+        }
+        Ok(Self(id.to_string()))
+    }
+}
+
+impl Drop for ChunkGuard {
+    fn drop(&mut self) {
+        IN_FLIGHT
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&self.0);
+    }
+}
+
+#[head("/uploads/{id}")]
+pub async fn upload_status_handler(id: web::Path<String>) -> Result<HttpResponse, SubmissionError> {
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let upload = UploadRepository::new(conn).get_upload(&id)?;
+
+    Ok(HttpResponse::Ok()
+        .append_header(("Tus-Resumable", TUS_VERSION))
+        .append_header(("Upload-Offset", upload.upload_offset.to_string()))
+        .append_header(("Upload-Length", upload.upload_length.to_string()))
+        .append_header(("Cache-Control", "no-store"))
+        .finish())
+}
+
+#[patch("/uploads/{id}")]
+pub async fn upload_chunk_handler(
+    req: HttpRequest,
+    id: web::Path<String>,
+    mut payload: web::Payload,
+) -> Result<HttpResponse, SubmissionError> {
+    let content_type = req
+        .headers()
+        .get("Content-Type")
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("");
+    if content_type != "application/offset+octet-stream" {
+        return Ok(HttpResponse::UnsupportedMediaType()
+            .append_header(("Tus-Resumable", TUS_VERSION))
+            .finish());
+    }
+    let client_offset = header_i64(&req, "Upload-Offset")?;
+    let _guard = ChunkGuard::acquire(&id)?;
+
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let repository = UploadRepository::new(conn);
+    let upload = repository.get_upload(&id)?;
+
+    if client_offset != upload.upload_offset {
+        return Err(SubmissionError::Conflict(format!(
+            "Upload-Offset {} does not match server offset {}",
+            client_offset, upload.upload_offset
+        )));
+    }
+
+    let mut file = OpenOptions::new()
+        .write(true)
+        .open(upload.partial_path())
+        .map_err(|e| SubmissionError::StorageError(format!("Failed to open upload: {}", e)))?;
+    // Drop any bytes written after the last recorded offset (e.g. a half-written chunk)
+    file.set_len(upload.upload_offset as u64)
+        .and_then(|_| file.seek(SeekFrom::End(0)))
+        .map_err(|e| SubmissionError::StorageError(format!("Failed to prepare upload: {}", e)))?;
+
+    let mut offset = upload.upload_offset;
+    let mut stream_error = None;
+    while let Some(chunk) = payload.next().await {
+        let data = match chunk {
+            Ok(data) => data,
+            Err(e) => {
+                // Keep what arrived so the client can resume from here
+                stream_error = Some(e.to_string());
+                break;
+            }
+        };
+        if offset + data.len() as i64 > upload.upload_length {
+            stream_error = Some("Chunk exceeds Upload-Length".to_string());
+            break;
+        }
+        file.write_all(&data)
+            .map_err(|e| SubmissionError::StorageError(format!("Failed to write upload: {}", e)))?;
+        offset += data.len() as i64;
+    }
+
+    if !repository.update_offset(&id, upload.upload_offset, offset)? {
+        return Err(SubmissionError::Conflict(format!(
+            "Upload {} changed while the chunk was being written",
+            id
+        )));
+    }
+
+    if let Some(e) = stream_error {
+        warn!("Upload {} interrupted at offset {}: {}", id, offset, e);
+        return Err(SubmissionError::FileProcessingError(format!(
+            "Upload interrupted at offset {}: {}",
+            offset, e
+        )));
+    }
+
+    Ok(HttpResponse::NoContent()
+        .append_header(("Tus-Resumable", TUS_VERSION))
+        .append_header(("Upload-Offset", offset.to_string()))
+        .finish())
+}
@@ -0,0 +1,276 @@
+use actix_web::{get, web, Error as ActixError, HttpResponse};
+use askama::Template;
+use chrono::{Datelike, Duration, Months, NaiveDate, Utc};
+use log::{error, info};
+use serde::Deserialize;
+
+use crate::{
+    auth::{csrf::CsrfToken, AuthenticatedUser},
+    config::get_journal_config,
+    db::{schema::init_db, usage_repository::UsageRepository},
+    errors::SubmissionError,
+    models::{
+        admin::Permission,
+        usage::{months_between, ArticleUsage, DailyUsage, UsageTotals},
+    },
+    utils::export::{csv_response, CsvWriter},
+};
+
+const DEFAULT_PERIOD_DAYS: i64 = 30;
+const MAX_PERIOD_DAYS: i64 = 366;
+const TOP_ARTICLES: i64 = 20;
+
+#[derive(Template)]
+#[template(path = "admin/usage.html")]
+struct UsageTemplate {
+    current_page: &'static str,
+    csrf_token: String,
+    from: String,
+    to: String,
+    totals: UsageTotals,
+    days: Vec<DailyUsage>,
+    top_articles: Vec<ArticleUsage>,
+    // Reporting period of the monthly report offered for download
+    report_begin: String,
+    report_end: String,
+    error: Option<String>,
+}
+
+impl UsageTemplate {
+    // Height of a bar in the daily chart, as a percentage of the busiest day
+    fn bar_height(&self, value: &i64) -> i64 {
+        let busiest = self
+            .days
+            .iter()
+            .map(|day| day.views.max(day.downloads))
+            .max()
+            .unwrap_or(0);
+        if busiest == 0 {
+            0
+        } else {
+            value * 100 / busiest
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct UsageQuery {
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct UsageReportQuery {
+    pub begin: Option<String>,
+    pub end: Option<String>,
+}
+
+fn parse_day(value: &Option<String>) -> Option<NaiveDate> {
+    value
+        .as_deref()
+        .and_then(|v| NaiveDate::parse_from_str(v.trim(), "%Y-%m-%d").ok())
+}
+
+// "YYYY-MM" as the first day of that month
+fn parse_month(value: &Option<String>) -> Option<NaiveDate> {
+    value
+        .as_deref()
+        .and_then(|v| NaiveDate::parse_from_str(&format!("{}-01", v.trim()), "%Y-%m-%d").ok())
+}
+
+// Views, downloads and the most used articles over a period (the last 30 days by default)
+#[get("/usage")]
+pub async fn usage_handler(
+    user: AuthenticatedUser,
+    csrf: CsrfToken,
+    query: web::Query<UsageQuery>,
+) -> Result<HttpResponse, ActixError> {
+    user.require(Permission::ViewDashboard)?;
+
+    let today = Utc::now().date_naive();
+    let mut to = parse_day(&query.to).unwrap_or(today).min(today);
+    let mut from = parse_day(&query.from).unwrap_or(to - Duration::days(DEFAULT_PERIOD_DAYS - 1));
+    let mut error = None;
+    if from > to {
+        error = Some("The start of the period must not be after its end".to_string());
+        to = today;
+        from = to - Duration::days(DEFAULT_PERIOD_DAYS - 1);
+    } else if (to - from).num_days() >= MAX_PERIOD_DAYS {
+        error = Some(format!(
+            "Periods are limited to {} days; showing the last {} days of the one chosen",
+            MAX_PERIOD_DAYS, MAX_PERIOD_DAYS
+        ));
+        from = to - Duration::days(MAX_PERIOD_DAYS - 1);
+    }
+
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let repository = UsageRepository::new(conn);
+    let last_month = today.with_day(1).unwrap_or(today) - Duration::days(1);
+
+    let template = UsageTemplate {
+        current_page: "usage",
+        csrf_token: csrf.into_inner(),
+        from: from.format("%Y-%m-%d").to_string(),
+        to: to.format("%Y-%m-%d").to_string(),
+        totals: repository.get_totals(from, to)?,
+        days: repository.get_daily_usage(from, to)?,
+        top_articles: repository.get_top_articles(from, to, TOP_ARTICLES)?,
+        report_begin: last_month.format("%Y-%m").to_string(),
+        report_end: last_month.format("%Y-%m").to_string(),
+        error,
+    };
+    let body = template.render().map_err(|e| {
+        error!("Usage template render error: {:?}", e);
+        SubmissionError::InternalError("Template error".to_string())
+    })?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(body))
+}
+
+// Item report modelled on COUNTER Release 5 "IR": a header block, then one row per
+// article and metric with a total for the period and a column for each month
+#[get("/usage/report")]
+pub async fn usage_report_handler(
+    user: AuthenticatedUser,
+    query: web::Query<UsageReportQuery>,
+) -> Result<HttpResponse, ActixError> {
+    user.require(Permission::ViewDashboard)?;
+
+    let today = Utc::now().date_naive();
+    let this_month = today.with_day(1).unwrap_or(today);
+    let begin = parse_month(&query.begin).ok_or_else(|| {
+        SubmissionError::ValidationError("begin must be a month (YYYY-MM)".to_string())
+    })?;
+    let end = match &query.end {
+        Some(_) => parse_month(&query.end).ok_or_else(|| {
+            SubmissionError::ValidationError("end must be a month (YYYY-MM)".to_string())
+        })?,
+        None => begin,
+    };
+    if begin > end {
+        return Err(
+            SubmissionError::ValidationError("begin must not be after end".to_string()).into(),
+        );
+    }
+    if end > this_month {
+        return Err(SubmissionError::ValidationError(
+            "The report cannot cover months that have not started".to_string(),
+        )
+        .into());
+    }
+    let last_day = end
+        .checked_add_months(Months::new(1))
+        .map(|next| next - Duration::days(1))
+        .unwrap_or(end);
+
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let articles = UsageRepository::new(conn).get_monthly_usage(begin, last_day)?;
+    let months = months_between(begin, end);
+
+    let journal_name = get_journal_config().name;
+    let metric_types = [
+        "Total_Item_Investigations",
+        "Unique_Item_Investigations",
+        "Total_Item_Requests",
+        "Unique_Item_Requests",
+    ];
+    let header = [
+        ("Report_Name", "Item Report".to_string()),
+        ("Report_ID", "IR".to_string()),
+        ("Release", "5".to_string()),
+        ("Institution_Name", String::new()),
+        ("Institution_ID", String::new()),
+        ("Metric_Types", metric_types.join("; ")),
+        (
+            "Report_Filters",
+            "Data_Type=Article; Access_Method=Regular".to_string(),
+        ),
+        ("Report_Attributes", String::new()),
+        ("Exceptions", String::new()),
+        (
+            "Reporting_Period",
+            format!(
+                "Begin_Date={}; End_Date={}",
+                begin.format("%Y-%m-%d"),
+                last_day.format("%Y-%m-%d")
+            ),
+        ),
+        (
+            "Created",
+            Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+        ),
+        ("Created_By", journal_name.clone()),
+    ];
+
+    let mut csv = CsvWriter::new(Vec::new());
+    for (name, value) in &header {
+        csv.write_row(&[name, value.as_str()])?;
+    }
+    // The blank row that ends the COUNTER header; the csv crate writes it as `""`
+    csv.write_row(&[""])?;
+
+    let mut columns = vec![
+        "Item".to_string(),
+        "Publisher".to_string(),
+        "Platform".to_string(),
+        "Authors".to_string(),
+        "Publication_Date".to_string(),
+        "Item_ID".to_string(),
+        "Metric_Type".to_string(),
+        "Reporting_Period_Total".to_string(),
+    ];
+    columns.extend(months.iter().map(|month| month.format("%b-%Y").to_string()));
+    csv.write_row(&columns)?;
+
+    for article in &articles {
+        for metric in metric_types {
+            let count = |totals: &UsageTotals| match metric {
+                "Total_Item_Investigations" => totals.investigations(),
+                "Unique_Item_Investigations" => totals.unique_investigations(),
+                "Total_Item_Requests" => totals.downloads,
+                _ => totals.unique_downloads,
+            };
+            let monthly: Vec<i64> = months
+                .iter()
+                .map(|month| count(&article.month(&month.format("%Y-%m").to_string())))
+                .collect();
+            // Requests are a subset of investigations, so an article only read online
+            // has no request rows
+            if monthly.iter().all(|value| *value == 0) {
+                continue;
+            }
+
+            let mut row = vec![
+                article.title.clone(),
+                journal_name.clone(),
+                journal_name.clone(),
+                article.authors.clone(),
+                article.publication_date.clone(),
+                article.journal_id.to_string(),
+                metric.to_string(),
+                monthly.iter().sum::<i64>().to_string(),
+            ];
+            row.extend(monthly.iter().map(|value| value.to_string()));
+            csv.write_row(&row)?;
+        }
+    }
+
+    info!(
+        "Admin {} exported the usage report for {} to {}",
+        user.email,
+        begin.format("%Y-%m"),
+        end.format("%Y-%m")
+    );
+
+    csv_response(
+        csv,
+        &format!(
+            "usage-report-{}-{}.csv",
+            begin.format("%Y%m"),
+            end.format("%Y%m")
+        ),
+    )
+    .map_err(ActixError::from)
+}
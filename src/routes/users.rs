@@ -0,0 +1,564 @@
+use actix_web::{get, post, web, Error as ActixError, HttpRequest, HttpResponse};
+use askama::Template;
+use chrono::Utc;
+use log::{error, info, warn};
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::HashMap;
+
+use crate::{
+    auth::{csrf::CsrfToken, AuthenticatedUser},
+    config::{get_base_url, get_session_config},
+    db::{
+        admin_repository::AdminRepository, login_attempt_repository::LoginAttemptRepository,
+        schema::init_db, session_repository::SessionRepository,
+        two_factor_repository::TwoFactorRepository,
+    },
+    errors::SubmissionError,
+    models::{
+        admin::{Admin, AdminInvite, Permission, Role},
+        audit::{snapshot, AuditAction},
+        auth_token::TokenPurpose,
+        login_attempt::LoginAttempt,
+    },
+    routes::account::{auth_message, send_verification_email},
+    utils::{
+        audit,
+        mail::send_email,
+        security::{generate_token, hash_password, validate_new_password},
+    },
+};
+
+// Failed logins listed on the users page
+const RECENT_FAILED_LOGINS: i64 = 20;
+
+#[derive(Template)]
+#[template(path = "admin/users.html")]
+struct AdminUsersTemplate {
+    current_page: &'static str,
+    csrf_token: String,
+    current_admin_id: i32,
+    admins: Vec<Admin>,
+    invites: Vec<AdminInvite>,
+    failed_logins: Vec<LoginAttempt>,
+    roles: &'static [Role],
+    session_counts: HashMap<i32, i64>,
+    error: Option<String>,
+    notice: Option<String>,
+}
+
+impl AdminUsersTemplate {
+    fn session_count(&self, admin_id: &i32) -> i64 {
+        self.session_counts.get(admin_id).copied().unwrap_or(0)
+    }
+}
+
+#[derive(Template)]
+#[template(path = "admin/accept_invite.html")]
+struct AcceptInviteTemplate {
+    invite: Option<AdminInvite>,
+    error: Option<String>,
+    csrf_token: String,
+}
+
+#[derive(Deserialize)]
+pub struct InviteForm {
+    email: String,
+    role: String,
+}
+
+#[derive(Deserialize)]
+pub struct RoleForm {
+    role: String,
+}
+
+#[derive(Deserialize)]
+pub struct AcceptInviteForm {
+    password: String,
+    confirm_password: String,
+}
+
+fn parse_role(value: &str) -> Result<Role, SubmissionError> {
+    Role::parse(value)
+        .ok_or_else(|| SubmissionError::ValidationError(format!("Unknown role: {}", value)))
+}
+
+fn render_users_page(
+    csrf: CsrfToken,
+    current_admin: &Admin,
+    error: Option<String>,
+    notice: Option<String>,
+) -> Result<HttpResponse, SubmissionError> {
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let repository = AdminRepository::new(conn);
+
+    let template = AdminUsersTemplate {
+        current_page: "users",
+        csrf_token: csrf.into_inner(),
+        current_admin_id: current_admin.id,
+        admins: repository.get_all_admins()?,
+        invites: repository.get_pending_invites()?,
+        failed_logins: LoginAttemptRepository::new(
+            init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?,
+        )
+        .get_recent_failures(RECENT_FAILED_LOGINS)?,
+        roles: &Role::ALL,
+        session_counts: SessionRepository::new(
+            init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?,
+        )
+        .count_sessions_by_admin(
+            (Utc::now() - get_session_config().absolute_timeout).timestamp(),
+        )?,
+        error,
+        notice,
+    };
+    let body = template.render().map_err(|e| {
+        error!("Users template render error: {:?}", e);
+        SubmissionError::InternalError("Template error".to_string())
+    })?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(body))
+}
+
+// Validation failures are shown inline on the users page rather than as JSON
+fn users_page_or_error(
+    csrf: CsrfToken,
+    current_admin: &Admin,
+    result: Result<String, SubmissionError>,
+) -> Result<HttpResponse, SubmissionError> {
+    match result {
+        Ok(notice) => render_users_page(csrf, current_admin, None, Some(notice)),
+        Err(SubmissionError::ValidationError(msg)) | Err(SubmissionError::Conflict(msg)) => {
+            render_users_page(csrf, current_admin, Some(msg), None)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+// Refuses changes that would leave the journal without an active super-admin
+fn ensure_super_admin_remains(
+    repository: &AdminRepository,
+    target: &Admin,
+) -> Result<(), SubmissionError> {
+    if target.role == Role::SuperAdmin
+        && target.is_active
+        && repository.count_active_super_admins()? <= 1
+    {
+        return Err(SubmissionError::ValidationError(
+            "At least one active super-admin is required".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+// Signs the account out on every device, so changes to it take effect immediately
+fn revoke_sessions(admin_id: i32) -> Result<usize, SubmissionError> {
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    SessionRepository::new(conn).revoke_admin_sessions(admin_id, None)
+}
+
+fn find_target(repository: &AdminRepository, id: i32) -> Result<Admin, SubmissionError> {
+    repository
+        .find_admin_by_id(id)?
+        .ok_or_else(|| SubmissionError::NotFound(format!("User with ID {} not found", id)))
+}
+
+#[get("/users")]
+pub async fn users_handler(
+    user: AuthenticatedUser,
+    csrf: CsrfToken,
+) -> Result<HttpResponse, ActixError> {
+    user.require(Permission::ManageUsers)?;
+    render_users_page(csrf, &user, None, None).map_err(ActixError::from)
+}
+
+#[post("/users/invite")]
+pub async fn invite_user_handler(
+    user: AuthenticatedUser,
+    csrf: CsrfToken,
+    req: HttpRequest,
+    form: web::Form<InviteForm>,
+) -> Result<HttpResponse, ActixError> {
+    user.require(Permission::ManageUsers)?;
+    let result = (|| {
+        let email = form.email.trim().to_lowercase();
+        if email.is_empty() || !email.contains('@') {
+            return Err(SubmissionError::ValidationError(
+                "A valid email address is required".to_string(),
+            ));
+        }
+        let role = parse_role(&form.role)?;
+
+        let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        let repository = AdminRepository::new(conn);
+        if repository.find_admin_by_email(&email)?.is_some() {
+            return Err(SubmissionError::Conflict(format!(
+                "An account for {} already exists",
+                email
+            )));
+        }
+
+        let invite = AdminInvite {
+            token: generate_token(),
+            email,
+            role,
+            invited_by: user.id,
+            expires_at: Utc::now() + TokenPurpose::AdminInvite.lifetime(),
+        };
+        repository.create_invite(&invite)?;
+        audit::record(
+            &req,
+            &user,
+            AuditAction::UserInvited,
+            "invite",
+            &invite.email,
+            snapshot(&json!({ "email": invite.email, "role": invite.role })),
+        )?;
+        info!(
+            "Admin {} invited {} as {}",
+            user.email,
+            invite.email,
+            invite.role.as_str()
+        );
+        Ok(invite)
+    })();
+
+    let result = match result {
+        Ok(invite) => {
+            let body = format!(
+                "You have been invited to join the Akungba Journal editorial system as {}.\n\n\
+                 Open the link below to set your password:\n\n{}{}\n\n\
+                 The invitation expires in {} days.",
+                invite.role.label(),
+                get_base_url(),
+                invite.invite_url(),
+                TokenPurpose::AdminInvite.lifetime().num_days()
+            );
+            // The link is shown to the inviter instead, so a failed send isn't fatal
+            match send_email(&invite.email, "Invitation to the Akungba Journal", body).await {
+                Ok(()) => Ok(format!("Invitation emailed to {}.", invite.email)),
+                Err(e) => {
+                    warn!("Failed to email invitation to {}: {}", invite.email, e);
+                    Ok(format!(
+                        "Invitation created for {}, but the email could not be sent. \
+                         Send them this link: {}",
+                        invite.email,
+                        invite.invite_url()
+                    ))
+                }
+            }
+        }
+        Err(e) => Err(e),
+    };
+
+    users_page_or_error(csrf, &user, result).map_err(ActixError::from)
+}
+
+#[post("/users/{id}/role")]
+pub async fn update_user_role_handler(
+    user: AuthenticatedUser,
+    csrf: CsrfToken,
+    req: HttpRequest,
+    id: web::Path<i32>,
+    form: web::Form<RoleForm>,
+) -> Result<HttpResponse, ActixError> {
+    user.require(Permission::ManageUsers)?;
+    let result = (|| {
+        let user_id = id.into_inner();
+        let role = parse_role(&form.role)?;
+        if user_id == user.id {
+            return Err(SubmissionError::ValidationError(
+                "You cannot change your own role".to_string(),
+            ));
+        }
+
+        let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        let repository = AdminRepository::new(conn);
+        let target = find_target(&repository, user_id)?;
+        if role != Role::SuperAdmin {
+            ensure_super_admin_remains(&repository, &target)?;
+        }
+        repository.update_role(user_id, role)?;
+        audit::record(
+            &req,
+            &user,
+            AuditAction::UserRoleChanged,
+            "admin",
+            user_id,
+            snapshot(&json!({ "role": { "before": target.role, "after": role } })),
+        )?;
+        revoke_sessions(user_id)?;
+        info!(
+            "Admin {} changed role of {} to {}",
+            user.email,
+            target.email,
+            role.as_str()
+        );
+        Ok(format!("{} is now {}.", target.email, role.label()))
+    })();
+
+    users_page_or_error(csrf, &user, result).map_err(ActixError::from)
+}
+
+fn set_user_active(
+    req: &HttpRequest,
+    admin: &Admin,
+    user_id: i32,
+    is_active: bool,
+) -> Result<String, SubmissionError> {
+    if user_id == admin.id {
+        return Err(SubmissionError::ValidationError(
+            "You cannot deactivate or reactivate your own account".to_string(),
+        ));
+    }
+
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let repository = AdminRepository::new(conn);
+    let target = find_target(&repository, user_id)?;
+    if !is_active {
+        ensure_super_admin_remains(&repository, &target)?;
+    }
+    repository.set_active(user_id, is_active)?;
+    if !is_active {
+        revoke_sessions(user_id)?;
+    }
+
+    let action = if is_active {
+        "reactivated"
+    } else {
+        "deactivated"
+    };
+    audit::record(
+        req,
+        admin,
+        if is_active {
+            AuditAction::UserReactivated
+        } else {
+            AuditAction::UserDeactivated
+        },
+        "admin",
+        user_id,
+        snapshot(&json!({ "email": target.email })),
+    )?;
+    info!("Admin {} {} {}", admin.email, action, target.email);
+    Ok(format!("{} has been {}.", target.email, action))
+}
+
+#[post("/users/{id}/deactivate")]
+pub async fn deactivate_user_handler(
+    user: AuthenticatedUser,
+    csrf: CsrfToken,
+    req: HttpRequest,
+    id: web::Path<i32>,
+) -> Result<HttpResponse, ActixError> {
+    user.require(Permission::ManageUsers)?;
+    let result = set_user_active(&req, &user, id.into_inner(), false);
+    users_page_or_error(csrf, &user, result).map_err(ActixError::from)
+}
+
+#[post("/users/{id}/activate")]
+pub async fn activate_user_handler(
+    user: AuthenticatedUser,
+    csrf: CsrfToken,
+    req: HttpRequest,
+    id: web::Path<i32>,
+) -> Result<HttpResponse, ActixError> {
+    user.require(Permission::ManageUsers)?;
+    let result = set_user_active(&req, &user, id.into_inner(), true);
+    users_page_or_error(csrf, &user, result).map_err(ActixError::from)
+}
+
+#[post("/users/{id}/unlock")]
+pub async fn unlock_user_handler(
+    user: AuthenticatedUser,
+    csrf: CsrfToken,
+    req: HttpRequest,
+    id: web::Path<i32>,
+) -> Result<HttpResponse, ActixError> {
+    user.require(Permission::ManageUsers)?;
+    let result = (|| {
+        let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        let repository = AdminRepository::new(conn);
+        let target = find_target(&repository, id.into_inner())?;
+        repository.reset_failed_logins(target.id)?;
+        audit::record(
+            &req,
+            &user,
+            AuditAction::UserUnlocked,
+            "admin",
+            target.id,
+            snapshot(&json!({ "email": target.email })),
+        )?;
+        info!("Admin {} unlocked {}", user.email, target.email);
+        Ok(format!("{} has been unlocked.", target.email))
+    })();
+
+    users_page_or_error(csrf, &user, result).map_err(ActixError::from)
+}
+
+#[post("/users/{id}/revoke-sessions")]
+pub async fn revoke_user_sessions_handler(
+    user: AuthenticatedUser,
+    csrf: CsrfToken,
+    req: HttpRequest,
+    id: web::Path<i32>,
+) -> Result<HttpResponse, ActixError> {
+    user.require(Permission::ManageUsers)?;
+    let result = (|| {
+        let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        let target = find_target(&AdminRepository::new(conn), id.into_inner())?;
+        if target.id == user.id {
+            return Err(SubmissionError::ValidationError(
+                "Use the Active Sessions page to sign out your own sessions".to_string(),
+            ));
+        }
+        let revoked = revoke_sessions(target.id)?;
+        audit::record(
+            &req,
+            &user,
+            AuditAction::SessionsRevoked,
+            "admin",
+            target.id,
+            snapshot(&json!({ "email": target.email, "sessions": revoked })),
+        )?;
+        warn!(
+            "Admin {} signed out {} session(s) of {}",
+            user.email, revoked, target.email
+        );
+        Ok(format!(
+            "{} has been signed out of {} session(s).",
+            target.email, revoked
+        ))
+    })();
+
+    users_page_or_error(csrf, &user, result).map_err(ActixError::from)
+}
+
+// For someone who has lost both their device and recovery codes
+#[post("/users/{id}/reset-2fa")]
+pub async fn reset_two_factor_handler(
+    user: AuthenticatedUser,
+    csrf: CsrfToken,
+    req: HttpRequest,
+    id: web::Path<i32>,
+) -> Result<HttpResponse, ActixError> {
+    user.require(Permission::ManageUsers)?;
+    let result = (|| {
+        let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        let target = find_target(&AdminRepository::new(conn), id.into_inner())?;
+        if !target.totp_enabled {
+            return Err(SubmissionError::ValidationError(format!(
+                "{} does not have two-factor authentication enabled",
+                target.email
+            )));
+        }
+        let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        TwoFactorRepository::new(conn).disable(target.id)?;
+        audit::record(
+            &req,
+            &user,
+            AuditAction::TwoFactorReset,
+            "admin",
+            target.id,
+            snapshot(&json!({ "email": target.email })),
+        )?;
+        warn!(
+            "Admin {} reset two-factor authentication for {}",
+            user.email, target.email
+        );
+        Ok(format!(
+            "Two-factor authentication has been reset for {}. They will need to set it up again.",
+            target.email
+        ))
+    })();
+
+    users_page_or_error(csrf, &user, result).map_err(ActixError::from)
+}
+
+fn render_accept_invite(
+    csrf: CsrfToken,
+    invite: Option<AdminInvite>,
+    error: Option<String>,
+) -> Result<String, SubmissionError> {
+    AcceptInviteTemplate {
+        invite,
+        error,
+        csrf_token: csrf.into_inner(),
+    }
+    .render()
+    .map_err(|e| SubmissionError::InternalError(format!("Template error: {}", e)))
+}
+
+fn load_invite(token: &str) -> Result<Option<AdminInvite>, SubmissionError> {
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    Ok(AdminRepository::new(conn)
+        .find_invite(token)?
+        .filter(|invite| !invite.is_expired()))
+}
+
+// Public: the invitee has no account yet
+#[get("/admin/invite/{token}")]
+pub async fn accept_invite_form_handler(
+    csrf: CsrfToken,
+    token: web::Path<String>,
+) -> Result<HttpResponse, SubmissionError> {
+    let invite = load_invite(&token)?;
+    let mut response = if invite.is_some() {
+        HttpResponse::Ok()
+    } else {
+        HttpResponse::NotFound()
+    };
+    Ok(response
+        .content_type("text/html; charset=utf-8")
+        .body(render_accept_invite(csrf, invite, None)?))
+}
+
+#[post("/admin/invite/{token}")]
+pub async fn accept_invite_handler(
+    csrf: CsrfToken,
+    token: web::Path<String>,
+    form: web::Form<AcceptInviteForm>,
+) -> Result<HttpResponse, SubmissionError> {
+    let Some(invite) = load_invite(&token)? else {
+        return Ok(HttpResponse::NotFound()
+            .content_type("text/html; charset=utf-8")
+            .body(render_accept_invite(csrf, None, None)?));
+    };
+
+    if let Some(error) = validate_new_password(&form.password, &form.confirm_password) {
+        return Ok(HttpResponse::BadRequest()
+            .content_type("text/html; charset=utf-8")
+            .body(render_accept_invite(csrf, Some(invite), Some(error))?));
+    }
+
+    let password = form.password.clone();
+    let password_hash = web::block(move || hash_password(&password))
+        .await
+        .map_err(|e| SubmissionError::InternalError(format!("Hashing task failed: {}", e)))??;
+
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let repository = AdminRepository::new(conn);
+    let admin_id =
+        repository.create_admin_with_role(&invite.email, &password_hash, invite.role)? as i32;
+    repository.mark_invite_accepted(&invite.token)?;
+    info!(
+        "Invitation accepted by {} ({})",
+        invite.email,
+        invite.role.as_str()
+    );
+
+    send_verification_email(admin_id, &invite.email).await?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(auth_message(
+            "Check your email",
+            format!(
+                "Your account has been created. We've sent a link to {} to confirm your \
+                 email address before you log in.",
+                invite.email
+            ),
+        )?))
+}
@@ -0,0 +1,163 @@
+use actix_web::{get, post, web, Error as ActixError, HttpRequest, HttpResponse};
+use askama::Template;
+use log::{error, info};
+use serde::Deserialize;
+
+use crate::{
+    auth::{csrf::CsrfToken, AuthenticatedUser},
+    db::{
+        author_repository::AuthorRepository, journal_repository::JournalRepository,
+        schema::init_db, taxonomy_repository::TaxonomyRepository,
+        version_repository::VersionRepository,
+    },
+    errors::SubmissionError,
+    models::{
+        admin::Permission,
+        audit::{field_changes, AuditAction},
+        journals::Journal,
+        version::VersionEntry,
+    },
+    utils::{self, audit},
+};
+
+#[derive(Template)]
+#[template(path = "admin/journal_versions.html")]
+struct JournalVersionsTemplate {
+    current_page: &'static str,
+    csrf_token: String,
+    journal: Journal,
+    entries: Vec<VersionEntry>,
+    error: Option<String>,
+    notice: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct RollbackForm {
+    #[serde(default)]
+    pub change_note: String,
+}
+
+fn render_versions_page(
+    csrf: CsrfToken,
+    journal_id: i32,
+    error: Option<String>,
+    notice: Option<String>,
+) -> Result<HttpResponse, SubmissionError> {
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let journal = JournalRepository::new(conn).get_journal_by_id(journal_id)?;
+
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let versions = VersionRepository::new(conn);
+    versions.ensure_initial_version(&journal)?;
+
+    let template = JournalVersionsTemplate {
+        current_page: "journals",
+        csrf_token: csrf.into_inner(),
+        entries: VersionEntry::from_versions(versions.get_versions(journal_id)?),
+        journal,
+        error,
+        notice,
+    };
+    let body = template.render().map_err(|e| {
+        error!("Journal versions template render error: {:?}", e);
+        SubmissionError::InternalError("Template error".to_string())
+    })?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(body))
+}
+
+#[get("/journals/{id}/versions")]
+pub async fn journal_versions_handler(
+    user: AuthenticatedUser,
+    csrf: CsrfToken,
+    id: web::Path<i32>,
+) -> Result<HttpResponse, ActixError> {
+    user.require(Permission::EditArticles)?;
+    render_versions_page(csrf, id.into_inner(), None, None).map_err(ActixError::from)
+}
+
+// Makes an earlier version live again. The rollback is itself recorded as a new
+// version, so the history is never rewritten.
+#[post("/journals/{id}/versions/{version}/rollback")]
+pub async fn rollback_journal_handler(
+    user: AuthenticatedUser,
+    csrf: CsrfToken,
+    req: HttpRequest,
+    path: web::Path<(i32, i32)>,
+    form: web::Form<RollbackForm>,
+) -> Result<HttpResponse, ActixError> {
+    user.require(Permission::EditArticles)?;
+    let (journal_id, version_number) = path.into_inner();
+    let result = (|| {
+        let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        let repository = JournalRepository::new(conn);
+        let current = repository.get_journal_by_id(journal_id)?;
+
+        let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+        let versions = VersionRepository::new(conn);
+        versions.ensure_initial_version(&current)?;
+        let target = versions.get_version(journal_id, version_number)?;
+
+        let restored = Journal {
+            id: current.id,
+            created_at: current.created_at,
+            ..target.journal
+        };
+        // Versions only ever record uploaded file names; anything else is refused rather
+        // than written back as the article's PDF
+        if !utils::is_bare_file_name(&restored.pdf_url) {
+            return Err(SubmissionError::ValidationError(format!(
+                "Version {} does not point to a stored PDF",
+                version_number
+            )));
+        }
+        let Some(changes) = field_changes(&current, &restored) else {
+            return Err(SubmissionError::ValidationError(format!(
+                "The article already matches version {}",
+                version_number
+            )));
+        };
+
+        let note = match form.change_note.trim() {
+            "" => format!("Restored version {}", version_number),
+            note => note.to_string(),
+        };
+        repository.update_journal(&restored)?;
+        let new_version = versions.record_version(&restored, Some(user.id), &note)?;
+        if restored.keywords != current.keywords {
+            let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+            TaxonomyRepository::new(conn).set_journal_keywords(journal_id, &restored.keywords)?;
+        }
+        if restored.authors != current.authors {
+            let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+            AuthorRepository::new(conn).set_journal_authors(journal_id, &restored.authors)?;
+        }
+        audit::record(
+            &req,
+            &user,
+            AuditAction::JournalRolledBack,
+            "journal",
+            journal_id,
+            Some(changes),
+        )?;
+        info!(
+            "Admin {} rolled journal {} back to version {}",
+            user.email, journal_id, version_number
+        );
+        Ok(format!(
+            "Version {} has been restored as version {}.",
+            version_number, new_version
+        ))
+    })();
+
+    match result {
+        Ok(notice) => render_versions_page(csrf, journal_id, None, Some(notice)),
+        Err(SubmissionError::ValidationError(msg)) | Err(SubmissionError::Conflict(msg)) => {
+            render_versions_page(csrf, journal_id, Some(msg), None)
+        }
+        Err(e) => Err(e),
+    }
+    .map_err(ActixError::from)
+}
@@ -0,0 +1,18 @@
+// Sends the page's CSRF token with same-origin fetch() requests that change state
+(function () {
+    const meta = document.querySelector('meta[name="csrf-token"]');
+    if (!meta) return;
+    const token = meta.getAttribute("content");
+    const originalFetch = window.fetch;
+
+    window.fetch = function (input, init = {}) {
+        const method = (init.method || (input instanceof Request && input.method) || "GET").toUpperCase();
+        const url = new URL(input instanceof Request ? input.url : input, window.location.href);
+        if (!["GET", "HEAD", "OPTIONS"].includes(method) && url.origin === window.location.origin) {
+            const headers = new Headers(init.headers || (input instanceof Request ? input.headers : undefined));
+            headers.set("X-CSRF-Token", token);
+            init = { ...init, headers };
+        }
+        return originalFetch.call(this, input, init);
+    };
+})();
@@ -0,0 +1,51 @@
+use actix_web::HttpRequest;
+
+use crate::{
+    auth::client_ip,
+    db::{
+        audit_repository::{AuditRepository, NewAuditEntry},
+        schema::init_db,
+    },
+    errors::SubmissionError,
+    models::{admin::Admin, audit::AuditAction},
+};
+
+// Appends an entry for an action `actor` has just taken
+pub fn record(
+    req: &HttpRequest,
+    actor: &Admin,
+    action: AuditAction,
+    target_type: &str,
+    target_id: impl ToString,
+    changes: Option<String>,
+) -> Result<(), SubmissionError> {
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    AuditRepository::new(conn).record(&NewAuditEntry {
+        actor_id: Some(actor.id),
+        actor_email: &actor.email,
+        action: action.as_str(),
+        target_type,
+        target_id: target_id.to_string(),
+        changes,
+        ip_address: &client_ip(req),
+    })
+}
+
+// Appends an entry for an action the application took on its own, such as the trash purge
+pub fn record_system(
+    action: AuditAction,
+    target_type: &str,
+    target_id: impl ToString,
+    changes: Option<String>,
+) -> Result<(), SubmissionError> {
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    AuditRepository::new(conn).record(&NewAuditEntry {
+        actor_id: None,
+        actor_email: "system",
+        action: action.as_str(),
+        target_type,
+        target_id: target_id.to_string(),
+        changes,
+        ip_address: "-",
+    })
+}
@@ -0,0 +1,276 @@
+use actix_web::{rt::task::spawn_blocking, web::Bytes, HttpResponse};
+use futures::stream;
+use log::error;
+use std::{
+    borrow::Cow,
+    io::{self, Write},
+};
+use tokio::sync::mpsc;
+use zip::{
+    write::{SimpleFileOptions, StreamWriter},
+    CompressionMethod, ZipWriter,
+};
+
+use crate::{
+    errors::SubmissionError,
+    models::export::{ExportColumn, ExportFormat},
+};
+
+// Bytes buffered before a chunk is sent to the client
+const CHUNK_SIZE: usize = 64 * 1024;
+// Chunks that may wait to be sent before the export pauses for a slow client
+const CHUNKS_IN_FLIGHT: usize = 8;
+// Longest text Excel keeps in a cell
+const MAX_CELL_CHARS: usize = 32767;
+
+const CONTENT_TYPES_XML: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Default Extension="xml" ContentType="application/xml"/><Override PartName="/xl/workbook.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml"/><Override PartName="/xl/worksheets/sheet1.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"/><Override PartName="/xl/styles.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.styles+xml"/></Types>"#;
+
+const ROOT_RELS_XML: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="xl/workbook.xml"/></Relationships>"#;
+
+const WORKBOOK_XML: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<workbook xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships"><sheets><sheet name="Export" sheetId="1" r:id="rId1"/></sheets></workbook>"#;
+
+const WORKBOOK_RELS_XML: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" Target="worksheets/sheet1.xml"/><Relationship Id="rId2" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles" Target="styles.xml"/></Relationships>"#;
+
+// Style 1 is the bold header row
+const STYLES_XML: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<styleSheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main"><fonts count="2"><font><sz val="11"/><name val="Calibri"/></font><font><b/><sz val="11"/><name val="Calibri"/></font></fonts><fills count="2"><fill><patternFill patternType="none"/></fill><fill><patternFill patternType="gray125"/></fill></fills><borders count="1"><border><left/><right/><top/><bottom/><diagonal/></border></borders><cellStyleXfs count="1"><xf numFmtId="0" fontId="0" fillId="0" borderId="0"/></cellStyleXfs><cellXfs count="2"><xf numFmtId="0" fontId="0" fillId="0" borderId="0" xfId="0"/><xf numFmtId="0" fontId="1" fillId="0" borderId="0" xfId="0" applyFont="1"/></cellXfs></styleSheet>"#;
+
+const SHEET_START_XML: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main"><sheetViews><sheetView workbookViewId="0"><pane ySplit="1" topLeftCell="A2" activePane="bottomLeft" state="frozen"/></sheetView></sheetViews><sheetData>"#;
+
+const SHEET_END_XML: &str = "</sheetData></worksheet>";
+
+fn write_error(e: impl ToString) -> SubmissionError {
+    SubmissionError::InternalError(format!("Export write failed: {}", e.to_string()))
+}
+
+// Spreadsheet programs run CSV cells starting with these as formulas, and submission
+// fields are typed by the public
+fn csv_safe(value: &str) -> Cow<'_, str> {
+    if value.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        Cow::Owned(format!("'{}", value))
+    } else {
+        Cow::Borrowed(value)
+    }
+}
+
+// Writes CSV for every export and report, quoting through the csv crate and
+// guarding each cell with `csv_safe`
+pub struct CsvWriter<W: Write>(csv::Writer<W>);
+
+impl<W: Write> CsvWriter<W> {
+    pub fn new(out: W) -> CsvWriter<W> {
+        // Reports such as COUNTER put a short header block above their table
+        CsvWriter(csv::WriterBuilder::new().flexible(true).from_writer(out))
+    }
+
+    pub fn write_row<T: AsRef<str>>(&mut self, fields: &[T]) -> Result<(), SubmissionError> {
+        self.0
+            .write_record(
+                fields
+                    .iter()
+                    .map(|field| csv_safe(field.as_ref()).into_owned()),
+            )
+            .map_err(write_error)
+    }
+
+    pub fn into_inner(self) -> Result<W, SubmissionError> {
+        self.0.into_inner().map_err(write_error)
+    }
+}
+
+// A finished CSV file as a download
+pub fn csv_response(
+    csv: CsvWriter<Vec<u8>>,
+    filename: &str,
+) -> Result<HttpResponse, SubmissionError> {
+    Ok(HttpResponse::Ok()
+        .content_type("text/csv; charset=utf-8")
+        .append_header((
+            "Content-Disposition",
+            format!("attachment; filename=\"{}\"", filename),
+        ))
+        .body(csv.into_inner()?))
+}
+
+// Escapes text for XML, dropping the control characters XML cannot hold
+fn xml_text(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars().take(MAX_CELL_CHARS) {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\t' | '\n' | '\r' => escaped.push(c),
+            c if (c as u32) < 0x20 || c == '\u{FFFE}' || c == '\u{FFFF}' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// Writes an export one row at a time, as CSV or as a one-sheet XLSX workbook
+pub enum SheetWriter<W: Write> {
+    Csv(Box<CsvWriter<W>>),
+    Xlsx {
+        zip: Box<ZipWriter<StreamWriter<W>>>,
+        numeric: Vec<bool>,
+    },
+}
+
+impl<W: Write> SheetWriter<W> {
+    // Starts the file and writes the header row
+    pub fn new(
+        format: ExportFormat,
+        out: W,
+        columns: &[ExportColumn],
+    ) -> Result<SheetWriter<W>, SubmissionError> {
+        let labels: Vec<String> = columns.iter().map(|c| c.label.to_string()).collect();
+        let mut sheet = match format {
+            ExportFormat::Csv => SheetWriter::Csv(Box::new(CsvWriter::new(out))),
+            ExportFormat::Xlsx => {
+                let mut zip = ZipWriter::new_stream(out);
+                let options =
+                    SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+                for (name, contents) in [
+                    ("[Content_Types].xml", CONTENT_TYPES_XML),
+                    ("_rels/.rels", ROOT_RELS_XML),
+                    ("xl/workbook.xml", WORKBOOK_XML),
+                    ("xl/_rels/workbook.xml.rels", WORKBOOK_RELS_XML),
+                    ("xl/styles.xml", STYLES_XML),
+                ] {
+                    zip.start_file(name, options).map_err(write_error)?;
+                    zip.write_all(contents.as_bytes()).map_err(write_error)?;
+                }
+                // The sheet is written as rows arrive, so its size is not known up front
+                zip.start_file("xl/worksheets/sheet1.xml", options.large_file(true))
+                    .map_err(write_error)?;
+                zip.write_all(SHEET_START_XML.as_bytes())
+                    .map_err(write_error)?;
+                SheetWriter::Xlsx {
+                    zip: Box::new(zip),
+                    numeric: columns.iter().map(|c| c.numeric).collect(),
+                }
+            }
+        };
+        sheet.write_row_styled(&labels, true)?;
+        Ok(sheet)
+    }
+
+    pub fn write_row(&mut self, fields: &[String]) -> Result<(), SubmissionError> {
+        self.write_row_styled(fields, false)
+    }
+
+    fn write_row_styled(&mut self, fields: &[String], header: bool) -> Result<(), SubmissionError> {
+        match self {
+            SheetWriter::Csv(csv) => csv.write_row(fields),
+            SheetWriter::Xlsx { zip, numeric } => {
+                let mut row = String::from("<row>");
+                for (field, is_numeric) in fields.iter().zip(numeric.iter()) {
+                    if field.is_empty() {
+                        row.push_str("<c/>");
+                    } else if !header
+                        && *is_numeric
+                        && field.parse::<f64>().is_ok_and(f64::is_finite)
+                    {
+                        row.push_str(&format!("<c><v>{}</v></c>", field));
+                    } else {
+                        row.push_str(&format!(
+                            "<c t=\"inlineStr\"{}><is><t xml:space=\"preserve\">{}</t></is></c>",
+                            if header { " s=\"1\"" } else { "" },
+                            xml_text(field)
+                        ));
+                    }
+                }
+                row.push_str("</row>");
+                zip.write_all(row.as_bytes()).map_err(write_error)
+            }
+        }
+    }
+
+    // Ends the file and sends anything still buffered
+    pub fn finish(self) -> Result<(), SubmissionError> {
+        let mut out = match self {
+            SheetWriter::Csv(csv) => csv.into_inner()?,
+            SheetWriter::Xlsx { mut zip, .. } => {
+                zip.write_all(SHEET_END_XML.as_bytes())
+                    .map_err(write_error)?;
+                zip.finish().map_err(write_error)?.into_inner()
+            }
+        };
+        out.flush().map_err(write_error)
+    }
+}
+
+// Passes what is written to a streamed response in chunks, waiting while the client
+// catches up. Writing fails once the client has gone away.
+pub struct ChannelWriter {
+    sender: mpsc::Sender<Result<Bytes, io::Error>>,
+    buffer: Vec<u8>,
+}
+
+impl ChannelWriter {
+    fn send_buffer(&mut self) -> io::Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        let chunk = Bytes::from(std::mem::replace(
+            &mut self.buffer,
+            Vec::with_capacity(CHUNK_SIZE),
+        ));
+        self.sender
+            .blocking_send(Ok(chunk))
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "client disconnected"))
+    }
+}
+
+impl Write for ChannelWriter {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(data);
+        if self.buffer.len() >= CHUNK_SIZE {
+            self.send_buffer()?;
+        }
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.send_buffer()
+    }
+}
+
+// Streams a file produced on a blocking thread as a download. The headers are sent
+// before the file is complete, so a failure part way through ends the response early
+// and is logged.
+pub fn stream_download<F>(content_type: &str, filename: &str, produce: F) -> HttpResponse
+where
+    F: FnOnce(ChannelWriter) -> Result<(), SubmissionError> + Send + 'static,
+{
+    let (sender, receiver) = mpsc::channel(CHUNKS_IN_FLIGHT);
+    let writer = ChannelWriter {
+        sender: sender.clone(),
+        buffer: Vec::with_capacity(CHUNK_SIZE),
+    };
+    let name = filename.to_string();
+    spawn_blocking(move || {
+        if let Err(e) = produce(writer) {
+            error!("Export {} failed: {}", name, e);
+            let _ = sender.blocking_send(Err(io::Error::other(e.to_string())));
+        }
+    });
+
+    let body = stream::unfold(receiver, |mut receiver| async move {
+        receiver.recv().await.map(|chunk| (chunk, receiver))
+    });
+    HttpResponse::Ok()
+        .content_type(content_type.to_string())
+        .append_header((
+            "Content-Disposition",
+            format!("attachment; filename=\"{}\"", filename),
+        ))
+        .streaming(body)
+}
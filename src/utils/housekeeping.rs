@@ -0,0 +1,75 @@
+use chrono::{Duration, Utc};
+use log::{error, info};
+
+use crate::{
+    config::{get_draft_retention_days, get_login_attempt_retention_days, get_session_config},
+    db::{
+        draft_repository::DraftRepository, login_attempt_repository::LoginAttemptRepository,
+        schema::init_db, session_repository::SessionRepository,
+        upload_repository::UploadRepository,
+    },
+    errors::SubmissionError,
+    utils::trash,
+};
+
+// How often the background job looks for expired records and files
+const INTERVAL: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+
+// Removes drafts and resumable uploads left idle past the draft retention window
+fn expire_drafts() -> Result<(usize, usize), SubmissionError> {
+    let cutoff = Utc::now() - Duration::days(get_draft_retention_days());
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let drafts = DraftRepository::new(conn).delete_expired(cutoff)?;
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let uploads = UploadRepository::new(conn).delete_abandoned(cutoff)?;
+    Ok((drafts, uploads))
+}
+
+// Removes login attempts older than the retention window
+fn expire_login_attempts() -> Result<usize, SubmissionError> {
+    let cutoff = Utc::now() - Duration::days(get_login_attempt_retention_days());
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    LoginAttemptRepository::new(conn).delete_older_than(cutoff)
+}
+
+// Removes sessions past their idle expiry or the absolute timeout
+fn expire_sessions() -> Result<usize, SubmissionError> {
+    let created_before = (Utc::now() - get_session_config().absolute_timeout).timestamp();
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    SessionRepository::new(conn).delete_expired(created_before)
+}
+
+// Runs each cleanup task once an hour for the lifetime of the server
+pub async fn run() {
+    let mut interval = tokio::time::interval(INTERVAL);
+    loop {
+        interval.tick().await;
+        match actix_web::web::block(trash::purge_expired).await {
+            Ok(Ok(0)) => {}
+            Ok(Ok(count)) => info!("Purged {} expired item(s) from the trash", count),
+            Ok(Err(e)) => error!("Trash purge failed: {}", e),
+            Err(e) => error!("Trash purge task failed: {}", e),
+        }
+        match actix_web::web::block(expire_drafts).await {
+            Ok(Ok((0, 0))) => {}
+            Ok(Ok((drafts, uploads))) => info!(
+                "Removed {} expired draft(s) and {} abandoned upload(s)",
+                drafts, uploads
+            ),
+            Ok(Err(e)) => error!("Draft cleanup failed: {}", e),
+            Err(e) => error!("Draft cleanup task failed: {}", e),
+        }
+        match actix_web::web::block(expire_login_attempts).await {
+            Ok(Ok(0)) => {}
+            Ok(Ok(count)) => info!("Removed {} old login attempt(s)", count),
+            Ok(Err(e)) => error!("Login attempt cleanup failed: {}", e),
+            Err(e) => error!("Login attempt cleanup task failed: {}", e),
+        }
+        match actix_web::web::block(expire_sessions).await {
+            Ok(Ok(0)) => {}
+            Ok(Ok(count)) => info!("Removed {} expired session(s)", count),
+            Ok(Err(e)) => error!("Session cleanup failed: {}", e),
+            Err(e) => error!("Session cleanup task failed: {}", e),
+        }
+    }
+}
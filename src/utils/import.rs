@@ -0,0 +1,468 @@
+use actix_multipart::Field;
+use chrono::NaiveDate;
+use futures::StreamExt;
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::Path;
+use zip::ZipArchive;
+
+use crate::{
+    db::journal_repository::JournalRepository,
+    errors::SubmissionError,
+    models::{
+        import::{field_name, field_names, ImportRowResult, ManifestRow},
+        journals::Journal,
+    },
+    utils::jats,
+};
+
+// Looked for anywhere in the archive when no manifest is uploaded separately
+const MANIFEST_NAMES: [&str; 2] = ["manifest.csv", "manifest.json"];
+const MAX_MANIFEST_BYTES: u64 = 10 * 1024 * 1024;
+
+fn archive_error(e: impl ToString) -> SubmissionError {
+    SubmissionError::ValidationError(format!("Could not read the archive: {}", e.to_string()))
+}
+
+// Saves an uploaded archive to `path`, refusing anything over `max_bytes`
+pub async fn save_archive(
+    mut field: Field,
+    path: &Path,
+    max_bytes: u64,
+) -> Result<(), SubmissionError> {
+    let mut file = File::create(path)
+        .map_err(|e| SubmissionError::StorageError(format!("Failed to create archive: {}", e)))?;
+    let mut size: u64 = 0;
+    while let Some(chunk) = field.next().await {
+        let data = chunk
+            .map_err(|e| SubmissionError::FileProcessingError(format!("Chunk error: {}", e)))?;
+        size += data.len() as u64;
+        if size > max_bytes {
+            drop(file);
+            let _ = fs::remove_file(path);
+            return Err(SubmissionError::ValidationError(format!(
+                "The archive exceeds the {} MB limit",
+                max_bytes / (1024 * 1024)
+            )));
+        }
+        file.write_all(&data)
+            .map_err(|e| SubmissionError::StorageError(format!("Failed to save archive: {}", e)))?;
+    }
+    Ok(())
+}
+
+// Reads a manifest uploaded next to the archive, as its file name and contents
+pub async fn read_manifest(mut field: Field) -> Result<Option<(String, Vec<u8>)>, SubmissionError> {
+    let name = field
+        .content_disposition()
+        .and_then(|cd| cd.get_filename())
+        .unwrap_or("")
+        .to_string();
+    let mut bytes = Vec::new();
+    while let Some(chunk) = field.next().await {
+        let data = chunk
+            .map_err(|e| SubmissionError::FileProcessingError(format!("Chunk error: {}", e)))?;
+        if (bytes.len() + data.len()) as u64 > MAX_MANIFEST_BYTES {
+            return Err(SubmissionError::ValidationError(
+                "The manifest is too large".to_string(),
+            ));
+        }
+        bytes.extend_from_slice(&data);
+    }
+    // Browsers send an empty part when the optional input is left blank
+    Ok((!name.is_empty()).then_some((name, bytes)))
+}
+
+fn is_hidden(name: &str) -> bool {
+    name.starts_with("__MACOSX/") || base_name(name).starts_with('.')
+}
+
+fn base_name(name: &str) -> &str {
+    name.rsplit('/').next().unwrap_or(name)
+}
+
+// The manifest rows from a CSV file with a header row, a JSON array of objects, or the
+// front matter of a JATS XML article. List fields may be JSON arrays; they are joined
+// with commas. A JATS article without a PDF link uses the PDF with the same name.
+pub fn parse_manifest(name: &str, bytes: &[u8]) -> Result<Vec<ManifestRow>, SubmissionError> {
+    let text = std::str::from_utf8(bytes).map_err(|_| {
+        SubmissionError::ValidationError("The manifest must be UTF-8 text".to_string())
+    })?;
+    // Spreadsheet programs often start UTF-8 files with a byte order mark
+    let text = text.trim_start_matches('\u{feff}');
+
+    let rows = if name.to_lowercase().ends_with(".xml") {
+        let mut row = jats::read_front(bytes).map_err(|e| {
+            SubmissionError::ValidationError(format!("{} is not valid JATS: {}", name, e))
+        })?;
+        if row.file.is_empty() {
+            row.file = pdf_name_for(name);
+        }
+        vec![row]
+    } else if name.to_lowercase().ends_with(".json") {
+        parse_json_manifest(text)?
+    } else if name.to_lowercase().ends_with(".csv") {
+        parse_csv_manifest(text)?
+    } else {
+        return Err(SubmissionError::ValidationError(
+            "The manifest must be a .csv, .json or JATS .xml file".to_string(),
+        ));
+    };
+    if rows.is_empty() {
+        return Err(SubmissionError::ValidationError(
+            "The manifest lists no articles".to_string(),
+        ));
+    }
+    Ok(rows)
+}
+
+fn pdf_name_for(xml_name: &str) -> String {
+    let stem = xml_name.rsplit_once('.').map_or(xml_name, |(stem, _)| stem);
+    format!("{}.pdf", stem)
+}
+
+fn unknown_column(column: &str) -> SubmissionError {
+    SubmissionError::ValidationError(format!(
+        "Unknown manifest column '{}'; expected {}",
+        column,
+        field_names().collect::<Vec<_>>().join(", ")
+    ))
+}
+
+fn parse_csv_manifest(text: &str) -> Result<Vec<ManifestRow>, SubmissionError> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(text.as_bytes());
+    let fields = reader
+        .headers()
+        .map_err(|e| SubmissionError::ValidationError(format!("Invalid manifest: {}", e)))?
+        .iter()
+        .map(|column| field_name(column).ok_or_else(|| unknown_column(column)))
+        .collect::<Result<Vec<_>, _>>()?;
+    let missing: Vec<&str> = field_names().filter(|f| !fields.contains(f)).collect();
+    if !missing.is_empty() {
+        return Err(SubmissionError::ValidationError(format!(
+            "The manifest has no {} column",
+            missing.join(", ")
+        )));
+    }
+
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let record = record
+            .map_err(|e| SubmissionError::ValidationError(format!("Invalid manifest: {}", e)))?;
+        let mut row = ManifestRow::default();
+        for (field, value) in fields.iter().zip(record.iter()) {
+            row.set(field, value.to_string());
+        }
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+fn parse_json_manifest(text: &str) -> Result<Vec<ManifestRow>, SubmissionError> {
+    let invalid =
+        |msg: String| SubmissionError::ValidationError(format!("Invalid manifest: {}", msg));
+    let value: serde_json::Value =
+        serde_json::from_str(text).map_err(|e| invalid(e.to_string()))?;
+    let entries = value
+        .as_array()
+        .ok_or_else(|| invalid("expected an array of articles".to_string()))?;
+
+    let mut rows = Vec::new();
+    for (index, entry) in entries.iter().enumerate() {
+        let object = entry
+            .as_object()
+            .ok_or_else(|| invalid(format!("article {} is not an object", index + 1)))?;
+        let mut row = ManifestRow::default();
+        for (key, value) in object {
+            let field = field_name(key).ok_or_else(|| unknown_column(key))?;
+            let text = match value {
+                serde_json::Value::Null => String::new(),
+                serde_json::Value::String(s) => s.clone(),
+                serde_json::Value::Array(items) => items
+                    .iter()
+                    .map(|item| match item {
+                        serde_json::Value::String(s) => s.clone(),
+                        other => other.to_string(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                other => other.to_string(),
+            };
+            row.set(field, text);
+        }
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+// A ZIP of article PDFs, optionally with the manifest inside it
+pub struct ImportArchive {
+    archive: ZipArchive<File>,
+    // Entry index by full path, and the paths sharing each file name
+    by_path: HashMap<String, usize>,
+    by_name: HashMap<String, Vec<String>>,
+}
+
+impl ImportArchive {
+    pub fn open(path: &Path) -> Result<ImportArchive, SubmissionError> {
+        let file = File::open(path).map_err(archive_error)?;
+        let mut archive = ZipArchive::new(file).map_err(archive_error)?;
+        let mut by_path = HashMap::new();
+        let mut by_name: HashMap<String, Vec<String>> = HashMap::new();
+        for index in 0..archive.len() {
+            let entry = archive.by_index(index).map_err(archive_error)?;
+            if entry.is_dir() || is_hidden(entry.name()) {
+                continue;
+            }
+            let name = entry.name().to_string();
+            by_name
+                .entry(base_name(&name).to_lowercase())
+                .or_default()
+                .push(name.clone());
+            by_path.insert(name, index);
+        }
+        Ok(ImportArchive {
+            archive,
+            by_path,
+            by_name,
+        })
+    }
+
+    // The manifests packed in the archive, as their names and contents: manifest.csv or
+    // manifest.json, or failing that one JATS XML file per article
+    pub fn manifests(&mut self) -> Result<Vec<(String, Vec<u8>)>, SubmissionError> {
+        let found: Vec<String> = MANIFEST_NAMES
+            .iter()
+            .filter_map(|name| self.by_name.get(*name))
+            .flatten()
+            .cloned()
+            .collect();
+        let names = match found.len() {
+            1 => found,
+            0 => {
+                let mut articles: Vec<String> = self
+                    .by_path
+                    .keys()
+                    .filter(|path| path.to_lowercase().ends_with(".xml"))
+                    .cloned()
+                    .collect();
+                articles.sort();
+                articles
+            }
+            _ => {
+                return Err(SubmissionError::ValidationError(
+                    "The archive contains more than one manifest".to_string(),
+                ))
+            }
+        };
+        if names.is_empty() {
+            return Err(SubmissionError::ValidationError(
+                "Upload a manifest or include manifest.csv, manifest.json or JATS XML files in the archive"
+                    .to_string(),
+            ));
+        }
+        names
+            .into_iter()
+            .map(|name| {
+                let bytes = self.read_manifest_entry(&name)?;
+                Ok((name, bytes))
+            })
+            .collect()
+    }
+
+    fn read_manifest_entry(&mut self, name: &str) -> Result<Vec<u8>, SubmissionError> {
+        let index = self.by_path[name];
+        let entry = self.archive.by_index(index).map_err(archive_error)?;
+        let mut bytes = Vec::new();
+        entry
+            .take(MAX_MANIFEST_BYTES + 1)
+            .read_to_end(&mut bytes)
+            .map_err(archive_error)?;
+        if bytes.len() as u64 > MAX_MANIFEST_BYTES {
+            return Err(SubmissionError::ValidationError(format!(
+                "{} is too large",
+                name
+            )));
+        }
+        Ok(bytes)
+    }
+
+    // The archive path a row's file refers to: an exact path, or a file name that only
+    // one PDF in the archive has
+    fn resolve(&self, file: &str) -> Result<String, String> {
+        let file = file.trim_start_matches("./");
+        if self.by_path.contains_key(file) {
+            return Ok(file.to_string());
+        }
+        match self
+            .by_name
+            .get(&base_name(file).to_lowercase())
+            .map(Vec::as_slice)
+        {
+            Some([path]) => Ok(path.clone()),
+            Some([_, _, ..]) => Err(format!(
+                "{} matches several files in the archive; give its full path",
+                file
+            )),
+            _ => Err(format!("{} is not in the archive", file)),
+        }
+    }
+
+    // Size and signature checks that need no extraction
+    fn check_pdf(&mut self, path: &str, max_bytes: u64) -> Result<(), String> {
+        if !path.to_lowercase().ends_with(".pdf") {
+            return Err(format!("{} is not a PDF", path));
+        }
+        let index = self.by_path[path];
+        let entry = self.archive.by_index(index).map_err(|e| e.to_string())?;
+        if entry.size() > max_bytes {
+            return Err(format!(
+                "{} exceeds the {} MB limit",
+                path,
+                max_bytes / (1024 * 1024)
+            ));
+        }
+        let mut signature = Vec::new();
+        entry
+            .take(5)
+            .read_to_end(&mut signature)
+            .map_err(|e| format!("{} could not be read: {}", path, e))?;
+        if signature != b"%PDF-" {
+            return Err(format!("{} is not a valid PDF", path));
+        }
+        Ok(())
+    }
+
+    // Copies a PDF out of the archive. The limit is enforced again while copying, since
+    // the size recorded in the archive may be wrong.
+    pub fn extract(
+        &mut self,
+        path: &str,
+        dest: &Path,
+        max_bytes: u64,
+    ) -> Result<(), SubmissionError> {
+        let index = self.by_path[path];
+        let entry = self.archive.by_index(index).map_err(archive_error)?;
+        let mut file = File::create(dest)
+            .map_err(|e| SubmissionError::StorageError(format!("Failed to create file: {}", e)))?;
+        let copied = io::copy(&mut entry.take(max_bytes + 1), &mut file)
+            .map_err(|e| SubmissionError::FileProcessingError(format!("{}: {}", path, e)))?;
+        if copied > max_bytes {
+            return Err(SubmissionError::ValidationError(format!(
+                "{} exceeds the {} MB limit",
+                path,
+                max_bytes / (1024 * 1024)
+            )));
+        }
+        Ok(())
+    }
+
+    pub fn pdf_paths(&self) -> Vec<String> {
+        let mut paths: Vec<String> = self
+            .by_path
+            .keys()
+            .filter(|path| path.to_lowercase().ends_with(".pdf"))
+            .cloned()
+            .collect();
+        paths.sort();
+        paths
+    }
+}
+
+// What an import would do: the outcome for every row, and the articles with the archive
+// path of each one's PDF when every row is valid
+pub struct ImportPlan {
+    pub rows: Vec<ImportRowResult>,
+    pub articles: Vec<(Journal, String)>,
+    pub unused_files: Vec<String>,
+}
+
+impl ImportPlan {
+    pub fn is_valid(&self) -> bool {
+        self.rows.iter().all(|row| row.errors.is_empty())
+    }
+}
+
+// Checks every row on its own, against the archive, against the other rows and against
+// articles already published
+pub fn plan_import(
+    archive: &mut ImportArchive,
+    rows: &[ManifestRow],
+    repository: &JournalRepository,
+    max_pdf_bytes: u64,
+    today: NaiveDate,
+) -> Result<ImportPlan, SubmissionError> {
+    let mut results = Vec::new();
+    let mut articles = Vec::new();
+    let mut used_files = HashSet::new();
+    let mut seen_articles = HashSet::new();
+
+    for (index, row) in rows.iter().enumerate() {
+        let mut errors = Vec::new();
+        let journal = row.to_journal(today).map_err(|e| errors.extend(e)).ok();
+
+        let path = if row.file.is_empty() {
+            None
+        } else {
+            match archive.resolve(&row.file) {
+                Ok(path) => {
+                    if !used_files.insert(path.clone()) {
+                        errors.push(format!("{} is used by an earlier row", path));
+                    } else if let Err(e) = archive.check_pdf(&path, max_pdf_bytes) {
+                        errors.push(e);
+                    }
+                    Some(path)
+                }
+                Err(e) => {
+                    errors.push(e);
+                    None
+                }
+            }
+        };
+
+        if let Some(journal) = &journal {
+            let key = (
+                journal.title.to_lowercase(),
+                journal.volume_number,
+                journal.issue_number,
+            );
+            if !seen_articles.insert(key) {
+                errors.push("An earlier row has the same title, volume and issue".to_string());
+            } else if repository.exists_in_issue(
+                &journal.title,
+                journal.volume_number,
+                journal.issue_number,
+            )? {
+                errors.push(format!(
+                    "Volume {} issue {} already has an article with this title",
+                    journal.volume_number, journal.issue_number
+                ));
+            }
+        }
+
+        if let (Some(journal), Some(path), true) = (journal, path, errors.is_empty()) {
+            articles.push((journal, path));
+        }
+        results.push(ImportRowResult {
+            row: index + 1,
+            title: row.title.clone(),
+            file: row.file.clone(),
+            errors,
+            journal_id: None,
+        });
+    }
+
+    let unused_files = archive
+        .pdf_paths()
+        .into_iter()
+        .filter(|path| !used_files.contains(path))
+        .collect();
+    Ok(ImportPlan {
+        rows: results,
+        articles,
+        unused_files,
+    })
+}
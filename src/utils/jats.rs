@@ -0,0 +1,661 @@
+use quick_xml::escape::resolve_predefined_entity;
+use quick_xml::events::{BytesDecl, BytesStart, BytesText, Event};
+use quick_xml::{Reader, Writer, XmlVersion};
+use std::io;
+
+use crate::config::{get_base_url, get_journal_config};
+use crate::models::author::{parse_authors, Author};
+use crate::models::import::ManifestRow;
+use crate::models::journals::Journal;
+use crate::models::taxonomy::parse_keywords;
+
+const DOCTYPE: &str = r#"article PUBLIC "-//NLM//DTD JATS (Z39.96) Journal Publishing DTD v1.3 20210610//EN" "https://jats.nlm.nih.gov/publishing/1.3/JATS-journalpublishing1-3.dtd""#;
+
+// Characters XML 1.0 does not allow, which PDF text extraction sometimes produces
+fn xml_safe(value: &str) -> String {
+    value
+        .chars()
+        .filter(|&c| {
+            matches!(c, '\t' | '\n' | '\r') || (c >= '\u{20}' && c != '\u{FFFE}' && c != '\u{FFFF}')
+        })
+        .collect()
+}
+
+// Blank lines separate paragraphs; the lines of a paragraph are joined with spaces
+fn paragraphs(text: &str) -> Vec<String> {
+    let mut paragraphs = Vec::new();
+    let mut current: Vec<&str> = Vec::new();
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() {
+            if !current.is_empty() {
+                paragraphs.push(current.join(" "));
+                current.clear();
+            }
+        } else {
+            current.push(line);
+        }
+    }
+    if !current.is_empty() {
+        paragraphs.push(current.join(" "));
+    }
+    paragraphs
+}
+
+fn text_element(writer: &mut Writer<Vec<u8>>, name: &str, value: &str) -> io::Result<()> {
+    writer
+        .create_element(name)
+        .write_text_content(BytesText::new(&xml_safe(value)))?;
+    Ok(())
+}
+
+fn paragraph_elements(writer: &mut Writer<Vec<u8>>, text: &str) -> io::Result<()> {
+    for paragraph in paragraphs(text) {
+        text_element(writer, "p", &paragraph)?;
+    }
+    Ok(())
+}
+
+fn contrib(writer: &mut Writer<Vec<u8>>, author: &Author) -> io::Result<()> {
+    writer
+        .create_element("contrib")
+        .with_attribute(("contrib-type", "author"))
+        .write_inner_content(|writer| {
+            if let Some(orcid) = author.orcid_url() {
+                writer
+                    .create_element("contrib-id")
+                    .with_attribute(("contrib-id-type", "orcid"))
+                    .write_text_content(BytesText::new(&orcid))?;
+            }
+            let (given_names, surname) = author
+                .name
+                .trim()
+                .rsplit_once(char::is_whitespace)
+                .unwrap_or(("", author.name.trim()));
+            writer
+                .create_element("name")
+                .write_inner_content(|writer| {
+                    text_element(writer, "surname", surname)?;
+                    if !given_names.is_empty() {
+                        text_element(writer, "given-names", given_names)?;
+                    }
+                    Ok(())
+                })?;
+            if !author.affiliation.is_empty() {
+                text_element(writer, "aff", &author.affiliation)?;
+            }
+            Ok(())
+        })?;
+    Ok(())
+}
+
+fn journal_meta(writer: &mut Writer<Vec<u8>>) -> io::Result<()> {
+    let config = get_journal_config();
+    writer
+        .create_element("journal-meta")
+        .write_inner_content(|writer| {
+            writer
+                .create_element("journal-id")
+                .with_attribute(("journal-id-type", "publisher-id"))
+                .write_text_content(BytesText::new(&config.name))?;
+            writer
+                .create_element("journal-title-group")
+                .write_inner_content(|writer| {
+                    text_element(writer, "journal-title", &config.name)
+                })?;
+            Ok(())
+        })?;
+    Ok(())
+}
+
+fn article_meta(
+    writer: &mut Writer<Vec<u8>>,
+    journal: &Journal,
+    authors: &[Author],
+) -> io::Result<()> {
+    let journal_id = journal.id_string();
+    writer
+        .create_element("article-meta")
+        .write_inner_content(|writer| {
+            writer
+                .create_element("article-id")
+                .with_attribute(("pub-id-type", "publisher-id"))
+                .write_text_content(BytesText::new(&journal_id))?;
+            writer
+                .create_element("title-group")
+                .write_inner_content(|writer| {
+                    text_element(writer, "article-title", &journal.title)
+                })?;
+            if !authors.is_empty() {
+                writer
+                    .create_element("contrib-group")
+                    .write_inner_content(|writer| {
+                        authors
+                            .iter()
+                            .try_for_each(|author| contrib(writer, author))
+                    })?;
+            }
+
+            let date = journal.publication_date;
+            writer
+                .create_element("pub-date")
+                .with_attributes([("publication-format", "electronic"), ("date-type", "pub")])
+                .write_inner_content(|writer| {
+                    for (name, format) in [("day", "%d"), ("month", "%m"), ("year", "%Y")] {
+                        text_element(writer, name, &date.format(format).to_string())?;
+                    }
+                    Ok(())
+                })?;
+            text_element(writer, "volume", &journal.volume_number.to_string())?;
+            text_element(writer, "issue", &journal.issue_number.to_string())?;
+            match journal.pages.split_once(['-', '\u{2013}']) {
+                Some((first, last)) => {
+                    text_element(writer, "fpage", first.trim())?;
+                    text_element(writer, "lpage", last.trim())?;
+                }
+                None if !journal.pages.trim().is_empty() => {
+                    text_element(writer, "fpage", journal.pages.trim())?;
+                }
+                None => {}
+            }
+
+            let pdf_url = format!("{}/journals/{}/pdf", get_base_url(), journal_id);
+            writer
+                .create_element("self-uri")
+                .with_attributes([("content-type", "pdf"), ("xlink:href", pdf_url.as_str())])
+                .write_empty()?;
+            writer
+                .create_element("abstract")
+                .write_inner_content(|writer| paragraph_elements(writer, &journal.abstract_text))?;
+            let keywords = parse_keywords(&journal.keywords);
+            if !keywords.is_empty() {
+                writer
+                    .create_element("kwd-group")
+                    .with_attribute(("kwd-group-type", "author"))
+                    .write_inner_content(|writer| {
+                        keywords
+                            .iter()
+                            .try_for_each(|keyword| text_element(writer, "kwd", keyword))
+                    })?;
+            }
+            Ok(())
+        })?;
+    Ok(())
+}
+
+// A published article as JATS 1.3 (Journal Publishing). `authors` are the article's
+// linked author records, which carry ORCID iDs and affiliations; when the article has not
+// been linked yet the names are taken from its authors field. The body holds the text
+// extracted from the PDF, when there is any.
+pub fn article_xml(journal: &Journal, authors: &[Author], full_text: Option<&str>) -> String {
+    let authors = if authors.is_empty() {
+        parse_authors(&journal.authors)
+            .into_iter()
+            .map(|name| Author {
+                id: 0,
+                name,
+                affiliation: String::new(),
+                orcid: String::new(),
+                article_count: 0,
+            })
+            .collect()
+    } else {
+        authors.to_vec()
+    };
+
+    let mut writer = Writer::new_with_indent(Vec::new(), b' ', 2);
+    let result: io::Result<()> = (|| {
+        writer.write_event(Event::Decl(BytesDecl::new("1.0", Some("UTF-8"), None)))?;
+        writer.write_event(Event::DocType(BytesText::from_escaped(DOCTYPE)))?;
+        writer
+            .create_element("article")
+            .with_attributes([
+                ("xmlns:xlink", "http://www.w3.org/1999/xlink"),
+                ("article-type", "research-article"),
+                ("dtd-version", "1.3"),
+                ("xml:lang", "en"),
+            ])
+            .write_inner_content(|writer| {
+                writer
+                    .create_element("front")
+                    .write_inner_content(|writer| {
+                        journal_meta(writer)?;
+                        article_meta(writer, journal, &authors)
+                    })?;
+                if let Some(text) = full_text.filter(|text| !text.trim().is_empty()) {
+                    writer
+                        .create_element("body")
+                        .write_inner_content(|writer| paragraph_elements(writer, text))?;
+                }
+                Ok(())
+            })?;
+        Ok(())
+    })();
+    // Writing to a Vec cannot fail
+    debug_assert!(result.is_ok());
+
+    String::from_utf8(writer.into_inner()).unwrap_or_default()
+}
+
+// Just enough of a DOM to pick the front matter out of a JATS document
+struct Element {
+    name: String,
+    attributes: Vec<(String, String)>,
+    children: Vec<Node>,
+}
+
+enum Node {
+    Element(Element),
+    Text(String),
+}
+
+impl Element {
+    fn from_start(start: &BytesStart) -> Result<Element, String> {
+        let name = start.local_name().as_ref().to_string();
+        let mut attributes = Vec::new();
+        for attribute in start.attributes() {
+            let attribute = attribute.map_err(|e| e.to_string())?;
+            let key = attribute.key.local_name().as_ref().to_string();
+            let value = attribute
+                .normalized_value(XmlVersion::Implicit1_0)
+                .map_err(|e| e.to_string())?;
+            attributes.push((key, value.into_owned()));
+        }
+        Ok(Element {
+            name,
+            attributes,
+            children: Vec::new(),
+        })
+    }
+
+    fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn children<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> + 'a {
+        self.children.iter().filter_map(move |node| match node {
+            Node::Element(element) if element.name == name => Some(element),
+            _ => None,
+        })
+    }
+
+    fn child(&self, name: &str) -> Option<&Element> {
+        self.children.iter().find_map(|node| match node {
+            Node::Element(element) if element.name == name => Some(element),
+            _ => None,
+        })
+    }
+
+    fn child_text(&self, name: &str) -> String {
+        self.child(name).map(Element::text).unwrap_or_default()
+    }
+
+    // Descendants with the given name, in document order
+    fn descendants<'a>(&'a self, name: &str, found: &mut Vec<&'a Element>) {
+        for node in &self.children {
+            if let Node::Element(element) = node {
+                if element.name == name {
+                    found.push(element);
+                }
+                element.descendants(name, found);
+            }
+        }
+    }
+
+    // All the text inside the element, including inline markup such as <italic>, with
+    // runs of whitespace collapsed
+    fn text(&self) -> String {
+        fn collect(element: &Element, text: &mut String) {
+            for node in &element.children {
+                match node {
+                    Node::Text(value) => text.push_str(value),
+                    Node::Element(child) => collect(child, text),
+                }
+            }
+        }
+        let mut text = String::new();
+        collect(self, &mut text);
+        text.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+}
+
+fn parse_document(xml: &str) -> Result<Element, String> {
+    let mut reader = Reader::from_str(xml);
+    let mut stack: Vec<Element> = Vec::new();
+    let push_text = |stack: &mut Vec<Element>, text: String| {
+        if let Some(parent) = stack.last_mut() {
+            parent.children.push(Node::Text(text));
+        }
+    };
+
+    loop {
+        let event = reader
+            .read_event()
+            .map_err(|e| format!("{} at byte {}", e, reader.error_position()))?;
+        let finished = match event {
+            Event::Start(start) => {
+                stack.push(Element::from_start(&start)?);
+                None
+            }
+            Event::Empty(start) => Some(Element::from_start(&start)?),
+            Event::End(_) => stack.pop(),
+            Event::Text(text) => {
+                push_text(&mut stack, text.xml10_content().into_owned());
+                None
+            }
+            Event::CData(data) => {
+                push_text(&mut stack, data.xml10_content().into_owned());
+                None
+            }
+            Event::GeneralRef(reference) => {
+                let resolved = match reference.resolve_char_ref().map_err(|e| e.to_string())? {
+                    Some(c) => c.to_string(),
+                    // Entities declared in the DTD, such as &nbsp;, are not expanded
+                    None => resolve_predefined_entity(&reference.xml10_content())
+                        .unwrap_or(" ")
+                        .to_string(),
+                };
+                push_text(&mut stack, resolved);
+                None
+            }
+            Event::Eof => return Err("the document ends early".to_string()),
+            _ => None,
+        };
+        if let Some(element) = finished {
+            match stack.last_mut() {
+                Some(parent) => parent.children.push(Node::Element(element)),
+                None => return Ok(element),
+            }
+        }
+    }
+}
+
+fn contrib_name(contrib: &Element) -> Option<String> {
+    let name = if let Some(name) = contrib.child("name") {
+        [name.child_text("given-names"), name.child_text("surname")]
+            .into_iter()
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ")
+    } else if let Some(name) = contrib.child("string-name") {
+        name.text()
+    } else {
+        contrib.child_text("collab")
+    };
+    (!name.is_empty()).then_some(name)
+}
+
+// The publication date as YYYY-MM-DD, preferring the date of publication over others
+// such as the date the issue was collected. A missing month or day counts as the first.
+fn pub_date(meta: &Element) -> String {
+    let dates: Vec<&Element> = meta.children("pub-date").collect();
+    let date = dates
+        .iter()
+        .find(|date| {
+            date.attribute("date-type") == Some("pub")
+                || matches!(
+                    date.attribute("pub-type"),
+                    Some("epub" | "ppub" | "epub-ppub")
+                )
+        })
+        .or(dates.first());
+    let Some(date) = date else {
+        return String::new();
+    };
+    let part = |name: &str| match date.child_text(name) {
+        value if value.is_empty() => "01".to_string(),
+        value => format!("{:0>2}", value),
+    };
+    format!(
+        "{}-{}-{}",
+        date.child_text("year"),
+        part("month"),
+        part("day")
+    )
+}
+
+// An article's front matter read from a JATS document, as an import manifest row. The
+// PDF is the one named by a relative <self-uri>, if any; links to other sites are ignored.
+pub fn read_front(bytes: &[u8]) -> Result<ManifestRow, String> {
+    let xml = std::str::from_utf8(bytes).map_err(|_| "the file is not UTF-8 text".to_string())?;
+    let article = parse_document(xml.trim_start_matches('\u{feff}'))?;
+    if article.name != "article" {
+        return Err(format!("expected a JATS <article>, not <{}>", article.name));
+    }
+    let meta = article
+        .child("front")
+        .and_then(|front| front.child("article-meta"))
+        .ok_or_else(|| "the article has no <front><article-meta>".to_string())?;
+
+    let mut row = ManifestRow::default();
+    row.set(
+        "title",
+        meta.child("title-group")
+            .map(|group| group.child_text("article-title"))
+            .unwrap_or_default(),
+    );
+
+    let authors: Vec<String> = meta
+        .children("contrib-group")
+        .flat_map(|group| group.children("contrib"))
+        .filter(|contrib| {
+            contrib
+                .attribute("contrib-type")
+                .is_none_or(|t| t == "author")
+        })
+        .filter_map(contrib_name)
+        .collect();
+    row.set("authors", authors.join(", "));
+
+    // A plain abstract rather than a teaser or graphical one
+    let abstracts: Vec<&Element> = meta.children("abstract").collect();
+    if let Some(abstract_element) = abstracts
+        .iter()
+        .find(|a| a.attribute("abstract-type").is_none())
+        .or(abstracts.first())
+    {
+        let mut found = Vec::new();
+        abstract_element.descendants("p", &mut found);
+        let text = if found.is_empty() {
+            abstract_element.text()
+        } else {
+            found
+                .iter()
+                .map(|p| p.text())
+                .collect::<Vec<_>>()
+                .join("\n\n")
+        };
+        row.set("abstract", text);
+    }
+
+    let keywords: Vec<String> = meta
+        .children("kwd-group")
+        .flat_map(|group| group.children("kwd"))
+        .map(Element::text)
+        .collect();
+    row.set("keywords", keywords.join(", "));
+
+    row.set("volume", meta.child_text("volume"));
+    row.set("issue", meta.child_text("issue"));
+    let pages = match (meta.child_text("fpage"), meta.child_text("lpage")) {
+        (first, last) if !first.is_empty() && !last.is_empty() => format!("{}-{}", first, last),
+        (first, _) if !first.is_empty() => first,
+        _ => match meta.child_text("page-range") {
+            range if range.is_empty() => meta.child_text("elocation-id"),
+            range => range,
+        },
+    };
+    row.set("pages", pages);
+    row.set("date", pub_date(meta));
+
+    let file = meta
+        .children("self-uri")
+        .filter_map(|uri| uri.attribute("href").map(|href| (uri, href)))
+        .find(|(uri, href)| {
+            !href.contains("://")
+                && (uri.attribute("content-type") == Some("pdf")
+                    || href.to_lowercase().ends_with(".pdf"))
+        })
+        .map(|(_, href)| href.to_string())
+        .unwrap_or_default();
+    row.set("file", file);
+    Ok(row)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn journal() -> Journal {
+        Journal {
+            id: Some(7),
+            title: "Mobile Learning in Rural Schools".to_string(),
+            authors: "Abebe Kebede, Sara Ali".to_string(),
+            abstract_text: "First paragraph\nwrapped over two lines.\n\nSecond paragraph."
+                .to_string(),
+            keywords: "mobile learning; Ethiopia, teacher training".to_string(),
+            volume_number: 3,
+            issue_number: 2,
+            pages: "12-34".to_string(),
+            publication_date: Utc.with_ymd_and_hms(2024, 5, 9, 0, 0, 0).unwrap(),
+            pdf_url: "article.pdf".to_string(),
+            created_at: None,
+        }
+    }
+
+    fn author(name: &str, affiliation: &str, orcid: &str) -> Author {
+        Author {
+            id: 1,
+            name: name.to_string(),
+            affiliation: affiliation.to_string(),
+            orcid: orcid.to_string(),
+            article_count: 1,
+        }
+    }
+
+    // Each author's ORCID link and affiliation as written to <contrib>
+    fn contrib_details(xml: &str) -> Vec<(String, String)> {
+        let article = parse_document(xml).expect("generated XML parses");
+        let mut found = Vec::new();
+        article.descendants("contrib", &mut found);
+        found
+            .iter()
+            .map(|contrib| (contrib.child_text("contrib-id"), contrib.child_text("aff")))
+            .collect()
+    }
+
+    #[test]
+    fn front_matter_survives_a_round_trip() {
+        let authors = [
+            author(
+                "Abebe Kebede",
+                "Addis Ababa University",
+                "0000-0002-1825-0097",
+            ),
+            author("Sara Ali", "", ""),
+        ];
+        let xml = article_xml(&journal(), &authors, Some("Body text."));
+        let row = read_front(xml.as_bytes()).unwrap();
+
+        assert_eq!(row.title, "Mobile Learning in Rural Schools");
+        assert_eq!(row.authors, "Abebe Kebede, Sara Ali");
+        assert_eq!(row.keywords, "mobile learning, Ethiopia, teacher training");
+        assert_eq!(
+            row.abstract_text,
+            "First paragraph wrapped over two lines.\n\nSecond paragraph."
+        );
+        assert_eq!(row.volume, "3");
+        assert_eq!(row.issue, "2");
+        assert_eq!(row.pages, "12-34");
+        assert_eq!(row.date, "2024-05-09");
+        // The PDF link is absolute, so it isn't taken for a file in an import archive
+        assert_eq!(row.file, "");
+    }
+
+    // Import rows carry no ORCID iDs or affiliations, so these are checked in the XML
+    #[test]
+    fn orcid_and_affiliation_are_written_per_author() {
+        let authors = [
+            author(
+                "Abebe Kebede",
+                "Addis Ababa University",
+                "0000-0002-1825-0097",
+            ),
+            author("Sara Ali", "", ""),
+        ];
+        let xml = article_xml(&journal(), &authors, None);
+        assert!(xml.contains(r#"contrib-id-type="orcid""#));
+        assert_eq!(
+            contrib_details(&xml),
+            [
+                (
+                    "https://orcid.org/0000-0002-1825-0097".to_string(),
+                    "Addis Ababa University".to_string()
+                ),
+                (String::new(), String::new()),
+            ]
+        );
+    }
+
+    #[test]
+    fn names_come_from_the_authors_field_when_no_authors_are_linked() {
+        let xml = article_xml(&journal(), &[], None);
+        let row = read_front(xml.as_bytes()).unwrap();
+        assert_eq!(row.authors, "Abebe Kebede, Sara Ali");
+    }
+
+    #[test]
+    fn special_characters_are_escaped() {
+        let journal = Journal {
+            title: "Fish & Chips: <Tags> \"Quoted\" 'Single'\u{1}".to_string(),
+            abstract_text: "a < b && c > d".to_string(),
+            keywords: "R&D, <xml>".to_string(),
+            ..journal()
+        };
+        let authors = [author("Zoë O'Brien", "Research & Development <Lab>", "")];
+        let xml = article_xml(&journal, &authors, Some("x < y & z"));
+        assert!(!xml.contains('\u{1}'));
+
+        let row = read_front(xml.as_bytes()).unwrap();
+        assert_eq!(row.title, "Fish & Chips: <Tags> \"Quoted\" 'Single'");
+        assert_eq!(row.abstract_text, "a < b && c > d");
+        assert_eq!(row.keywords, "R&D, <xml>");
+        assert_eq!(row.authors, "Zoë O'Brien");
+        assert_eq!(contrib_details(&xml)[0].1, "Research & Development <Lab>");
+    }
+
+    #[test]
+    fn body_is_left_out_without_full_text() {
+        for full_text in [None, Some(""), Some(" \n ")] {
+            let xml = article_xml(&journal(), &[], full_text);
+            assert!(!xml.contains("<body>"));
+            assert_eq!(read_front(xml.as_bytes()).unwrap().title, journal().title);
+        }
+
+        let xml = article_xml(&journal(), &[], Some("One.\n\nTwo."));
+        assert!(xml.contains("<body>"));
+    }
+
+    #[test]
+    fn malformed_or_foreign_documents_are_errors() {
+        for input in [
+            &b""[..],
+            b"not xml at all",
+            b"<article><front><article-meta>",
+            b"<article></front></article>",
+            b"<article><front><article-meta><title-group a=\"1></title-group>",
+            b"<html><body><p>Hello</p></body></html>",
+            b"<article><body><p>No front matter</p></body></article>",
+            b"\xff\xfe<article/>",
+        ] {
+            assert!(
+                read_front(input).is_err(),
+                "{:?} was read",
+                String::from_utf8_lossy(input)
+            );
+        }
+    }
+}
@@ -0,0 +1,46 @@
+use lettre::{
+    message::header::ContentType, transport::smtp::authentication::Credentials, AsyncSmtpTransport,
+    AsyncTransport, Message, Tokio1Executor,
+};
+use log::{info, warn};
+
+use crate::{config::get_smtp_config, errors::SubmissionError};
+
+// Sends a plain-text email. Without SMTP_HOST configured the message is written to the
+// log instead, which keeps password resets usable in development.
+pub async fn send_email(to: &str, subject: &str, body: String) -> Result<(), SubmissionError> {
+    let Some(config) = get_smtp_config() else {
+        warn!(
+            "SMTP_HOST not set; email to {} not sent.\nSubject: {}\n\n{}",
+            to, subject, body
+        );
+        return Ok(());
+    };
+
+    let message = Message::builder()
+        .from(config.from.parse().map_err(|e| {
+            SubmissionError::InternalError(format!("Invalid MAIL_FROM address: {}", e))
+        })?)
+        .to(to.parse().map_err(|e| {
+            SubmissionError::ValidationError(format!("Invalid email address {}: {}", to, e))
+        })?)
+        .subject(subject)
+        .header(ContentType::TEXT_PLAIN)
+        .body(body)
+        .map_err(|e| SubmissionError::InternalError(format!("Failed to build email: {}", e)))?;
+
+    let mut transport = AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.host)
+        .map_err(|e| SubmissionError::InternalError(format!("Invalid SMTP_HOST: {}", e)))?
+        .port(config.port);
+    if let (Some(username), Some(password)) = (config.username, config.password) {
+        transport = transport.credentials(Credentials::new(username, password));
+    }
+
+    transport
+        .build()
+        .send(message)
+        .await
+        .map_err(|e| SubmissionError::InternalError(format!("Failed to send email: {}", e)))?;
+    info!("Sent email '{}' to {}", subject, to);
+    Ok(())
+}
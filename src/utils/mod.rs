@@ -1,18 +1,35 @@
+use actix_files::NamedFile;
 use actix_multipart::Field;
-use actix_web::web::Bytes;
-use futures::{StreamExt, TryStreamExt};
-use log::info;
+use actix_web::http::header::{ContentDisposition, ContentType, DispositionParam, DispositionType};
+use actix_web::{HttpRequest, HttpResponse};
+use futures::StreamExt;
+use log::{info, warn};
+use std::ffi::OsStr;
 use std::fs;
-use std::io::Write; // Import Write trait
-use std::path::Path;
+use std::io::{self, ErrorKind, Write}; // Import Write trait
+use std::path::{Path, PathBuf};
 use uuid::Uuid; // Import Uuid
 
 use crate::errors::SubmissionError; // Assuming SubmissionError is in scope
+use crate::models::attachment::{Attachment, AttachmentKind};
 
+pub mod audit;
+pub mod export;
+pub mod housekeeping;
+pub mod import;
+pub mod jats;
+pub mod mail;
+pub mod pdf;
+pub mod query;
 pub mod security;
+pub mod similarity;
+pub mod trash;
+pub mod usage;
+
+const UPLOAD_DIR: &str = "./data/uploads";
 
 pub fn ensure_upload_dir() -> std::io::Result<()> {
-    let upload_dir = Path::new("./data/uploads");
+    let upload_dir = Path::new(UPLOAD_DIR);
     if !upload_dir.exists() {
         info!("Creating uploads directory...");
         fs::create_dir_all(upload_dir)?;
@@ -20,16 +37,100 @@ pub fn ensure_upload_dir() -> std::io::Result<()> {
     Ok(())
 }
 
+// Whether a stored file name is a single plain path component, so joining it to the
+// uploads directory can't name a file anywhere else
+pub fn is_bare_file_name(name: &str) -> bool {
+    Path::new(name).file_name() == Some(OsStr::new(name))
+}
+
+// Resolves the path of a stored file, symlinks included, and fails with PermissionDenied
+// unless it lies inside the uploads directory
+pub fn confined_upload_path(path: impl AsRef<Path>) -> io::Result<PathBuf> {
+    let root = fs::canonicalize(UPLOAD_DIR)?;
+    let resolved = fs::canonicalize(path)?;
+    if resolved.starts_with(&root) {
+        Ok(resolved)
+    } else {
+        Err(io::Error::new(
+            ErrorKind::PermissionDenied,
+            "outside the uploads directory",
+        ))
+    }
+}
+
+// The path of an upload recorded by its bare file name, such as an article's PDF
+pub fn upload_path(file_name: &str) -> io::Result<PathBuf> {
+    if !is_bare_file_name(file_name) {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            "not a bare file name",
+        ));
+    }
+    confined_upload_path(Path::new(UPLOAD_DIR).join(file_name))
+}
+
+// Files written while a form is still being read. They are removed when this is dropped,
+// so a request that fails validation or storage leaves nothing behind, unless `keep` was
+// called once the records referring to them were saved.
+#[derive(Default)]
+pub struct StagedFiles(Vec<String>);
+
+impl StagedFiles {
+    pub fn add(&mut self, path: impl Into<String>) {
+        self.0.push(path.into());
+    }
+
+    pub fn keep(&mut self) {
+        self.0.clear();
+    }
+}
+
+impl Drop for StagedFiles {
+    fn drop(&mut self) {
+        for path in &self.0 {
+            if let Err(e) = fs::remove_file(path) {
+                warn!("Failed to remove staged file {}: {}", path, e);
+            }
+        }
+    }
+}
+
 // Helper to read text fields from multipart
-pub async fn read_field(mut field: Field) -> Result<String, SubmissionError> {
+pub async fn read_field(field: Field) -> Result<String, SubmissionError> {
+    let bytes = read_field_bytes(field).await?;
+    String::from_utf8(bytes)
+        .map_err(|e| SubmissionError::FileProcessingError(format!("Invalid UTF-8: {}", e)))
+}
+
+// Helper to read a whole multipart field (e.g. a small file) into memory
+pub async fn read_field_bytes(mut field: Field) -> Result<Vec<u8>, SubmissionError> {
     let mut bytes = Vec::new();
     while let Some(chunk) = field.next().await {
         let data = chunk
             .map_err(|e| SubmissionError::FileProcessingError(format!("Chunk error: {}", e)))?;
         bytes.extend_from_slice(&data);
     }
-    String::from_utf8(bytes)
-        .map_err(|e| SubmissionError::FileProcessingError(format!("Invalid UTF-8: {}", e)))
+    Ok(bytes)
+}
+
+// Reads a whole multipart field into memory, refusing anything over `max_bytes`
+pub async fn read_field_bytes_capped(
+    mut field: Field,
+    max_bytes: usize,
+) -> Result<Vec<u8>, SubmissionError> {
+    let mut bytes = Vec::new();
+    while let Some(chunk) = field.next().await {
+        let data = chunk
+            .map_err(|e| SubmissionError::FileProcessingError(format!("Chunk error: {}", e)))?;
+        if bytes.len() + data.len() > max_bytes {
+            return Err(SubmissionError::ValidationError(format!(
+                "The file exceeds the {} MB limit",
+                max_bytes / (1024 * 1024)
+            )));
+        }
+        bytes.extend_from_slice(&data);
+    }
+    Ok(bytes)
 }
 
 // Helper to save uploaded files
@@ -62,16 +163,158 @@ pub async fn save_uploaded_file(mut field: Field) -> Result<String, SubmissionEr
         ))
     })?;
 
-    while let Some(chunk) = field.next().await {
-        let data = chunk
-            .map_err(|e| SubmissionError::FileProcessingError(format!("Chunk error: {}", e)))?;
-        file.write_all(&data).map_err(|e| {
-            SubmissionError::FileProcessingError(format!(
-                "Failed to write to file {}: {}",
-                file_path_str, e
-            ))
-        })?;
+    let written = async {
+        while let Some(chunk) = field.next().await {
+            let data = chunk
+                .map_err(|e| SubmissionError::FileProcessingError(format!("Chunk error: {}", e)))?;
+            file.write_all(&data).map_err(|e| {
+                SubmissionError::FileProcessingError(format!(
+                    "Failed to write to file {}: {}",
+                    file_path_str, e
+                ))
+            })?;
+        }
+        Ok(())
+    }
+    .await;
+    if let Err(e) = written {
+        drop(file);
+        let _ = fs::remove_file(file_path);
+        return Err(e);
     }
 
     Ok(file_name) // Return only the generated filename
 }
+
+// Bytes read from the start of an attachment to check its format
+const SNIFF_BYTES: usize = 8;
+
+// Helper to save an attachment, enforcing the per-type extension, content and size limits.
+// The returned attachment still needs its owner set before it is stored.
+pub async fn save_attachment(
+    mut field: Field,
+    kind: AttachmentKind,
+) -> Result<Option<Attachment>, SubmissionError> {
+    let original_name = field
+        .content_disposition()
+        .and_then(|cd| cd.get_filename())
+        .unwrap_or("")
+        .to_string();
+
+    // Browsers send an empty part when an optional file input is left blank
+    if original_name.is_empty() {
+        while field.next().await.is_some() {}
+        return Ok(None);
+    }
+
+    let extension = Path::new(&original_name)
+        .extension()
+        .and_then(std::ffi::OsStr::to_str)
+        .unwrap_or("")
+        .to_lowercase();
+    let mime_type = kind.mime_for_extension(&extension).ok_or_else(|| {
+        SubmissionError::ValidationError(format!(
+            "{} files of type .{} are not accepted",
+            kind.label(),
+            extension
+        ))
+    })?;
+
+    let file_name = format!("{}.{}", Uuid::new_v4(), extension);
+    let file_path_str = format!("./data/uploads/attachments/{}", file_name);
+    fs::create_dir_all("./data/uploads/attachments").map_err(|e| {
+        SubmissionError::StorageError(format!("Failed to create attachments dir: {}", e))
+    })?;
+    let mut file = fs::File::create(&file_path_str).map_err(|e| {
+        SubmissionError::FileProcessingError(format!(
+            "Failed to create file {}: {}",
+            file_path_str, e
+        ))
+    })?;
+
+    let mut size: u64 = 0;
+    // Enough of the start of the file to recognise its format
+    let mut head = Vec::with_capacity(SNIFF_BYTES);
+    let written = async {
+        while let Some(chunk) = field.next().await {
+            let data = chunk
+                .map_err(|e| SubmissionError::FileProcessingError(format!("Chunk error: {}", e)))?;
+            let wanted = SNIFF_BYTES.saturating_sub(head.len()).min(data.len());
+            head.extend_from_slice(&data[..wanted]);
+            size += data.len() as u64;
+            if size > kind.max_size() {
+                return Err(SubmissionError::ValidationError(format!(
+                    "{} {} exceeds the {} MB limit",
+                    kind.label(),
+                    original_name,
+                    kind.max_size() / (1024 * 1024)
+                )));
+            }
+            file.write_all(&data).map_err(|e| {
+                SubmissionError::FileProcessingError(format!(
+                    "Failed to write to file {}: {}",
+                    file_path_str, e
+                ))
+            })?;
+        }
+        if !AttachmentKind::content_matches(mime_type, &head) {
+            return Err(SubmissionError::ValidationError(format!(
+                "{} {} is not a valid .{} file",
+                kind.label(),
+                original_name,
+                extension
+            )));
+        }
+        Ok(())
+    }
+    .await;
+    // Don't leave a partial file behind
+    if let Err(e) = written {
+        drop(file);
+        let _ = fs::remove_file(&file_path_str);
+        return Err(e);
+    }
+
+    Ok(Some(Attachment {
+        id: None,
+        owner_type: String::new(),
+        owner_id: 0,
+        kind,
+        original_name,
+        file_name,
+        mime_type: mime_type.to_string(),
+        size_bytes: size as i64,
+        created_at: None,
+    }))
+}
+
+// Serves a stored attachment as a download under its original filename
+pub async fn serve_attachment(
+    attachment: &Attachment,
+    req: &HttpRequest,
+) -> Result<HttpResponse, SubmissionError> {
+    let named_file = NamedFile::open_async(attachment.file_path())
+        .await
+        .map_err(|e| {
+            if e.kind() == std::io::ErrorKind::NotFound {
+                SubmissionError::NotFound(format!(
+                    "Attachment file {} not found",
+                    attachment.original_name
+                ))
+            } else {
+                SubmissionError::StorageError(format!("Error opening attachment: {:?}", e))
+            }
+        })?;
+
+    let content_type = attachment
+        .mime_type
+        .parse()
+        .unwrap_or_else(|_| ContentType::octet_stream().0);
+    Ok(named_file
+        .set_content_type(content_type)
+        .set_content_disposition(ContentDisposition {
+            disposition: DispositionType::Attachment,
+            parameters: vec![DispositionParam::Filename(attachment.original_name.clone())],
+        })
+        .into_response(req))
+}
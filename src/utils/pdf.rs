@@ -0,0 +1,161 @@
+use lopdf::{decode_text_string, Document, Object};
+use serde::Serialize;
+use std::path::Path;
+
+use crate::errors::SubmissionError;
+
+// Upper bounds for suggested values so a badly parsed PDF can't flood the form
+const MAX_TITLE_LEN: usize = 300;
+const MAX_ABSTRACT_LEN: usize = 3000;
+
+#[derive(Debug, Default, Serialize)]
+pub struct PdfMetadata {
+    pub title: Option<String>,
+    pub abstract_text: Option<String>,
+    pub keywords: Vec<String>,
+    pub page_count: usize,
+    #[serde(skip)]
+    pub text: String,
+}
+
+// Extracts text and document info from a PDF already saved on disk
+pub fn extract_pdf_metadata(path: &Path) -> Result<PdfMetadata, SubmissionError> {
+    let bytes = std::fs::read(path).map_err(|e| {
+        SubmissionError::FileProcessingError(format!("Failed to read PDF {:?}: {}", path, e))
+    })?;
+    extract_pdf_metadata_from_mem(&bytes)
+}
+
+// Extracts text and document info from an in-memory PDF
+pub fn extract_pdf_metadata_from_mem(bytes: &[u8]) -> Result<PdfMetadata, SubmissionError> {
+    let doc = Document::load_mem(bytes)
+        .map_err(|e| SubmissionError::FileProcessingError(format!("Invalid PDF: {}", e)))?;
+    let text = pdf_extract::extract_text_from_mem(bytes).map_err(|e| {
+        SubmissionError::FileProcessingError(format!("PDF text extraction failed: {}", e))
+    })?;
+
+    let lines: Vec<&str> = text
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .collect();
+
+    let title = info_string(&doc, b"Title")
+        .map(|t| clean_info_title(&t))
+        .filter(|t| !t.is_empty())
+        .or_else(|| guess_title(&lines))
+        .map(|t| truncate(&t, MAX_TITLE_LEN));
+
+    let keywords = match info_string(&doc, b"Keywords") {
+        Some(k) if !k.trim().is_empty() => split_keywords(&k),
+        _ => guess_keywords(&lines),
+    };
+
+    Ok(PdfMetadata {
+        title,
+        abstract_text: guess_abstract(&lines).map(|a| truncate(&a, MAX_ABSTRACT_LEN)),
+        keywords,
+        page_count: doc.get_pages().len(),
+        text,
+    })
+}
+
+// Reads a string entry from the document's Info dictionary
+fn info_string(doc: &Document, key: &[u8]) -> Option<String> {
+    let info = match doc.trailer.get(b"Info").ok()? {
+        Object::Reference(id) => doc.get_dictionary(*id).ok()?,
+        Object::Dictionary(dict) => dict,
+        _ => return None,
+    };
+    let value = info.get(key).ok()?;
+    let (_, value) = doc.dereference(value).ok()?;
+    decode_text_string(value).ok().map(|s| s.trim().to_string())
+}
+
+// Word processors often write "Microsoft Word - paper.docx" as the title
+fn clean_info_title(title: &str) -> String {
+    let title = title.trim_start_matches("Microsoft Word - ").trim();
+    let lower = title.to_lowercase();
+    if lower.ends_with(".doc") || lower.ends_with(".docx") || lower.ends_with(".pdf") {
+        return String::new();
+    }
+    title.to_string()
+}
+
+// First line that looks like a sentence rather than a running header
+fn guess_title(lines: &[&str]) -> Option<String> {
+    lines
+        .iter()
+        .take(15)
+        .find(|l| l.split_whitespace().count() >= 3 && !is_heading(l, "abstract"))
+        .map(|l| l.to_string())
+}
+
+fn guess_abstract(lines: &[&str]) -> Option<String> {
+    let start = lines.iter().position(|l| is_heading(l, "abstract"))?;
+    let mut parts = Vec::new();
+
+    // The heading line may carry the first sentence, e.g. "Abstract: This study..."
+    let first = strip_heading(lines[start], "abstract");
+    if !first.is_empty() {
+        parts.push(first);
+    }
+
+    for line in &lines[start + 1..] {
+        if is_heading(line, "keywords")
+            || is_heading(line, "key words")
+            || is_heading(line, "introduction")
+            || is_heading(line, "1. introduction")
+            || is_heading(line, "1 introduction")
+        {
+            break;
+        }
+        parts.push(line);
+    }
+
+    let abstract_text = parts.join(" ");
+    if abstract_text.is_empty() {
+        None
+    } else {
+        Some(abstract_text)
+    }
+}
+
+fn guess_keywords(lines: &[&str]) -> Vec<String> {
+    lines
+        .iter()
+        .find(|l| is_heading(l, "keywords") || is_heading(l, "key words"))
+        .map(|l| {
+            let rest = strip_heading(strip_heading(l, "keywords"), "key words");
+            split_keywords(rest)
+        })
+        .unwrap_or_default()
+}
+
+fn split_keywords(value: &str) -> Vec<String> {
+    value
+        .split([',', ';'])
+        .map(|k| k.trim().trim_end_matches('.').trim())
+        .filter(|k| !k.is_empty())
+        .map(|k| k.to_string())
+        .collect()
+}
+
+fn is_heading(line: &str, heading: &str) -> bool {
+    line.to_lowercase().starts_with(heading)
+}
+
+// Removes a leading heading word plus any separator that follows it
+fn strip_heading<'a>(line: &'a str, heading: &str) -> &'a str {
+    if !is_heading(line, heading) {
+        return line;
+    }
+    line.get(heading.len()..)
+        .unwrap_or("")
+        .trim_start_matches([':', '-', '—', '–', '.', ' '])
+        .trim()
+}
+
+fn truncate(value: &str, max_chars: usize) -> String {
+    value.chars().take(max_chars).collect()
+}
@@ -1,5 +1,13 @@
 use crate::errors::SubmissionError;
+use crate::models::auth_token::TokenPurpose;
 use bcrypt::{hash, verify, BcryptError, DEFAULT_COST}; // Assuming SubmissionError is in scope
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::sync::LazyLock;
+
+// Checked when no account matches a login, so the response takes as long as a real check
+static DUMMY_PASSWORD_HASH: LazyLock<String> =
+    LazyLock::new(|| hash(generate_token(), DEFAULT_COST).unwrap_or_default());
 
 // Hashes a password using bcrypt
 pub fn hash_password(password: &str) -> Result<String, BcryptError> {
@@ -12,9 +20,88 @@ pub fn verify_password(password: &str, hash: &str) -> Result<bool, BcryptError>
     verify(password, hash)
 }
 
+// Spends the time of a password check without an account to check against
+pub fn verify_dummy_password(password: &str) {
+    let _ = verify(password, &DUMMY_PASSWORD_HASH);
+}
+
 // Optional: Map BcryptError to your SubmissionError if needed elsewhere
 impl From<BcryptError> for SubmissionError {
     fn from(err: BcryptError) -> Self {
         SubmissionError::HashingError(format!("bcrypt error: {}", err))
     }
 }
+
+pub const MIN_PASSWORD_LEN: usize = 8;
+
+// Checks a newly chosen password, returning a message suitable for the form
+pub fn validate_new_password(password: &str, confirm_password: &str) -> Option<String> {
+    if password.chars().count() < MIN_PASSWORD_LEN {
+        Some(format!(
+            "Password must be at least {} characters",
+            MIN_PASSWORD_LEN
+        ))
+    } else if password != confirm_password {
+        Some("Passwords do not match".to_string())
+    } else {
+        None
+    }
+}
+
+// 256 random bits, hex encoded so the token is URL safe
+pub fn generate_token() -> String {
+    let bytes: [u8; 32] = rand::random();
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+// Compares secrets without exiting at the first differing byte
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+// HMAC-SHA256 of `value` keyed with SESSION_SECRET_KEY and bound to `label`.
+// Only such digests are stored, so secrets can't be forged or replayed from a database dump.
+fn keyed_hash(label: &str, value: &str) -> String {
+    let secret = std::env::var("SESSION_SECRET_KEY").unwrap_or_default();
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(label.as_bytes());
+    mac.update(b":");
+    mac.update(value.as_bytes());
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+pub fn sign_token(purpose: TokenPurpose, token: &str) -> String {
+    keyed_hash(purpose.as_str(), token)
+}
+
+// Signs the CSRF cookie for one login (empty when signed out), so a cookie planted by
+// another site or taken from another session doesn't verify
+pub fn sign_csrf_token(token: &str, login_id: &str) -> String {
+    keyed_hash("csrf", &format!("{}.{}", login_id, token))
+}
+
+// Sessions are stored under this digest, so the table can't be used to hijack live cookies
+pub fn hash_session_key(session_key: &str) -> String {
+    keyed_hash("session", session_key)
+}
+
+// Recovery codes carry 40 random bits, so a keyed hash is enough and avoids
+// running bcrypt once per stored code on every attempt. Case and dashes are ignored.
+pub fn hash_recovery_code(code: &str) -> String {
+    let normalized: String = code
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+    keyed_hash("recovery_code", &normalized)
+}
+
+// Usage statistics identify a reader session only by this digest, never by IP address
+pub fn hash_usage_session(session: &str) -> String {
+    keyed_hash("usage", session)
+}
@@ -0,0 +1,96 @@
+use std::collections::HashSet;
+
+// Number of hash functions in a signature; the error of the Jaccard estimate is ~1/sqrt(N)
+pub const SIGNATURE_SIZE: usize = 128;
+// Shingle widths (in words) for full text and for short abstracts
+pub const TEXT_SHINGLE_SIZE: usize = 5;
+pub const ABSTRACT_SHINGLE_SIZE: usize = 3;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MinHash {
+    values: Vec<u64>,
+}
+
+impl MinHash {
+    pub fn from_text(text: &str, shingle_size: usize) -> Option<Self> {
+        let shingles = shingles(text, shingle_size);
+        if shingles.is_empty() {
+            return None;
+        }
+
+        let values = (0..SIGNATURE_SIZE as u64)
+            .map(|seed| {
+                shingles
+                    .iter()
+                    .map(|s| mix(s ^ mix(seed.wrapping_add(1))))
+                    .min()
+                    .unwrap_or(u64::MAX)
+            })
+            .collect();
+        Some(Self { values })
+    }
+
+    // Estimated Jaccard similarity of the two shingle sets (0.0 - 1.0)
+    pub fn similarity(&self, other: &MinHash) -> f64 {
+        if self.values.len() != other.values.len() || self.values.is_empty() {
+            return 0.0;
+        }
+        let matching = self
+            .values
+            .iter()
+            .zip(&other.values)
+            .filter(|(a, b)| a == b)
+            .count();
+        matching as f64 / self.values.len() as f64
+    }
+
+    // Stored as fixed-width hex so signatures survive in a TEXT column
+    pub fn to_hex(&self) -> String {
+        self.values.iter().map(|v| format!("{:016x}", v)).collect()
+    }
+
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        if hex.is_empty() || !hex.len().is_multiple_of(16) {
+            return None;
+        }
+        let values = (0..hex.len())
+            .step_by(16)
+            .map(|i| u64::from_str_radix(hex.get(i..i + 16)?, 16).ok())
+            .collect::<Option<Vec<u64>>>()?;
+        Some(Self { values })
+    }
+}
+
+// Word n-grams of lower-cased alphanumeric tokens, hashed to u64
+fn shingles(text: &str, size: usize) -> HashSet<u64> {
+    let words: Vec<String> = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+
+    if words.is_empty() {
+        return HashSet::new();
+    }
+    if words.len() < size {
+        return HashSet::from([fnv1a(&words.join(" "))]);
+    }
+    words
+        .windows(size)
+        .map(|window| fnv1a(&window.join(" ")))
+        .collect()
+}
+
+// FNV-1a: stable across Rust versions, unlike std's DefaultHasher
+fn fnv1a(value: &str) -> u64 {
+    value.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+// splitmix64 finaliser, used to derive independent hash functions from one seed
+fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+    x ^ (x >> 31)
+}
@@ -0,0 +1,85 @@
+use chrono::{Duration, Utc};
+use log::{error, info, warn};
+use std::{fs, io::ErrorKind};
+
+use crate::{
+    config::get_trash_retention_days,
+    db::{
+        journal_repository::JournalRepository, schema::init_db,
+        submission_repository::SubmissionRepository, trash_repository::TrashRepository,
+    },
+    errors::SubmissionError,
+    models::{
+        audit::{snapshot, AuditAction},
+        trash::TrashKind,
+    },
+    utils::{audit, confined_upload_path},
+};
+
+pub fn retention() -> Duration {
+    Duration::days(get_trash_retention_days())
+}
+
+// Deletes the files of a purged record. The records are already gone, so a failure can't
+// be rolled back; it is logged for an administrator to clean up. A file that is already
+// gone from disk is fine, and one outside the uploads directory is never touched.
+fn remove_files(paths: &[String]) {
+    for path in paths {
+        let resolved = match confined_upload_path(path) {
+            Ok(resolved) => resolved,
+            Err(e) if e.kind() == ErrorKind::NotFound => continue,
+            Err(e) => {
+                error!("Not deleting {} after purging its record: {}", path, e);
+                continue;
+            }
+        };
+        match fs::remove_file(&resolved) {
+            Ok(()) => info!("Deleted file {}", path),
+            Err(e) if e.kind() == ErrorKind::NotFound => {}
+            Err(e) => error!("Failed to delete {} after purging its record: {}", path, e),
+        }
+    }
+}
+
+// Permanently deletes a trashed article or submission together with its files and
+// attachments, returning a snapshot of the record for the audit log. Files are only
+// removed once every record is gone, so a failed purge leaves the item restorable.
+pub fn purge(kind: TrashKind, id: i32) -> Result<Option<String>, SubmissionError> {
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let (record, files) = match kind {
+        TrashKind::Journal => {
+            let repository = JournalRepository::new(conn);
+            let record = snapshot(&repository.get_trashed_journal(id)?);
+            (record, repository.purge_journal(id)?)
+        }
+        TrashKind::Submission => {
+            let repository = SubmissionRepository::new(conn);
+            let record = snapshot(&repository.get_trashed_submission(id)?);
+            (record, repository.purge_submission(id)?)
+        }
+    };
+    remove_files(&files);
+    Ok(record)
+}
+
+// Purges everything that has been in the trash longer than the retention window
+pub fn purge_expired() -> Result<usize, SubmissionError> {
+    let conn = init_db().map_err(|e| SubmissionError::DatabaseError(e.to_string()))?;
+    let expired = TrashRepository::new(conn).get_expired(Utc::now() - retention())?;
+
+    let mut purged = 0;
+    for (kind, id) in expired {
+        match purge(kind, id) {
+            Ok(record) => {
+                let action = match kind {
+                    TrashKind::Journal => AuditAction::JournalPurged,
+                    TrashKind::Submission => AuditAction::SubmissionPurged,
+                };
+                audit::record_system(action, kind.as_str(), id, record)?;
+                purged += 1;
+            }
+            Err(e) => warn!("Failed to purge {} {}: {}", kind.as_str(), id, e),
+        }
+    }
+    Ok(purged)
+}
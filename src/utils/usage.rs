@@ -0,0 +1,91 @@
+use actix_web::{http::header, HttpRequest};
+use chrono::Utc;
+use log::warn;
+
+use crate::{
+    auth::client_ip,
+    config::get_extra_robot_agents,
+    db::{schema::init_db, usage_repository::UsageRepository},
+    errors::SubmissionError,
+    models::usage::UsageMetric,
+    utils::security::hash_usage_session,
+};
+
+// User-agent fragments of crawlers, link checkers and scripts, after the COUNTER robots
+// list. Matched case-insensitively; ROBOT_USER_AGENTS adds more.
+const ROBOT_AGENTS: &[&str] = &[
+    "bot",
+    "crawler",
+    "spider",
+    "slurp",
+    "archiver",
+    "facebookexternalhit",
+    "feedfetcher",
+    "mediapartners",
+    "headlesschrome",
+    "phantomjs",
+    "lighthouse",
+    "pingdom",
+    "uptime",
+    "monitor",
+    "curl",
+    "wget",
+    "python",
+    "java/",
+    "go-http-client",
+    "okhttp",
+    "libwww",
+    "httpclient",
+    "axios",
+    "node-fetch",
+    "scrapy",
+    "postman",
+    "zgrab",
+];
+
+pub fn is_robot(user_agent: &str) -> bool {
+    let user_agent = user_agent.to_lowercase();
+    // Browsers always send a user agent
+    user_agent.trim().is_empty()
+        || ROBOT_AGENTS
+            .iter()
+            .any(|pattern| user_agent.contains(pattern))
+        || get_extra_robot_agents()
+            .iter()
+            .any(|pattern| user_agent.contains(pattern.as_str()))
+}
+
+// Counts a view or download of an article by the requesting reader. Statistics are best
+// effort: a failure is logged and never fails the reader's request.
+pub fn record(req: &HttpRequest, journal_id: i32, metric: UsageMetric) {
+    let user_agent = req
+        .headers()
+        .get(header::USER_AGENT)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("");
+    if is_robot(user_agent) {
+        return;
+    }
+
+    // Without logins, a COUNTER user session is the IP address and user agent on a day
+    let now = Utc::now();
+    let session = hash_usage_session(&format!(
+        "{}|{}|{}",
+        client_ip(req),
+        user_agent,
+        now.format("%Y-%m-%d")
+    ));
+    let result = init_db()
+        .map_err(|e| SubmissionError::DatabaseError(e.to_string()))
+        .and_then(|conn| {
+            UsageRepository::new(conn).record_event(journal_id, metric, &session, now)
+        });
+    if let Err(e) = result {
+        warn!(
+            "Failed to record {} of journal {}: {}",
+            metric.as_str(),
+            journal_id,
+            e
+        );
+    }
+}